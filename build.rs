@@ -0,0 +1,28 @@
+use std::path::Path;
+use std::process::Command;
+
+// Rebuild when migrations change, so the embedded migrator stays current, and
+// record the commit and time of the build for `GET /version`.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+
+    // Builds outside a checkout (e.g. a Docker context without .git) can pass
+    // the sha in through the environment instead.
+    let sha = std::env::var("GIT_SHA").ok().filter(|sha| !sha.is_empty()).or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    });
+    println!("cargo:rustc-env=GIT_SHA={}", sha.unwrap_or_else(|| "unknown".to_string()));
+    println!("cargo:rustc-env=BUILD_TIME={}", chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+
+    for path in [".git/HEAD", ".git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+}
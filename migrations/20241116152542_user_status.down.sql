@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP TYPE IF EXISTS user_status;
@@ -0,0 +1,9 @@
+-- Existing databases created the type by hand, before it had a migration.
+DO
+$$
+    BEGIN
+        CREATE TYPE user_status AS ENUM ('verified', 'pending');
+    EXCEPTION
+        WHEN duplicate_object THEN NULL;
+    END
+$$;
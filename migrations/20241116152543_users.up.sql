@@ -0,0 +1,25 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TABLE IF NOT EXISTS users
+(
+    id           UUID PRIMARY KEY         NOT NULL DEFAULT (uuid_generate_v4()),
+    name         VARCHAR(255)             NOT NULL,
+    email        VARCHAR(255)             NOT NULL UNIQUE,
+    password     TEXT                     NOT NULL,
+    phone_number VARCHAR(255)             NOT NULL UNIQUE,
+    title        VARCHAR(255)             NOT NULL DEFAULT '',
+    status       user_status              NOT NULL DEFAULT 'pending',
+    role_id      UUID                     NOT NULL,
+    school_id    UUID                     NOT NULL,
+    created_at   TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at   TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    deleted_at   TIMESTAMP WITH TIME ZONE NULL,
+    CONSTRAINT fk_role
+        FOREIGN KEY (role_id) REFERENCES roles (id)
+            ON DELETE RESTRICT
+            ON UPDATE CASCADE,
+    CONSTRAINT fk_school
+        FOREIGN KEY (school_id) REFERENCES schools (id)
+            ON DELETE CASCADE
+            ON UPDATE CASCADE
+);
@@ -0,0 +1,13 @@
+-- Add down migration script here
+ALTER TABLE schools
+    ALTER COLUMN address DROP NOT NULL,
+    ALTER COLUMN address DROP DEFAULT,
+    ALTER COLUMN logo_path DROP NOT NULL,
+    ALTER COLUMN logo_path DROP DEFAULT,
+    ALTER COLUMN created_at DROP NOT NULL,
+    ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE subscription_types ALTER COLUMN created_at DROP NOT NULL, ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE subscriptions ALTER COLUMN created_at DROP NOT NULL, ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE roles ALTER COLUMN created_at DROP NOT NULL, ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE provinces ALTER COLUMN created_at DROP NOT NULL, ALTER COLUMN updated_at DROP NOT NULL;
+ALTER TABLE cities ALTER COLUMN created_at DROP NOT NULL, ALTER COLUMN updated_at DROP NOT NULL;
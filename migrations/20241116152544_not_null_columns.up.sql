@@ -0,0 +1,44 @@
+-- Entities read these columns into non-optional fields, so a NULL fails the row.
+UPDATE schools SET address = '' WHERE address IS NULL;
+UPDATE schools SET logo_path = '' WHERE logo_path IS NULL;
+ALTER TABLE schools
+    ALTER COLUMN address SET DEFAULT '',
+    ALTER COLUMN address SET NOT NULL,
+    ALTER COLUMN logo_path SET DEFAULT '',
+    ALTER COLUMN logo_path SET NOT NULL;
+
+UPDATE subscription_types SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE subscription_types SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE subscription_types
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE subscriptions SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE subscriptions SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE subscriptions
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE roles SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE roles SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE roles
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE provinces SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE provinces SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE provinces
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE cities SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE cities SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE cities
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
+
+UPDATE schools SET created_at = NOW() WHERE created_at IS NULL;
+UPDATE schools SET updated_at = NOW() WHERE updated_at IS NULL;
+ALTER TABLE schools
+    ALTER COLUMN created_at SET NOT NULL,
+    ALTER COLUMN updated_at SET NOT NULL;
@@ -0,0 +1,8 @@
+-- The last time the scheduler fired each schedule, so a new leader carries on
+-- where the previous one stopped.
+CREATE TABLE IF NOT EXISTS schedule_runs (
+    name TEXT PRIMARY KEY,
+    last_run_at TIMESTAMPTZ NOT NULL,
+    last_job_id UUID REFERENCES jobs (id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,32 @@
+DO
+$$
+    BEGIN
+        CREATE TYPE audit_action AS ENUM ('created', 'updated', 'deleted');
+    EXCEPTION
+        WHEN duplicate_object THEN NULL;
+    END
+$$;
+
+-- No foreign keys: entries outlive what they describe.
+CREATE TABLE IF NOT EXISTS audit_logs (
+    id UUID PRIMARY KEY,
+    entity_type TEXT NOT NULL,
+    entity_id UUID NOT NULL,
+    action audit_action NOT NULL,
+    -- The user id from a bearer token; NULL for basic auth and anonymous calls.
+    actor_id TEXT,
+    -- The token's email or the basic auth username.
+    actor TEXT,
+    -- The school whose data changed, if any.
+    school_id UUID,
+    ip TEXT,
+    request_id TEXT,
+    before JSONB,
+    after JSONB,
+    changes JSONB NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS audit_logs_entity_idx ON audit_logs (entity_type, entity_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_logs_actor_idx ON audit_logs (actor_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS audit_logs_created_at_idx ON audit_logs (created_at DESC);
@@ -0,0 +1,19 @@
+-- Add down migration script here
+-- Fails if live and deleted rows share a value; purge the deleted ones first.
+-- The audit_action values stay, as Postgres can't drop an enum value.
+DROP INDEX IF EXISTS users_phone_number_key;
+ALTER TABLE users ADD CONSTRAINT users_phone_number_key UNIQUE (phone_number);
+
+DROP INDEX IF EXISTS users_email_key;
+ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email);
+
+DROP INDEX IF EXISTS roles_name_key;
+ALTER TABLE roles ADD CONSTRAINT roles_name_key UNIQUE (name);
+
+DROP INDEX IF EXISTS subscriptions_name_key;
+ALTER TABLE subscriptions ADD CONSTRAINT subscriptions_name_key UNIQUE (name);
+
+DROP INDEX IF EXISTS subscription_types_name_key;
+ALTER TABLE subscription_types ADD CONSTRAINT subscription_types_name_key UNIQUE (name);
+
+ALTER TABLE subscription_types ALTER COLUMN deleted_at SET DEFAULT NOW();
@@ -0,0 +1,25 @@
+-- Rows are soft-deleted, so subscription_types.deleted_at must start out
+-- NULL like every other table's. Rows that got the old default were never
+-- deleted, only inserted.
+ALTER TABLE subscription_types ALTER COLUMN deleted_at DROP DEFAULT;
+UPDATE subscription_types SET deleted_at = NULL WHERE deleted_at = created_at;
+
+-- Unique among live rows only, so a deleted row's name, email or phone
+-- number can be taken again. Restoring the deleted row then conflicts.
+ALTER TABLE subscription_types DROP CONSTRAINT IF EXISTS subscription_types_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS subscription_types_name_key ON subscription_types (name) WHERE deleted_at IS NULL;
+
+ALTER TABLE subscriptions DROP CONSTRAINT IF EXISTS subscriptions_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS subscriptions_name_key ON subscriptions (name) WHERE deleted_at IS NULL;
+
+ALTER TABLE roles DROP CONSTRAINT IF EXISTS roles_name_key;
+CREATE UNIQUE INDEX IF NOT EXISTS roles_name_key ON roles (name) WHERE deleted_at IS NULL;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;
+CREATE UNIQUE INDEX IF NOT EXISTS users_email_key ON users (email) WHERE deleted_at IS NULL;
+
+ALTER TABLE users DROP CONSTRAINT IF EXISTS users_phone_number_key;
+CREATE UNIQUE INDEX IF NOT EXISTS users_phone_number_key ON users (phone_number) WHERE deleted_at IS NULL;
+
+ALTER TYPE audit_action ADD VALUE IF NOT EXISTS 'restored';
+ALTER TYPE audit_action ADD VALUE IF NOT EXISTS 'purged';
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE users DROP COLUMN IF EXISTS version;
+ALTER TABLE schools DROP COLUMN IF EXISTS version;
+ALTER TABLE roles DROP COLUMN IF EXISTS version;
+ALTER TABLE subscriptions DROP COLUMN IF EXISTS version;
+ALTER TABLE subscription_types DROP COLUMN IF EXISTS version;
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    key TEXT NOT NULL,
+    -- Method and path, so one key can't replay another endpoint's response.
+    endpoint TEXT NOT NULL,
+    -- SHA-256 of the request body.
+    fingerprint TEXT NOT NULL,
+    -- NULL while the first request with the key is still being handled.
+    status_code INT,
+    content_type TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (key, endpoint)
+);
+
+CREATE INDEX IF NOT EXISTS idempotency_keys_expires_at_idx ON idempotency_keys (expires_at);
@@ -0,0 +1,4 @@
+-- pg_trgm stays, as something else may have come to depend on it.
+DROP INDEX IF EXISTS users_email_trgm_idx;
+DROP INDEX IF EXISTS users_name_trgm_idx;
+DROP INDEX IF EXISTS schools_name_trgm_idx;
@@ -0,0 +1,7 @@
+-- Listings search with `column ILIKE '%term%'`, which a btree can't serve;
+-- trigram indexes can.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS schools_name_trgm_idx ON schools USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS users_name_trgm_idx ON users USING GIN (name gin_trgm_ops);
+CREATE INDEX IF NOT EXISTS users_email_trgm_idx ON users USING GIN (email gin_trgm_ops);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS schools_subscription_ends_at_idx;
+ALTER TABLE schools DROP COLUMN IF EXISTS subscription_ends_at;
@@ -0,0 +1,6 @@
+-- When a school's plan runs out; the daily expiry check clears
+-- `subscription_id` once it has passed. NULL means the plan doesn't end.
+ALTER TABLE schools ADD COLUMN IF NOT EXISTS subscription_ends_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS schools_subscription_ends_at_idx ON schools (subscription_ends_at)
+    WHERE subscription_id IS NOT NULL AND deleted_at IS NULL;
@@ -0,0 +1,3 @@
+-- How long the request holding an unfinished key gets before a retry may take
+-- the key over, in case it died without releasing it.
+ALTER TABLE idempotency_keys ADD COLUMN IF NOT EXISTS locked_until TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,53 @@
+use actix_cors::Cors;
+use actix_multipart::form::MultipartFormConfig;
+use actix_web::http::header::{self, HeaderName};
+use actix_web::web;
+use crate::cmd::middlewares::idempotency::{Idempotency, IDEMPOTENCY_KEY_HEADER, IDEMPOTENCY_REPLAYED_HEADER};
+use crate::cmd::middlewares::request_context::TrustedProxies;
+use crate::cmd::routes::api::api_router;
+use crate::cmd::routes::health_router::health_router;
+use crate::cmd::routes::metrics_router::metrics_router;
+use crate::config::app_config::ServerConfig;
+use crate::internal::app::state::AppState;
+use crate::internal::handlers::health_handler::HealthHandlerImpl;
+use crate::internal::handlers::metrics_handler::MetricsHandlerImpl;
+
+// Everything an `App` needs from the state: shared data plus every router.
+// The health and metrics routes go first, since the legacy API scope claims every path.
+pub fn configure_app(cfg: &mut web::ServiceConfig, state: &AppState) {
+    cfg.app_data(web::Data::new(state.config.auth.clone()))
+        .app_data(web::Data::new(TrustedProxies(state.config.server.trusted_proxies.clone())))
+        .app_data(web::Data::from(state.metrics.clone()))
+        .app_data(web::JsonConfig::default().limit(state.config.server.json_limit))
+        .app_data(MultipartFormConfig::default().total_limit(state.config.server.multipart_limit))
+        .app_data(web::Data::new(Idempotency {
+            repository: state.repositories.idempotency.clone(),
+            ttl: state.config.server.idempotency_ttl,
+            lock: state.config.server.idempotency_lock,
+            json_limit: state.config.server.json_limit,
+            multipart_limit: state.config.server.multipart_limit,
+        }))
+        .configure(|cfg| health_router(cfg, HealthHandlerImpl::new(state.usecases.health.clone())))
+        .configure(|cfg| metrics_router(cfg, MetricsHandlerImpl::new(state.usecases.health.clone(), state.metrics.clone())))
+        .configure(|cfg| api_router(cfg, state));
+}
+
+pub fn cors(config: &ServerConfig) -> Cors {
+    config.cors_allowed_origins.iter()
+        .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+        .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
+        .allowed_headers(vec![
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            header::ACCEPT,
+            header::IF_MATCH,
+            HeaderName::from_static(IDEMPOTENCY_KEY_HEADER),
+            HeaderName::from_static("x-request-id"),
+        ])
+        .expose_headers(vec![
+            header::ETAG,
+            HeaderName::from_static(IDEMPOTENCY_REPLAYED_HEADER),
+            HeaderName::from_static("x-request-id"),
+        ])
+        .supports_credentials()
+}
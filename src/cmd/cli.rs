@@ -0,0 +1,77 @@
+use clap::{Args, Parser, Subcommand};
+use uuid::Uuid;
+
+#[derive(Debug, Parser)]
+#[command(name = "sekula-be", version, about = "Sekula backend server and operations tooling")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Cli {
+    // Running the binary without a subcommand starts the server, as it always has.
+    pub fn command(&self) -> Command {
+        self.command.clone().unwrap_or(Command::Serve)
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Start the HTTP server (the default)
+    Serve,
+    /// Run background job workers without the HTTP server
+    Worker,
+    /// Apply, revert or list database migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Insert the default roles and subscription types if they're missing
+    Seed,
+    /// Import provinces and cities from wilayah.id
+    SyncRegions,
+    /// Create a verified user with the admin role
+    CreateAdmin(CreateAdminArgs),
+    /// Generate a new JWT signing key, keeping the current one for verification
+    RotateKeys(RotateKeysArgs),
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// Revert the most recently applied migrations
+    Down {
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// List migrations and whether they have been applied
+    Status,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CreateAdminArgs {
+    #[arg(long)]
+    pub name: String,
+    #[arg(long)]
+    pub email: String,
+    #[arg(long)]
+    pub phone_number: String,
+    /// Read from ADMIN_PASSWORD when omitted, so it stays out of shell history
+    #[arg(long, env = "ADMIN_PASSWORD", hide_env_values = true)]
+    pub password: String,
+    /// Existing school to attach the admin to
+    #[arg(long, conflicts_with = "school_name")]
+    pub school_id: Option<Uuid>,
+    /// Name of a new school to create for the admin
+    #[arg(long, default_value = "Sekula")]
+    pub school_name: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RotateKeysArgs {
+    /// How many earlier keys stay valid for verifying tokens
+    #[arg(long, default_value_t = 1)]
+    pub keep: usize,
+    /// Also generate a new password for the basic auth secret
+    #[arg(long)]
+    pub basic_auth: bool,
+}
@@ -0,0 +1,71 @@
+use std::error::Error;
+use actix_web::web::Json;
+use chrono::Utc;
+use uuid::Uuid;
+use crate::cmd::cli::CreateAdminArgs;
+use crate::helpers::precondition::IfMatch;
+use crate::internal::app::state::AppState;
+use crate::internal::entities::school::School;
+use crate::internal::entities::user::{User, UserStatus};
+use crate::pkg::dto::user_dto::{CreateUserDto, UpdateUserDto};
+
+pub const ADMIN_ROLE: &str = "admin";
+
+pub async fn run(state: &AppState, args: CreateAdminArgs) -> Result<(), Box<dyn Error>> {
+    let admin = create_admin(state, args).await?;
+    println!("✅ Created admin {} ({})", admin.email, admin.id);
+    Ok(())
+}
+
+pub async fn create_admin(state: &AppState, args: CreateAdminArgs) -> Result<User, Box<dyn Error>> {
+    let role = match state.repositories.role.get_by_name(ADMIN_ROLE.to_string()).await {
+        Ok(role) => role,
+        Err(sqlx::Error::RowNotFound) => return Err("the admin role does not exist, run `seed` first".into()),
+        Err(error) => return Err(error.into()),
+    };
+
+    let school_id = match args.school_id {
+        Some(school_id) => school_id,
+        None => {
+            let school = School {
+                id: Uuid::new_v4(),
+                name: args.school_name,
+                address: "".to_string(),
+                logo_path: "".to_string(),
+                subscription_id: None,
+                subscription_ends_at: None,
+                province_id: None,
+                city_id: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            };
+            state.repositories.school.create(&school).await?.id
+        }
+    };
+
+    let user = state.usecases.user.create(Json(CreateUserDto {
+        name: args.name,
+        email: args.email,
+        phone_number: args.phone_number,
+        password: args.password,
+        title: None,
+        role_id: Some(role.id),
+        school_id: Some(school_id),
+    })).await?;
+
+    // Admins are created by an operator, so there's nothing left to verify.
+    let user = state.usecases.user.update(user.id.to_string(), IfMatch::Versions(vec![user.version]), Json(UpdateUserDto {
+        name: None,
+        email: None,
+        phone_number: None,
+        password: None,
+        title: None,
+        status: Some(UserStatus::Verified),
+        role_id: None,
+        school_id: None,
+    })).await?;
+
+    Ok(user)
+}
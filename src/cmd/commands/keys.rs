@@ -0,0 +1,80 @@
+use uuid::Uuid;
+use crate::cmd::cli::RotateKeysArgs;
+use crate::config::app_config::AuthConfig;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotatedKeys {
+    pub jwt_secret: String,
+    pub jwt_previous_secrets: Vec<String>,
+    pub basic_auth_secret: Option<String>,
+}
+
+// Secrets live in the environment, so this prints the new values for the
+// operator to deploy rather than writing them anywhere.
+pub fn run(config: &AuthConfig, args: RotateKeysArgs) {
+    let keys = rotate(config, &args);
+
+    println!("🔑 New keys generated. Deploy these values and restart:");
+    println!("JWT_SECRET={}", keys.jwt_secret);
+    println!("JWT_PREVIOUS_SECRETS={}", keys.jwt_previous_secrets.join(","));
+    if let Some(secret) = keys.basic_auth_secret {
+        println!("BASIC_AUTH_SECRET={}", secret);
+    }
+}
+
+/// The current key moves to the front of the previous keys, which are cut
+/// down to `keep` entries.
+pub fn rotate(config: &AuthConfig, args: &RotateKeysArgs) -> RotatedKeys {
+    let jwt_previous_secrets = std::iter::once(&config.jwt_secret)
+        .chain(config.jwt_previous_secrets.iter())
+        .filter(|secret| !secret.is_empty())
+        .take(args.keep)
+        .cloned()
+        .collect();
+
+    // Keep the basic auth user name, only the password part is replaced.
+    let basic_auth_secret = args.basic_auth.then(|| {
+        let user = config.basic_auth_secret.split_once(':').map_or("admin", |(user, _)| user);
+        format!("{}:{}", user, random_secret())
+    });
+
+    RotatedKeys { jwt_secret: random_secret(), jwt_previous_secrets, basic_auth_secret }
+}
+
+// 244 random bits from two v4 UUIDs, as 64 hex characters.
+fn random_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            jwt_secret: "current".to_string(),
+            jwt_previous_secrets: vec!["older".to_string(), "oldest".to_string()],
+            basic_auth_secret: "root:password".to_string(),
+        }
+    }
+
+    #[test]
+    fn current_key_becomes_previous() {
+        let keys = rotate(&config(), &RotateKeysArgs { keep: 2, basic_auth: false });
+
+        assert_eq!(keys.jwt_secret.len(), 64);
+        assert_ne!(keys.jwt_secret, "current");
+        assert_eq!(keys.jwt_previous_secrets, vec!["current".to_string(), "older".to_string()]);
+        assert_eq!(keys.basic_auth_secret, None);
+    }
+
+    #[test]
+    fn basic_auth_keeps_user_name() {
+        let keys = rotate(&config(), &RotateKeysArgs { keep: 0, basic_auth: true });
+
+        assert!(keys.jwt_previous_secrets.is_empty());
+        let secret = keys.basic_auth_secret.unwrap();
+        assert!(secret.starts_with("root:"));
+        assert_ne!(secret, "root:password");
+    }
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use sqlx::migrate::{Migrate, MigrateError};
+use sqlx::PgPool;
+use crate::cmd::cli::MigrateCommand;
+use crate::config::app_config::DatabaseConfig;
+use crate::database::migrations::{status, MIGRATOR};
+use crate::database::postgresql::get_pool;
+
+pub async fn run(config: &DatabaseConfig, command: MigrateCommand) -> Result<(), Box<dyn Error>> {
+    let pool = get_pool(config).await?;
+
+    match command {
+        MigrateCommand::Up => {
+            MIGRATOR.run(&pool).await?;
+            println!("✅ Database migrations are up to date!");
+        }
+        MigrateCommand::Down { steps } => {
+            let reverted = revert(&pool, steps).await?;
+            if reverted.is_empty() {
+                println!("Nothing to revert.");
+            }
+            for version in reverted {
+                println!("↩️ Reverted {}", version);
+            }
+        }
+        MigrateCommand::Status => {
+            for migration in status(&pool).await? {
+                let state = if migration.applied { "applied" } else { "pending" };
+                println!("{:<16} {:<8} {}", migration.version, state, migration.description);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reverts the `steps` most recently applied migrations and returns their
+/// versions, newest first.
+pub async fn revert(pool: &PgPool, steps: usize) -> Result<Vec<i64>, MigrateError> {
+    let mut applied: Vec<i64> = {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        conn.list_applied_migrations().await?.into_iter().map(|migration| migration.version).collect()
+    };
+    applied.sort_unstable_by(|a, b| b.cmp(a));
+
+    let reverted: Vec<i64> = applied.iter().take(steps).copied().collect();
+    if reverted.is_empty() {
+        return Ok(reverted);
+    }
+    // `undo` reverts everything newer than the target version.
+    let target = applied.get(steps).copied().unwrap_or(0);
+    MIGRATOR.undo(pool, target).await?;
+
+    Ok(reverted)
+}
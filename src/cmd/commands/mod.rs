@@ -0,0 +1,25 @@
+pub mod serve;
+pub mod migrate;
+pub mod seed;
+pub mod regions;
+pub mod admin;
+pub mod keys;
+pub mod worker;
+
+use std::error::Error;
+use crate::cmd::cli::Command;
+use crate::config::app_config::AppConfig;
+use crate::internal::app::state::AppState;
+
+pub async fn run(command: Command, config: AppConfig) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Serve => serve::run(AppState::build(config).await?).await?,
+        Command::Worker => worker::run(&AppState::build(config).await?).await?,
+        Command::Migrate(command) => migrate::run(&config.database, command).await?,
+        Command::Seed => seed::run(&AppState::build(config).await?).await?,
+        Command::SyncRegions => regions::run(&AppState::build(config).await?).await?,
+        Command::CreateAdmin(args) => admin::run(&AppState::build(config).await?, args).await?,
+        Command::RotateKeys(args) => keys::run(&config.auth, args),
+    }
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use std::error::Error;
+use actix_web::rt;
+use crate::internal::app::state::AppState;
+use crate::pkg::supervisor::shutdown_signal;
+
+// Cities are fetched per stored province, so provinces go first. Ctrl-C stops
+// the city sync between provinces; running the command again resumes it.
+pub async fn run(state: &AppState) -> Result<(), Box<dyn Error>> {
+    state.usecases.province.create().await?;
+    println!("✅ Provinces synced!");
+
+    let cancel = state.supervisor.cancellation_token();
+    let supervisor = state.supervisor.clone();
+    rt::spawn(async move {
+        shutdown_signal().await;
+        supervisor.cancel();
+    });
+
+    state.usecases.city.sync(cancel.clone()).await?;
+    if cancel.is_cancelled() {
+        println!("⚠️ City sync interrupted, run sync-regions again to finish it");
+    } else {
+        println!("✅ Cities synced!");
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::error::Error;
+use actix_web::web::Json;
+use crate::database::list_query::{ListQuery, PageRequest};
+use crate::internal::app::state::AppState;
+use crate::internal::entities::subscription_type::SubscriptionType;
+use crate::internal::entities::trash::Trashed;
+use crate::pkg::dto::role_dto::CreateRoleDto;
+use crate::pkg::dto::subscription_type_dto::CreateSubscriptionTypeDto;
+
+// `register` assigns the "user" role; `create-admin` the "admin" one.
+pub const DEFAULT_ROLES: &[&str] = &["admin", "user"];
+pub const DEFAULT_SUBSCRIPTION_TYPES: &[&str] = &["Monthly", "Yearly"];
+
+pub async fn run(state: &AppState) -> Result<(), Box<dyn Error>> {
+    let created = seed(state).await?;
+    if created.is_empty() {
+        println!("Nothing to seed.");
+    }
+    for name in created {
+        println!("🌱 Created {}", name);
+    }
+    Ok(())
+}
+
+/// Creates whichever defaults are missing and describes what it created, so
+/// running it again is harmless.
+pub async fn seed(state: &AppState) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut created = vec![];
+
+    for name in DEFAULT_ROLES {
+        match state.repositories.role.get_by_name(name.to_string()).await {
+            Ok(_) => continue,
+            Err(sqlx::Error::RowNotFound) => {}
+            Err(error) => return Err(error.into()),
+        }
+        state.usecases.role.create(Json(CreateRoleDto { name: name.to_string() })).await?;
+        created.push(format!("role {}", name));
+    }
+
+    let existing = state.repositories.subscription_type.list(&ListQuery::all::<SubscriptionType>(Trashed::Without), &PageRequest::first(u32::MAX)).await?.rows;
+    for name in DEFAULT_SUBSCRIPTION_TYPES {
+        if existing.iter().any(|subscription_type| subscription_type.name == *name) {
+            continue;
+        }
+        state.usecases.subscription_type.create(Json(CreateSubscriptionTypeDto { name: name.to_string() })).await?;
+        created.push(format!("subscription type {}", name));
+    }
+
+    Ok(created)
+}
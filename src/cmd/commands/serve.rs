@@ -0,0 +1,54 @@
+use actix_web::middleware::from_fn;
+use actix_web::{rt, App, HttpServer};
+use tracing::info;
+use crate::cmd::app::{configure_app, cors};
+use crate::cmd::middlewares::metrics::metrics_middleware;
+use crate::cmd::middlewares::request_context::request_context_middleware;
+use crate::cmd::middlewares::request_id::request_id_middleware;
+use crate::internal::app::state::AppState;
+use crate::pkg::supervisor::shutdown_signal;
+
+// On SIGTERM or Ctrl-C the server stops accepting connections and gives
+// in-flight requests `shutdown_timeout` to finish, while background tasks,
+// job workers, the outbox relay and the scheduler included, are told to stop; they then get
+// `task_shutdown_timeout` to do so.
+pub async fn run(state: AppState) -> std::io::Result<()> {
+    let server_config = state.config.server.clone();
+    let profile = state.config.profile;
+    let supervisor = state.supervisor.clone();
+    if state.config.worker.enabled {
+        state.workers.start(&supervisor);
+        state.relay.start(&supervisor);
+    }
+    if state.config.scheduler.enabled {
+        state.scheduler.start(&supervisor);
+    }
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .configure(|cfg| configure_app(cfg, &state))
+            .wrap(cors(&state.config.server))
+            .wrap(from_fn(request_context_middleware))
+            .wrap(from_fn(metrics_middleware))
+            .wrap(from_fn(request_id_middleware))
+    })
+        .shutdown_timeout(server_config.shutdown_timeout.as_secs())
+        .disable_signals()
+        .bind((server_config.host.as_str(), server_config.port))?
+        .run();
+    info!(profile = profile.as_str(), host = %server_config.host, port = server_config.port, "Server started");
+
+    let handle = server.handle();
+    let tasks = supervisor.clone();
+    rt::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutting down, draining in-flight requests");
+        tasks.cancel();
+        handle.stop(true).await;
+    });
+
+    server.await?;
+    supervisor.shutdown(server_config.task_shutdown_timeout).await;
+    info!("Shutdown complete");
+    Ok(())
+}
@@ -0,0 +1,21 @@
+use std::error::Error;
+use tracing::info;
+use crate::internal::app::state::AppState;
+use crate::pkg::supervisor::shutdown_signal;
+
+// Runs job workers, the outbox relay and the scheduler without the HTTP server, so they can be scaled on their
+// own. On SIGTERM or Ctrl-C the running jobs are told to stop and get
+// `task_shutdown_timeout` to do so.
+pub async fn run(state: &AppState) -> Result<(), Box<dyn Error>> {
+    state.workers.start(&state.supervisor);
+    state.relay.start(&state.supervisor);
+    if state.config.scheduler.enabled {
+        state.scheduler.start(&state.supervisor);
+    }
+
+    shutdown_signal().await;
+    info!("Shutting down job workers");
+    state.supervisor.shutdown(state.config.server.task_shutdown_timeout).await;
+    info!("Shutdown complete");
+    Ok(())
+}
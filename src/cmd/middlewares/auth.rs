@@ -1,12 +1,13 @@
+use crate::config::app_config::AuthConfig;
 use crate::helpers::auth::decode_basic_auth_token;
 use crate::helpers::custom_error::{ErrorResponse, ResponseError};
 use crate::internal::entities::auth::Claims;
 use actix_web::body::MessageBody;
-use actix_web::dev::{Service, ServiceRequest, ServiceResponse};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
 use actix_web::error::InternalError;
 use actix_web::http::StatusCode;
 use actix_web::middleware::Next;
-use actix_web::{Error, HttpMessage};
+use actix_web::{web, Error, HttpMessage};
 use jsonwebtoken::{decode, DecodingKey, Validation};
 
 pub async fn role_middleware(
@@ -17,18 +18,10 @@ pub async fn role_middleware(
     // Extract the Authorization header
     if let Some(auth_header) = req.headers().get("Authorization") {
         if let Ok(auth_header) = auth_header.to_str() {
-            if auth_header.starts_with("Bearer ") {
-                let token = &auth_header[7..];
-                let secret = std::env::var("JWT_SECRET").unwrap_or_default();
-
-                // Decode and validate the token
-                if let Ok(token_data) = decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret(secret.as_ref()),
-                    &Validation::default(),
-                ) {
-                    let claims = token_data.claims;
+            if let Some(token) = auth_header.strip_prefix("Bearer ") {
+                let config = auth_config(&req);
 
+                if let Some(claims) = bearer_claims(&config, token) {
                     // Check if the role is allowed
                     if allowed_roles.contains(&claims.role) {
                         // Add claims to the request for further use
@@ -57,58 +50,90 @@ pub async fn role_middleware(
 }
 
 
+// Fails closed: a request without valid basic auth credentials, including one
+// with no Authorization header at all, is answered with 401.
 pub async fn super_admin_middleware(
     req: ServiceRequest,
     next: Next<impl MessageBody>,
 ) -> Result<ServiceResponse<impl MessageBody>, Error> {
-    if let Some(auth_header) = req.headers().get("Authorization") {
-        if let Ok(auth_header) = auth_header.to_str() {
-            if auth_header.starts_with("Basic ") {
-                let token = &auth_header[6..];
-                match decode_basic_auth_token(token) {
-                    Ok(credentials) => {
-                        // Retrieve the secret from environment
-                        let expected_secret = std::env::var("BASIC_AUTH_SECRET")
-                            .unwrap_or_else(|_| "".to_string());
+    let authorized = req.headers().get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Basic "))
+        .and_then(|token| basic_auth_user(&auth_config(&req), token))
+        .is_some();
+    if !authorized {
+        // The submitted credentials are never echoed back.
+        let response = ErrorResponse::new(
+            StatusCode::UNAUTHORIZED,
+            Some("Missing or invalid super admin credentials".to_string()),
+            Some("Unauthorized".to_string()),
+        ).error_response();
+        return Err(InternalError::from_response("Unauthorized", response).into());
+    }
 
-                        if format!("{}:{}", credentials.0, credentials.1) == expected_secret {
-                            // Continue with the next middleware or handler
-                            return next.call(req).await;
-                        } else {
-                            let error_response = ErrorResponse::new(
-                                StatusCode::UNAUTHORIZED,
-                                Some(format!("Invalid credentials: {:?}", credentials)),
-                                Some("Unauthorized".to_string()),
-                            );
+    next.call(req).await
+}
 
-                            let response = error_response.error_response();
-                            return Err(InternalError::from_response(
-                                "Unauthorized",
-                                response,
-                            )
-                                .into());
-                        }
-                    }
-                    Err(e) => {
-                        // Token decoding failed
-                        let error_response = ErrorResponse::new(
-                            StatusCode::UNAUTHORIZED,
-                            Some(format!("Invalid token: {}", e)),
-                            Some("Unauthorized".to_string()),
-                        );
+// Decodes and validates a bearer token, with the current key first. Empty keys
+// are skipped: anyone can sign an HS256 token with one, so a deployment without
+// a JWT secret (dev profile, or no config registered) accepts no bearer tokens.
+pub fn bearer_claims(config: &AuthConfig, token: &str) -> Option<Claims> {
+    std::iter::once(&config.jwt_secret)
+        .chain(config.jwt_previous_secrets.iter())
+        .filter(|secret| !secret.is_empty())
+        .find_map(|secret| decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(secret.as_ref()),
+            &Validation::default(),
+        ).ok())
+        .map(|token_data| token_data.claims)
+}
 
-                        let response = error_response.error_response();
-                        return Err(InternalError::from_response(
-                            "Unauthorized",
-                            response,
-                        )
-                            .into());
-                    },
-                }
-            }
-        }
+// The username of valid basic auth credentials. An empty secret matches
+// nothing.
+pub fn basic_auth_user(config: &AuthConfig, token: &str) -> Option<String> {
+    if config.basic_auth_secret.is_empty() {
+        return None;
+    }
+    let (user, password) = decode_basic_auth_token(token).ok()?;
+    (format!("{}:{}", user, password) == config.basic_auth_secret).then_some(user)
+}
+
+// Registered on the `App` at startup; falls back to empty secrets, which match
+// nothing.
+pub(crate) fn auth_config(req: &ServiceRequest) -> AuthConfig {
+    req.app_data::<web::Data<AuthConfig>>()
+        .map(|config| config.get_ref().clone())
+        .unwrap_or(AuthConfig {
+            jwt_secret: String::new(),
+            jwt_previous_secrets: vec![],
+            basic_auth_secret: String::new(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use super::*;
+
+    fn token(secret: &str) -> String {
+        let claims = Claims {
+            sub: "user".to_string(),
+            email: "user@example.com".to_string(),
+            role: "admin".to_string(),
+            exp: (Utc::now().timestamp() + 3600) as usize,
+        };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref())).unwrap()
+    }
+
+    #[test]
+    fn empty_secrets_accept_no_tokens() {
+        let config = AuthConfig { jwt_secret: String::new(), jwt_previous_secrets: vec![String::new()], basic_auth_secret: String::new() };
+        assert!(bearer_claims(&config, &token("")).is_none());
+
+        let config = AuthConfig { jwt_secret: "current".to_string(), ..config };
+        assert!(bearer_claims(&config, &token("current")).is_some());
+        assert!(bearer_claims(&config, &token("")).is_none());
     }
-    // pre-processing
-    next.call(req).await
-    // post-processing
 }
@@ -0,0 +1,25 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue, LINK};
+use actix_web::middleware::Next;
+use actix_web::Error;
+use crate::cmd::routes::api::{ApiVersion, LEGACY_SUNSET};
+
+// Marks responses served from the legacy unversioned paths as deprecated and
+// points clients at the `/api/v1` equivalent.
+pub async fn deprecation_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let successor = format!("<{}{}>; rel=\"successor-version\"", ApiVersion::V1.prefix(), req.path());
+
+    let mut res = next.call(req).await?;
+    let headers = res.headers_mut();
+    headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+    headers.insert(HeaderName::from_static("sunset"), HeaderValue::from_static(LEGACY_SUNSET));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(LINK, link);
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,244 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use actix_web::body::{to_bytes, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::error::{ErrorInternalServerError, PayloadError};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::middleware::Next;
+use actix_web::web::Bytes;
+use actix_web::{web, Error, HttpMessage, HttpResponse};
+use chrono::{SubsecRound, Utc};
+use futures_util::{stream, Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::repositories::idempotency_repository::IdempotencyRepository;
+use crate::internal::entities::idempotency::IdempotencyKey;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+pub const IDEMPOTENCY_REPLAYED_HEADER: &str = "idempotency-replayed";
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Where keys are kept and for how long, shared through app data.
+pub struct Idempotency {
+    pub repository: Arc<dyn IdempotencyRepository>,
+    pub ttl: Duration,
+    // How long a claim holds off retries if its request dies without
+    // releasing it.
+    pub lock: Duration,
+    // The body limits the handlers apply, which the middleware reads up to.
+    pub json_limit: usize,
+    pub multipart_limit: usize,
+}
+
+// Makes a create safe to retry: the first request with a given `Idempotency-Key`
+// runs and has its response recorded, retries with the same body get that
+// response back instead of running again, and a retry with a different body is
+// refused. Requests without the header are left alone.
+pub async fn idempotency_middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key.to_string(),
+        _ => return Ok(reject(req, StatusCode::BAD_REQUEST, "Idempotency-Key must be 1 to 255 visible characters")),
+    };
+    let idempotency = req.app_data::<web::Data<Idempotency>>()
+        .expect("idempotency is registered as app data")
+        .clone();
+
+    // The handler still needs the body, so it's read whole and put back. It
+    // would refuse one over its limit anyway, so reading stops there.
+    let limit = if is_multipart(&req) { idempotency.multipart_limit } else { idempotency.json_limit };
+    let Some(body) = read_payload(req.take_payload(), limit).await? else {
+        return Ok(reject(req, StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large"));
+    };
+    let fingerprint = fingerprint(&req, &body);
+    req.set_payload(Payload::from(Box::pin(stream::once(async move { Ok(body) }))
+        as Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>));
+
+    // Whole microseconds, as Postgres keeps them, since the claim is later
+    // matched on `created_at`.
+    let now = Utc::now().trunc_subsecs(6);
+    let claim = IdempotencyKey {
+        key,
+        endpoint: format!("{} {}", req.method(), req.path()),
+        fingerprint,
+        status_code: None,
+        content_type: None,
+        response_body: None,
+        created_at: now,
+        expires_at: now + chrono::Duration::from_std(idempotency.ttl).unwrap_or_default(),
+        locked_until: now + chrono::Duration::from_std(idempotency.lock).unwrap_or_default(),
+    };
+
+    let existing = match idempotency.repository.claim(&claim).await {
+        Ok(existing) => existing,
+        // The first request failed and released the key between our insert and
+        // lookup; it's as good as still running.
+        Err(sqlx::Error::RowNotFound) => return Ok(reject(req, StatusCode::CONFLICT, "A request with this Idempotency-Key is still being processed")),
+        Err(err) => return Ok(reject_with(req, ErrorResponse::from(err))),
+    };
+    if let Some(existing) = existing {
+        if existing.fingerprint != claim.fingerprint {
+            return Ok(reject(req, StatusCode::UNPROCESSABLE_ENTITY, "Idempotency-Key was already used with a different request body"));
+        }
+        if !existing.is_completed() {
+            return Ok(reject(req, StatusCode::CONFLICT, "A request with this Idempotency-Key is still being processed"));
+        }
+        return Ok(replay(req, existing));
+    }
+
+    // Released if this future is dropped, as it is when the client hangs up.
+    let claim = Claim { idempotency, key: claim, held: true };
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        Err(err) => {
+            claim.release().await;
+            return Err(err);
+        }
+    };
+
+    // Server errors aren't recorded, so the retry the client will make gets a
+    // fresh attempt rather than the same failure.
+    let status = res.status();
+    if status.is_server_error() {
+        claim.release().await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let body = match to_bytes(body).await {
+        Ok(body) => body,
+        Err(_) => {
+            claim.release().await;
+            return Err(ErrorInternalServerError("Failed to read the response body"));
+        }
+    };
+    let content_type = res.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    match claim.idempotency.repository.complete(&claim.key, status.as_u16() as i32, content_type, body.to_vec()).await {
+        Ok(()) => claim.keep(),
+        Err(err) => {
+            warn!(error = %err, "Failed to record idempotent response");
+            claim.release().await;
+        }
+    }
+
+    Ok(ServiceResponse::new(req, res.set_body(BoxBody::new(body))))
+}
+
+// `None` once the body passes `limit`.
+async fn read_payload(mut payload: Payload, limit: usize) -> Result<Option<Bytes>, Error> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if body.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(body.freeze()))
+}
+
+fn is_multipart(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/"))
+}
+
+// Hex SHA-256 of the body. Multipart bodies are hashed without their boundary,
+// which clients pick afresh for every attempt.
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> String {
+    let boundary = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| value.starts_with("multipart/"))
+        .and_then(|value| value.split(';').find_map(|param| param.trim().strip_prefix("boundary=")))
+        .map(|boundary| boundary.trim_matches('"'));
+
+    let mut hasher = Sha256::new();
+    match boundary {
+        Some(boundary) if !boundary.is_empty() => {
+            let mut rest = body;
+            while let Some(at) = rest.windows(boundary.len()).position(|window| window == boundary.as_bytes()) {
+                hasher.update(&rest[..at]);
+                rest = &rest[at + boundary.len()..];
+            }
+            hasher.update(rest);
+        }
+        _ => hasher.update(body),
+    }
+    hex::encode(hasher.finalize())
+}
+
+fn replay(req: ServiceRequest, key: IdempotencyKey) -> ServiceResponse<BoxBody> {
+    let status = key.status_code
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut res = HttpResponse::build(status);
+    if let Some(content_type) = key.content_type {
+        res.insert_header((header::CONTENT_TYPE, content_type));
+    }
+    res.insert_header((HeaderName::from_static(IDEMPOTENCY_REPLAYED_HEADER), HeaderValue::from_static("true")));
+
+    req.into_response(res.body(key.response_body.unwrap_or_default()))
+}
+
+// A key this request claimed, released on drop unless it was kept.
+struct Claim {
+    idempotency: web::Data<Idempotency>,
+    key: IdempotencyKey,
+    held: bool,
+}
+
+impl Claim {
+    fn keep(mut self) {
+        self.held = false;
+    }
+
+    async fn release(mut self) {
+        self.held = false;
+        release(&self.idempotency.repository, &self.key).await;
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if !self.held {
+            return;
+        }
+        // Past a runtime shutdown, the lock running out frees the key instead.
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let repository = self.idempotency.repository.clone();
+            let key = self.key.clone();
+            runtime.spawn(async move { release(&repository, &key).await });
+        }
+    }
+}
+
+async fn release(repository: &Arc<dyn IdempotencyRepository>, key: &IdempotencyKey) {
+    if let Err(err) = repository.release(key).await {
+        warn!(error = %err, "Failed to release idempotency key");
+    }
+}
+
+fn reject(req: ServiceRequest, status: StatusCode, message: &str) -> ServiceResponse<BoxBody> {
+    reject_with(req, ErrorResponse::new(status, Some(message.to_string()), Some("FAILED".to_string())))
+}
+
+fn reject_with(req: ServiceRequest, err: ErrorResponse) -> ServiceResponse<BoxBody> {
+    let res = err.respond(req.request());
+    req.into_response(res)
+}
@@ -0,0 +1,34 @@
+use std::time::Instant;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use crate::pkg::metrics::Metrics;
+
+// Counts and times every request, labelled by the route pattern rather than
+// the raw path so ids don't explode the label set.
+pub async fn metrics_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+    let started = Instant::now();
+
+    let res = next.call(req).await;
+
+    if let Some(metrics) = metrics {
+        // Middleware further in rejects with an error, which is rendered later.
+        // Only peek at its status: `InternalError` hands out its response once.
+        let status = match &res {
+            Ok(res) => res.status(),
+            Err(err) => err.as_response_error().status_code(),
+        };
+        let labels = [method.as_str(), route.as_str(), status.as_str()];
+        metrics.http_requests.with_label_values(&labels).inc();
+        metrics.http_request_duration.with_label_values(&labels).observe(started.elapsed().as_secs_f64());
+    }
+
+    res
+}
@@ -1 +1,6 @@
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod deprecation;
+pub mod idempotency;
+pub mod metrics;
+pub mod request_id;
+pub mod request_context;
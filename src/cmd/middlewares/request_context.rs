@@ -0,0 +1,52 @@
+use std::net::IpAddr;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::AUTHORIZATION;
+use actix_web::middleware::Next;
+use actix_web::{web, Error};
+use crate::cmd::middlewares::auth::{auth_config, basic_auth_user, bearer_claims};
+use crate::helpers::request_context::{RequestContext, CURRENT_REQUEST_CONTEXT};
+
+/// The peers allowed to report the client address in forwarding headers.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(pub Vec<IpAddr>);
+
+// Serves the request with the caller's identity and address in scope, for the
+// audit log. Doesn't reject anything: routes that need credentials check them
+// themselves, and invalid ones just leave the caller anonymous. The address is
+// the connecting peer's; `Forwarded` or `X-Forwarded-For` only count when that
+// peer is one of the trusted proxies, since anyone else can send them.
+pub async fn request_context_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let config = auth_config(&req);
+    let authorization = req.headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+
+    let mut context = RequestContext {
+        ip: client_ip(&req),
+        ..RequestContext::default()
+    };
+    if let Some(claims) = authorization.strip_prefix("Bearer ").and_then(|token| bearer_claims(&config, token)) {
+        context.actor_id = Some(claims.sub);
+        context.actor = Some(claims.email);
+    } else if let Some(user) = authorization.strip_prefix("Basic ").and_then(|token| basic_auth_user(&config, token)) {
+        context.actor = Some(user);
+    }
+
+    CURRENT_REQUEST_CONTEXT.scope(context, next.call(req)).await
+}
+
+fn client_ip(req: &ServiceRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    let trusted = req.app_data::<web::Data<TrustedProxies>>()
+        .is_some_and(|proxies| proxies.0.contains(&peer));
+    if trusted {
+        req.connection_info().realip_remote_addr().map(|ip| ip.to_string())
+    } else {
+        Some(peer.to_string())
+    }
+}
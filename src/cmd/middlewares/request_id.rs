@@ -0,0 +1,83 @@
+use std::time::Instant;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::middleware::Next;
+use actix_web::error::InternalError;
+use actix_web::Error;
+use tracing::{error, info, info_span, warn, Instrument};
+use uuid::Uuid;
+use crate::cmd::routes::health_router::HEALTH_PATHS;
+use crate::cmd::routes::metrics_router::METRICS_PATH;
+use crate::helpers::custom_response::{CURRENT_REQUEST_ID, REQUEST_ID_HEADER};
+
+// Longer ids from clients are replaced rather than trusted.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+// Takes the caller's `X-Request-Id` or makes one up, and serves the request
+// inside a span carrying it, so every log line and error body names it. Also
+// writes the access log, which this replaces actix's `Logger` for.
+pub async fn request_id_middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let request_id = req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid(value))
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let header = HeaderValue::from_str(&request_id).expect("request id is visible ASCII");
+    req.headers_mut().insert(HeaderName::from_static("x-request-id"), header.clone());
+
+    let span = info_span!(
+        "http_request",
+        request_id = %request_id,
+        method = %req.method(),
+        route = %req.match_pattern().unwrap_or_else(|| "unmatched".to_string()),
+    );
+    let quiet = HEALTH_PATHS.contains(&req.path()) || req.path() == METRICS_PATH;
+    let path = req.path().to_string();
+    let started = Instant::now();
+
+    // Errors are rendered here rather than by actix, so their bodies are built
+    // while the request id is still in scope and they get the header too.
+    let res = CURRENT_REQUEST_ID
+        .scope(request_id, async move {
+            match next.call(req).await {
+                Ok(mut res) => {
+                    res.headers_mut().insert(HeaderName::from_static("x-request-id"), header);
+                    Ok(res)
+                }
+                Err(err) => {
+                    let mut response = err.error_response();
+                    response.headers_mut().insert(HeaderName::from_static("x-request-id"), header);
+                    Err(Error::from(InternalError::from_response(err, response)))
+                }
+            }
+        })
+        .instrument(span.clone())
+        .await;
+
+    if !quiet {
+        let status = match &res {
+            Ok(res) => res.status(),
+            Err(err) => err.as_response_error().status_code(),
+        }
+        .as_u16();
+        let latency_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| match status {
+            500.. => error!(status, latency_ms, path, "Request failed"),
+            400.. => warn!(status, latency_ms, path, "Request rejected"),
+            _ => info!(status, latency_ms, path, "Request served"),
+        });
+    }
+
+    res
+}
+
+fn is_valid(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LEN
+        && request_id.bytes().all(|byte| byte.is_ascii_graphic())
+}
@@ -1,2 +1,5 @@
 pub mod routes;
-pub mod middlewares;
\ No newline at end of file
+pub mod middlewares;
+pub mod app;
+pub mod cli;
+pub mod commands;
\ No newline at end of file
@@ -0,0 +1,136 @@
+use actix_web::http::Method;
+use actix_web::middleware::from_fn;
+use actix_web::{guard, web, Route};
+use crate::cmd::middlewares::deprecation::deprecation_middleware;
+use crate::cmd::routes::audit_log_router::audit_log_router;
+use crate::cmd::routes::auth::auth_router;
+use crate::cmd::routes::city_router::city_router;
+use crate::cmd::routes::job_router::job_router;
+use crate::cmd::routes::province_router::province_router;
+use crate::cmd::routes::role_router::role_router;
+use crate::cmd::routes::schedule_router::schedule_router;
+use crate::cmd::routes::school_router::school_router;
+use crate::cmd::routes::subscription_router::subscription_router;
+use crate::cmd::routes::subscription_type_router::subscription_type_router;
+use crate::cmd::routes::user_router::user_router;
+use crate::cmd::routes::webhook_router::webhook_router;
+use crate::internal::app::state::AppState;
+use crate::internal::handlers::audit_log_handler::AuditLogHandlerImpl;
+use crate::internal::handlers::auth_handler::AuthHandlerImpl;
+use crate::internal::handlers::city_handler::CityHandlerImpl;
+use crate::internal::handlers::job_handler::JobHandlerImpl;
+use crate::internal::handlers::province_handler::ProvinceHandlerImpl;
+use crate::internal::handlers::role_handler::RoleHandlerImpl;
+use crate::internal::handlers::schedule_handler::ScheduleHandlerImpl;
+use crate::internal::handlers::school_handler::SchoolHandlerImpl;
+use crate::internal::handlers::subscription_handler::SubscriptionHandlerImpl;
+use crate::internal::handlers::subscription_type_handler::{subscription_type_handler_get_v2, subscription_type_handler_list_v2, SubscriptionTypeHandlerImpl};
+use crate::internal::handlers::user_handler::UserHandlerImpl;
+use crate::internal::handlers::webhook_handler::WebhookHandlerImpl;
+
+// Date after which the unversioned paths are removed.
+pub const LEGACY_SUNSET: &str = "Thu, 01 Apr 2027 00:00:00 GMT";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiVersion {
+    V1,
+    V2,
+}
+
+impl ApiVersion {
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "/api/v1",
+            ApiVersion::V2 => "/api/v2",
+        }
+    }
+}
+
+/// A handler registered for a single route of a newer API version. Requests to
+/// that version that don't match an override fall through to the v1 routes.
+#[derive(Clone)]
+pub struct RouteOverride {
+    pub version: ApiVersion,
+    pub method: Method,
+    pub path: &'static str,
+    pub route: fn() -> Route,
+}
+
+impl RouteOverride {
+    // The method guard sits on the resource rather than the route, so other
+    // methods on the same path don't match here and reach v1 instead of a 405.
+    fn register(&self, cfg: &mut web::ServiceConfig) {
+        cfg.service(
+            web::resource(self.path)
+                .guard(guard::Method(self.method.clone()))
+                .route((self.route)()),
+        );
+    }
+}
+
+// Add entries here to serve a route differently under a newer version.
+pub const ROUTE_OVERRIDES: &[RouteOverride] = &[
+    // Subscription types only nest their subscriptions when asked to.
+    RouteOverride {
+        version: ApiVersion::V2,
+        method: Method::GET,
+        path: "/subscription_types",
+        route: || web::get().to(subscription_type_handler_list_v2),
+    },
+    RouteOverride {
+        version: ApiVersion::V2,
+        method: Method::GET,
+        path: "/subscription_types/{id}",
+        route: || web::get().to(subscription_type_handler_get_v2),
+    },
+];
+
+pub fn v1_routes(cfg: &mut web::ServiceConfig, state: &AppState) {
+    let usecases = &state.usecases;
+    cfg.configure(|cfg| subscription_router(cfg, SubscriptionHandlerImpl::new(usecases.subscription.clone())))
+        .configure(|cfg| subscription_type_router(cfg, SubscriptionTypeHandlerImpl::new(usecases.subscription_type.clone())))
+        .configure(|cfg| role_router(cfg, RoleHandlerImpl::new(usecases.role.clone())))
+        .configure(|cfg| province_router(cfg, ProvinceHandlerImpl::new(usecases.province.clone())))
+        .configure(|cfg| city_router(cfg, CityHandlerImpl::new(usecases.city.clone())))
+        .configure(|cfg| school_router(cfg, SchoolHandlerImpl::new(usecases.school.clone())))
+        .configure(|cfg| user_router(cfg, UserHandlerImpl::new(usecases.user.clone())))
+        .configure(|cfg| auth_router(cfg, AuthHandlerImpl::new(usecases.auth.clone())))
+        .configure(|cfg| job_router(cfg, JobHandlerImpl::new(usecases.job.clone())))
+        .configure(|cfg| schedule_router(cfg, ScheduleHandlerImpl::new(usecases.schedule.clone())))
+        .configure(|cfg| webhook_router(cfg, WebhookHandlerImpl::new(usecases.webhook.clone())))
+        .configure(|cfg| audit_log_router(cfg, AuditLogHandlerImpl::new(usecases.audit_log.clone())));
+}
+
+pub fn api_router(cfg: &mut web::ServiceConfig, state: &AppState) {
+    api_router_with(cfg, state, ROUTE_OVERRIDES)
+}
+
+/// Mounts every API version under its prefix plus the deprecated unversioned
+/// paths. A version other than v1 is only mounted once it has an override.
+pub fn api_router_with(cfg: &mut web::ServiceConfig, state: &AppState, overrides: &[RouteOverride]) {
+    for version in ApiVersion::ALL {
+        let version_overrides: Vec<&RouteOverride> = overrides
+            .iter()
+            .filter(|route| route.version == version)
+            .collect();
+
+        if version != ApiVersion::V1 && version_overrides.is_empty() {
+            continue;
+        }
+
+        let mut scope = web::scope(version.prefix());
+        // Overrides are registered first so they take precedence over v1.
+        for route in version_overrides {
+            scope = scope.configure(|cfg| route.register(cfg));
+        }
+        cfg.service(scope.configure(|cfg| v1_routes(cfg, state)));
+    }
+
+    cfg.service(
+        web::scope("")
+            .wrap(from_fn(deprecation_middleware))
+            .configure(|cfg| v1_routes(cfg, state)),
+    );
+}
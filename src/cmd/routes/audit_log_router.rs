@@ -0,0 +1,13 @@
+use actix_web::middleware::from_fn;
+use actix_web::web;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::audit_log_handler::{audit_log_handler_list, AuditLogHandlerImpl};
+
+pub fn audit_log_router(conf: &mut web::ServiceConfig, handler: AuditLogHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .service(
+            web::scope("/audit-logs")
+                .wrap(from_fn(super_admin_middleware))
+                .route("", web::get().to(audit_log_handler_list))
+        );
+}
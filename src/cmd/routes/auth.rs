@@ -1,10 +1,12 @@
+use actix_web::middleware::from_fn;
 use actix_web::web;
+use crate::cmd::middlewares::idempotency::idempotency_middleware;
 use crate::internal::handlers::auth_handler::{register, AuthHandlerImpl};
 
 pub fn auth_router(conf: &mut web::ServiceConfig, handler: AuthHandlerImpl) {
     conf.app_data(web::Data::new(handler))
         .service(
             web::scope("/auth")
-                .route("/register", web::post().to(register))
+                .route("/register", web::post().to(register).wrap(from_fn(idempotency_middleware)))
         );
 }
\ No newline at end of file
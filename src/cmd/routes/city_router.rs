@@ -1,10 +1,13 @@
+use actix_web::middleware::from_fn;
 use actix_web::web;
-use crate::internal::handlers::city_handler::{city_handler_create, city_handler_list, CityHandlerImpl};
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::city_handler::{city_handler_create, city_handler_get, city_handler_list, CityHandlerImpl};
 pub fn city_router(conf: &mut web::ServiceConfig, handler: CityHandlerImpl) {
     conf.app_data(web::Data::new(handler))
         .service(
             web::scope("/cities")
                 .route("", web::get().to(city_handler_list))
-                .route("", web::post().to(city_handler_create))
+                .route("", web::post().to(city_handler_create).wrap(from_fn(super_admin_middleware)))
+                .route("/{id}", web::get().to(city_handler_get))
         );
 }
\ No newline at end of file
@@ -0,0 +1,12 @@
+use actix_web::web;
+use crate::internal::handlers::health_handler::{health_handler_live, health_handler_ready, health_handler_version, HealthHandlerImpl};
+
+// Probed constantly by the orchestrator, so they skip auth and the access log.
+pub const HEALTH_PATHS: [&str; 3] = ["/health/live", "/health/ready", "/version"];
+
+pub fn health_router(conf: &mut web::ServiceConfig, handler: HealthHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .route("/health/live", web::get().to(health_handler_live))
+        .route("/health/ready", web::get().to(health_handler_ready))
+        .route("/version", web::get().to(health_handler_version));
+}
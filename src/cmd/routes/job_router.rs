@@ -0,0 +1,16 @@
+use actix_web::middleware::from_fn;
+use actix_web::web;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::job_handler::{job_handler_cancel, job_handler_get, job_handler_list, job_handler_retry, JobHandlerImpl};
+
+pub fn job_router(conf: &mut web::ServiceConfig, handler: JobHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .service(
+            web::scope("/admin/jobs")
+                .wrap(from_fn(super_admin_middleware))
+                .route("", web::get().to(job_handler_list))
+                .route("/{id}", web::get().to(job_handler_get))
+                .route("/{id}/retry", web::post().to(job_handler_retry))
+                .route("/{id}/cancel", web::post().to(job_handler_cancel))
+        );
+}
@@ -0,0 +1,9 @@
+use actix_web::web;
+use crate::internal::handlers::metrics_handler::{metrics_handler_export, MetricsHandlerImpl};
+
+pub const METRICS_PATH: &str = "/metrics";
+
+pub fn metrics_router(conf: &mut web::ServiceConfig, handler: MetricsHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .route(METRICS_PATH, web::get().to(metrics_handler_export));
+}
@@ -5,4 +5,11 @@ pub mod province_router;
 pub mod city_router;
 pub mod school_router;
 pub mod user_router;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod api;
+pub mod health_router;
+pub mod metrics_router;
+pub mod job_router;
+pub mod schedule_router;
+pub mod webhook_router;
+pub mod audit_log_router;
@@ -1,11 +1,14 @@
+use actix_web::middleware::from_fn;
 use actix_web::web;
-use crate::internal::handlers::province_handler::{province_handler_create, province_handler_list, ProvinceHandlerImpl};
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::province_handler::{province_handler_create, province_handler_get, province_handler_list, ProvinceHandlerImpl};
 
 pub fn province_router(conf: &mut web::ServiceConfig, handler: ProvinceHandlerImpl) {
     conf.app_data(web::Data::new(handler))
         .service(
             web::scope("/provinces")
                 .route("", web::get().to(province_handler_list))
-                .route("", web::post().to(province_handler_create))
+                .route("", web::post().to(province_handler_create).wrap(from_fn(super_admin_middleware)))
+                .route("/{id}", web::get().to(province_handler_get))
         );
 }
\ No newline at end of file
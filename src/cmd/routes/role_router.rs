@@ -1,5 +1,7 @@
+use actix_web::middleware::from_fn;
+use crate::cmd::middlewares::auth::super_admin_middleware;
 use actix_web::web;
-use crate::internal::handlers::role_handler::{role_handler_create, role_handler_delete, role_handler_list, role_handler_update, RoleHandlerImpl};
+use crate::internal::handlers::role_handler::{role_handler_create, role_handler_get, role_handler_delete, role_handler_list, role_handler_patch, role_handler_purge, role_handler_restore, role_handler_update, RoleHandlerImpl};
 
 pub fn role_router(conf: &mut web::ServiceConfig, handler: RoleHandlerImpl) {
     conf.app_data(web::Data::new(handler))
@@ -7,7 +9,15 @@ pub fn role_router(conf: &mut web::ServiceConfig, handler: RoleHandlerImpl) {
             web::scope("/roles")
                 .route("", web::get().to(role_handler_list))
                 .route("", web::post().to(role_handler_create))
+                .route("/{id}", web::get().to(role_handler_get))
                 .route("/{id}", web::put().to(role_handler_update))
+                .route("/{id}", web::patch().to(role_handler_patch))
                 .route("/{id}", web::delete().to(role_handler_delete))
+                .route("/{id}/restore", web::post().to(role_handler_restore))
+                .service(
+                    web::resource("/{id}/purge")
+                        .wrap(from_fn(super_admin_middleware))
+                        .route(web::delete().to(role_handler_purge))
+                )
         );
 }
\ No newline at end of file
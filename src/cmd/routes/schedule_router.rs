@@ -0,0 +1,13 @@
+use actix_web::middleware::from_fn;
+use actix_web::web;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::schedule_handler::{schedule_handler_list, ScheduleHandlerImpl};
+
+pub fn schedule_router(conf: &mut web::ServiceConfig, handler: ScheduleHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .service(
+            web::scope("/admin/schedules")
+                .wrap(from_fn(super_admin_middleware))
+                .route("", web::get().to(schedule_handler_list))
+        );
+}
@@ -1,4 +1,7 @@
-use crate::internal::handlers::school_handler::{school_handler_create, school_handler_delete, school_handler_list, school_handler_update, SchoolHandlerImpl};
+use actix_web::middleware::from_fn;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::cmd::middlewares::idempotency::idempotency_middleware;
+use crate::internal::handlers::school_handler::{school_handler_create, school_handler_get, school_handler_delete, school_handler_list, school_handler_patch, school_handler_purge, school_handler_restore, school_handler_update, SchoolHandlerImpl};
 use actix_web::web;
 
 pub fn school_router(conf: &mut web::ServiceConfig, handler: SchoolHandlerImpl) {
@@ -6,8 +9,16 @@ pub fn school_router(conf: &mut web::ServiceConfig, handler: SchoolHandlerImpl)
         .service(
             web::scope("/schools")
                 .route("", web::get().to(school_handler_list))
-                .route("", web::post().to(school_handler_create))
+                .route("", web::post().to(school_handler_create).wrap(from_fn(idempotency_middleware)))
+                .route("/{id}", web::get().to(school_handler_get))
                 .route("/{id}", web::put().to(school_handler_update))
+                .route("/{id}", web::patch().to(school_handler_patch))
                 .route("/{id}", web::delete().to(school_handler_delete))
+                .route("/{id}/restore", web::post().to(school_handler_restore))
+                .service(
+                    web::resource("/{id}/purge")
+                        .wrap(from_fn(super_admin_middleware))
+                        .route(web::delete().to(school_handler_purge))
+                )
         );
 }
\ No newline at end of file
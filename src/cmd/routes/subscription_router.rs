@@ -1,4 +1,7 @@
-use crate::internal::handlers::subscription_handler::{subscription_handler_create, subscription_handler_delete, subscription_handler_list, subscription_handler_update, SubscriptionHandlerImpl};
+use actix_web::middleware::from_fn;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::cmd::middlewares::idempotency::idempotency_middleware;
+use crate::internal::handlers::subscription_handler::{subscription_handler_create, subscription_handler_get, subscription_handler_delete, subscription_handler_list, subscription_handler_patch, subscription_handler_purge, subscription_handler_restore, subscription_handler_update, SubscriptionHandlerImpl};
 use actix_web::web;
 
 pub fn subscription_router(conf: &mut web::ServiceConfig, handler: SubscriptionHandlerImpl) {
@@ -6,8 +9,16 @@ pub fn subscription_router(conf: &mut web::ServiceConfig, handler: SubscriptionH
         .service(
             web::scope("/subscriptions")
                 .route("", web::get().to(subscription_handler_list))
-                .route("", web::post().to(subscription_handler_create))
+                .route("", web::post().to(subscription_handler_create).wrap(from_fn(idempotency_middleware)))
+                .route("/{id}", web::get().to(subscription_handler_get))
                 .route("/{id}", web::put().to(subscription_handler_update))
+                .route("/{id}", web::patch().to(subscription_handler_patch))
                 .route("/{id}", web::delete().to(subscription_handler_delete))
+                .route("/{id}/restore", web::post().to(subscription_handler_restore))
+                .service(
+                    web::resource("/{id}/purge")
+                        .wrap(from_fn(super_admin_middleware))
+                        .route(web::delete().to(subscription_handler_purge))
+                )
         );
 }
\ No newline at end of file
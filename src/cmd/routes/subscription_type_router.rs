@@ -1,4 +1,6 @@
-use crate::internal::handlers::subscription_type_handler::{subscription_type_handler_create, subscription_type_handler_delete, subscription_type_handler_list, subscription_type_handler_update, SubscriptionTypeHandlerImpl};
+use actix_web::middleware::from_fn;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::subscription_type_handler::{subscription_type_handler_create, subscription_type_handler_get, subscription_type_handler_delete, subscription_type_handler_list, subscription_type_handler_patch, subscription_type_handler_purge, subscription_type_handler_restore, subscription_type_handler_update, SubscriptionTypeHandlerImpl};
 use actix_web::web;
 
 pub fn subscription_type_router(conf: &mut web::ServiceConfig, handler: SubscriptionTypeHandlerImpl) {
@@ -7,7 +9,15 @@ pub fn subscription_type_router(conf: &mut web::ServiceConfig, handler: Subscrip
             web::scope("/subscription_types")
                 .route("", web::get().to(subscription_type_handler_list))
                 .route("", web::post().to(subscription_type_handler_create))
+                .route("/{id}", web::get().to(subscription_type_handler_get))
                 .route("/{id}", web::put().to(subscription_type_handler_update))
+                .route("/{id}", web::patch().to(subscription_type_handler_patch))
                 .route("/{id}", web::delete().to(subscription_type_handler_delete))
+                .route("/{id}/restore", web::post().to(subscription_type_handler_restore))
+                .service(
+                    web::resource("/{id}/purge")
+                        .wrap(from_fn(super_admin_middleware))
+                        .route(web::delete().to(subscription_type_handler_purge))
+                )
         );
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use actix_web::middleware::from_fn;
 use actix_web::web;
 use crate::cmd::middlewares::auth::{super_admin_middleware};
-use crate::internal::handlers::user_handler::{user_handler_create, user_handler_delete, user_handler_list, user_handler_update, UserHandlerImpl};
+use crate::internal::handlers::user_handler::{user_handler_create, user_handler_get, user_handler_delete, user_handler_list, user_handler_patch, user_handler_purge, user_handler_restore, user_handler_update, UserHandlerImpl};
 
 pub fn user_router(conf: &mut web::ServiceConfig, handler: UserHandlerImpl) {
     conf.app_data(web::Data::new(handler))
@@ -11,7 +11,11 @@ pub fn user_router(conf: &mut web::ServiceConfig, handler: UserHandlerImpl) {
                 // .wrap(from_fn(|req, next| role_middleware(req, next, vec!["admin".to_string()])))
                 .route("", web::get().to(user_handler_list))
                 .route("", web::post().to(user_handler_create))
+                .route("/{id}", web::get().to(user_handler_get))
                 .route("/{id}", web::put().to(user_handler_update))
+                .route("/{id}", web::patch().to(user_handler_patch))
                 .route("/{id}", web::delete().to(user_handler_delete))
+                .route("/{id}/restore", web::post().to(user_handler_restore))
+                .route("/{id}/purge", web::delete().to(user_handler_purge))
         );
 }
\ No newline at end of file
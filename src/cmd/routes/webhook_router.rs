@@ -0,0 +1,19 @@
+use actix_web::middleware::from_fn;
+use actix_web::web;
+use crate::cmd::middlewares::auth::super_admin_middleware;
+use crate::internal::handlers::webhook_handler::{webhook_handler_create, webhook_handler_delete, webhook_handler_deliveries, webhook_handler_get, webhook_handler_list, webhook_handler_test, webhook_handler_update, WebhookHandlerImpl};
+
+pub fn webhook_router(conf: &mut web::ServiceConfig, handler: WebhookHandlerImpl) {
+    conf.app_data(web::Data::new(handler))
+        .service(
+            web::scope("/admin/webhooks")
+                .wrap(from_fn(super_admin_middleware))
+                .route("", web::get().to(webhook_handler_list))
+                .route("", web::post().to(webhook_handler_create))
+                .route("/{id}", web::get().to(webhook_handler_get))
+                .route("/{id}", web::put().to(webhook_handler_update))
+                .route("/{id}", web::delete().to(webhook_handler_delete))
+                .route("/{id}/deliveries", web::get().to(webhook_handler_deliveries))
+                .route("/{id}/test", web::post().to(webhook_handler_test))
+        );
+}
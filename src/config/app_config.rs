@@ -0,0 +1,473 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use cron::Schedule;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Profile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Profile::Dev => "dev",
+            Profile::Staging => "staging",
+            Profile::Prod => "prod",
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "dev" => Ok(Profile::Dev),
+            "staging" => Ok(Profile::Staging),
+            "prod" => Ok(Profile::Prod),
+            other => Err(format!("unknown profile \"{}\", expected dev, staging or prod", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Json,
+    Pretty,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(LogFormat::Json),
+            "pretty" => Ok(LogFormat::Pretty),
+            other => Err(format!("unknown log format \"{}\", expected json or pretty", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub cors_allowed_origins: Vec<String>,
+    // How long in-flight requests get to finish once shutdown begins.
+    pub shutdown_timeout: Duration,
+    // How long background tasks get to stop after the server has.
+    pub task_shutdown_timeout: Duration,
+    // How long a recorded response is replayed for retries with the same
+    // Idempotency-Key.
+    pub idempotency_ttl: Duration,
+    // How long an unfinished request keeps its Idempotency-Key from retries,
+    // in case it died without letting go of it.
+    pub idempotency_lock: Duration,
+    // The largest JSON and multipart request bodies accepted, in bytes.
+    pub json_limit: usize,
+    pub multipart_limit: usize,
+    // Peers whose `Forwarded`/`X-Forwarded-For` headers are believed. Anyone
+    // else is recorded by the address they connected from.
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    pub run_migrations: bool,
+    pub check_schema: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub jwt_secret: String,
+    // Still accepted when verifying tokens, so sessions survive a key rotation.
+    pub jwt_previous_secrets: Vec<String>,
+    pub basic_auth_secret: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub public_url: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    // An `EnvFilter` directive such as `info,sqlx::query=debug`.
+    pub level: String,
+    // Spans are exported over OTLP/HTTP when set, e.g. `http://localhost:4318`.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    // Whether `serve` also runs job workers; the `worker` command always does.
+    pub enabled: bool,
+    pub concurrency: usize,
+    // How long an idle worker waits before looking for due jobs again.
+    pub poll_interval: Duration,
+    // A running job whose lock is older than this is assumed lost and retried.
+    pub lock_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    // Whether `serve` and `worker` take part in leader election.
+    pub enabled: bool,
+    // Cron expressions with a seconds field, in UTC; `None` turns a schedule off.
+    pub sync_regions: Option<Schedule>,
+    pub purge_deleted: Option<Schedule>,
+    pub clean_uploads: Option<Schedule>,
+    pub check_subscription_expiry: Option<Schedule>,
+    // How long soft-deleted rows are kept before they're purged.
+    pub retention: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub profile: Profile,
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub s3: S3Config,
+    pub log: LogConfig,
+    pub worker: WorkerConfig,
+    pub scheduler: SchedulerConfig,
+}
+
+// Every key the application reads, with the environment variable that overrides it.
+const ENV_KEYS: &[(&str, &str)] = &[
+    ("server.host", "SERVER_HOST"),
+    ("server.port", "SERVER_PORT"),
+    ("server.cors_allowed_origins", "CORS_ALLOWED_ORIGINS"),
+    ("server.shutdown_timeout_secs", "SERVER_SHUTDOWN_TIMEOUT_SECS"),
+    ("server.task_shutdown_timeout_secs", "SERVER_TASK_SHUTDOWN_TIMEOUT_SECS"),
+    ("server.idempotency_ttl_secs", "SERVER_IDEMPOTENCY_TTL_SECS"),
+    ("server.idempotency_lock_secs", "SERVER_IDEMPOTENCY_LOCK_SECS"),
+    ("server.json_limit_bytes", "SERVER_JSON_LIMIT_BYTES"),
+    ("server.multipart_limit_bytes", "SERVER_MULTIPART_LIMIT_BYTES"),
+    ("server.trusted_proxies", "SERVER_TRUSTED_PROXIES"),
+    ("database.url", "DATABASE_URL"),
+    ("database.max_connections", "DATABASE_MAX_CONNECTIONS"),
+    ("database.min_connections", "DATABASE_MIN_CONNECTIONS"),
+    ("database.acquire_timeout_secs", "DATABASE_ACQUIRE_TIMEOUT_SECS"),
+    ("database.idle_timeout_secs", "DATABASE_IDLE_TIMEOUT_SECS"),
+    ("database.run_migrations", "DATABASE_RUN_MIGRATIONS"),
+    ("database.check_schema", "DATABASE_CHECK_SCHEMA"),
+    ("auth.jwt_secret", "JWT_SECRET"),
+    ("auth.jwt_previous_secrets", "JWT_PREVIOUS_SECRETS"),
+    ("auth.basic_auth_secret", "BASIC_AUTH_SECRET"),
+    ("s3.endpoint", "S3_ENDPOINT"),
+    ("s3.bucket", "S3_BUCKET"),
+    ("s3.region", "S3_REGION"),
+    ("s3.public_url", "S3_PUBLIC_URL"),
+    ("s3.access_key_id", "AWS_S3_ACCESS_KEY_ID"),
+    ("s3.secret_access_key", "AWS_S3_SECRET_ACCESS_KEY"),
+    ("log.format", "LOG_FORMAT"),
+    ("log.level", "RUST_LOG"),
+    ("log.otlp_endpoint", "OTEL_EXPORTER_OTLP_ENDPOINT"),
+    ("log.service_name", "OTEL_SERVICE_NAME"),
+    ("worker.enabled", "WORKER_ENABLED"),
+    ("worker.concurrency", "WORKER_CONCURRENCY"),
+    ("worker.poll_interval_ms", "WORKER_POLL_INTERVAL_MS"),
+    ("worker.lock_timeout_secs", "WORKER_LOCK_TIMEOUT_SECS"),
+    ("scheduler.enabled", "SCHEDULER_ENABLED"),
+    ("scheduler.sync_regions", "SCHEDULE_SYNC_REGIONS"),
+    ("scheduler.purge_deleted", "SCHEDULE_PURGE_DELETED"),
+    ("scheduler.clean_uploads", "SCHEDULE_CLEAN_UPLOADS"),
+    ("scheduler.check_subscription_expiry", "SCHEDULE_CHECK_SUBSCRIPTION_EXPIRY"),
+    ("scheduler.retention_days", "SCHEDULER_RETENTION_DAYS"),
+];
+
+#[derive(Debug)]
+pub struct ConfigIssue {
+    pub key: String,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub issues: Vec<ConfigIssue>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+        for issue in &self.issues {
+            match env_name(&issue.key) {
+                Some(env) => writeln!(f, "  - {} ({}): {}", issue.key, env, issue.reason)?,
+                None => writeln!(f, "  - {}: {}", issue.key, issue.reason)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AppConfig {
+    /// Loads the configuration from the process environment and, when present,
+    /// the TOML file named by `APP_CONFIG_FILE` (default `config.toml`).
+    pub fn load() -> Result<AppConfig, ConfigError> {
+        let env: HashMap<String, String> = std::env::vars().collect();
+        let explicit_file = env.get("APP_CONFIG_FILE").cloned();
+        let path = explicit_file.clone().unwrap_or_else(|| DEFAULT_CONFIG_FILE.to_string());
+
+        let file = if Path::new(&path).exists() {
+            match std::fs::read_to_string(&path) {
+                Ok(contents) => Some(contents),
+                Err(err) => return Err(single_issue("APP_CONFIG_FILE", format!("cannot read {}: {}", path, err))),
+            }
+        } else if explicit_file.is_some() {
+            return Err(single_issue("APP_CONFIG_FILE", format!("{} does not exist", path)));
+        } else {
+            None
+        };
+
+        Self::from_sources(file.as_deref(), &env)
+    }
+
+    /// Builds the configuration from TOML contents and environment variables.
+    /// Values from the `[default]` table are overridden by the active profile's
+    /// table, which is in turn overridden by the environment.
+    pub fn from_sources(file: Option<&str>, env: &HashMap<String, String>) -> Result<AppConfig, ConfigError> {
+        let profile = match env.get("APP_PROFILE").map(|value| value.parse::<Profile>()) {
+            None => Profile::Dev,
+            Some(Ok(profile)) => profile,
+            Some(Err(reason)) => return Err(single_issue("APP_PROFILE", reason)),
+        };
+
+        let mut values = HashMap::new();
+        if let Some(contents) = file {
+            let document: toml::Table = contents
+                .parse()
+                .map_err(|err: toml::de::Error| single_issue("APP_CONFIG_FILE", err.message().to_string()))?;
+            for section in ["default", profile.as_str()] {
+                if let Some(toml::Value::Table(table)) = document.get(section) {
+                    flatten("", table, &mut values);
+                }
+            }
+        }
+        for (key, env_name) in ENV_KEYS {
+            if let Some(value) = env.get(*env_name) {
+                values.insert(key.to_string(), value.clone());
+            }
+        }
+
+        let mut reader = Reader { values, issues: vec![] };
+        let strict = profile != Profile::Dev;
+
+        let server = ServerConfig {
+            host: reader.string_or("server.host", "127.0.0.1"),
+            port: reader.parse_or("server.port", 8000),
+            cors_allowed_origins: reader.list_or("server.cors_allowed_origins", &["http://localhost:3000"]),
+            shutdown_timeout: Duration::from_secs(reader.parse_or("server.shutdown_timeout_secs", 30)),
+            task_shutdown_timeout: Duration::from_secs(reader.parse_or("server.task_shutdown_timeout_secs", 30)),
+            idempotency_ttl: Duration::from_secs(reader.parse_or("server.idempotency_ttl_secs", 86400)),
+            idempotency_lock: Duration::from_secs(reader.parse_or("server.idempotency_lock_secs", 60)),
+            json_limit: reader.parse_or("server.json_limit_bytes", 2 * 1024 * 1024),
+            multipart_limit: reader.parse_or("server.multipart_limit_bytes", 50 * 1024 * 1024),
+            trusted_proxies: reader.ip_list("server.trusted_proxies"),
+        };
+
+        let database = DatabaseConfig {
+            url: reader.required("database.url"),
+            max_connections: reader.parse_or("database.max_connections", 10),
+            min_connections: reader.parse_or("database.min_connections", 5),
+            acquire_timeout: Duration::from_secs(reader.parse_or("database.acquire_timeout_secs", 5)),
+            idle_timeout: Duration::from_secs(reader.parse_or("database.idle_timeout_secs", 60)),
+            run_migrations: reader.parse_or("database.run_migrations", true),
+            check_schema: reader.parse_or("database.check_schema", true),
+        };
+        if database.min_connections > database.max_connections {
+            reader.invalid("database.min_connections", "must not exceed database.max_connections".to_string());
+        }
+
+        let auth = AuthConfig {
+            jwt_secret: reader.secret("auth.jwt_secret", strict),
+            jwt_previous_secrets: reader.list_or("auth.jwt_previous_secrets", &[]),
+            basic_auth_secret: reader.secret("auth.basic_auth_secret", strict),
+        };
+
+        let s3 = S3Config {
+            endpoint: reader.string_or("s3.endpoint", "https://is3.cloudhost.id"),
+            bucket: reader.string_or("s3.bucket", "sekula-storage"),
+            region: reader.string_or("s3.region", "custom-region"),
+            public_url: reader.string_or("s3.public_url", "https://is3.cloudhost.id/sekula-storage"),
+            access_key_id: reader.secret("s3.access_key_id", strict),
+            secret_access_key: reader.secret("s3.secret_access_key", strict),
+        };
+
+        // Readable output while developing, one JSON object per line elsewhere.
+        let default_format = if profile == Profile::Dev { LogFormat::Pretty } else { LogFormat::Json };
+        let log = LogConfig {
+            format: reader.parse_or("log.format", default_format),
+            level: reader.string_or("log.level", "info"),
+            otlp_endpoint: Some(reader.string_or("log.otlp_endpoint", "")).filter(|endpoint| !endpoint.trim().is_empty()),
+            service_name: reader.string_or("log.service_name", env!("CARGO_PKG_NAME")),
+        };
+
+        let worker = WorkerConfig {
+            enabled: reader.parse_or("worker.enabled", true),
+            concurrency: reader.parse_or("worker.concurrency", 2),
+            poll_interval: Duration::from_millis(reader.parse_or("worker.poll_interval_ms", 1000)),
+            lock_timeout: Duration::from_secs(reader.parse_or("worker.lock_timeout_secs", 600)),
+        };
+        if worker.concurrency == 0 {
+            reader.invalid("worker.concurrency", "must be at least 1".to_string());
+        }
+
+        let scheduler = SchedulerConfig {
+            enabled: reader.parse_or("scheduler.enabled", true),
+            sync_regions: reader.schedule_or("scheduler.sync_regions", "0 0 2 * * *"),
+            purge_deleted: reader.schedule_or("scheduler.purge_deleted", "0 0 3 * * *"),
+            clean_uploads: reader.schedule_or("scheduler.clean_uploads", "0 30 3 * * *"),
+            check_subscription_expiry: reader.schedule_or("scheduler.check_subscription_expiry", "0 0 1 * * *"),
+            retention: Duration::from_secs(60 * 60 * 24 * reader.parse_or("scheduler.retention_days", 30)),
+        };
+
+        if !reader.issues.is_empty() {
+            return Err(ConfigError { issues: reader.issues });
+        }
+
+        Ok(AppConfig { profile, server, database, auth, s3, log, worker, scheduler })
+    }
+}
+
+struct Reader {
+    values: HashMap<String, String>,
+    issues: Vec<ConfigIssue>,
+}
+
+impl Reader {
+    fn invalid(&mut self, key: &str, reason: String) {
+        self.issues.push(ConfigIssue { key: key.to_string(), reason });
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        match self.values.get(key) {
+            Some(value) if !value.trim().is_empty() => value.clone(),
+            _ => {
+                self.invalid(key, "is required".to_string());
+                String::new()
+            }
+        }
+    }
+
+    // Secrets may be left empty while developing locally, never elsewhere.
+    fn secret(&mut self, key: &str, required: bool) -> String {
+        if required {
+            self.required(key)
+        } else {
+            self.values.get(key).cloned().unwrap_or_default()
+        }
+    }
+
+    fn string_or(&self, key: &str, default: &str) -> String {
+        self.values.get(key).cloned().unwrap_or_else(|| default.to_string())
+    }
+
+    fn list_or(&self, key: &str, default: &[&str]) -> Vec<String> {
+        match self.values.get(key) {
+            Some(value) => value
+                .split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+            None => default.iter().map(|item| item.to_string()).collect(),
+        }
+    }
+
+    fn ip_list(&mut self, key: &str) -> Vec<IpAddr> {
+        let mut ips = vec![];
+        for item in self.list_or(key, &[]) {
+            match item.parse() {
+                Ok(ip) => ips.push(ip),
+                Err(err) => self.invalid(key, format!("invalid IP address \"{}\": {}", item, err)),
+            }
+        }
+        ips
+    }
+
+    // An empty value turns the schedule off.
+    fn schedule_or(&mut self, key: &str, default: &str) -> Option<Schedule> {
+        let value = self.string_or(key, default);
+        if value.trim().is_empty() {
+            return None;
+        }
+        match Schedule::from_str(value.trim()) {
+            Ok(schedule) => Some(schedule),
+            Err(err) => {
+                self.invalid(key, format!("invalid cron expression \"{}\": {}", value, err));
+                None
+            }
+        }
+    }
+
+    fn parse_or<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        match self.values.get(key).map(|value| value.trim().parse::<T>()) {
+            None => default,
+            Some(Ok(value)) => value,
+            Some(Err(err)) => {
+                let value = self.values[key].clone();
+                self.invalid(key, format!("invalid value \"{}\": {}", value, err));
+                default
+            }
+        }
+    }
+}
+
+fn flatten(prefix: &str, table: &toml::Table, values: &mut HashMap<String, String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match value {
+            toml::Value::Table(nested) => flatten(&key, nested, values),
+            toml::Value::String(text) => {
+                values.insert(key, text.clone());
+            }
+            toml::Value::Array(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|item| match item {
+                        toml::Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                values.insert(key, items.join(","));
+            }
+            other => {
+                values.insert(key, other.to_string());
+            }
+        }
+    }
+}
+
+fn env_name(key: &str) -> Option<&'static str> {
+    ENV_KEYS.iter().find(|(name, _)| *name == key).map(|(_, env)| *env)
+}
+
+fn single_issue(key: &str, reason: String) -> ConfigError {
+    ConfigError { issues: vec![ConfigIssue { key: key.to_string(), reason }] }
+}
@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+use actix_web::http::StatusCode;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+use crate::database::schema::{ColumnType, TableSchema};
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::entities::trash::Trashed;
+
+/// The columns of an entity's table that its listing lets clients filter on,
+/// sort by and search, by name.
+pub trait Listable: TableSchema {
+    const FILTERABLE: &'static [&'static str];
+    const SORTABLE: &'static [&'static str];
+    // Matched with ILIKE, so each one wants a trigram index.
+    const SEARCHABLE: &'static [&'static str];
+    const DEFAULT_SORT: &'static [Sort];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sort {
+    pub column: &'static str,
+    pub column_type: ColumnType,
+    pub descending: bool,
+}
+
+impl Sort {
+    pub const fn asc(column: &'static str, column_type: ColumnType) -> Sort {
+        Sort { column, column_type, descending: false }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Text(String),
+    Uuid(Uuid),
+    Int(i32),
+    Timestamp(DateTime<Utc>),
+    // Compared as text, since the bound value can't name the enum's type.
+    Enum(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub column: &'static str,
+    pub value: FilterValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Search {
+    pub columns: &'static [&'static str],
+    pub term: String,
+}
+
+/// A validated listing: every column in it comes from the entity's whitelist,
+/// so only values are ever bound and names can go into the SQL as they are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListQuery {
+    pub trashed: Trashed,
+    pub filters: Vec<Filter>,
+    pub sort: Vec<Sort>,
+    pub search: Option<Search>,
+}
+
+impl ListQuery {
+    /// Everything `trashed` lets through, in the entity's default order.
+    pub fn all<T: Listable>(trashed: Trashed) -> ListQuery {
+        ListQuery { trashed, filters: vec![], sort: T::DEFAULT_SORT.to_vec(), search: None }
+    }
+
+    /// Reads `filter[field]=value`, `sort=-field,field` and `q=term`, failing
+    /// with 400 on anything `T` doesn't allow.
+    pub fn parse<T: Listable>(trashed: Trashed, params: &HashMap<String, String>) -> Result<ListQuery, ErrorResponse> {
+        let mut query = ListQuery::all::<T>(trashed);
+
+        let mut filters: Vec<(&String, &String)> = params.iter()
+            .filter(|(key, _)| key.starts_with("filter["))
+            .collect();
+        filters.sort();
+        for (key, value) in filters {
+            let field = key.strip_prefix("filter[")
+                .and_then(|key| key.strip_suffix(']'))
+                .unwrap_or_default();
+            let column = allowed::<T>(T::FILTERABLE, field, "filter")?;
+            query.filters.push(Filter { column, value: filter_value(column_type::<T>(column), field, value)? });
+        }
+
+        if let Some(sort) = params.get("sort").filter(|sort| !sort.is_empty()) {
+            query.sort = sort.split(',')
+                .map(|field| {
+                    let (field, descending) = match field.trim().strip_prefix('-') {
+                        Some(field) => (field, true),
+                        None => (field.trim(), false),
+                    };
+                    let column = allowed::<T>(T::SORTABLE, field, "sort")?;
+                    Ok(Sort { column, column_type: column_type::<T>(column), descending })
+                })
+                .collect::<Result<_, ErrorResponse>>()?;
+        }
+
+        if let Some(term) = params.get("q").map(|term| term.trim()).filter(|term| !term.is_empty()) {
+            if T::SEARCHABLE.is_empty() {
+                return Err(bad_request(format!("Searching {} is not supported", T::TABLE)));
+            }
+            query.search = Some(Search { columns: T::SEARCHABLE, term: term.to_string() });
+        }
+
+        Ok(query)
+    }
+
+    /// Appends the conditions for the trashed state, filters and search, for
+    /// use after `WHERE`.
+    pub fn push_conditions(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        builder.push("((deleted_at IS NULL AND ")
+            .push_bind(self.trashed.includes_live())
+            .push(") OR (deleted_at IS NOT NULL AND ")
+            .push_bind(self.trashed.includes_trashed())
+            .push("))");
+
+        for filter in &self.filters {
+            builder.push(" AND ").push(filter.column);
+            if let FilterValue::Enum(_) = filter.value {
+                builder.push("::text");
+            }
+            builder.push(" = ");
+            push_value(builder, &filter.value);
+        }
+
+        if let Some(search) = &self.search {
+            let pattern = format!("%{}%", escape_like(&search.term));
+            builder.push(" AND (");
+            for (i, column) in search.columns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(" OR ");
+                }
+                builder.push(*column).push(" ILIKE ").push_bind(pattern.clone());
+            }
+            builder.push(")");
+        }
+    }
+
+    /// Appends what picks out the page after the conditions: the keyset
+    /// condition for a cursor, then `ORDER BY`, `LIMIT` and `OFFSET`. One row
+    /// more than the page holds is asked for, so `Page::new` can tell whether
+    /// there are more.
+    pub fn push_page(&self, builder: &mut QueryBuilder<'_, Postgres>, page: &PageRequest) {
+        let (key, backwards) = match &page.position {
+            Position::Page(_) => (None, false),
+            Position::After(key) => (Some(key), false),
+            Position::Before(key) => (Some(key), true),
+        };
+
+        // (a, b, id) after (x, y, z) in ascending order is
+        // `a > x OR (a = x AND b > y) OR (a = x AND b = y AND id > z)`.
+        if let Some(key) = key {
+            let columns = self.key_columns();
+            builder.push(" AND (");
+            for (i, (column, descending)) in columns.iter().enumerate() {
+                if i > 0 {
+                    builder.push(" OR ");
+                }
+                builder.push("(");
+                for (equal, value) in columns[..i].iter().zip(key) {
+                    builder.push(equal.0).push(" = ");
+                    push_value(builder, value);
+                    builder.push(" AND ");
+                }
+                builder.push(*column).push(if *descending != backwards { " < " } else { " > " });
+                push_value(builder, &key[i]);
+                builder.push(")");
+            }
+            builder.push(")");
+        }
+
+        builder.push(" ORDER BY ");
+        for (i, (column, descending)) in self.key_columns().iter().enumerate() {
+            if i > 0 {
+                builder.push(", ");
+            }
+            builder.push(*column).push(if *descending != backwards { " DESC" } else { " ASC" });
+        }
+
+        builder.push(" LIMIT ").push_bind(page.size as i64 + 1);
+        if let Position::Page(number) = page.position {
+            builder.push(" OFFSET ").push_bind(page.offset(number));
+        }
+    }
+
+    // The sort columns and `id` after them, which makes every row's key unique
+    // so that pages never overlap.
+    pub fn key_columns(&self) -> Vec<(&'static str, bool)> {
+        self.sort.iter()
+            .map(|sort| (sort.column, sort.descending))
+            .chain([("id", false)])
+            .collect()
+    }
+
+    fn sort_signature(&self) -> String {
+        self.sort.iter()
+            .map(|sort| format!("{}{}", if sort.descending { "-" } else { "" }, sort.column))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Which rows of a listing to return and whether to count them all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRequest {
+    pub size: u32,
+    pub position: Position,
+    pub include_total: bool,
+}
+
+/// Where a page starts: a 1-based page number, skipped to with OFFSET, or
+/// just past the sort key of a row from a cursor, which holds up under
+/// concurrent inserts and doesn't slow down deep into a table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Position {
+    Page(u32),
+    After(Vec<FilterValue>),
+    Before(Vec<FilterValue>),
+}
+
+impl PageRequest {
+    /// The first `size` rows, uncounted.
+    pub fn first(size: u32) -> PageRequest {
+        PageRequest { size, position: Position::Page(1), include_total: false }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.size > 0 && self.position != Position::Page(0)
+    }
+
+    pub fn offset(&self, number: u32) -> i64 {
+        (number.max(1) as i64 - 1) * self.size as i64
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorData {
+    sort: String,
+    before: bool,
+    key: Vec<Value>,
+}
+
+/// Reads a cursor that `Page::new` made for the same sort, failing with 400
+/// on anything else.
+pub fn decode_cursor(query: &ListQuery, cursor: &str) -> Result<Position, ErrorResponse> {
+    let invalid = || bad_request("Invalid cursor".to_string());
+    let data: CursorData = URL_SAFE_NO_PAD.decode(cursor).ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .ok_or_else(invalid)?;
+    if data.sort != query.sort_signature() {
+        return Err(bad_request("Cursor was made for a different sort".to_string()));
+    }
+
+    let types = query.sort.iter().map(|sort| sort.column_type).chain([ColumnType::Uuid]);
+    if data.key.len() != query.sort.len() + 1 {
+        return Err(invalid());
+    }
+    let key = types.zip(&data.key)
+        .map(|(column_type, value)| key_value(column_type, value).ok_or_else(invalid))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(if data.before { Position::Before(key) } else { Position::After(key) })
+}
+
+fn encode_cursor<T: Serialize>(query: &ListQuery, row: &T, before: bool) -> String {
+    let row = serde_json::to_value(row).unwrap_or_default();
+    let data = CursorData {
+        sort: query.sort_signature(),
+        before,
+        key: query.key_columns().iter().map(|(column, _)| row[*column].clone()).collect(),
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(&data).unwrap_or_default())
+}
+
+fn key_value(column_type: ColumnType, value: &Value) -> Option<FilterValue> {
+    Some(match column_type {
+        ColumnType::Uuid => FilterValue::Uuid(value.as_str()?.parse().ok()?),
+        ColumnType::Int4 => FilterValue::Int(value.as_i64()?.try_into().ok()?),
+        ColumnType::Timestamptz => FilterValue::Timestamp(value.as_str()?.parse().ok()?),
+        _ => FilterValue::Text(value.as_str()?.to_string()),
+    })
+}
+
+/// A page of a listing, with cursors to the pages either side of it when
+/// there are any.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub rows: Vec<T>,
+    pub total: Option<i64>,
+    pub next_cursor: Option<String>,
+    pub prev_cursor: Option<String>,
+}
+
+impl<T: Serialize> Page<T> {
+    /// Takes the rows a query built with `push_page` returned.
+    pub fn new(mut rows: Vec<T>, query: &ListQuery, page: &PageRequest, total: Option<i64>) -> Page<T> {
+        let more = rows.len() > page.size as usize;
+        rows.truncate(page.size as usize);
+        let (has_prev, has_next) = match page.position {
+            Position::Page(number) => (number > 1, more),
+            Position::After(_) => (true, more),
+            Position::Before(_) => {
+                rows.reverse();
+                (more, true)
+            }
+        };
+
+        Page {
+            next_cursor: rows.last().filter(|_| has_next).map(|row| encode_cursor(query, row, false)),
+            prev_cursor: rows.first().filter(|_| has_prev).map(|row| encode_cursor(query, row, true)),
+            rows,
+            total,
+        }
+    }
+}
+
+impl<T> Page<T> {
+    /// The same page, cursors and all, holding `rows` made from its own.
+    pub fn with_rows<U>(self, rows: Vec<U>) -> Page<U> {
+        Page { rows, total: self.total, next_cursor: self.next_cursor, prev_cursor: self.prev_cursor }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        let Page { rows, total, next_cursor, prev_cursor } = self;
+        Page { rows: rows.into_iter().map(f).collect(), total, next_cursor, prev_cursor }
+    }
+}
+
+fn push_value(builder: &mut QueryBuilder<'_, Postgres>, value: &FilterValue) {
+    match value {
+        FilterValue::Text(value) | FilterValue::Enum(value) => builder.push_bind(value.clone()),
+        FilterValue::Uuid(value) => builder.push_bind(*value),
+        FilterValue::Int(value) => builder.push_bind(*value),
+        FilterValue::Timestamp(value) => builder.push_bind(*value),
+    };
+}
+
+fn allowed<T: Listable>(whitelist: &'static [&'static str], field: &str, what: &str) -> Result<&'static str, ErrorResponse> {
+    whitelist.iter()
+        .find(|column| **column == field)
+        .copied()
+        .ok_or_else(|| bad_request(format!(
+            "Cannot {} {} by '{}'; allowed fields are: {}",
+            what,
+            T::TABLE,
+            field,
+            whitelist.join(", "),
+        )))
+}
+
+fn column_type<T: TableSchema>(name: &str) -> ColumnType {
+    T::COLUMNS.iter()
+        .find(|column| column.name == name)
+        .map(|column| column.column_type)
+        .unwrap_or(ColumnType::Text)
+}
+
+fn filter_value(column_type: ColumnType, field: &str, value: &str) -> Result<FilterValue, ErrorResponse> {
+    let invalid = || bad_request(format!("Invalid value for filter[{}]: '{}'", field, value));
+    Ok(match column_type {
+        ColumnType::Uuid => FilterValue::Uuid(value.parse().map_err(|_| invalid())?),
+        ColumnType::Int4 => FilterValue::Int(value.parse().map_err(|_| invalid())?),
+        ColumnType::Timestamptz => FilterValue::Timestamp(value.parse().map_err(|_| invalid())?),
+        ColumnType::Enum(_) => FilterValue::Enum(value.to_string()),
+        _ => FilterValue::Text(value.to_string()),
+    })
+}
+
+// The term is matched literally, wildcards included.
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+fn bad_request(message: String) -> ErrorResponse {
+    ErrorResponse::new(StatusCode::BAD_REQUEST, Some(message), Some("FAILED".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::internal::entities::school::School;
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn parses_filters_sort_and_search() {
+        let province_id = "31";
+        let query = ListQuery::parse::<School>(Trashed::Without, &params(&[
+            ("filter[province_id]", province_id),
+            ("sort", "-created_at,name"),
+            ("q", " sma "),
+            ("page", "2"),
+        ])).unwrap();
+
+        assert_eq!(query.filters, vec![Filter { column: "province_id", value: FilterValue::Text(province_id.to_string()) }]);
+        assert_eq!(query.sort.iter().map(|sort| (sort.column, sort.descending)).collect::<Vec<_>>(), vec![("created_at", true), ("name", false)]);
+        assert_eq!(query.search.unwrap().term, "sma");
+    }
+
+    #[test]
+    fn rejects_fields_outside_the_whitelist() {
+        for pairs in [[("filter[logo_path]", "x")], [("sort", "password")], [("filter[subscription_id]", "not-a-uuid")]] {
+            let err = ListQuery::parse::<School>(Trashed::Without, &params(&pairs)).unwrap_err();
+            assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn builds_parameterized_sql() {
+        let query = ListQuery::parse::<School>(Trashed::Without, &params(&[
+            ("filter[city_id]", "31.71'; DROP TABLE schools; --"),
+            ("q", "50%"),
+        ])).unwrap();
+        let mut builder = QueryBuilder::new("SELECT * FROM schools WHERE ");
+        query.push_conditions(&mut builder);
+        query.push_page(&mut builder, &PageRequest { size: 10, position: Position::Page(3), include_total: true });
+
+        assert_eq!(
+            builder.sql(),
+            "SELECT * FROM schools WHERE ((deleted_at IS NULL AND $1) OR (deleted_at IS NOT NULL AND $2)) AND city_id = $3 AND (name ILIKE $4) ORDER BY created_at ASC, id ASC LIMIT $5 OFFSET $6",
+        );
+        assert_eq!(escape_like("50%_\\"), "50\\%\\_\\\\");
+    }
+
+    #[test]
+    fn cursors_continue_after_the_last_row() {
+        let query = ListQuery::parse::<School>(Trashed::Without, &params(&[("sort", "-name")])).unwrap();
+        let school = |name: &str| json!({"id": Uuid::new_v4(), "name": name});
+        let first = PageRequest { size: 2, position: Position::Page(1), include_total: false };
+
+        let page = Page::new(vec![school("c"), school("b"), school("a")], &query, &first, None);
+        assert_eq!(page.rows.len(), 2);
+        assert!(page.prev_cursor.is_none());
+
+        let position = decode_cursor(&query, page.next_cursor.as_ref().unwrap()).unwrap();
+        let Position::After(key) = &position else { panic!("expected a forward cursor") };
+        assert_eq!(key[0], FilterValue::Text("b".to_string()));
+
+        let mut builder = QueryBuilder::new("SELECT * FROM schools WHERE ");
+        query.push_conditions(&mut builder);
+        query.push_page(&mut builder, &PageRequest { size: 2, position, include_total: false });
+        assert!(builder.sql().ends_with("AND ((name < $3) OR (name = $4 AND id > $5)) ORDER BY name DESC, id ASC LIMIT $6"));
+    }
+
+    #[test]
+    fn cursors_are_tied_to_their_sort() {
+        let by_name = ListQuery::parse::<School>(Trashed::Without, &params(&[("sort", "name")])).unwrap();
+        let by_date = ListQuery::all::<School>(Trashed::Without);
+        let page = Page::new(vec![json!({"id": Uuid::new_v4(), "name": "a"}), json!({"id": Uuid::new_v4(), "name": "b"})], &by_name, &PageRequest::first(1), None);
+        let cursor = page.next_cursor.unwrap();
+
+        assert!(decode_cursor(&by_name, &cursor).is_ok());
+        assert_eq!(decode_cursor(&by_date, &cursor).unwrap_err().err_type, StatusCode::BAD_REQUEST);
+        assert_eq!(decode_cursor(&by_name, "not a cursor").unwrap_err().err_type, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+use std::error::Error;
+use sqlx::migrate::{Migrate, MigrateError, Migrator};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use crate::config::app_config::DatabaseConfig;
+use crate::database::schema::check_schema;
+
+// Every file in `migrations/`, compiled into the binary.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub applied: bool,
+}
+
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn.list_applied_migrations().await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(MIGRATOR.iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            applied: applied.contains(&migration.version),
+        })
+        .collect())
+}
+
+// Brings the schema up to date and refuses to continue if the entities no
+// longer match it, each step as enabled in the config.
+pub async fn prepare(pool: &PgPool, config: &DatabaseConfig) -> Result<(), Box<dyn Error>> {
+    if config.run_migrations {
+        MIGRATOR.run(pool).await?;
+        info!("Database migrations are up to date");
+    }
+
+    if config.check_schema {
+        for warning in check_schema(pool).await? {
+            warn!("{}", warning);
+        }
+    }
+
+    Ok(())
+}
@@ -1 +1,4 @@
-pub mod postgresql;
\ No newline at end of file
+pub mod postgresql;
+pub mod migrations;
+pub mod schema;
+pub mod list_query;
\ No newline at end of file
@@ -1,20 +1,18 @@
-use std::time::Duration;
 use sqlx::{Error, Pool, Postgres};
 use sqlx::postgres::PgPoolOptions;
+use tracing::info;
+use crate::config::app_config::DatabaseConfig;
 
-pub async fn get_pool() -> Result<Pool<Postgres>, Error> {
-    let database_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
-
+pub async fn get_pool(config: &DatabaseConfig) -> Result<Pool<Postgres>, Error> {
     // Create a connection pool
     let pool = PgPoolOptions::new()
-        .max_connections(10)
-        .min_connections(5)
-        .acquire_timeout(Duration::from_secs(5))
-        .idle_timeout(Duration::from_secs(60))
-        .connect(&database_url)
+        .max_connections(config.max_connections)
+        .min_connections(config.min_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .idle_timeout(config.idle_timeout)
+        .connect(&config.url)
         .await?;
 
-    println!("✅ Connection to the database is successful!");
+    info!("Connected to the database");
     Ok(pool)
 }
@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use sqlx::PgPool;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::city::City;
+use crate::internal::entities::event::OutboxEvent;
+use crate::internal::entities::idempotency::IdempotencyKey;
+use crate::internal::entities::job::Job;
+use crate::internal::entities::province::ProvinceFromTable;
+use crate::internal::entities::role::Role;
+use crate::internal::entities::schedule::ScheduleRun;
+use crate::internal::entities::school::School;
+use crate::internal::entities::subscription::Subscription;
+use crate::internal::entities::subscription_type::SubscriptionType;
+use crate::internal::entities::user::User;
+use crate::internal::entities::webhook::{Webhook, WebhookDelivery};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Uuid,
+    Text,
+    Int4,
+    Timestamptz,
+    Jsonb,
+    Bytea,
+    Enum(&'static str),
+}
+
+impl ColumnType {
+    // `udt_name` as reported by information_schema.columns.
+    fn matches(&self, udt_name: &str) -> bool {
+        match self {
+            ColumnType::Uuid => udt_name == "uuid",
+            ColumnType::Text => matches!(udt_name, "text" | "varchar" | "bpchar"),
+            ColumnType::Int4 => udt_name == "int4",
+            ColumnType::Timestamptz => udt_name == "timestamptz",
+            ColumnType::Jsonb => udt_name == "jsonb",
+            ColumnType::Bytea => udt_name == "bytea",
+            ColumnType::Enum(name) => udt_name == *name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+pub const fn column(name: &'static str, column_type: ColumnType) -> Column {
+    Column { name, column_type, nullable: false }
+}
+
+pub const fn nullable(name: &'static str, column_type: ColumnType) -> Column {
+    Column { name, column_type, nullable: true }
+}
+
+/// The table an entity is read from and the columns its fields map to.
+pub trait TableSchema {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [Column];
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EntitySchema {
+    pub table: &'static str,
+    pub columns: &'static [Column],
+}
+
+fn entity<T: TableSchema>() -> EntitySchema {
+    EntitySchema { table: T::TABLE, columns: T::COLUMNS }
+}
+
+pub fn entities() -> Vec<EntitySchema> {
+    vec![
+        entity::<SubscriptionType>(),
+        entity::<Subscription>(),
+        entity::<Role>(),
+        entity::<ProvinceFromTable>(),
+        entity::<City>(),
+        entity::<School>(),
+        entity::<User>(),
+        entity::<Job>(),
+        entity::<ScheduleRun>(),
+        entity::<OutboxEvent>(),
+        entity::<Webhook>(),
+        entity::<WebhookDelivery>(),
+        entity::<AuditLog>(),
+        entity::<IdempotencyKey>(),
+    ]
+}
+
+#[derive(Debug)]
+pub struct SchemaDrift {
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "database schema does not match the entities:")?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for SchemaDrift {}
+
+/// Compares every entity against the live schema. Missing tables or columns
+/// and type mismatches are errors; a nullable column behind a non-optional
+/// field only fails when a NULL is actually read, so it is returned as a
+/// warning instead.
+pub async fn check_schema(pool: &PgPool) -> Result<Vec<String>, Box<dyn Error>> {
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        r#"
+            SELECT table_name::TEXT, column_name::TEXT, udt_name::TEXT, is_nullable::TEXT
+            FROM information_schema.columns
+            WHERE table_schema = current_schema()
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut live: HashMap<String, HashMap<String, (String, bool)>> = HashMap::new();
+    for (table, column, udt_name, is_nullable) in rows {
+        live.entry(table).or_default().insert(column, (udt_name, is_nullable == "YES"));
+    }
+
+    let (problems, warnings) = compare(&entities(), &live);
+    if problems.is_empty() {
+        Ok(warnings)
+    } else {
+        Err(Box::new(SchemaDrift { problems }))
+    }
+}
+
+fn compare(
+    entities: &[EntitySchema],
+    live: &HashMap<String, HashMap<String, (String, bool)>>,
+) -> (Vec<String>, Vec<String>) {
+    let mut problems = vec![];
+    let mut warnings = vec![];
+
+    for entity in entities {
+        let Some(columns) = live.get(entity.table) else {
+            problems.push(format!("table {} is missing", entity.table));
+            continue;
+        };
+        for expected in entity.columns {
+            let name = format!("{}.{}", entity.table, expected.name);
+            match columns.get(expected.name) {
+                None => problems.push(format!("column {} is missing", name)),
+                Some((udt_name, _)) if !expected.column_type.matches(udt_name) => problems.push(format!(
+                    "column {} is {} but the entity expects {:?}",
+                    name, udt_name, expected.column_type
+                )),
+                Some((_, true)) if !expected.nullable => {
+                    warnings.push(format!("column {} is nullable but the entity field is not optional", name))
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    (problems, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: &[Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        nullable("deleted_at", ColumnType::Timestamptz),
+    ];
+
+    fn live(columns: &[(&str, &str, bool)]) -> HashMap<String, HashMap<String, (String, bool)>> {
+        let columns = columns
+            .iter()
+            .map(|(name, udt_name, nullable)| (name.to_string(), (udt_name.to_string(), *nullable)))
+            .collect();
+        HashMap::from([("roles".to_string(), columns)])
+    }
+
+    #[test]
+    fn matching_schema_has_no_problems() {
+        let entities = [EntitySchema { table: "roles", columns: COLUMNS }];
+        let live = live(&[("id", "uuid", false), ("name", "varchar", false), ("deleted_at", "timestamptz", true), ("extra", "int4", true)]);
+
+        assert_eq!(compare(&entities, &live), (vec![], vec![]));
+    }
+
+    #[test]
+    fn reports_missing_and_mistyped_columns() {
+        let entities = [EntitySchema { table: "roles", columns: COLUMNS }];
+        let live = live(&[("id", "int4", false), ("deleted_at", "timestamptz", true)]);
+
+        let (problems, _) = compare(&entities, &live);
+
+        assert_eq!(problems, vec![
+            "column roles.id is int4 but the entity expects Uuid".to_string(),
+            "column roles.name is missing".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn reports_missing_table() {
+        let entities = [EntitySchema { table: "users", columns: COLUMNS }];
+
+        let (problems, _) = compare(&entities, &HashMap::new());
+
+        assert_eq!(problems, vec!["table users is missing".to_string()]);
+    }
+
+    #[test]
+    fn nullable_column_behind_required_field_is_a_warning() {
+        let entities = [EntitySchema { table: "roles", columns: COLUMNS }];
+        let live = live(&[("id", "uuid", false), ("name", "text", true), ("deleted_at", "timestamptz", true)]);
+
+        let (problems, warnings) = compare(&entities, &live);
+
+        assert!(problems.is_empty());
+        assert_eq!(warnings, vec!["column roles.name is nullable but the entity field is not optional".to_string()]);
+    }
+}
@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+pub const NAME: &str = env!("CARGO_PKG_NAME");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+// Both set by build.rs.
+pub const GIT_SHA: &str = env!("GIT_SHA");
+pub const BUILD_TIME: &str = env!("BUILD_TIME");
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub git_sha: &'static str,
+    pub build_time: &'static str,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self { name: NAME, version: VERSION, git_sha: GIT_SHA, build_time: BUILD_TIME }
+    }
+}
@@ -1,7 +1,8 @@
+use std::fmt;
 use actix_web::body::BoxBody;
 use actix_web::http::StatusCode;
-use actix_web::HttpResponse;
-use serde::{Deserialize, Serialize};
+use actix_web::{HttpRequest, HttpResponse};
+use crate::helpers::custom_response::{current_request_id, ApiError, ApiResponse};
 
 pub trait ResponseError {
     fn status_code(&self) -> StatusCode;
@@ -22,12 +23,21 @@ impl ErrorResponse {
     ) -> ErrorResponse {
         ErrorResponse {err_type, message, status}
     }
-}
 
-#[derive(Serialize, Deserialize)]
-struct ActualResponse {
-    pub message: Option<String>,
-    pub status: Option<String>,
+    fn envelope(&self) -> ApiResponse<()> {
+        let mut response = ApiResponse::error(ApiError {
+            message: self.message.clone(),
+            status: self.status.clone(),
+        });
+        response.meta.code = self.status_code().as_u16();
+        response.request_id = current_request_id();
+        response
+    }
+
+    /// Same as `error_response`, but carries the request id of `req`.
+    pub fn respond(&self, req: &HttpRequest) -> HttpResponse {
+        self.envelope().respond(req, self.status_code())
+    }
 }
 
 impl ResponseError for ErrorResponse {
@@ -36,10 +46,28 @@ impl ResponseError for ErrorResponse {
     }
 
     fn error_response(&self) -> HttpResponse {
-        let res = ActualResponse {
-            message: self.message.clone(),
-            status: self.status.clone()
-        };
-        HttpResponse::build(self.status_code()).json(res)
+        HttpResponse::build(self.status_code()).json(self.envelope())
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for ErrorResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{} ({})", message, self.err_type),
+            None => write!(f, "{}", self.err_type),
+        }
+    }
+}
+
+impl std::error::Error for ErrorResponse {}
+
+// A database failure with nothing more specific to say about it.
+impl From<sqlx::Error> for ErrorResponse {
+    fn from(error: sqlx::Error) -> Self {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Some(error.to_string()),
+            Some("FAILED".to_string()),
+        )
+    }
+}
@@ -1,21 +1,303 @@
+use std::collections::HashMap;
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::database::list_query::{decode_cursor, ListQuery, Listable, Page, PageRequest, Position};
+use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::fields::{Fields, View};
+use crate::helpers::include::{Includable, Includes};
+use crate::helpers::precondition::etag;
+use crate::internal::entities::trash::Trashed;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
 
 #[derive(Deserialize, Debug)]
 pub struct PaginationParams {
     pub page: Option<u32>,
     pub page_size: Option<u32>,
+    // A `next_cursor` or `prev_cursor` from an earlier page; `page` is then
+    // ignored.
+    pub cursor: Option<String>,
+    // `false` skips counting every matching row.
+    pub include_total: Option<bool>,
+}
+
+impl PaginationParams {
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1)
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size.unwrap_or(10)
+    }
+
+    /// The page of `query` asked for, failing with 400 on a cursor made for
+    /// another listing or sort.
+    pub fn request(&self, query: &ListQuery) -> Result<PageRequest, ErrorResponse> {
+        let position = match self.cursor.as_deref().filter(|cursor| !cursor.is_empty()) {
+            Some(cursor) => decode_cursor(query, cursor)?,
+            None => Position::Page(self.page()),
+        };
+        Ok(PageRequest { size: self.page_size(), position, include_total: self.include_total.unwrap_or(true) })
+    }
+}
+
+// `?filter[field]=value&sort=-field&q=term` on listings. Every parameter is
+// collected, and `ListQuery::parse` picks out the ones it knows.
+#[derive(Deserialize, Debug)]
+#[serde(transparent)]
+pub struct ListParams(HashMap<String, String>);
+
+impl ListParams {
+    pub fn query<T: Listable>(&self, trashed: Trashed) -> Result<ListQuery, ErrorResponse> {
+        ListQuery::parse::<T>(trashed, &self.0)
+    }
+}
+
+// `?trashed=with|only` on the listings of soft-deleted resources.
+#[derive(Deserialize, Debug)]
+pub struct TrashedParams {
+    pub trashed: Option<Trashed>,
+}
+
+impl TrashedParams {
+    pub fn trashed(&self) -> Trashed {
+        self.trashed.unwrap_or_default()
+    }
+}
+
+// `?include=a,b` on resources with relations to nest under their rows.
+#[derive(Deserialize, Debug)]
+pub struct IncludeParams {
+    pub include: Option<String>,
+}
+
+impl IncludeParams {
+    pub fn includes<T: Includable>(&self) -> Result<Includes, ErrorResponse> {
+        Includes::parse::<T>(self.include.as_deref())
+    }
+
+    // Like `includes`, but `default` stands in when `include` is left out;
+    // an empty `include=` still asks for nothing.
+    pub fn includes_or<T: Includable>(&self, default: &str) -> Result<Includes, ErrorResponse> {
+        Includes::parse::<T>(Some(self.include.as_deref().unwrap_or(default)))
+    }
+}
+
+// `?fields=a,b` to leave the other fields of a view out of the response.
+#[derive(Deserialize, Debug)]
+pub struct FieldsParams {
+    pub fields: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct PaginatedResponse<T> {
-    pub data: T,        // Dynamic data type
-    pub page: u32,      // Current page
+impl FieldsParams {
+    pub fn fields<V: View>(&self) -> Result<Fields, ErrorResponse> {
+        Fields::parse::<V>(self.fields.as_deref())
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PaginationMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<u32>,      // Current page, unless paging by cursor
     pub page_size: u32,  // Items per page
-    pub total_pages: u32,
-    pub total_data: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_pages: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_data: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev_cursor: Option<String>,
+}
+
+impl PaginationMeta {
+    pub fn new(page: u32, page_size: u32, total_data: i64) -> Self {
+        Self {
+            page: Some(page),
+            page_size,
+            total_pages: Some(total_pages(page_size, total_data)),
+            total_data: Some(total_data as u32),
+            next_cursor: None,
+            prev_cursor: None,
+        }
+    }
+
+    pub fn of<T>(request: &PageRequest, page: &Page<T>) -> Self {
+        Self {
+            page: match request.position {
+                Position::Page(number) => Some(number),
+                _ => None,
+            },
+            page_size: request.size,
+            total_pages: page.total.map(|total| total_pages(request.size, total)),
+            total_data: page.total.map(|total| total as u32),
+            next_cursor: page.next_cursor.clone(),
+            prev_cursor: page.prev_cursor.clone(),
+        }
+    }
+}
+
+fn total_pages(page_size: u32, total_data: i64) -> u32 {
+    (total_data as f32 / page_size as f32).ceil() as u32
 }
 
-#[derive(Serialize)]
-pub struct Response<T> {
-    pub data: T,        // Dynamic data type
-}
\ No newline at end of file
+#[derive(Serialize, Debug, Default)]
+pub struct Meta {
+    pub code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pagination: Option<PaginationMeta>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiError {
+    pub status: Option<String>,
+    pub message: Option<String>,
+}
+
+/// The envelope every handler and error responds with.
+#[derive(Serialize, Debug)]
+pub struct ApiResponse<T: Serialize> {
+    pub data: Option<T>,
+    pub meta: Meta,
+    pub errors: Vec<ApiError>,
+    pub request_id: Option<String>,
+    #[serde(skip)]
+    pub etag: Option<String>,
+}
+
+impl ApiResponse<()> {
+    pub fn empty() -> Self {
+        Self::build(None)
+    }
+
+    pub fn error(error: ApiError) -> Self {
+        let mut response = Self::build(None);
+        response.errors.push(error);
+        response
+    }
+}
+
+impl<T: Serialize> ApiResponse<T> {
+    pub fn new(data: T) -> Self {
+        Self::build(Some(data))
+    }
+
+    fn build(data: Option<T>) -> Self {
+        Self {
+            data,
+            meta: Meta::default(),
+            errors: vec![],
+            request_id: None,
+            etag: None,
+        }
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.meta.message = Some(message.to_string());
+        self
+    }
+
+    pub fn pagination(mut self, pagination: PaginationMeta) -> Self {
+        self.meta.pagination = Some(pagination);
+        self
+    }
+
+    /// Sends the row version of `data` as the `ETag` header.
+    pub fn etag(mut self, version: i32) -> Self {
+        self.etag = Some(etag(version));
+        self
+    }
+
+    /// Responds with the JSON envelope.
+    pub fn respond(mut self, req: &HttpRequest, status: StatusCode) -> HttpResponse {
+        self.meta.code = status.as_u16();
+        self.request_id = request_id(req);
+        let mut response = HttpResponse::build(status);
+        if let Some(etag) = self.etag.take() {
+            response.insert_header((header::ETAG, etag));
+        }
+        response.json(self)
+    }
+
+    /// Responds with the JSON envelope, or with the bare `data` rows as CSV when
+    /// the client sends `Accept: text/csv`.
+    pub fn respond_negotiated(self, req: &HttpRequest, status: StatusCode) -> HttpResponse {
+        if !accepts_csv(req) {
+            return self.respond(req, status);
+        }
+
+        let rows = self.data.as_ref().map(serde_json::to_value);
+        match rows {
+            Some(Ok(Value::Array(rows))) => match to_csv(&rows) {
+                Ok(body) => HttpResponse::build(status)
+                    .content_type("text/csv; charset=utf-8")
+                    .body(body),
+                Err(err) => ApiResponse::error(ApiError {
+                    status: Some("FAILED".to_string()),
+                    message: Some(format!("Failed to render CSV: {}", err)),
+                })
+                    .respond(req, StatusCode::INTERNAL_SERVER_ERROR),
+            },
+            _ => self.respond(req, status),
+        }
+    }
+}
+
+tokio::task_local! {
+    // Id of the request being served, for code that has no `HttpRequest`.
+    pub static CURRENT_REQUEST_ID: String;
+}
+
+pub fn request_id(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
+
+pub fn current_request_id() -> Option<String> {
+    CURRENT_REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn accepts_csv(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media| media.split(';').next().unwrap_or("").trim() == "text/csv")
+        })
+        .unwrap_or(false)
+}
+
+// Columns come from the first row; nested values are written as JSON.
+fn to_csv(rows: &[Value]) -> Result<String, csv::Error> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    let columns: Vec<String> = match rows.first() {
+        Some(Value::Object(first)) => first.keys().cloned().collect(),
+        _ => vec![],
+    };
+    writer.write_record(&columns)?;
+
+    for row in rows {
+        let record: Vec<String> = columns
+            .iter()
+            .map(|column| match row.get(column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+            })
+            .collect();
+        writer.write_record(&record)?;
+    }
+
+    let bytes = writer.into_inner().map_err(|err| csv::Error::from(err.into_error()))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,94 @@
+use actix_web::http::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use crate::helpers::custom_error::ErrorResponse;
+
+/// A response view model, whose fields `?fields=` picks from.
+pub trait View: Serialize {
+    const FIELDS: &'static [&'static str];
+}
+
+/// The fields of `V` that `?fields=a,b` asked for, or all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fields {
+    all: &'static [&'static str],
+    selected: Option<Vec<&'static str>>,
+}
+
+impl Fields {
+    /// Fails with 400 on a field `V` doesn't have.
+    pub fn parse<V: View>(fields: Option<&str>) -> Result<Fields, ErrorResponse> {
+        let names: Vec<&str> = fields.unwrap_or_default().split(',').map(str::trim).filter(|name| !name.is_empty()).collect();
+        if names.is_empty() {
+            return Ok(Fields { all: V::FIELDS, selected: None });
+        }
+
+        let mut selected = vec![];
+        for name in names {
+            let name = V::FIELDS.iter().find(|allowed| **allowed == name).ok_or_else(|| ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some(format!("Unknown field '{}'; allowed fields are: {}", name, V::FIELDS.join(", "))),
+                Some("FAILED".to_string()),
+            ))?;
+            if !selected.contains(name) {
+                selected.push(*name);
+            }
+        }
+        Ok(Fields { all: V::FIELDS, selected: Some(selected) })
+    }
+
+    /// `data`, a row or a list of rows, without the fields that weren't asked
+    /// for. Included relations are kept.
+    pub fn select<S: Serialize>(&self, data: &S) -> Value {
+        let mut data = serde_json::to_value(data).unwrap_or_default();
+        if let Some(selected) = &self.selected {
+            match &mut data {
+                Value::Array(rows) => rows.iter_mut().for_each(|row| self.retain(row, selected)),
+                row => self.retain(row, selected),
+            }
+        }
+        data
+    }
+
+    fn retain(&self, row: &mut Value, selected: &[&str]) {
+        if let Value::Object(row) = row {
+            row.retain(|key, _| selected.contains(&key.as_str()) || !self.all.contains(&key.as_str()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        id: i32,
+        name: String,
+        email: String,
+    }
+
+    impl View for Row {
+        const FIELDS: &'static [&'static str] = &["id", "name", "email"];
+    }
+
+    #[test]
+    fn parses_known_fields_only() {
+        assert_eq!(Fields::parse::<Row>(None).unwrap().selected, None);
+        assert_eq!(Fields::parse::<Row>(Some("name, id,name")).unwrap().selected, Some(vec!["name", "id"]));
+
+        let err = Fields::parse::<Row>(Some("password")).unwrap_err();
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn selects_fields_of_every_row() {
+        let fields = Fields::parse::<Row>(Some("id,name")).unwrap();
+        let mut row = serde_json::to_value(Row { id: 1, name: "Siti".to_string(), email: "siti@example.com".to_string() }).unwrap();
+        row["role"] = json!({"name": "admin"});
+
+        assert_eq!(fields.select(&row), json!({"id": 1, "name": "Siti", "role": {"name": "admin"}}));
+        assert_eq!(fields.select(&vec![row]), json!([{"id": 1, "name": "Siti", "role": {"name": "admin"}}]));
+    }
+}
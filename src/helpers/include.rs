@@ -0,0 +1,139 @@
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use actix_web::http::StatusCode;
+use serde::Serialize;
+use serde_json::Value;
+use crate::helpers::custom_error::ErrorResponse;
+
+/// The relations a resource can nest under its rows with `?include=`.
+pub trait Includable {
+    const INCLUDABLE: &'static [&'static str];
+}
+
+/// The relations `?include=a,b` asked for, each one `T` allows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Includes(Vec<&'static str>);
+
+impl Includes {
+    /// Fails with 400 on a relation `T` doesn't have.
+    pub fn parse<T: Includable>(include: Option<&str>) -> Result<Includes, ErrorResponse> {
+        let mut includes = vec![];
+        for name in include.unwrap_or_default().split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let name = T::INCLUDABLE.iter().find(|allowed| **allowed == name).ok_or_else(|| ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some(format!("Cannot include '{}'; allowed relations are: {}", name, T::INCLUDABLE.join(", "))),
+                Some("FAILED".to_string()),
+            ))?;
+            if !includes.contains(name) {
+                includes.push(*name);
+            }
+        }
+        Ok(Includes(includes))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains(&name)
+    }
+}
+
+/// A row with the related rows it was asked to include nested under it, by
+/// relation name.
+#[derive(Debug, Clone, Serialize)]
+pub struct Included<T> {
+    #[serde(flatten)]
+    pub row: T,
+    #[serde(flatten)]
+    pub relations: BTreeMap<&'static str, Value>,
+}
+
+impl<T> Included<T> {
+    pub fn wrap(rows: Vec<T>) -> Vec<Included<T>> {
+        rows.into_iter().map(|row| Included { row, relations: BTreeMap::new() }).collect()
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Included<U> {
+        Included { row: f(self.row), relations: self.relations }
+    }
+}
+
+/// The distinct non-null keys of `rows`, to load their relations in one query.
+pub fn keys<T, K: Eq + Hash + Clone>(rows: &[Included<T>], key: impl Fn(&T) -> Option<K>) -> Vec<K> {
+    let mut keys: Vec<K> = vec![];
+    for key in rows.iter().filter_map(|row| key(&row.row)) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+/// Nests under `name` the related row each row's `foreign_key` points at, or
+/// null when there is none.
+pub fn belongs_to<T, R: Serialize, K: Eq + Hash>(
+    rows: &mut [Included<T>],
+    name: &'static str,
+    related: Vec<R>,
+    key: impl Fn(&R) -> K,
+    foreign_key: impl Fn(&T) -> Option<K>,
+) {
+    let related: HashMap<K, Value> = related.into_iter()
+        .map(|row| (key(&row), serde_json::to_value(row).unwrap_or_default()))
+        .collect();
+    for row in rows {
+        let value = foreign_key(&row.row).and_then(|key| related.get(&key).cloned()).unwrap_or_default();
+        row.relations.insert(name, value);
+    }
+}
+
+/// Nests under `name` the related rows whose `foreign_key` points at each row.
+pub fn has_many<T, R: Serialize, K: Eq + Hash>(
+    rows: &mut [Included<T>],
+    name: &'static str,
+    related: Vec<R>,
+    foreign_key: impl Fn(&R) -> K,
+    key: impl Fn(&T) -> K,
+) {
+    let mut grouped: HashMap<K, Vec<Value>> = HashMap::new();
+    for row in related {
+        grouped.entry(foreign_key(&row)).or_default().push(serde_json::to_value(row).unwrap_or_default());
+    }
+    for row in rows {
+        let value = grouped.remove(&key(&row.row)).unwrap_or_default();
+        row.relations.insert(name, Value::Array(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    struct Parent;
+
+    impl Includable for Parent {
+        const INCLUDABLE: &'static [&'static str] = &["owner", "children"];
+    }
+
+    #[test]
+    fn parses_allowed_relations_only() {
+        let includes = Includes::parse::<Parent>(Some("owner, children,owner")).unwrap();
+        assert_eq!(includes, Includes(vec!["owner", "children"]));
+
+        let err = Includes::parse::<Parent>(Some("password")).unwrap_err();
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn nests_related_rows() {
+        let mut rows = Included::wrap(vec![json!({"id": 1, "owner_id": 7}), json!({"id": 2, "owner_id": null})]);
+
+        assert_eq!(keys(&rows, |row| row["owner_id"].as_i64()), vec![7]);
+        belongs_to(&mut rows, "owner", vec![json!({"id": 7})], |owner| owner["id"].as_i64().unwrap(), |row| row["owner_id"].as_i64());
+        has_many(&mut rows, "children", vec![json!({"parent_id": 1}), json!({"parent_id": 1})], |child| child["parent_id"].as_i64().unwrap(), |row| row["id"].as_i64().unwrap());
+
+        assert_eq!(serde_json::to_value(&rows).unwrap(), json!([
+            {"id": 1, "owner_id": 7, "owner": {"id": 7}, "children": [{"parent_id": 1}, {"parent_id": 1}]},
+            {"id": 2, "owner_id": null, "owner": null, "children": []},
+        ]));
+    }
+}
@@ -0,0 +1,68 @@
+use actix_web::http::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use crate::helpers::custom_error::ErrorResponse;
+
+/// Applies an RFC 7396 JSON Merge Patch: objects merge key by key, `null`
+/// removes a key and anything else replaces the target.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Merges `patch` over `current` and reads the result back, failing with 400
+/// when it no longer fits `T`.
+pub fn apply<T: Serialize + DeserializeOwned>(current: &T, patch: &Value) -> Result<T, ErrorResponse> {
+    let mut target = serde_json::to_value(current).map_err(|err| ErrorResponse::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Some(err.to_string()),
+        Some("FAILED".to_string()),
+    ))?;
+    merge(&mut target, patch);
+
+    serde_json::from_value(target).map_err(|err| ErrorResponse::new(
+        StatusCode::BAD_REQUEST,
+        Some(format!("Invalid patch: {}", err)),
+        Some("FAILED".to_string()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn merges_objects_and_removes_nulls() {
+        let mut target = json!({"a": "b", "c": {"d": "e", "f": "g"}});
+
+        merge(&mut target, &json!({"a": "z", "c": {"f": null}}));
+
+        assert_eq!(target, json!({"a": "z", "c": {"d": "e"}}));
+    }
+
+    #[test]
+    fn non_object_patch_replaces_target() {
+        let mut target = json!({"a": "b"});
+
+        merge(&mut target, &json!(["c"]));
+
+        assert_eq!(target, json!(["c"]));
+    }
+}
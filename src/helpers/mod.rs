@@ -1,3 +1,9 @@
 pub mod custom_response;
 pub mod custom_error;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod build_info;
+pub mod request_context;
+pub mod precondition;
+pub mod merge_patch;
+pub mod include;
+pub mod fields;
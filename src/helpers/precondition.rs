@@ -0,0 +1,97 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::HttpRequest;
+use crate::helpers::custom_error::ErrorResponse;
+
+/// The `If-Match` header of a write, checked against the row version that
+/// the ETag of reads carries.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfMatch {
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl IfMatch {
+    /// Fails with 428 when the header is missing, so that clients can't
+    /// overwrite a change they never saw by forgetting it.
+    pub fn from_request(req: &HttpRequest) -> Result<IfMatch, ErrorResponse> {
+        let values: Vec<&str> = req.headers()
+            .get_all(header::IF_MATCH)
+            .filter_map(|value| value.to_str().ok())
+            .collect();
+
+        if values.is_empty() {
+            return Err(ErrorResponse::new(
+                StatusCode::PRECONDITION_REQUIRED,
+                Some("If-Match header is required".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let tags: Vec<&str> = values.iter()
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        if tags.contains(&"*") {
+            return Ok(IfMatch::Any);
+        }
+
+        // Weak and malformed tags never match, as If-Match compares strongly.
+        Ok(IfMatch::Versions(tags.into_iter().filter_map(parse_etag).collect()))
+    }
+
+    /// Fails with 412 unless the header matches `version`.
+    pub fn check(&self, version: i32) -> Result<(), ErrorResponse> {
+        match self {
+            IfMatch::Any => Ok(()),
+            IfMatch::Versions(versions) if versions.contains(&version) => Ok(()),
+            IfMatch::Versions(_) => Err(precondition_failed()),
+        }
+    }
+}
+
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// The write lost a race with another one after the version was checked.
+pub fn precondition_failed() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::PRECONDITION_FAILED,
+        Some("Resource was modified by another request".to_string()),
+        Some("FAILED".to_string()),
+    )
+}
+
+fn parse_etag(tag: &str) -> Option<i32> {
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+    use super::*;
+
+    fn if_match(value: &str) -> IfMatch {
+        IfMatch::from_request(&TestRequest::default().insert_header((header::IF_MATCH, value)).to_http_request()).unwrap()
+    }
+
+    #[test]
+    fn missing_header_is_required() {
+        let err = IfMatch::from_request(&TestRequest::default().to_http_request()).unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::PRECONDITION_REQUIRED);
+    }
+
+    #[test]
+    fn matches_strong_tags_only() {
+        assert_eq!(if_match("\"3\", \"4\""), IfMatch::Versions(vec![3, 4]));
+        assert!(if_match("\"3\"").check(3).is_ok());
+        assert_eq!(if_match("W/\"3\"").check(3).unwrap_err().err_type, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(if_match("3").check(3).unwrap_err().err_type, StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[test]
+    fn star_matches_any_version() {
+        assert!(if_match("*").check(7).is_ok());
+    }
+}
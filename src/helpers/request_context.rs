@@ -0,0 +1,20 @@
+/// Who made the request being served and from where, as far as the
+/// credentials it carried show.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    // The `sub` of a valid bearer token.
+    pub actor_id: Option<String>,
+    // The email of a valid bearer token, or the username of valid basic auth.
+    pub actor: Option<String>,
+    pub ip: Option<String>,
+}
+
+tokio::task_local! {
+    // Context of the request being served, for code that has no `HttpRequest`.
+    pub static CURRENT_REQUEST_CONTEXT: RequestContext;
+}
+
+// Empty outside a request, as in jobs and commands.
+pub fn current_request_context() -> RequestContext {
+    CURRENT_REQUEST_CONTEXT.try_with(|context| context.clone()).unwrap_or_default()
+}
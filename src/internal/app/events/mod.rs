@@ -0,0 +1,127 @@
+pub mod relay;
+pub mod webhooks;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::entities::event::PublishedEvent;
+
+/// Reacts to domain events. Each event reaches each subscriber at least once,
+/// through its own job, so a handler should tolerate seeing an event twice;
+/// `PublishedEvent::id` tells repeats apart. An `Err` retries just this
+/// subscriber.
+#[async_trait]
+pub trait EventSubscriber: Send + Sync {
+    // Stored on queued deliveries, so renaming a subscriber strands them.
+    fn name(&self) -> &'static str;
+    async fn handle(&self, event: &PublishedEvent) -> Result<(), String>;
+}
+
+/// The subscribers that every domain event is handed to.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: HashMap<&'static str, Arc<dyn EventSubscriber>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(mut self, subscriber: impl EventSubscriber + 'static) -> Self {
+        self.subscribers.insert(subscriber.name(), Arc::new(subscriber));
+        self
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.subscribers.keys().map(|name| name.to_string()).collect();
+        names.sort();
+        names
+    }
+}
+
+/// Hands one event to one subscriber; queued by the outbox relay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliverEvent {
+    pub subscriber: String,
+    pub event: PublishedEvent,
+}
+
+impl JobDefinition for DeliverEvent {
+    const KIND: &'static str = "deliver_event";
+}
+
+pub struct DeliverEventHandler {
+    bus: EventBus,
+}
+
+impl DeliverEventHandler {
+    pub fn new(bus: EventBus) -> Self {
+        Self { bus }
+    }
+}
+
+#[async_trait]
+impl JobHandler<DeliverEvent> for DeliverEventHandler {
+    async fn handle(&self, job: DeliverEvent, _cancel: CancellationToken) -> Result<(), String> {
+        match self.bus.subscribers.get(job.subscriber.as_str()) {
+            Some(subscriber) => subscriber.handle(&job.event).await,
+            None => Err(format!("No subscriber is named {}", job.subscriber)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use uuid::Uuid;
+    use super::*;
+    use crate::internal::app::events::relay::OutboxRelay;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::internal::entities::event::DomainEvent;
+
+    #[derive(Clone, Default)]
+    struct Recorder {
+        events: Arc<Mutex<Vec<PublishedEvent>>>,
+    }
+
+    #[async_trait]
+    impl EventSubscriber for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+
+        async fn handle(&self, event: &PublishedEvent) -> Result<(), String> {
+            self.events.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn relayed_events_are_delivered_once_per_subscriber() {
+        let database = InMemoryDatabase::new();
+        let r = database.repositories();
+        let event = DomainEvent::UserDeleted { user_id: Uuid::new_v4() };
+        r.outbox.append(std::slice::from_ref(&event)).await.unwrap();
+
+        let recorder = Recorder::default();
+        let bus = EventBus::new().subscribe(recorder.clone());
+        let relay = OutboxRelay::new(r.outbox, &bus, Duration::from_secs(1));
+        assert_eq!(relay.run_once().await.unwrap(), 1);
+        assert_eq!(relay.run_once().await.unwrap(), 0);
+
+        let job = database.tables().jobs.pop().unwrap();
+        assert_eq!(job.kind, DeliverEvent::KIND);
+        let delivery: DeliverEvent = serde_json::from_value(job.payload).unwrap();
+        DeliverEventHandler::new(bus).handle(delivery, CancellationToken::new()).await.unwrap();
+
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, event);
+        assert_eq!(events[0].id, database.tables().outbox_events[0].id);
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+use crate::internal::app::events::{DeliverEvent, EventBus};
+use crate::internal::app::jobs::JobDefinition;
+use crate::internal::app::repositories::outbox_repository::OutboxRepository;
+use crate::pkg::supervisor::TaskSupervisor;
+
+const BATCH_SIZE: u32 = 100;
+
+/// Moves committed events from the outbox to the job queue, one delivery per
+/// subscriber. Any number of instances can run one.
+#[derive(Clone)]
+pub struct OutboxRelay {
+    repository: Arc<dyn OutboxRepository>,
+    subscribers: Vec<String>,
+    poll_interval: Duration,
+}
+
+impl OutboxRelay {
+    pub fn new(repository: Arc<dyn OutboxRepository>, bus: &EventBus, poll_interval: Duration) -> Self {
+        Self { repository, subscribers: bus.names(), poll_interval }
+    }
+
+    pub fn start(&self, supervisor: &TaskSupervisor) {
+        info!(subscribers = ?self.subscribers, "Starting outbox relay");
+        let relay = self.clone();
+        supervisor.spawn("outbox_relay", move |cancel| async move { relay.run(cancel).await });
+    }
+
+    async fn run(&self, cancel: CancellationToken) {
+        while !cancel.is_cancelled() {
+            if let Err(err) = self.run_once().await {
+                error!(error = %err, "Failed to relay outbox events");
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = tokio::time::sleep(self.poll_interval) => {}
+            }
+        }
+    }
+
+    /// Relays every event waiting in the outbox. Returns how many there were.
+    pub async fn run_once(&self) -> Result<u64, Error> {
+        let mut total = 0;
+        loop {
+            let relayed = self.repository
+                .relay(BATCH_SIZE, DeliverEvent::KIND, &self.subscribers, DeliverEvent::MAX_ATTEMPTS)
+                .await?;
+            total += relayed;
+            if relayed < BATCH_SIZE as u64 {
+                break;
+            }
+        }
+        if total > 0 {
+            debug!(events = total, "Relayed outbox events");
+        }
+        Ok(total)
+    }
+}
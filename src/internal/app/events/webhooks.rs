@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use sqlx::Error;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use crate::internal::app::events::EventSubscriber;
+use crate::internal::app::jobs::{JobDefinition, JobHandler, JobQueue};
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::webhook_repository::WebhookRepository;
+use crate::internal::entities::event::PublishedEvent;
+use crate::internal::entities::webhook::{DeliveryAttempt, Webhook, WebhookDelivery, WebhookDeliveryStatus};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+// How much of a response body the delivery log keeps.
+const RESPONSE_BODY_LIMIT: usize = 1024;
+
+/// `sha256=` and the hex HMAC-SHA256, keyed with the webhook's secret, of
+/// the timestamp, a dot and the body. Receivers recompute it to check that a
+/// delivery came from us, and reject old timestamps to stop replays.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Posts deliveries to their webhooks and logs how each attempt went.
+#[derive(Clone)]
+pub struct WebhookSender {
+    repository: Arc<dyn WebhookRepository>,
+    client: reqwest::Client,
+}
+
+impl WebhookSender {
+    pub fn new(repository: Arc<dyn WebhookRepository>) -> Self {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build().unwrap_or_default();
+        Self { repository, client }
+    }
+
+    /// Makes one attempt at `delivery`. Unless it succeeds the delivery stays
+    /// pending, or is marked failed if this was its `last` attempt.
+    pub async fn send(&self, webhook: &Webhook, delivery: &WebhookDelivery, last: bool) -> Result<WebhookDelivery, Error> {
+        let body = serde_json::to_vec(&delivery.payload).map_err(|err| Error::Encode(Box::new(err)))?;
+        let timestamp = Utc::now().timestamp();
+        let response = self.client.post(&webhook.url)
+            .header("Content-Type", "application/json")
+            .header("X-Sekula-Event", &delivery.event_type)
+            .header("X-Sekula-Delivery", delivery.id.to_string())
+            .header("X-Sekula-Timestamp", timestamp.to_string())
+            .header("X-Sekula-Signature", sign(&webhook.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await;
+
+        let failed = if last { WebhookDeliveryStatus::Failed } else { WebhookDeliveryStatus::Pending };
+        let attempt = match response {
+            Ok(response) => {
+                let code = response.status();
+                let response_body = read_limited(response).await;
+                DeliveryAttempt {
+                    status: if code.is_success() { WebhookDeliveryStatus::Succeeded } else { failed },
+                    response_status: Some(code.as_u16() as i32),
+                    response_body: Some(response_body),
+                    error: (!code.is_success()).then(|| format!("Endpoint responded with {}", code)),
+                }
+            }
+            Err(err) => DeliveryAttempt {
+                status: failed,
+                response_status: None,
+                response_body: None,
+                error: Some(err.to_string()),
+            },
+        };
+        self.repository.record_attempt(delivery.id, &attempt).await
+    }
+}
+
+// The start of a response body, read no further than the delivery log keeps.
+async fn read_limited(mut response: reqwest::Response) -> String {
+    let mut body = Vec::new();
+    while body.len() < RESPONSE_BODY_LIMIT {
+        match response.chunk().await {
+            Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+            Ok(None) | Err(_) => break,
+        }
+    }
+    let mut body = String::from_utf8_lossy(&body).into_owned();
+    body.truncate(body.floor_char_boundary(RESPONSE_BODY_LIMIT));
+    body
+}
+
+/// Logs a delivery for each webhook that wants an event and queues a job to
+/// send it.
+pub struct WebhookSubscriber {
+    repository: Arc<dyn WebhookRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+}
+
+impl WebhookSubscriber {
+    pub fn new(repository: Arc<dyn WebhookRepository>, db_transaction_repository: Arc<dyn DbTransactionRepository>) -> Self {
+        Self { repository, db_transaction_repository }
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for WebhookSubscriber {
+    fn name(&self) -> &'static str {
+        "webhooks"
+    }
+
+    async fn handle(&self, event: &PublishedEvent) -> Result<(), String> {
+        let event_type = event.event.event_type();
+        let webhooks = self.repository.matching(event_type, event.event.school_id()).await.map_err(|err| err.to_string())?;
+        let payload = serde_json::to_value(event).map_err(|err| err.to_string())?;
+
+        for webhook in webhooks {
+            let delivery = WebhookDelivery::new(webhook.id, event.id, event_type, payload.clone());
+            with_transaction(&*self.db_transaction_repository, |tx| async move {
+                // Already there if this event was handed over before.
+                if tx.webhook.create_delivery(&delivery).await? {
+                    JobQueue::new(tx.job.clone()).enqueue(&DeliverWebhook { delivery_id: delivery.id }).await?;
+                }
+                Ok::<_, Error>(())
+            }).await.map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Sends one logged delivery; retried with the queue's backoff until the
+/// endpoint answers with a 2xx.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliverWebhook {
+    pub delivery_id: Uuid,
+}
+
+impl JobDefinition for DeliverWebhook {
+    const KIND: &'static str = "deliver_webhook";
+    // Backing off from 10 seconds, the last attempt comes about 20 minutes
+    // after the first.
+    const MAX_ATTEMPTS: i32 = 8;
+}
+
+pub struct DeliverWebhookHandler {
+    repository: Arc<dyn WebhookRepository>,
+    sender: WebhookSender,
+}
+
+impl DeliverWebhookHandler {
+    pub fn new(repository: Arc<dyn WebhookRepository>, sender: WebhookSender) -> Self {
+        Self { repository, sender }
+    }
+}
+
+#[async_trait]
+impl JobHandler<DeliverWebhook> for DeliverWebhookHandler {
+    async fn handle(&self, job: DeliverWebhook, _cancel: CancellationToken) -> Result<(), String> {
+        let delivery = match self.repository.get_delivery(job.delivery_id).await {
+            Ok(delivery) => delivery,
+            // Deleted along with its webhook.
+            Err(Error::RowNotFound) => return Ok(()),
+            Err(err) => return Err(err.to_string()),
+        };
+        if delivery.status != WebhookDeliveryStatus::Pending {
+            return Ok(());
+        }
+        let webhook = match self.repository.get_by_id(delivery.webhook_id).await {
+            Ok(webhook) => webhook,
+            Err(Error::RowNotFound) => return Ok(()),
+            Err(err) => return Err(err.to_string()),
+        };
+
+        let last = delivery.attempts + 1 >= DeliverWebhook::MAX_ATTEMPTS;
+        let delivery = self.sender.send(&webhook, &delivery, last).await.map_err(|err| err.to_string())?;
+        match delivery.status {
+            WebhookDeliveryStatus::Succeeded => Ok(()),
+            _ => Err(delivery.error.unwrap_or_else(|| "Delivery failed".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        // echo -n '1700000000.{"a":1}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            sign("secret", 1_700_000_000, br#"{"a":1}"#),
+            "sha256=49f24e537407743fa4a0242bb63b94b9a47ee99cbbe071ccd8a22550ae411686",
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::school_repository::SchoolRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::event::DomainEvent;
+use crate::internal::entities::school::School;
+
+/// Takes schools off subscriptions that ended at or before `now`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckSubscriptionExpiry {
+    pub now: DateTime<Utc>,
+}
+
+impl JobDefinition for CheckSubscriptionExpiry {
+    const KIND: &'static str = "check_subscription_expiry";
+}
+
+pub struct CheckSubscriptionExpiryHandler {
+    repository: Arc<dyn SchoolRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+}
+
+impl CheckSubscriptionExpiryHandler {
+    pub fn new(repository: Arc<dyn SchoolRepository>, db_transaction_repository: Arc<dyn DbTransactionRepository>) -> Self {
+        Self { repository, db_transaction_repository }
+    }
+}
+
+#[async_trait]
+impl JobHandler<CheckSubscriptionExpiry> for CheckSubscriptionExpiryHandler {
+    async fn handle(&self, job: CheckSubscriptionExpiry, cancel: CancellationToken) -> Result<(), String> {
+        let schools = self.repository.get_expired_subscriptions(job.now).await.map_err(|err| err.to_string())?;
+
+        let mut expired = 0;
+        for school in schools {
+            if cancel.is_cancelled() {
+                return Err("Interrupted by shutdown".to_string());
+            }
+            // The end date stays, so clients can still see when the
+            // subscription ran out.
+            let Some(subscription_id) = school.subscription_id else { continue };
+            let after = School { subscription_id: None, updated_at: Utc::now(), ..school.clone() };
+            let result = with_transaction(&*self.db_transaction_repository, move |tx| async move {
+                let updated = tx.school.update(&after).await?;
+                tx.audit_log.record(&AuditLog::updated(&school, &updated)).await?;
+                tx.outbox.append(&[DomainEvent::SchoolSubscriptionExpired { school_id: school.id, subscription_id }]).await?;
+                Ok::<_, sqlx::Error>(())
+            }).await;
+            match result {
+                Ok(()) => expired += 1,
+                // Edited since it was read; the next run looks at it again.
+                Err(sqlx::Error::RowNotFound) => {}
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        info!(expired, "Expired school subscriptions");
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::app::repositories::maintenance_repository::MaintenanceRepository;
+use crate::pkg::s3::FileStorage;
+
+// Uploads are stored before the row that points at them, so anything younger
+// than this may still be waiting for its row.
+const GRACE_PERIOD: chrono::Duration = chrono::Duration::hours(24);
+
+// Folders the application uploads into.
+const UPLOAD_PREFIXES: &[&str] = &["school-logo/"];
+
+/// Deletes uploaded files that no row refers to any more.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CleanUploads {}
+
+impl JobDefinition for CleanUploads {
+    const KIND: &'static str = "clean_uploads";
+}
+
+pub struct CleanUploadsHandler {
+    repository: Arc<dyn MaintenanceRepository>,
+    storage: Arc<dyn FileStorage>,
+}
+
+impl CleanUploadsHandler {
+    pub fn new(repository: Arc<dyn MaintenanceRepository>, storage: Arc<dyn FileStorage>) -> Self {
+        Self { repository, storage }
+    }
+}
+
+#[async_trait]
+impl JobHandler<CleanUploads> for CleanUploadsHandler {
+    async fn handle(&self, _job: CleanUploads, cancel: CancellationToken) -> Result<(), String> {
+        // Listed before the references are read, so a file uploaded and
+        // referenced in between is never mistaken for an orphan.
+        let mut objects = Vec::new();
+        for prefix in UPLOAD_PREFIXES {
+            objects.extend(self.storage.list(prefix).await.map_err(|err| err.to_string())?);
+        }
+        let referenced = self.repository.referenced_files().await.map_err(|err| err.to_string())?;
+        let cutoff = Utc::now() - GRACE_PERIOD;
+
+        let mut deleted = 0;
+        for object in objects {
+            if cancel.is_cancelled() {
+                return Err("Interrupted by shutdown".to_string());
+            }
+            if object.last_modified < cutoff && !referenced.contains(&object.path) {
+                self.storage.delete(&object.path).await.map_err(|err| err.to_string())?;
+                deleted += 1;
+            }
+        }
+        info!(deleted, "Removed orphaned uploads");
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+pub mod worker;
+pub mod scheduler;
+pub mod sync_cities;
+pub mod sync_regions;
+pub mod purge_deleted;
+pub mod clean_uploads;
+pub mod check_subscription_expiry;
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::Error;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use crate::internal::app::repositories::job_repository::JobRepository;
+use crate::internal::entities::job::{Job, JobStatus};
+
+/// A kind of job, stored as its JSON serialisation under `KIND`.
+pub trait JobDefinition: Serialize + DeserializeOwned + Send + Sync + 'static {
+    const KIND: &'static str;
+    const MAX_ATTEMPTS: i32 = 5;
+}
+
+/// Runs jobs of one kind. An `Err` is recorded on the job and the job is
+/// retried until it runs out of attempts. `cancel` fires on shutdown; a
+/// handler that stops early should return an error so the job runs again.
+#[async_trait]
+pub trait JobHandler<J: JobDefinition>: Send + Sync {
+    async fn handle(&self, job: J, cancel: CancellationToken) -> Result<(), String>;
+}
+
+#[async_trait]
+trait ErasedHandler: Send + Sync {
+    async fn run(&self, payload: Value, cancel: CancellationToken) -> Result<(), String>;
+}
+
+struct Typed<J, H> {
+    handler: H,
+    job: PhantomData<fn() -> J>,
+}
+
+#[async_trait]
+impl<J: JobDefinition, H: JobHandler<J>> ErasedHandler for Typed<J, H> {
+    async fn run(&self, payload: Value, cancel: CancellationToken) -> Result<(), String> {
+        let job: J = serde_json::from_value(payload).map_err(|err| format!("Invalid payload: {}", err))?;
+        self.handler.handle(job, cancel).await
+    }
+}
+
+/// Maps each job kind to its handler.
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, Arc<dyn ErasedHandler>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<J: JobDefinition>(mut self, handler: impl JobHandler<J> + 'static) -> Self {
+        self.handlers.insert(J::KIND, Arc::new(Typed { handler, job: PhantomData }));
+        self
+    }
+
+    pub async fn run(&self, job: &Job, cancel: CancellationToken) -> Result<(), String> {
+        match self.handlers.get(job.kind.as_str()) {
+            Some(handler) => handler.run(job.payload.clone(), cancel).await,
+            None => Err(format!("No handler is registered for job kind {}", job.kind)),
+        }
+    }
+}
+
+/// Adds jobs to the queue for a worker to pick up.
+#[derive(Clone)]
+pub struct JobQueue {
+    repository: Arc<dyn JobRepository>,
+}
+
+impl JobQueue {
+    pub fn new(repository: Arc<dyn JobRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn enqueue<J: JobDefinition>(&self, job: &J) -> Result<Job, Error> {
+        let payload = serde_json::to_value(job).map_err(|err| Error::Encode(Box::new(err)))?;
+        let job = Job {
+            id: Uuid::new_v4(),
+            kind: J::KIND.to_string(),
+            payload,
+            status: JobStatus::Pending,
+            attempts: 0,
+            max_attempts: J::MAX_ATTEMPTS,
+            run_at: Utc::now(),
+            locked_at: None,
+            last_error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        self.repository.enqueue(&job).await?;
+        Ok(job)
+    }
+}
+
+const BACKOFF_BASE: Duration = Duration::from_secs(10);
+const BACKOFF_MAX: Duration = Duration::from_secs(60 * 60);
+
+/// Wait before the next attempt after `attempts` failed ones: 10s, 20s, 40s,
+/// ... up to an hour.
+pub fn backoff(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    BACKOFF_BASE.saturating_mul(2u32.pow(exponent)).min(BACKOFF_MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_an_hour() {
+        assert_eq!(backoff(1), Duration::from_secs(10));
+        assert_eq!(backoff(2), Duration::from_secs(20));
+        assert_eq!(backoff(4), Duration::from_secs(80));
+        assert_eq!(backoff(20), Duration::from_secs(3600));
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::app::repositories::idempotency_repository::IdempotencyRepository;
+use crate::internal::app::repositories::maintenance_repository::MaintenanceRepository;
+
+/// Permanently deletes rows that were soft-deleted before `before`, along
+/// with expired idempotency keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeDeleted {
+    pub before: DateTime<Utc>,
+}
+
+impl JobDefinition for PurgeDeleted {
+    const KIND: &'static str = "purge_deleted";
+}
+
+pub struct PurgeDeletedHandler {
+    repository: Arc<dyn MaintenanceRepository>,
+    idempotency_repository: Arc<dyn IdempotencyRepository>,
+}
+
+impl PurgeDeletedHandler {
+    pub fn new(repository: Arc<dyn MaintenanceRepository>, idempotency_repository: Arc<dyn IdempotencyRepository>) -> Self {
+        Self { repository, idempotency_repository }
+    }
+}
+
+#[async_trait]
+impl JobHandler<PurgeDeleted> for PurgeDeletedHandler {
+    async fn handle(&self, job: PurgeDeleted, _cancel: CancellationToken) -> Result<(), String> {
+        let purged = self.repository.purge_deleted(job.before).await.map_err(|err| err.to_string())?;
+        for (table, rows) in purged {
+            info!(table, rows, "Purged soft-deleted rows");
+        }
+        let rows = self.idempotency_repository.purge_expired(Utc::now()).await.map_err(|err| err.to_string())?;
+        info!(rows, "Purged expired idempotency keys");
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use futures_util::future::BoxFuture;
+use sqlx::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+use crate::internal::app::jobs::{JobDefinition, JobQueue};
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::schedule_repository::{Leadership, ScheduleRepository};
+use crate::internal::entities::job::Job;
+use crate::pkg::supervisor::TaskSupervisor;
+
+// How often the leader looks for due schedules, and how often the others try
+// to take over.
+const TICK: Duration = Duration::from_secs(10);
+
+type Enqueue = dyn Fn(JobQueue, DateTime<Utc>) -> BoxFuture<'static, Result<Job, Error>> + Send + Sync;
+
+/// Queues a job of kind `J` whenever `schedule` fires. The schedule is named
+/// after the job kind.
+pub struct ScheduledJob {
+    pub name: &'static str,
+    pub schedule: Schedule,
+    enqueue: Box<Enqueue>,
+}
+
+impl ScheduledJob {
+    // `build` gets the time the schedule fired and returns the job to queue.
+    pub fn new<J: JobDefinition>(schedule: Schedule, build: impl Fn(DateTime<Utc>) -> J + Send + Sync + 'static) -> Self {
+        let enqueue = move |queue: JobQueue, now: DateTime<Utc>| -> BoxFuture<'static, Result<Job, Error>> {
+            let job = build(now);
+            Box::pin(async move { queue.enqueue(&job).await })
+        };
+        Self { name: J::KIND, schedule, enqueue: Box::new(enqueue) }
+    }
+}
+
+/// Fires schedules by queueing jobs for the workers. Every instance runs one,
+/// but only the instance holding the leader lock fires anything.
+#[derive(Clone)]
+pub struct Scheduler {
+    schedules: Arc<Vec<ScheduledJob>>,
+    repository: Arc<dyn ScheduleRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+    started_at: DateTime<Utc>,
+}
+
+impl Scheduler {
+    pub fn new(schedules: Vec<ScheduledJob>, repository: Arc<dyn ScheduleRepository>, db_transaction_repository: Arc<dyn DbTransactionRepository>) -> Self {
+        Self { schedules: Arc::new(schedules), repository, db_transaction_repository, started_at: Utc::now() }
+    }
+
+    pub fn schedules(&self) -> &[ScheduledJob] {
+        &self.schedules
+    }
+
+    /// When `schedule` fires next, given when it last did. A schedule that
+    /// never fired counts from startup, so a new deploy doesn't run everything
+    /// at once.
+    pub fn next_run(&self, schedule: &ScheduledJob, last_run_at: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+        let since = last_run_at.unwrap_or(self.started_at);
+        schedule.schedule.after(&since).next()
+    }
+
+    pub fn start(&self, supervisor: &TaskSupervisor) {
+        info!(schedules = self.schedules.len(), "Starting scheduler");
+        let scheduler = self.clone();
+        supervisor.spawn("scheduler", move |cancel| async move { scheduler.run(cancel).await });
+    }
+
+    async fn run(&self, cancel: CancellationToken) {
+        while !cancel.is_cancelled() {
+            match self.repository.try_lead().await {
+                Ok(Some(leadership)) => self.lead(leadership, &cancel).await,
+                Ok(None) => {}
+                Err(err) => error!(error = %err, "Failed to contend for scheduler leadership"),
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = tokio::time::sleep(TICK) => {}
+            }
+        }
+    }
+
+    async fn lead(&self, mut leadership: Box<dyn Leadership>, cancel: &CancellationToken) {
+        info!("Became scheduler leader");
+        while !cancel.is_cancelled() && leadership.is_held().await {
+            if let Err(err) = self.run_due(Utc::now()).await {
+                error!(error = %err, "Failed to run due schedules");
+            }
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = tokio::time::sleep(TICK) => {}
+            }
+        }
+        leadership.release().await;
+        info!("Gave up scheduler leadership");
+    }
+
+    /// Queues a job for every schedule due at `now`. A schedule that was due
+    /// several times while no leader ran fires once, not once per miss. The
+    /// job and the record of the run are stored together, so a failure in
+    /// between can't fire the schedule twice.
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Result<Vec<Job>, Error> {
+        let runs = self.repository.list().await?;
+        let mut queued = Vec::new();
+
+        for schedule in self.schedules.iter() {
+            let last_run_at = runs.iter().find(|run| run.name == schedule.name).map(|run| run.last_run_at);
+            match self.next_run(schedule, last_run_at) {
+                Some(next) if next <= now => {}
+                _ => continue,
+            }
+
+            let job = with_transaction(&*self.db_transaction_repository, |tx| async move {
+                let job = (schedule.enqueue)(JobQueue::new(tx.job.clone()), now).await?;
+                tx.schedule.record(schedule.name, now, job.id).await?;
+                Ok::<_, Error>(job)
+            }).await?;
+            info!(schedule = schedule.name, job_id = %job.id, "Schedule fired");
+            queued.push(job);
+        }
+
+        Ok(queued)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use serde::{Deserialize, Serialize};
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+
+    #[derive(Serialize, Deserialize)]
+    struct Tick {
+        fired_at: DateTime<Utc>,
+    }
+
+    impl JobDefinition for Tick {
+        const KIND: &'static str = "tick";
+    }
+
+    fn scheduler(database: &InMemoryDatabase, expression: &str) -> Scheduler {
+        let r = database.repositories();
+        let schedule = ScheduledJob::new(Schedule::from_str(expression).unwrap(), |fired_at| Tick { fired_at });
+        Scheduler::new(vec![schedule], r.schedule, r.db_transaction)
+    }
+
+    #[tokio::test]
+    async fn fires_once_when_due() {
+        let database = InMemoryDatabase::new();
+        let scheduler = scheduler(&database, "0 * * * * *");
+        let start = scheduler.started_at;
+
+        assert!(scheduler.run_due(start).await.unwrap().is_empty());
+
+        // Three minutes late still fires only once.
+        let late = start + chrono::Duration::minutes(3);
+        let queued = scheduler.run_due(late).await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].kind, "tick");
+        assert_eq!(queued[0].payload["fired_at"], serde_json::to_value(late).unwrap());
+        assert!(scheduler.run_due(late).await.unwrap().is_empty());
+
+        let runs = database.tables().schedule_runs.clone();
+        assert_eq!(runs[0].last_run_at, late);
+        assert_eq!(runs[0].last_job_id, Some(queued[0].id));
+    }
+
+    #[tokio::test]
+    async fn only_one_leader_at_a_time() {
+        let r = InMemoryDatabase::new().repositories();
+
+        let leader = r.schedule.try_lead().await.unwrap().expect("first instance leads");
+        assert!(r.schedule.try_lead().await.unwrap().is_none());
+
+        leader.release().await;
+        assert!(r.schedule.try_lead().await.unwrap().is_some());
+    }
+}
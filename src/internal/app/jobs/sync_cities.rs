@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::app::usecases::city_usecase::CityUseCase;
+
+/// Imports the cities of every stored province from wilayah.id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncCities {}
+
+impl JobDefinition for SyncCities {
+    const KIND: &'static str = "sync_cities";
+    const MAX_ATTEMPTS: i32 = 3;
+}
+
+pub struct SyncCitiesHandler {
+    usecase: Arc<dyn CityUseCase>,
+}
+
+impl SyncCitiesHandler {
+    pub fn new(usecase: Arc<dyn CityUseCase>) -> Self {
+        Self { usecase }
+    }
+}
+
+#[async_trait]
+impl JobHandler<SyncCities> for SyncCitiesHandler {
+    async fn handle(&self, _job: SyncCities, cancel: CancellationToken) -> Result<(), String> {
+        self.usecase.sync(cancel.clone()).await.map_err(|err| err.to_string())?;
+        // The sync resumes where it stopped, so another attempt finishes it.
+        if cancel.is_cancelled() {
+            return Err("Interrupted by shutdown".to_string());
+        }
+        Ok(())
+    }
+}
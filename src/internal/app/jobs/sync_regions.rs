@@ -0,0 +1,39 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use crate::internal::app::jobs::{JobDefinition, JobHandler};
+use crate::internal::app::usecases::city_usecase::CityUseCase;
+use crate::internal::app::usecases::province_usecase::ProvinceUseCase;
+
+/// Imports provinces and then their cities from wilayah.id, as `sync-regions` does.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncRegions {}
+
+impl JobDefinition for SyncRegions {
+    const KIND: &'static str = "sync_regions";
+    const MAX_ATTEMPTS: i32 = 3;
+}
+
+pub struct SyncRegionsHandler {
+    provinces: Arc<dyn ProvinceUseCase>,
+    cities: Arc<dyn CityUseCase>,
+}
+
+impl SyncRegionsHandler {
+    pub fn new(provinces: Arc<dyn ProvinceUseCase>, cities: Arc<dyn CityUseCase>) -> Self {
+        Self { provinces, cities }
+    }
+}
+
+#[async_trait]
+impl JobHandler<SyncRegions> for SyncRegionsHandler {
+    async fn handle(&self, _job: SyncRegions, cancel: CancellationToken) -> Result<(), String> {
+        self.provinces.create().await.map_err(|err| err.to_string())?;
+        self.cities.sync(cancel.clone()).await.map_err(|err| err.to_string())?;
+        if cancel.is_cancelled() {
+            return Err("Interrupted by shutdown".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use sqlx::Error;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, info_span, warn, Instrument};
+use crate::config::app_config::WorkerConfig;
+use crate::internal::app::jobs::{backoff, JobRegistry};
+use crate::internal::app::repositories::job_repository::JobRepository;
+use crate::internal::entities::job::Job;
+use crate::pkg::supervisor::TaskSupervisor;
+
+/// Workers that claim due jobs and run them with the registered handlers.
+#[derive(Clone)]
+pub struct WorkerPool {
+    repository: Arc<dyn JobRepository>,
+    registry: JobRegistry,
+    config: WorkerConfig,
+}
+
+impl WorkerPool {
+    pub fn new(repository: Arc<dyn JobRepository>, registry: JobRegistry, config: WorkerConfig) -> Self {
+        Self { repository, registry, config }
+    }
+
+    /// Starts `concurrency` workers on the supervisor, which stops them on shutdown.
+    pub fn start(&self, supervisor: &TaskSupervisor) {
+        info!(concurrency = self.config.concurrency, "Starting job workers");
+        for _ in 0..self.config.concurrency {
+            let pool = self.clone();
+            supervisor.spawn("job_worker", move |cancel| async move { pool.work(cancel).await });
+        }
+    }
+
+    async fn work(&self, cancel: CancellationToken) {
+        while !cancel.is_cancelled() {
+            let claimed = match self.run_once(&cancel).await {
+                Ok(claimed) => claimed,
+                Err(err) => {
+                    error!(error = %err, "Failed to claim jobs");
+                    0
+                }
+            };
+            if claimed == 0 {
+                tokio::select! {
+                    _ = cancel.cancelled() => {}
+                    _ = tokio::time::sleep(self.config.poll_interval) => {}
+                }
+            }
+        }
+    }
+
+    /// Claims one due job, if there is one, and runs it. Returns how many
+    /// jobs were run.
+    pub async fn run_once(&self, cancel: &CancellationToken) -> Result<usize, Error> {
+        let jobs = self.repository.claim(1, self.config.lock_timeout).await?;
+        let claimed = jobs.len();
+        for job in jobs {
+            let span = info_span!("job", id = %job.id, kind = %job.kind, attempt = job.attempts);
+            self.execute(job, cancel.clone()).instrument(span).await;
+        }
+        Ok(claimed)
+    }
+
+    async fn execute(&self, job: Job, cancel: CancellationToken) {
+        // Long jobs keep refreshing their lock so no other worker takes them
+        // over while this one is still running them.
+        let run = self.registry.run(&job, cancel);
+        tokio::pin!(run);
+        let mut heartbeat = tokio::time::interval((self.config.lock_timeout / 3).max(Duration::from_secs(1)));
+        heartbeat.tick().await;
+        let outcome = loop {
+            tokio::select! {
+                outcome = &mut run => break outcome,
+                _ = heartbeat.tick() => {
+                    if let Err(err) = self.repository.heartbeat(job.id, job.attempts).await {
+                        warn!(error = %err, "Failed to refresh job lock");
+                    }
+                }
+            }
+        };
+
+        let result = match outcome {
+            Ok(()) => {
+                info!("Job succeeded");
+                self.repository.succeed(job.id, job.attempts).await
+            }
+            Err(reason) if job.attempts >= job.max_attempts => {
+                error!(error = %reason, "Job failed on its last attempt and is now dead");
+                self.repository.fail(job.id, job.attempts, &reason, None).await
+            }
+            Err(reason) => {
+                let delay = backoff(job.attempts);
+                warn!(error = %reason, retry_in_secs = delay.as_secs(), "Job failed, will retry");
+                let retry_at = Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+                self.repository.fail(job.id, job.attempts, &reason, Some(retry_at)).await
+            }
+        };
+        if let Err(err) = result {
+            error!(error = %err, "Failed to record job outcome");
+        }
+    }
+}
@@ -1,2 +1,5 @@
 pub mod repositories;
-pub mod usecases;
\ No newline at end of file
+pub mod usecases;
+pub mod state;
+pub mod jobs;
+pub mod events;
@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use sqlx::{query_as, Error};
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
+use crate::internal::entities::audit_log::{AuditLog, AuditLogFilter};
+
+#[async_trait]
+pub trait AuditLogRepository: Send + Sync {
+    // Call on transaction-bound repositories, so an entry exists only if the
+    // change it describes commits.
+    async fn record(&self, log: &AuditLog) -> Result<(), Error>;
+    // Newest first. `from` is inclusive and `to` exclusive.
+    async fn list(&self, filter: &AuditLogFilter, offset: u32, page_size: u32) -> Result<(Vec<AuditLog>, i64), Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLogRepositoryImpl {
+    database: PgExecutor,
+}
+
+impl AuditLogRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for AuditLogRepositoryImpl {
+    #[instrument(name = "AuditLogRepository::record", skip_all)]
+    async fn record(&self, log: &AuditLog) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO audit_logs (id, entity_type, entity_id, action, actor_id, actor, school_id, ip, request_id, before, after, changes, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        "#;
+
+        sqlx::query(query)
+            .bind(log.id)
+            .bind(&log.entity_type)
+            .bind(log.entity_id)
+            .bind(log.action)
+            .bind(&log.actor_id)
+            .bind(&log.actor)
+            .bind(log.school_id)
+            .bind(&log.ip)
+            .bind(&log.request_id)
+            .bind(&log.before)
+            .bind(&log.after)
+            .bind(&log.changes)
+            .bind(log.created_at)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "AuditLogRepository::list", skip_all)]
+    async fn list(&self, filter: &AuditLogFilter, offset: u32, page_size: u32) -> Result<(Vec<AuditLog>, i64), Error> {
+        let mut conn = self.database.acquire().await?;
+        let conditions = r#"
+            ($1::text IS NULL OR entity_type = $1)
+            AND ($2::uuid IS NULL OR entity_id = $2)
+            AND ($3::text IS NULL OR actor_id = $3 OR actor = $3)
+            AND ($4::timestamptz IS NULL OR created_at >= $4)
+            AND ($5::timestamptz IS NULL OR created_at < $5)
+        "#;
+        let query = format!("SELECT * FROM audit_logs WHERE {} ORDER BY created_at DESC, id LIMIT $6 OFFSET $7", conditions);
+        let count_query = format!("SELECT COUNT(*) AS total FROM audit_logs WHERE {}", conditions);
+
+        let rows = query_as(&query)
+            .bind(&filter.entity_type)
+            .bind(filter.entity_id)
+            .bind(&filter.actor)
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(page_size as i64)
+            .bind(offset as i64)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let total: (i64,) = query_as(&count_query)
+            .bind(&filter.entity_type)
+            .bind(filter.entity_id)
+            .bind(&filter.actor)
+            .bind(filter.from)
+            .bind(filter.to)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok((rows, total.0))
+    }
+}
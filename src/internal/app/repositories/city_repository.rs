@@ -1,11 +1,14 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use sqlx::{query_as, Error};
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::city::City;
 
-pub trait CityRepository {
-    fn new(database: PgPool) -> Self;
-
+#[async_trait]
+pub trait CityRepository: Send + Sync {
     async fn list(&self) -> Result<Vec<City>, Error>;
     async fn get_by_id(&self, id: String) -> Result<City, Error>;
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<City>, Error>;
     async fn create(&self, city: &City) -> Result<(), Error>;
     // async fn update(&self, city: &City) -> Result<(), Error>;
 
@@ -13,42 +16,66 @@ pub trait CityRepository {
 
 #[derive(Debug, Clone)]
 pub struct CityRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl CityRepository for CityRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl CityRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
 
+#[async_trait]
+impl CityRepository for CityRepositoryImpl {
+    #[instrument(name = "CityRepository::list", skip_all)]
     async fn list(&self) -> Result<Vec<City>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM cities ORDER BY name
         "#;
 
 
         let rows = query_as(query)
-            .fetch_all(&self.database)
+            .fetch_all(&mut *conn)
             .await?;
 
 
         Ok(rows)
     }
 
+    #[instrument(name = "CityRepository::get_by_id", skip_all)]
     async fn get_by_id(&self, id: String) -> Result<City, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
         SELECT * FROM cities WHERE id = $1
     "#;
 
         let city = query_as::<_, City>(query)
             .bind(id)
-            .fetch_one(&self.database)
+            .fetch_one(&mut *conn)
             .await?;
 
         Ok(city)
     }
 
+    #[instrument(name = "CityRepository::get_by_ids", skip_all)]
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<City>, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM cities WHERE id = ANY($1)
+        "#;
+
+        let rows = query_as(query)
+            .bind(ids)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(rows)
+    }
+
+    #[instrument(name = "CityRepository::create", skip_all)]
     async fn create(&self, city: &City) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             INSERT INTO cities (id, name, province_id, created_at, updated_at, deleted_at)
             VALUES ($1, $2, $3, $4, $5, $6)
@@ -61,7 +88,7 @@ impl CityRepository for CityRepositoryImpl {
             .bind(city.created_at)
             .bind(city.updated_at)
             .bind(city.deleted_at)
-            .execute(&self.database)
+            .execute(&mut *conn)
             .await?;
 
         Ok(())
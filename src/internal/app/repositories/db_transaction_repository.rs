@@ -1,35 +1,288 @@
-use sqlx::{Error, PgPool, Postgres, Transaction};
+use std::fmt;
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use async_trait::async_trait;
+use futures_util::FutureExt;
+use sqlx::pool::PoolConnection;
+use sqlx::{Error, PgConnection, PgPool, Postgres, Transaction};
+use tokio::sync::{Mutex, OwnedMappedMutexGuard, OwnedMutexGuard};
+use tracing::instrument;
+use crate::internal::app::state::Repositories;
+
+// An open transaction, whatever the backing store.
+#[async_trait]
+pub trait DbTransaction: Send {
+    // Repositories whose reads and writes happen in this transaction.
+    fn repositories(&self) -> Repositories;
+    async fn commit(self: Box<Self>) -> Result<(), Error>;
+    async fn rollback(self: Box<Self>) -> Result<(), Error>;
+}
 
 // Define the DbTransactionRepository trait
+#[async_trait]
 pub trait DbTransactionRepository: Send + Sync {
-    async fn begin_transaction(&self) -> Result<Transaction<Postgres>, Error>;
-    async fn commit_transaction(&self, transaction: Transaction<'_, Postgres>) -> Result<(), Error>;
+    // Begins a transaction, or a savepoint when these repositories are
+    // already in one.
+    async fn begin_transaction(&self) -> Result<Box<dyn DbTransaction>, Error>;
+    async fn commit_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error>;
+    async fn rollback_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error>;
+}
+
+/// Runs `work` with repositories bound to a new transaction, committing it if
+/// `work` returns `Ok` and rolling it back if it returns `Err` or panics; a
+/// panic carries on once the rollback is done.
+///
+/// Given the `db_transaction` of repositories that are already in a
+/// transaction, `work` runs in a savepoint of it instead, so its failure
+/// undoes only its own writes and the caller decides what happens to the rest.
+pub async fn with_transaction<T, E, F, Fut>(db_transaction: &dyn DbTransactionRepository, work: F) -> Result<T, E>
+where
+    F: FnOnce(Repositories) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: From<Error>,
+{
+    let transaction = db_transaction.begin_transaction().await?;
+    let result = AssertUnwindSafe(work(transaction.repositories())).catch_unwind().await;
+    match result {
+        Ok(Ok(value)) => {
+            db_transaction.commit_transaction(transaction).await?;
+            Ok(value)
+        }
+        Ok(Err(error)) => {
+            let _ = db_transaction.rollback_transaction(transaction).await;
+            Err(error)
+        }
+        Err(panic) => {
+            let _ = db_transaction.rollback_transaction(transaction).await;
+            std::panic::resume_unwind(panic)
+        }
+    }
+}
+
+/// Where a Postgres repository runs its queries: on a pooled connection, or
+/// in a transaction it shares with the other repositories bound to it.
+#[derive(Clone, Debug)]
+pub enum PgExecutor {
+    Pool(PgPool),
+    Transaction(Arc<SharedTransaction>),
+}
+
+impl PgExecutor {
+    /// A connection to run queries on. In a transaction it is the
+    /// transaction's, held exclusively until dropped.
+    pub async fn acquire(&self) -> Result<PgConn, Error> {
+        match self {
+            PgExecutor::Pool(pool) => Ok(PgConn::Pool(Box::new(pool.acquire().await?))),
+            PgExecutor::Transaction(shared) => {
+                let transaction = shared.transaction.clone().lock_owned().await;
+                OwnedMutexGuard::try_map(transaction, |transaction| transaction.as_deref_mut())
+                    .map(PgConn::Transaction)
+                    .map_err(|_| finished())
+            }
+        }
+    }
+}
+
+// A transaction and the savepoints opened in it. Committing or rolling back
+// takes the transaction out, after which its repositories fail.
+pub struct SharedTransaction {
+    transaction: Arc<Mutex<Option<Transaction<'static, Postgres>>>>,
+    savepoints: AtomicUsize,
+}
+
+impl fmt::Debug for SharedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedTransaction").finish_non_exhaustive()
+    }
+}
+
+pub enum PgConn {
+    Pool(Box<PoolConnection<Postgres>>),
+    Transaction(OwnedMappedMutexGuard<Option<Transaction<'static, Postgres>>, PgConnection>),
+}
+
+impl Deref for PgConn {
+    type Target = PgConnection;
+
+    fn deref(&self) -> &PgConnection {
+        match self {
+            PgConn::Pool(connection) => connection,
+            PgConn::Transaction(connection) => connection,
+        }
+    }
+}
+
+impl DerefMut for PgConn {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        match self {
+            PgConn::Pool(connection) => connection,
+            PgConn::Transaction(connection) => connection,
+        }
+    }
+}
+
+fn finished() -> Error {
+    Error::Protocol("the transaction has already finished".to_string())
+}
+
+pub struct PgDbTransaction {
+    // For the repositories that manage their own connections.
+    pool: PgPool,
+    shared: Arc<SharedTransaction>,
+    savepoint: Option<String>,
+}
+
+impl PgDbTransaction {
+    async fn execute(&self, statement: &str) -> Result<(), Error> {
+        let mut conn = PgExecutor::Transaction(self.shared.clone()).acquire().await?;
+        sqlx::query(statement).execute(&mut *conn).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DbTransaction for PgDbTransaction {
+    fn repositories(&self) -> Repositories {
+        Repositories::bound(self.pool.clone(), PgExecutor::Transaction(self.shared.clone()))
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), Error> {
+        match &self.savepoint {
+            Some(savepoint) => self.execute(&format!("RELEASE SAVEPOINT {}", savepoint)).await,
+            None => match self.shared.transaction.lock().await.take() {
+                Some(transaction) => transaction.commit().await,
+                None => Err(finished()),
+            },
+        }
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), Error> {
+        match &self.savepoint {
+            Some(savepoint) => {
+                self.execute(&format!("ROLLBACK TO SAVEPOINT {}", savepoint)).await?;
+                self.execute(&format!("RELEASE SAVEPOINT {}", savepoint)).await
+            }
+            None => match self.shared.transaction.lock().await.take() {
+                Some(transaction) => transaction.rollback().await,
+                None => Err(finished()),
+            },
+        }
+    }
 }
 
 // Implementation of the DbTransactionRepository
 #[derive(Clone, Debug)]
 pub struct DbTransactionRepositoryImpl {
     pool: PgPool,
+    database: PgExecutor,
 }
 
 impl DbTransactionRepositoryImpl {
-    pub fn new(pool: PgPool) -> Self {
-        DbTransactionRepositoryImpl { pool }
+    pub fn new(pool: PgPool, database: PgExecutor) -> Self {
+        DbTransactionRepositoryImpl { pool, database }
     }
 }
 
+#[async_trait]
 impl DbTransactionRepository for DbTransactionRepositoryImpl {
-    async fn begin_transaction(&self) -> Result<Transaction<Postgres>, Error> {
-        match self.pool.begin().await {
-            Ok(transaction) => Ok(transaction),
-            Err(error) => Err(error)
+    #[instrument(name = "DbTransactionRepository::begin_transaction", skip_all)]
+    async fn begin_transaction(&self) -> Result<Box<dyn DbTransaction>, Error> {
+        match &self.database {
+            PgExecutor::Pool(pool) => {
+                let transaction = pool.begin().await?;
+                let shared = SharedTransaction {
+                    transaction: Arc::new(Mutex::new(Some(transaction))),
+                    savepoints: AtomicUsize::new(0),
+                };
+                Ok(Box::new(PgDbTransaction { pool: self.pool.clone(), shared: Arc::new(shared), savepoint: None }))
+            }
+            PgExecutor::Transaction(shared) => {
+                // Numbered across the whole transaction, so a savepoint never
+                // shadows another one's name.
+                let savepoint = format!("savepoint_{}", shared.savepoints.fetch_add(1, Ordering::Relaxed) + 1);
+                let transaction = PgDbTransaction { pool: self.pool.clone(), shared: shared.clone(), savepoint: Some(savepoint.clone()) };
+                transaction.execute(&format!("SAVEPOINT {}", savepoint)).await?;
+                Ok(Box::new(transaction))
+            }
         }
     }
 
-    async fn commit_transaction(&self, mut transaction: Transaction<'_, Postgres>) -> Result<(), Error> {
-        match transaction.commit().await {
-            Ok(_) => Ok(()),
-            Err(error) => Err(error)
-        }
+    #[instrument(name = "DbTransactionRepository::commit_transaction", skip_all)]
+    async fn commit_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error> {
+        transaction.commit().await
+    }
+
+    #[instrument(name = "DbTransactionRepository::rollback_transaction", skip_all)]
+    async fn rollback_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error> {
+        transaction.rollback().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+    use super::*;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::internal::entities::role::Role;
+
+    fn role(name: &str) -> Role {
+        Role { id: Uuid::new_v4(), name: name.to_string(), created_at: Utc::now(), updated_at: Utc::now(), deleted_at: None, version: 1 }
+    }
+
+    fn role_names(database: &InMemoryDatabase) -> Vec<String> {
+        database.tables().roles.iter().map(|role| role.name.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn commits_on_ok_and_rolls_back_on_err() {
+        let database = InMemoryDatabase::new();
+        let r = database.repositories();
+
+        with_transaction(&*r.db_transaction, |tx| async move { tx.role.create(&role("admin")).await }).await.unwrap();
+        let result: Result<(), Error> = with_transaction(&*r.db_transaction, |tx| async move {
+            tx.role.create(&role("user")).await?;
+            Err(Error::RowNotFound)
+        }).await;
+
+        assert!(matches!(result, Err(Error::RowNotFound)));
+        assert_eq!(role_names(&database), ["admin"]);
+    }
+
+    #[tokio::test]
+    async fn rolls_back_on_panic() {
+        let database = InMemoryDatabase::new();
+        let r = database.repositories();
+
+        let result = AssertUnwindSafe(with_transaction(&*r.db_transaction, |tx| async move {
+            tx.role.create(&role("admin")).await?;
+            panic!("boom");
+            #[allow(unreachable_code)]
+            Ok::<(), Error>(())
+        })).catch_unwind().await;
+
+        assert!(result.is_err());
+        assert!(role_names(&database).is_empty());
+    }
+
+    #[tokio::test]
+    async fn nested_transactions_roll_back_to_their_savepoint() {
+        let database = InMemoryDatabase::new();
+        let r = database.repositories();
+
+        with_transaction(&*r.db_transaction, |tx| async move {
+            tx.role.create(&role("admin")).await?;
+            let nested: Result<(), Error> = with_transaction(&*tx.db_transaction, |tx| async move {
+                tx.role.create(&role("user")).await?;
+                Err(Error::RowNotFound)
+            }).await;
+            assert!(nested.is_err());
+            Ok::<(), Error>(())
+        }).await.unwrap();
+
+        assert_eq!(role_names(&database), ["admin"]);
     }
 }
@@ -0,0 +1,67 @@
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use sqlx::{Error, PgPool};
+use tracing::instrument;
+use crate::database::migrations::status;
+
+#[async_trait]
+pub trait HealthRepository: Send + Sync {
+    async fn ping(&self) -> Result<(), Error>;
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error>;
+    async fn pool_stats(&self) -> Result<PoolStats, Error>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    // How long checking out a connection took just now.
+    pub acquire_wait: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthRepositoryImpl {
+    database: PgPool,
+}
+
+impl HealthRepositoryImpl {
+    pub fn new(database: PgPool) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl HealthRepository for HealthRepositoryImpl {
+    #[instrument(name = "HealthRepository::ping", skip_all)]
+    async fn ping(&self) -> Result<(), Error> {
+        sqlx::query("SELECT 1").execute(&self.database).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "HealthRepository::pending_migrations", skip_all)]
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+        let migrations = status(&self.database)
+            .await
+            .map_err(|error| Error::Migrate(Box::new(error)))?;
+
+        Ok(migrations
+            .into_iter()
+            .filter(|migration| !migration.applied)
+            .map(|migration| migration.version)
+            .collect())
+    }
+
+    #[instrument(name = "HealthRepository::pool_stats", skip_all)]
+    async fn pool_stats(&self) -> Result<PoolStats, Error> {
+        let started = Instant::now();
+        let connection = self.database.acquire().await?;
+        let acquire_wait = started.elapsed();
+        drop(connection);
+
+        Ok(PoolStats {
+            size: self.database.size(),
+            idle: self.database.num_idle(),
+            acquire_wait,
+        })
+    }
+}
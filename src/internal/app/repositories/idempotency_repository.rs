@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query_as, Error, PgPool};
+use tracing::instrument;
+use crate::internal::entities::idempotency::IdempotencyKey;
+
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    // Stores `key` as in flight unless an unexpired one with the same key and
+    // endpoint exists, which is returned instead. An unfinished key whose lock
+    // has run out counts as expired.
+    async fn claim(&self, key: &IdempotencyKey) -> Result<Option<IdempotencyKey>, Error>;
+    // Both only touch `key` while it is still this claim, told apart by
+    // `created_at`, rather than one a retry took over since.
+    async fn complete(&self, key: &IdempotencyKey, status_code: i32, content_type: Option<String>, body: Vec<u8>) -> Result<(), Error>;
+    // Forgets a key whose request failed, so that a retry runs it again.
+    async fn release(&self, key: &IdempotencyKey) -> Result<(), Error>;
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, Error>;
+}
+
+// Runs on the pool rather than in the request's transaction: the claim has to
+// be visible to concurrent retries before the handler commits anything.
+#[derive(Debug, Clone)]
+pub struct IdempotencyRepositoryImpl {
+    database: PgPool,
+}
+
+impl IdempotencyRepositoryImpl {
+    pub fn new(database: PgPool) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for IdempotencyRepositoryImpl {
+    #[instrument(name = "IdempotencyRepository::claim", skip_all)]
+    async fn claim(&self, key: &IdempotencyKey) -> Result<Option<IdempotencyKey>, Error> {
+        // An expired key is taken over as if it were new.
+        let query = r#"
+            INSERT INTO idempotency_keys (key, endpoint, fingerprint, created_at, expires_at, locked_until)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (key, endpoint) DO UPDATE
+            SET fingerprint = EXCLUDED.fingerprint, status_code = NULL, content_type = NULL, response_body = NULL,
+                created_at = EXCLUDED.created_at, expires_at = EXCLUDED.expires_at, locked_until = EXCLUDED.locked_until
+            WHERE idempotency_keys.expires_at <= EXCLUDED.created_at
+            OR (idempotency_keys.status_code IS NULL AND idempotency_keys.locked_until <= EXCLUDED.created_at)
+            RETURNING key
+        "#;
+
+        let claimed: Option<(String,)> = query_as(query)
+            .bind(&key.key)
+            .bind(&key.endpoint)
+            .bind(&key.fingerprint)
+            .bind(key.created_at)
+            .bind(key.expires_at)
+            .bind(key.locked_until)
+            .fetch_optional(&self.database)
+            .await?;
+        if claimed.is_some() {
+            return Ok(None);
+        }
+
+        let query = r#"
+            SELECT * FROM idempotency_keys WHERE key = $1 AND endpoint = $2
+        "#;
+
+        let existing = query_as(query)
+            .bind(&key.key)
+            .bind(&key.endpoint)
+            .fetch_one(&self.database)
+            .await?;
+
+        Ok(Some(existing))
+    }
+
+    #[instrument(name = "IdempotencyRepository::complete", skip_all)]
+    async fn complete(&self, key: &IdempotencyKey, status_code: i32, content_type: Option<String>, body: Vec<u8>) -> Result<(), Error> {
+        let query = r#"
+            UPDATE idempotency_keys SET status_code = $4, content_type = $5, response_body = $6
+            WHERE key = $1 AND endpoint = $2 AND created_at = $3
+        "#;
+
+        sqlx::query(query)
+            .bind(&key.key)
+            .bind(&key.endpoint)
+            .bind(key.created_at)
+            .bind(status_code)
+            .bind(content_type)
+            .bind(body)
+            .execute(&self.database)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "IdempotencyRepository::release", skip_all)]
+    async fn release(&self, key: &IdempotencyKey) -> Result<(), Error> {
+        let query = r#"
+            DELETE FROM idempotency_keys WHERE key = $1 AND endpoint = $2 AND created_at = $3 AND status_code IS NULL
+        "#;
+
+        sqlx::query(query)
+            .bind(&key.key)
+            .bind(&key.endpoint)
+            .bind(key.created_at)
+            .execute(&self.database)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "IdempotencyRepository::purge_expired", skip_all)]
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let query = r#"
+            DELETE FROM idempotency_keys WHERE expires_at <= $1
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .execute(&self.database)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
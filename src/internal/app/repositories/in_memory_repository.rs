@@ -0,0 +1,1437 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::error::{DatabaseError, ErrorKind};
+use sqlx::Error;
+use uuid::Uuid;
+use crate::database::list_query::{FilterValue, ListQuery, Page, PageRequest, Position};
+use crate::database::schema::ColumnType;
+use crate::internal::app::repositories::audit_log_repository::AuditLogRepository;
+use crate::internal::app::repositories::city_repository::CityRepository;
+use crate::internal::app::repositories::db_transaction_repository::{DbTransaction, DbTransactionRepository};
+use crate::internal::app::repositories::health_repository::{HealthRepository, PoolStats};
+use crate::internal::app::repositories::idempotency_repository::IdempotencyRepository;
+use crate::internal::app::repositories::job_repository::JobRepository;
+use crate::internal::app::repositories::maintenance_repository::MaintenanceRepository;
+use crate::internal::app::repositories::outbox_repository::OutboxRepository;
+use crate::internal::app::repositories::province_repository::ProvinceRepository;
+use crate::internal::app::repositories::role_repository::RoleRepository;
+use crate::internal::app::repositories::schedule_repository::{Leadership, ScheduleRepository};
+use crate::internal::app::repositories::school_repository::SchoolRepository;
+use crate::internal::app::repositories::subscription_repository::SubscriptionRepository;
+use crate::internal::app::repositories::subscription_type_repository::SubscriptionTypeRepository;
+use crate::internal::app::repositories::user_repository::UserRepository;
+use crate::internal::app::repositories::webhook_repository::WebhookRepository;
+use crate::internal::app::state::Repositories;
+use crate::internal::entities::audit_log::{AuditLog, AuditLogFilter};
+use crate::internal::entities::city::City;
+use crate::internal::entities::event::{DomainEvent, OutboxEvent};
+use crate::internal::entities::idempotency::IdempotencyKey;
+use crate::internal::entities::job::{Job, JobStatus};
+use crate::internal::entities::province::{Province, ProvinceFromTable};
+use crate::internal::entities::role::Role;
+use crate::internal::entities::schedule::ScheduleRun;
+use crate::internal::entities::school::School;
+use crate::internal::entities::subscription::Subscription;
+use crate::internal::entities::subscription_type::SubscriptionType;
+use crate::internal::entities::user::User;
+use crate::internal::entities::webhook::{DeliveryAttempt, Webhook, WebhookDelivery, WebhookDeliveryStatus};
+
+#[derive(Debug, Clone, Default)]
+pub struct Tables {
+    pub subscription_types: Vec<SubscriptionType>,
+    pub subscriptions: Vec<Subscription>,
+    pub roles: Vec<Role>,
+    pub provinces: Vec<ProvinceFromTable>,
+    pub cities: Vec<City>,
+    pub schools: Vec<School>,
+    pub users: Vec<User>,
+    pub jobs: Vec<Job>,
+    pub schedule_runs: Vec<ScheduleRun>,
+    pub scheduler_leader: bool,
+    pub outbox_events: Vec<OutboxEvent>,
+    pub webhooks: Vec<Webhook>,
+    pub webhook_deliveries: Vec<WebhookDelivery>,
+    pub audit_logs: Vec<AuditLog>,
+    pub idempotency_keys: Vec<IdempotencyKey>,
+}
+
+// Shared state behind every in-memory repository. Cloning it shares the same
+// tables, so repositories built from one database see each other's writes.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryDatabase {
+    tables: Arc<Mutex<Tables>>,
+}
+
+impl InMemoryDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap()
+    }
+
+    pub fn repositories(&self) -> Repositories {
+        Repositories {
+            subscription: Arc::new(InMemorySubscriptionRepository::new(self.clone())),
+            subscription_type: Arc::new(InMemorySubscriptionTypeRepository::new(self.clone())),
+            role: Arc::new(InMemoryRoleRepository::new(self.clone())),
+            province: Arc::new(InMemoryProvinceRepository::new(self.clone())),
+            city: Arc::new(InMemoryCityRepository::new(self.clone())),
+            school: Arc::new(InMemorySchoolRepository::new(self.clone())),
+            user: Arc::new(InMemoryUserRepository::new(self.clone())),
+            db_transaction: Arc::new(InMemoryDbTransactionRepository::new(self.clone())),
+            health: Arc::new(InMemoryHealthRepository),
+            job: Arc::new(InMemoryJobRepository::new(self.clone())),
+            schedule: Arc::new(InMemoryScheduleRepository::new(self.clone())),
+            maintenance: Arc::new(InMemoryMaintenanceRepository::new(self.clone())),
+            outbox: Arc::new(InMemoryOutboxRepository::new(self.clone())),
+            webhook: Arc::new(InMemoryWebhookRepository::new(self.clone())),
+            audit_log: Arc::new(InMemoryAuditLogRepository::new(self.clone())),
+            idempotency: Arc::new(InMemoryIdempotencyRepository::new(self.clone())),
+        }
+    }
+}
+
+// Mirrors the error Postgres reports for a violated unique constraint.
+fn unique_violation(constraint: &str) -> Error {
+    Error::Database(Box::new(UniqueViolation {
+        message: format!("duplicate key value violates unique constraint \"{}\"", constraint),
+        constraint: constraint.to_string(),
+    }))
+}
+
+#[derive(Debug)]
+struct UniqueViolation {
+    message: String,
+    constraint: String,
+}
+
+impl fmt::Display for UniqueViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for UniqueViolation {}
+
+impl DatabaseError for UniqueViolation {
+    fn message(&self) -> &str {
+        &self.message
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        Some(&self.constraint)
+    }
+
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::UniqueViolation
+    }
+}
+
+// Mirrors `ListQuery::push_conditions` and `push_page` on the serialized
+// rows, so the whitelisted names resolve the same way they do in SQL.
+fn list<T: Serialize + Clone>(rows: &[T], query: &ListQuery, page: &PageRequest) -> Page<T> {
+    let mut rows: Vec<(Value, T)> = rows.iter()
+        .map(|row| (serde_json::to_value(row).unwrap_or_default(), row.clone()))
+        .filter(|(value, _)| {
+            let deleted_at = value["deleted_at"].as_str().and_then(|at| at.parse().ok());
+            query.trashed.matches(deleted_at)
+        })
+        .filter(|(value, _)| query.filters.iter().all(|filter| matches_filter(&value[filter.column], &filter.value)))
+        .filter(|(value, _)| query.search.as_ref().is_none_or(|search| {
+            let term = search.term.to_lowercase();
+            search.columns.iter().any(|column| value[*column].as_str().is_some_and(|text| text.to_lowercase().contains(&term)))
+        }))
+        .collect();
+    let total = rows.len() as i64;
+
+    let columns = query.key_columns();
+    let types: Vec<ColumnType> = query.sort.iter().map(|sort| sort.column_type).chain([ColumnType::Uuid]).collect();
+    let compare_key = |row: &Value, key: &[Value]| {
+        columns.iter().zip(&types).zip(key)
+            .map(|(((column, descending), column_type), value)| {
+                let ordering = compare_column(&row[*column], value, *column_type);
+                if *descending { ordering.reverse() } else { ordering }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    };
+    rows.sort_by(|(a, _), (b, _)| {
+        let key: Vec<Value> = columns.iter().map(|(column, _)| b[*column].clone()).collect();
+        compare_key(a, &key)
+    });
+
+    let limit = page.size as usize + 1;
+    let rows: Vec<T> = match &page.position {
+        Position::Page(number) => rows.into_iter()
+            .skip(page.offset(*number) as usize)
+            .take(limit)
+            .map(|(_, row)| row)
+            .collect(),
+        Position::After(key) => {
+            let key: Vec<Value> = key.iter().map(key_json).collect();
+            rows.into_iter()
+                .filter(|(value, _)| compare_key(value, &key).is_gt())
+                .take(limit)
+                .map(|(_, row)| row)
+                .collect()
+        }
+        Position::Before(key) => {
+            let key: Vec<Value> = key.iter().map(key_json).collect();
+            rows.into_iter()
+                .rev()
+                .filter(|(value, _)| compare_key(value, &key).is_lt())
+                .take(limit)
+                .map(|(_, row)| row)
+                .collect()
+        }
+    };
+    Page::new(rows, query, page, page.include_total.then_some(total))
+}
+
+fn key_json(value: &FilterValue) -> Value {
+    match value {
+        FilterValue::Text(value) | FilterValue::Enum(value) => Value::String(value.clone()),
+        FilterValue::Uuid(value) => Value::String(value.to_string()),
+        FilterValue::Int(value) => Value::from(*value),
+        FilterValue::Timestamp(value) => Value::String(value.to_rfc3339()),
+    }
+}
+
+fn matches_filter(value: &Value, filter: &FilterValue) -> bool {
+    match filter {
+        FilterValue::Text(expected) => value.as_str() == Some(expected.as_str()),
+        FilterValue::Uuid(expected) => value.as_str() == Some(expected.to_string().as_str()),
+        FilterValue::Int(expected) => value.as_i64() == Some(*expected as i64),
+        FilterValue::Timestamp(expected) => value.as_str().and_then(|at| at.parse::<DateTime<Utc>>().ok()) == Some(*expected),
+        // Enums serialize in a different case than Postgres spells them.
+        FilterValue::Enum(expected) => value.as_str().is_some_and(|value| value.eq_ignore_ascii_case(expected)),
+    }
+}
+
+// NULLs sort last, as they do in Postgres.
+fn compare_column(a: &Value, b: &Value, column_type: ColumnType) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        _ if column_type == ColumnType::Timestamptz => {
+            let parse = |value: &Value| value.as_str().and_then(|at| at.parse::<DateTime<Utc>>().ok());
+            parse(a).cmp(&parse(b))
+        }
+        _ => a.as_str().cmp(&b.as_str()),
+    }
+}
+
+fn paginate<T: Clone>(rows: Vec<T>, offset: u32, page_size: u32) -> (Vec<T>, i64) {
+    let total = rows.len() as i64;
+    let page = rows.into_iter().skip(offset as usize).take(page_size as usize).collect();
+    (page, total)
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemorySubscriptionTypeRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemorySubscriptionTypeRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SubscriptionTypeRepository for InMemorySubscriptionTypeRepository {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<SubscriptionType>, Error> {
+        Ok(list(&self.database.tables().subscription_types, query, page))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        self.database.tables().subscription_types.iter()
+            .find(|row| row.id == id && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        self.database.tables().subscription_types.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn create(&self, subscription_type: &SubscriptionType) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.subscription_types.iter().any(|row| row.id == subscription_type.id) {
+            return Err(unique_violation("subscription_types_pkey"));
+        }
+        if tables.subscription_types.iter().any(|row| row.deleted_at.is_none() && row.name == subscription_type.name) {
+            return Err(unique_violation("subscription_types_name_key"));
+        }
+        tables.subscription_types.push(subscription_type.clone());
+        Ok(())
+    }
+
+    async fn update(&self, subscription_type: &SubscriptionType) -> Result<SubscriptionType, Error> {
+        let mut tables = self.database.tables();
+        if tables.subscription_types.iter().any(|row| row.id != subscription_type.id && row.deleted_at.is_none() && row.name == subscription_type.name) {
+            return Err(unique_violation("subscription_types_name_key"));
+        }
+        let row = tables.subscription_types.iter_mut()
+            .find(|row| row.id == subscription_type.id && row.version == subscription_type.version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.name = subscription_type.name.clone();
+        row.updated_at = subscription_type.updated_at;
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.subscription_types.iter_mut()
+            .find(|row| row.id == id && row.version == version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = Some(Utc::now());
+        row.version += 1;
+        Ok(())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        let mut tables = self.database.tables();
+        let restored = tables.subscription_types.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)?;
+        if tables.subscription_types.iter().any(|row| row.deleted_at.is_none() && row.name == restored.name) {
+            return Err(unique_violation("subscription_types_name_key"));
+        }
+        let row = tables.subscription_types.iter_mut().find(|row| row.id == id).ok_or(Error::RowNotFound)?;
+        row.deleted_at = None;
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+        let referenced = tables.subscriptions.iter().any(|subscription| subscription.subscription_type_id == id);
+        let count = tables.subscription_types.len();
+        tables.subscription_types.retain(|row| row.id != id || row.deleted_at.is_none() || referenced);
+        if tables.subscription_types.len() == count {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemorySubscriptionRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemorySubscriptionRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for InMemorySubscriptionRepository {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Subscription>, Error> {
+        Ok(list(&self.database.tables().subscriptions, query, page))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Subscription, Error> {
+        self.database.tables().subscriptions.iter()
+            .find(|row| row.id == id && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<Subscription, Error> {
+        self.database.tables().subscriptions.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error> {
+        Ok(self.database.tables().subscriptions.iter()
+            .filter(|row| ids.contains(&row.id) && row.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_by_subscription_type_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error> {
+        let mut rows: Vec<Subscription> = self.database.tables().subscriptions.iter()
+            .filter(|row| ids.contains(&row.subscription_type_id) && row.deleted_at.is_none())
+            .cloned()
+            .collect();
+        rows.sort_by(|a, b| a.price.cmp(&b.price).then(a.id.cmp(&b.id)));
+        Ok(rows)
+    }
+
+    async fn create(&self, subscription: &Subscription) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.subscriptions.iter().any(|row| row.id == subscription.id) {
+            return Err(unique_violation("subscriptions_pkey"));
+        }
+        if tables.subscriptions.iter().any(|row| row.deleted_at.is_none() && row.name == subscription.name) {
+            return Err(unique_violation("subscriptions_name_key"));
+        }
+        tables.subscriptions.push(subscription.clone());
+        Ok(())
+    }
+
+    async fn update(&self, subscription: &Subscription) -> Result<Subscription, Error> {
+        let mut tables = self.database.tables();
+        if tables.subscriptions.iter().any(|row| row.id != subscription.id && row.deleted_at.is_none() && row.name == subscription.name) {
+            return Err(unique_violation("subscriptions_name_key"));
+        }
+        let row = tables.subscriptions.iter_mut()
+            .find(|row| row.id == subscription.id && row.version == subscription.version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.name = subscription.name.clone();
+        row.price = subscription.price;
+        row.subscription_type_id = subscription.subscription_type_id;
+        row.updated_at = subscription.updated_at;
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.subscriptions.iter_mut()
+            .find(|row| row.id == id && row.version == version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = Some(Utc::now());
+        row.version += 1;
+        Ok(())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Subscription, Error> {
+        let mut tables = self.database.tables();
+        let restored = tables.subscriptions.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)?;
+        if tables.subscriptions.iter().any(|row| row.deleted_at.is_none() && row.name == restored.name) {
+            return Err(unique_violation("subscriptions_name_key"));
+        }
+        let row = tables.subscriptions.iter_mut().find(|row| row.id == id).ok_or(Error::RowNotFound)?;
+        row.deleted_at = None;
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+        let referenced = tables.schools.iter().any(|school| school.subscription_id == Some(id));
+        let count = tables.subscriptions.len();
+        tables.subscriptions.retain(|row| row.id != id || row.deleted_at.is_none() || referenced);
+        if tables.subscriptions.len() == count {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryRoleRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryRoleRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl RoleRepository for InMemoryRoleRepository {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Role>, Error> {
+        Ok(list(&self.database.tables().roles, query, page))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Role, Error> {
+        self.database.tables().roles.iter()
+            .find(|row| row.id == id && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<Role, Error> {
+        self.database.tables().roles.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Role>, Error> {
+        Ok(self.database.tables().roles.iter()
+            .filter(|row| ids.contains(&row.id) && row.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_by_name(&self, name: String) -> Result<Role, Error> {
+        self.database.tables().roles.iter()
+            .find(|row| row.name == name && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn create(&self, role: &Role) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.roles.iter().any(|row| row.id == role.id) {
+            return Err(unique_violation("roles_pkey"));
+        }
+        if tables.roles.iter().any(|row| row.deleted_at.is_none() && row.name == role.name) {
+            return Err(unique_violation("roles_name_key"));
+        }
+        tables.roles.push(role.clone());
+        Ok(())
+    }
+
+    async fn update(&self, role: &Role) -> Result<Role, Error> {
+        let mut tables = self.database.tables();
+        if tables.roles.iter().any(|row| row.id != role.id && row.deleted_at.is_none() && row.name == role.name) {
+            return Err(unique_violation("roles_name_key"));
+        }
+        let row = tables.roles.iter_mut()
+            .find(|row| row.id == role.id && row.version == role.version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.name = role.name.clone();
+        row.updated_at = role.updated_at;
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.roles.iter_mut()
+            .find(|row| row.id == id && row.version == version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = Some(Utc::now());
+        row.version += 1;
+        Ok(())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Role, Error> {
+        let mut tables = self.database.tables();
+        let restored = tables.roles.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)?;
+        if tables.roles.iter().any(|row| row.deleted_at.is_none() && row.name == restored.name) {
+            return Err(unique_violation("roles_name_key"));
+        }
+        let row = tables.roles.iter_mut().find(|row| row.id == id).ok_or(Error::RowNotFound)?;
+        row.deleted_at = None;
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+        let referenced = tables.users.iter().any(|user| user.role_id == id);
+        let count = tables.roles.len();
+        tables.roles.retain(|row| row.id != id || row.deleted_at.is_none() || referenced);
+        if tables.roles.len() == count {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryProvinceRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryProvinceRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl ProvinceRepository for InMemoryProvinceRepository {
+    async fn list(&self) -> Result<Vec<ProvinceFromTable>, Error> {
+        let mut rows = self.database.tables().provinces.clone();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+
+    async fn get_by_id(&self, id: String) -> Result<ProvinceFromTable, Error> {
+        self.database.tables().provinces.iter()
+            .find(|row| row.id == id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<ProvinceFromTable>, Error> {
+        Ok(self.database.tables().provinces.iter()
+            .filter(|row| ids.contains(&row.id))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, province: &Province) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.provinces.iter().any(|row| row.id == province.code) {
+            return Err(unique_violation("provinces_pkey"));
+        }
+        if tables.provinces.iter().any(|row| row.name == province.name) {
+            return Err(unique_violation("provinces_name_key"));
+        }
+        tables.provinces.push(ProvinceFromTable {
+            id: province.code.clone(),
+            name: province.name.clone(),
+            created_at: province.created_at,
+            updated_at: province.updated_at,
+            deleted_at: province.deleted_at,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryCityRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryCityRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl CityRepository for InMemoryCityRepository {
+    async fn list(&self) -> Result<Vec<City>, Error> {
+        let mut rows = self.database.tables().cities.clone();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+
+    async fn get_by_id(&self, id: String) -> Result<City, Error> {
+        self.database.tables().cities.iter()
+            .find(|row| row.code == id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<City>, Error> {
+        Ok(self.database.tables().cities.iter()
+            .filter(|row| ids.contains(&row.code))
+            .cloned()
+            .collect())
+    }
+
+    async fn create(&self, city: &City) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.cities.iter().any(|row| row.code == city.code) {
+            return Err(unique_violation("cities_pkey"));
+        }
+        if tables.cities.iter().any(|row| row.name == city.name) {
+            return Err(unique_violation("cities_name_key"));
+        }
+        tables.cities.push(city.clone());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemorySchoolRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemorySchoolRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SchoolRepository for InMemorySchoolRepository {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<School>, Error> {
+        Ok(list(&self.database.tables().schools, query, page))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<School, Error> {
+        self.database.tables().schools.iter()
+            .find(|row| row.id == id && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<School, Error> {
+        self.database.tables().schools.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<School>, Error> {
+        Ok(self.database.tables().schools.iter()
+            .filter(|row| ids.contains(&row.id) && row.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_by_subscription_id(&self, id: Uuid) -> Result<Vec<School>, Error> {
+        Ok(self.database.tables().schools.iter()
+            .filter(|row| row.subscription_id == Some(id) && row.deleted_at.is_none())
+            .cloned()
+            .collect())
+    }
+
+    async fn get_expired_subscriptions(&self, now: DateTime<Utc>) -> Result<Vec<School>, Error> {
+        let mut schools: Vec<School> = self.database.tables().schools.iter()
+            .filter(|row| row.subscription_id.is_some() && row.subscription_ends_at.is_some_and(|ends_at| ends_at <= now) && row.deleted_at.is_none())
+            .cloned()
+            .collect();
+        schools.sort_by_key(|row| (row.subscription_ends_at, row.id));
+        Ok(schools)
+    }
+
+    async fn create(&self, school: &School) -> Result<School, Error> {
+        let mut tables = self.database.tables();
+        if tables.schools.iter().any(|row| row.id == school.id) {
+            return Err(unique_violation("schools_pkey"));
+        }
+        tables.schools.push(school.clone());
+        Ok(school.clone())
+    }
+
+    async fn update(&self, school: &School) -> Result<School, Error> {
+        let mut tables = self.database.tables();
+        let row = tables.schools.iter_mut()
+            .find(|row| row.id == school.id && row.version == school.version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        *row = School { created_at: row.created_at, deleted_at: None, version: row.version + 1, ..school.clone() };
+        Ok(row.clone())
+    }
+
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.schools.iter_mut()
+            .find(|row| row.id == id && row.version == version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = Some(Utc::now());
+        row.version += 1;
+        Ok(())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<School, Error> {
+        let mut tables = self.database.tables();
+        let row = tables.schools.iter_mut()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = None;
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+        let referenced = tables.users.iter().any(|user| user.school_id == id);
+        let count = tables.schools.len();
+        tables.schools.retain(|row| row.id != id || row.deleted_at.is_none() || referenced);
+        if tables.schools.len() == count {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryUserRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryUserRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryUserRepository {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<User>, Error> {
+        Ok(list(&self.database.tables().users, query, page))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<User, Error> {
+        self.database.tables().users.iter()
+            .find(|row| row.id == id && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<User, Error> {
+        self.database.tables().users.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_email(&self, email: String) -> Result<User, Error> {
+        self.database.tables().users.iter()
+            .find(|row| row.email == email && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_by_phone(&self, phone_number: String) -> Result<User, Error> {
+        self.database.tables().users.iter()
+            .find(|row| row.phone_number == phone_number && row.deleted_at.is_none())
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn create(&self, user: &User) -> Result<User, Error> {
+        let mut tables = self.database.tables();
+        if tables.users.iter().any(|row| row.id == user.id) {
+            return Err(unique_violation("users_pkey"));
+        }
+        if tables.users.iter().any(|row| row.deleted_at.is_none() && row.email == user.email) {
+            return Err(unique_violation("users_email_key"));
+        }
+        if tables.users.iter().any(|row| row.deleted_at.is_none() && row.phone_number == user.phone_number) {
+            return Err(unique_violation("users_phone_number_key"));
+        }
+        tables.users.push(user.clone());
+        Ok(user.clone())
+    }
+
+    async fn update(&self, user: &User) -> Result<User, Error> {
+        let mut tables = self.database.tables();
+        if tables.users.iter().any(|row| row.id != user.id && row.deleted_at.is_none() && row.email == user.email) {
+            return Err(unique_violation("users_email_key"));
+        }
+        if tables.users.iter().any(|row| row.id != user.id && row.deleted_at.is_none() && row.phone_number == user.phone_number) {
+            return Err(unique_violation("users_phone_number_key"));
+        }
+        let row = tables.users.iter_mut()
+            .find(|row| row.id == user.id && row.version == user.version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        *row = User { created_at: row.created_at, deleted_at: None, version: row.version + 1, ..user.clone() };
+        Ok(row.clone())
+    }
+
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.users.iter_mut()
+            .find(|row| row.id == id && row.version == version && row.deleted_at.is_none())
+            .ok_or(Error::RowNotFound)?;
+        row.deleted_at = Some(Utc::now());
+        row.version += 1;
+        Ok(())
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<User, Error> {
+        let mut tables = self.database.tables();
+        let restored = tables.users.iter()
+            .find(|row| row.id == id && row.deleted_at.is_some())
+            .cloned()
+            .ok_or(Error::RowNotFound)?;
+        if tables.users.iter().any(|row| row.deleted_at.is_none() && row.email == restored.email) {
+            return Err(unique_violation("users_email_key"));
+        }
+        if tables.users.iter().any(|row| row.deleted_at.is_none() && row.phone_number == restored.phone_number) {
+            return Err(unique_violation("users_phone_number_key"));
+        }
+        let row = tables.users.iter_mut().find(|row| row.id == id).ok_or(Error::RowNotFound)?;
+        row.deleted_at = None;
+        row.updated_at = Utc::now();
+        row.version += 1;
+        Ok(row.clone())
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let count = tables.users.len();
+        tables.users.retain(|row| row.id != id || row.deleted_at.is_none());
+        if tables.users.len() == count {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
+
+// A transaction over the in-memory tables: beginning one snapshots every
+// table, rolling back restores the snapshot and committing drops it. The
+// tables aren't isolated, so a transaction's repositories are the usual ones,
+// and one begun inside another acts as a savepoint.
+pub struct InMemoryDbTransaction {
+    database: InMemoryDatabase,
+    snapshot: Tables,
+}
+
+#[async_trait]
+impl DbTransaction for InMemoryDbTransaction {
+    fn repositories(&self) -> Repositories {
+        self.database.repositories()
+    }
+
+    async fn commit(self: Box<Self>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn rollback(self: Box<Self>) -> Result<(), Error> {
+        *self.database.tables() = self.snapshot;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryDbTransactionRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryDbTransactionRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl DbTransactionRepository for InMemoryDbTransactionRepository {
+    async fn begin_transaction(&self) -> Result<Box<dyn DbTransaction>, Error> {
+        let snapshot = self.database.tables().clone();
+        Ok(Box::new(InMemoryDbTransaction { database: self.database.clone(), snapshot }))
+    }
+
+    async fn commit_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error> {
+        transaction.commit().await
+    }
+
+    async fn rollback_transaction(&self, transaction: Box<dyn DbTransaction>) -> Result<(), Error> {
+        transaction.rollback().await
+    }
+}
+
+// Always reachable and never behind on migrations.
+#[derive(Debug, Clone)]
+pub struct InMemoryHealthRepository;
+
+#[async_trait]
+impl HealthRepository for InMemoryHealthRepository {
+    async fn ping(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+        Ok(vec![])
+    }
+
+    async fn pool_stats(&self) -> Result<PoolStats, Error> {
+        Ok(PoolStats::default())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryJobRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryJobRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl JobRepository for InMemoryJobRepository {
+    async fn list(&self, status: Option<JobStatus>, offset: u32, page_size: u32) -> Result<(Vec<Job>, i64), Error> {
+        let mut rows: Vec<Job> = self.database.tables().jobs.iter()
+            .filter(|row| status.is_none_or(|status| row.status == status))
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.created_at));
+        Ok(paginate(rows, offset, page_size))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Job, Error> {
+        self.database.tables().jobs.iter()
+            .find(|row| row.id == id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn enqueue(&self, job: &Job) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if tables.jobs.iter().any(|row| row.id == job.id) {
+            return Err(unique_violation("jobs_pkey"));
+        }
+        tables.jobs.push(job.clone());
+        Ok(())
+    }
+
+    async fn claim(&self, limit: u32, lock_timeout: Duration) -> Result<Vec<Job>, Error> {
+        let now = Utc::now();
+        let expired = now - chrono::Duration::from_std(lock_timeout).unwrap_or_default();
+        let mut tables = self.database.tables();
+        tables.jobs.sort_by_key(|row| row.run_at);
+        let claimed = tables.jobs.iter_mut()
+            .filter(|row| match row.status {
+                JobStatus::Pending => row.run_at <= now,
+                JobStatus::Running => row.locked_at.is_some_and(|locked_at| locked_at < expired),
+                _ => false,
+            })
+            .take(limit as usize)
+            .map(|row| {
+                row.status = JobStatus::Running;
+                row.attempts += 1;
+                row.locked_at = Some(now);
+                row.updated_at = now;
+                row.clone()
+            })
+            .collect();
+        Ok(claimed)
+    }
+
+    async fn heartbeat(&self, id: Uuid, attempt: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let row = tables.jobs.iter_mut()
+            .find(|row| row.id == id && row.status == JobStatus::Running && row.attempts == attempt)
+            .ok_or(Error::RowNotFound)?;
+        row.locked_at = Some(Utc::now());
+        Ok(())
+    }
+
+    async fn succeed(&self, id: Uuid, attempt: i32) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if let Some(row) = tables.jobs.iter_mut().find(|row| row.id == id && row.status == JobStatus::Running && row.attempts == attempt) {
+            row.status = JobStatus::Succeeded;
+            row.locked_at = None;
+            row.last_error = None;
+            row.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn fail(&self, id: Uuid, attempt: i32, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if let Some(row) = tables.jobs.iter_mut().find(|row| row.id == id && row.status == JobStatus::Running && row.attempts == attempt) {
+            row.status = if retry_at.is_some() { JobStatus::Pending } else { JobStatus::Dead };
+            row.run_at = retry_at.unwrap_or(row.run_at);
+            row.locked_at = None;
+            row.last_error = Some(error.to_string());
+            row.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+
+    async fn retry(&self, id: Uuid) -> Result<Job, Error> {
+        let mut tables = self.database.tables();
+        let row = tables.jobs.iter_mut()
+            .find(|row| row.id == id && matches!(row.status, JobStatus::Dead | JobStatus::Cancelled))
+            .ok_or(Error::RowNotFound)?;
+        row.status = JobStatus::Pending;
+        row.attempts = 0;
+        row.run_at = Utc::now();
+        row.updated_at = Utc::now();
+        Ok(row.clone())
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Job, Error> {
+        let mut tables = self.database.tables();
+        let row = tables.jobs.iter_mut()
+            .find(|row| row.id == id && row.status == JobStatus::Pending)
+            .ok_or(Error::RowNotFound)?;
+        row.status = JobStatus::Cancelled;
+        row.updated_at = Utc::now();
+        Ok(row.clone())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryScheduleRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryScheduleRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+pub struct InMemoryLeadership {
+    database: InMemoryDatabase,
+}
+
+#[async_trait]
+impl Leadership for InMemoryLeadership {
+    async fn is_held(&mut self) -> bool {
+        true
+    }
+
+    async fn release(self: Box<Self>) {
+        self.database.tables().scheduler_leader = false;
+    }
+}
+
+#[async_trait]
+impl ScheduleRepository for InMemoryScheduleRepository {
+    async fn list(&self) -> Result<Vec<ScheduleRun>, Error> {
+        let mut rows = self.database.tables().schedule_runs.clone();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(rows)
+    }
+
+    async fn record(&self, name: &str, run_at: DateTime<Utc>, job_id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        let run = ScheduleRun { name: name.to_string(), last_run_at: run_at, last_job_id: Some(job_id), updated_at: Utc::now() };
+        match tables.schedule_runs.iter_mut().find(|row| row.name == name) {
+            Some(row) => *row = run,
+            None => tables.schedule_runs.push(run),
+        }
+        Ok(())
+    }
+
+    async fn try_lead(&self) -> Result<Option<Box<dyn Leadership>>, Error> {
+        let mut tables = self.database.tables();
+        if tables.scheduler_leader {
+            return Ok(None);
+        }
+        tables.scheduler_leader = true;
+        Ok(Some(Box::new(InMemoryLeadership { database: self.database.clone() })))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryMaintenanceRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryMaintenanceRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+// Follows the same rules as the Postgres purge: a row that live rows still
+// reference is kept.
+#[async_trait]
+impl MaintenanceRepository for InMemoryMaintenanceRepository {
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<(&'static str, u64)>, Error> {
+        let expired = |deleted_at: Option<DateTime<Utc>>| deleted_at.is_some_and(|deleted_at| deleted_at < before);
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+
+        let count = tables.users.len();
+        tables.users.retain(|row| !expired(row.deleted_at));
+        let users = (count - tables.users.len()) as u64;
+
+        let count = tables.schools.len();
+        let users_of = &tables.users;
+        tables.schools.retain(|row| !expired(row.deleted_at) || users_of.iter().any(|user| user.school_id == row.id));
+        let schools = (count - tables.schools.len()) as u64;
+
+        let count = tables.subscriptions.len();
+        let schools_of = &tables.schools;
+        tables.subscriptions.retain(|row| !expired(row.deleted_at) || schools_of.iter().any(|school| school.subscription_id == Some(row.id)));
+        let subscriptions = (count - tables.subscriptions.len()) as u64;
+
+        let count = tables.roles.len();
+        tables.roles.retain(|row| !expired(row.deleted_at) || users_of.iter().any(|user| user.role_id == row.id));
+        let roles = (count - tables.roles.len()) as u64;
+
+        let count = tables.subscription_types.len();
+        let subscriptions_of = &tables.subscriptions;
+        tables.subscription_types.retain(|row| !expired(row.deleted_at) || subscriptions_of.iter().any(|subscription| subscription.subscription_type_id == row.id));
+        let subscription_types = (count - tables.subscription_types.len()) as u64;
+
+        Ok(vec![("users", users), ("schools", schools), ("subscriptions", subscriptions), ("roles", roles), ("subscription_types", subscription_types)])
+    }
+
+    async fn referenced_files(&self) -> Result<HashSet<String>, Error> {
+        Ok(self.database.tables().schools.iter()
+            .filter(|row| !row.logo_path.is_empty())
+            .map(|row| row.logo_path.clone())
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryOutboxRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for InMemoryOutboxRepository {
+    async fn append(&self, events: &[DomainEvent]) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        for event in events {
+            let payload = serde_json::to_value(event).map_err(|err| Error::Encode(Box::new(err)))?;
+            tables.outbox_events.push(OutboxEvent {
+                id: Uuid::new_v4(),
+                event_type: event.event_type().to_string(),
+                payload,
+                occurred_at: Utc::now(),
+                dispatched_at: None,
+            });
+        }
+        Ok(())
+    }
+
+    async fn relay(&self, limit: u32, kind: &str, subscribers: &[String], max_attempts: i32) -> Result<u64, Error> {
+        let mut tables = self.database.tables();
+        let tables = &mut *tables;
+        tables.outbox_events.sort_by_key(|row| row.occurred_at);
+
+        let mut relayed = 0;
+        for event in tables.outbox_events.iter_mut().filter(|row| row.dispatched_at.is_none()).take(limit as usize) {
+            event.dispatched_at = Some(Utc::now());
+            relayed += 1;
+
+            let mut published = event.payload.clone();
+            published["id"] = serde_json::json!(event.id);
+            published["occurred_at"] = serde_json::json!(event.occurred_at);
+            for subscriber in subscribers {
+                tables.jobs.push(Job {
+                    id: Uuid::new_v4(),
+                    kind: kind.to_string(),
+                    payload: serde_json::json!({ "subscriber": subscriber, "event": published }),
+                    status: JobStatus::Pending,
+                    attempts: 0,
+                    max_attempts,
+                    run_at: Utc::now(),
+                    locked_at: None,
+                    last_error: None,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                });
+            }
+        }
+        Ok(relayed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryWebhookRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryWebhookRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for InMemoryWebhookRepository {
+    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Webhook>, i64), Error> {
+        let mut rows = self.database.tables().webhooks.clone();
+        rows.sort_by_key(|row| row.created_at);
+        Ok(paginate(rows, offset, page_size))
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Webhook, Error> {
+        self.database.tables().webhooks.iter()
+            .find(|row| row.id == id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn create(&self, webhook: &Webhook) -> Result<(), Error> {
+        self.database.tables().webhooks.push(webhook.clone());
+        Ok(())
+    }
+
+    async fn update(&self, webhook: &Webhook) -> Result<(), Error> {
+        if let Some(row) = self.database.tables().webhooks.iter_mut().find(|row| row.id == webhook.id) {
+            row.url = webhook.url.clone();
+            row.event_types = webhook.event_types.clone();
+            row.updated_at = webhook.updated_at;
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if !tables.webhooks.iter().any(|row| row.id == id) {
+            return Err(Error::RowNotFound);
+        }
+        tables.webhooks.retain(|row| row.id != id);
+        tables.webhook_deliveries.retain(|row| row.webhook_id != id);
+        Ok(())
+    }
+
+    async fn matching(&self, event_type: &str, school_id: Option<Uuid>) -> Result<Vec<Webhook>, Error> {
+        let mut rows: Vec<Webhook> = self.database.tables().webhooks.iter()
+            .filter(|row| row.wants(event_type, school_id))
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| row.created_at);
+        Ok(rows)
+    }
+
+    async fn create_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, Error> {
+        let mut tables = self.database.tables();
+        if tables.webhook_deliveries.iter().any(|row| row.webhook_id == delivery.webhook_id && row.event_id == delivery.event_id) {
+            return Ok(false);
+        }
+        tables.webhook_deliveries.push(delivery.clone());
+        Ok(true)
+    }
+
+    async fn get_delivery(&self, id: Uuid) -> Result<WebhookDelivery, Error> {
+        self.database.tables().webhook_deliveries.iter()
+            .find(|row| row.id == id)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn list_deliveries(&self, webhook_id: Uuid, offset: u32, page_size: u32) -> Result<(Vec<WebhookDelivery>, i64), Error> {
+        let mut rows: Vec<WebhookDelivery> = self.database.tables().webhook_deliveries.iter()
+            .filter(|row| row.webhook_id == webhook_id)
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.created_at));
+        Ok(paginate(rows, offset, page_size))
+    }
+
+    async fn record_attempt(&self, id: Uuid, attempt: &DeliveryAttempt) -> Result<WebhookDelivery, Error> {
+        let mut tables = self.database.tables();
+        let row = tables.webhook_deliveries.iter_mut().find(|row| row.id == id).ok_or(Error::RowNotFound)?;
+        row.status = attempt.status;
+        row.attempts += 1;
+        row.response_status = attempt.response_status;
+        row.response_body = attempt.response_body.clone();
+        row.error = attempt.error.clone();
+        row.updated_at = Utc::now();
+        Ok(row.clone())
+    }
+
+    async fn reset_delivery(&self, id: Uuid) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if let Some(row) = tables.webhook_deliveries.iter_mut().find(|row| row.id == id && row.status == WebhookDeliveryStatus::Failed) {
+            row.status = WebhookDeliveryStatus::Pending;
+            row.attempts = 0;
+            row.updated_at = Utc::now();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryAuditLogRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryAuditLogRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl AuditLogRepository for InMemoryAuditLogRepository {
+    async fn record(&self, log: &AuditLog) -> Result<(), Error> {
+        self.database.tables().audit_logs.push(log.clone());
+        Ok(())
+    }
+
+    async fn list(&self, filter: &AuditLogFilter, offset: u32, page_size: u32) -> Result<(Vec<AuditLog>, i64), Error> {
+        let mut rows: Vec<AuditLog> = self.database.tables().audit_logs.iter()
+            .filter(|row| filter.entity_type.as_ref().is_none_or(|entity_type| &row.entity_type == entity_type))
+            .filter(|row| filter.entity_id.is_none_or(|entity_id| row.entity_id == entity_id))
+            .filter(|row| filter.actor.as_ref().is_none_or(|actor| row.actor_id.as_ref() == Some(actor) || row.actor.as_ref() == Some(actor)))
+            .filter(|row| filter.from.is_none_or(|from| row.created_at >= from))
+            .filter(|row| filter.to.is_none_or(|to| row.created_at < to))
+            .cloned()
+            .collect();
+        rows.sort_by_key(|row| std::cmp::Reverse(row.created_at));
+        Ok(paginate(rows, offset, page_size))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryIdempotencyRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryIdempotencyRepository {
+    pub fn new(database: InMemoryDatabase) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for InMemoryIdempotencyRepository {
+    async fn claim(&self, key: &IdempotencyKey) -> Result<Option<IdempotencyKey>, Error> {
+        let mut tables = self.database.tables();
+        match tables.idempotency_keys.iter_mut().find(|row| row.key == key.key && row.endpoint == key.endpoint) {
+            Some(row) if row.expires_at > key.created_at && (row.is_completed() || row.locked_until > key.created_at) => Ok(Some(row.clone())),
+            Some(row) => {
+                *row = key.clone();
+                Ok(None)
+            }
+            None => {
+                tables.idempotency_keys.push(key.clone());
+                Ok(None)
+            }
+        }
+    }
+
+    async fn complete(&self, key: &IdempotencyKey, status_code: i32, content_type: Option<String>, body: Vec<u8>) -> Result<(), Error> {
+        let mut tables = self.database.tables();
+        if let Some(row) = tables.idempotency_keys.iter_mut().find(|row| row.key == key.key && row.endpoint == key.endpoint && row.created_at == key.created_at) {
+            row.status_code = Some(status_code);
+            row.content_type = content_type;
+            row.response_body = Some(body);
+        }
+        Ok(())
+    }
+
+    async fn release(&self, key: &IdempotencyKey) -> Result<(), Error> {
+        self.database.tables().idempotency_keys
+            .retain(|row| row.key != key.key || row.endpoint != key.endpoint || row.created_at != key.created_at || row.status_code.is_some());
+        Ok(())
+    }
+
+    async fn purge_expired(&self, now: DateTime<Utc>) -> Result<u64, Error> {
+        let mut tables = self.database.tables();
+        let before = tables.idempotency_keys.len();
+        tables.idempotency_keys.retain(|row| row.expires_at > now);
+        Ok((before - tables.idempotency_keys.len()) as u64)
+    }
+}
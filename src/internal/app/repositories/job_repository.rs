@@ -0,0 +1,197 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query_as, Error};
+use uuid::Uuid;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
+use crate::internal::entities::job::{Job, JobStatus};
+
+#[async_trait]
+pub trait JobRepository: Send + Sync {
+    async fn list(&self, status: Option<JobStatus>, offset: u32, page_size: u32) -> Result<(Vec<Job>, i64), Error>;
+    async fn get_by_id(&self, id: Uuid) -> Result<Job, Error>;
+    async fn enqueue(&self, job: &Job) -> Result<(), Error>;
+    // Locks up to `limit` due jobs for this worker and marks them running. A
+    // running job whose lock is older than `lock_timeout` is taken over, since
+    // the worker holding it has died.
+    async fn claim(&self, limit: u32, lock_timeout: Duration) -> Result<Vec<Job>, Error>;
+    // Refreshes the lock of a job still running as `attempt`, so it isn't
+    // taken over while its worker is alive. Returns RowNotFound once another
+    // worker has taken it over.
+    async fn heartbeat(&self, id: Uuid, attempt: i32) -> Result<(), Error>;
+    // Both only record the outcome of `attempt`, so a worker that lost its
+    // lock can't overwrite the outcome of the attempt that took over.
+    async fn succeed(&self, id: Uuid, attempt: i32) -> Result<(), Error>;
+    // Schedules another attempt at `retry_at`, or marks the job dead without one.
+    async fn fail(&self, id: Uuid, attempt: i32, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<(), Error>;
+    // Dead and cancelled jobs only; returns RowNotFound for any other.
+    async fn retry(&self, id: Uuid) -> Result<Job, Error>;
+    // Pending jobs only; returns RowNotFound for any other.
+    async fn cancel(&self, id: Uuid) -> Result<Job, Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRepositoryImpl {
+    database: PgExecutor,
+}
+
+impl JobRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl JobRepository for JobRepositoryImpl {
+    #[instrument(name = "JobRepository::list", skip_all)]
+    async fn list(&self, status: Option<JobStatus>, offset: u32, page_size: u32) -> Result<(Vec<Job>, i64), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM jobs WHERE ($1::job_status IS NULL OR status = $1)
+            ORDER BY created_at DESC LIMIT $2 OFFSET $3
+        "#;
+
+        let count_query = r#"
+            SELECT COUNT(*) AS total FROM jobs WHERE ($1::job_status IS NULL OR status = $1)
+        "#;
+
+        let rows = query_as(query)
+            .bind(status)
+            .bind(page_size as i64)
+            .bind(offset as i64)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let total: (i64,) = query_as(count_query)
+            .bind(status)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok((rows, total.0))
+    }
+
+    #[instrument(name = "JobRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<Job, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM jobs WHERE id = $1
+        "#;
+
+        query_as(query).bind(id).fetch_one(&mut *conn).await
+    }
+
+    #[instrument(name = "JobRepository::enqueue", skip_all)]
+    async fn enqueue(&self, job: &Job) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO jobs (id, kind, payload, status, attempts, max_attempts, run_at, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        "#;
+
+        sqlx::query(query)
+            .bind(job.id)
+            .bind(&job.kind)
+            .bind(&job.payload)
+            .bind(job.status)
+            .bind(job.attempts)
+            .bind(job.max_attempts)
+            .bind(job.run_at)
+            .bind(job.created_at)
+            .bind(job.updated_at)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(name = "JobRepository::claim", skip_all)]
+    async fn claim(&self, limit: u32, lock_timeout: Duration) -> Result<Vec<Job>, Error> {
+        let mut conn = self.database.acquire().await?;
+        // SKIP LOCKED lets concurrent workers each take different rows
+        // instead of queueing behind one another.
+        let query = r#"
+            UPDATE jobs SET status = 'running', attempts = attempts + 1, locked_at = NOW(), updated_at = NOW()
+            WHERE id IN (
+                SELECT id FROM jobs
+                WHERE (status = 'pending' AND run_at <= NOW())
+                   OR (status = 'running' AND locked_at < NOW() - make_interval(secs => $2))
+                ORDER BY run_at ASC
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+        "#;
+
+        query_as(query)
+            .bind(limit as i64)
+            .bind(lock_timeout.as_secs_f64())
+            .fetch_all(&mut *conn)
+            .await
+    }
+
+    #[instrument(name = "JobRepository::heartbeat", skip_all)]
+    async fn heartbeat(&self, id: Uuid, attempt: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE jobs SET locked_at = NOW()
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+        "#;
+
+        let result = sqlx::query(query).bind(id).bind(attempt).execute(&mut *conn).await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "JobRepository::succeed", skip_all)]
+    async fn succeed(&self, id: Uuid, attempt: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE jobs SET status = 'succeeded', locked_at = NULL, last_error = NULL, updated_at = NOW()
+            WHERE id = $1 AND status = 'running' AND attempts = $2
+        "#;
+
+        sqlx::query(query).bind(id).bind(attempt).execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "JobRepository::fail", skip_all)]
+    async fn fail(&self, id: Uuid, attempt: i32, error: &str, retry_at: Option<DateTime<Utc>>) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE jobs
+            SET status = CASE WHEN $3::timestamptz IS NULL THEN 'dead'::job_status ELSE 'pending'::job_status END,
+                run_at = COALESCE($3, run_at), locked_at = NULL, last_error = $2, updated_at = NOW()
+            WHERE id = $1 AND status = 'running' AND attempts = $4
+        "#;
+
+        sqlx::query(query).bind(id).bind(error).bind(retry_at).bind(attempt).execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "JobRepository::retry", skip_all)]
+    async fn retry(&self, id: Uuid) -> Result<Job, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE jobs SET status = 'pending', attempts = 0, run_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status IN ('dead', 'cancelled')
+            RETURNING *
+        "#;
+
+        query_as(query).bind(id).fetch_one(&mut *conn).await
+    }
+
+    #[instrument(name = "JobRepository::cancel", skip_all)]
+    async fn cancel(&self, id: Uuid) -> Result<Job, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE jobs SET status = 'cancelled', updated_at = NOW()
+            WHERE id = $1 AND status = 'pending'
+            RETURNING *
+        "#;
+
+        query_as(query).bind(id).fetch_one(&mut *conn).await
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashSet;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query_scalar, Error, PgPool};
+use tracing::instrument;
+
+#[async_trait]
+pub trait MaintenanceRepository: Send + Sync {
+    // Permanently deletes rows soft-deleted before `before`, returning how
+    // many went from each table.
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<(&'static str, u64)>, Error>;
+    // Storage paths still referenced by a row, deleted or not.
+    async fn referenced_files(&self) -> Result<HashSet<String>, Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct MaintenanceRepositoryImpl {
+    database: PgPool,
+}
+
+impl MaintenanceRepositoryImpl {
+    pub fn new(database: PgPool) -> Self {
+        Self { database }
+    }
+}
+
+// Children first, and a row that other rows still reference is kept, since
+// the foreign keys would cascade its deletion into them (or refuse it).
+// Provinces and cities are synced from wilayah.id rather than deleted.
+const PURGES: &[(&str, &str)] = &[
+    ("users", r#"
+        DELETE FROM users WHERE deleted_at < $1
+    "#),
+    ("schools", r#"
+        DELETE FROM schools WHERE deleted_at < $1
+        AND NOT EXISTS (SELECT 1 FROM users WHERE users.school_id = schools.id)
+    "#),
+    ("subscriptions", r#"
+        DELETE FROM subscriptions WHERE deleted_at < $1
+        AND NOT EXISTS (SELECT 1 FROM schools WHERE schools.subscription_id = subscriptions.id)
+    "#),
+    ("roles", r#"
+        DELETE FROM roles WHERE deleted_at < $1
+        AND NOT EXISTS (SELECT 1 FROM users WHERE users.role_id = roles.id)
+    "#),
+    ("subscription_types", r#"
+        DELETE FROM subscription_types WHERE deleted_at < $1
+        AND NOT EXISTS (SELECT 1 FROM subscriptions WHERE subscriptions.subscription_type_id = subscription_types.id)
+    "#),
+];
+
+#[async_trait]
+impl MaintenanceRepository for MaintenanceRepositoryImpl {
+    #[instrument(name = "MaintenanceRepository::purge_deleted", skip_all)]
+    async fn purge_deleted(&self, before: DateTime<Utc>) -> Result<Vec<(&'static str, u64)>, Error> {
+        let mut purged = Vec::with_capacity(PURGES.len());
+        for (table, query) in PURGES {
+            let result = sqlx::query(query).bind(before).execute(&self.database).await?;
+            purged.push((*table, result.rows_affected()));
+        }
+        Ok(purged)
+    }
+
+    #[instrument(name = "MaintenanceRepository::referenced_files", skip_all)]
+    async fn referenced_files(&self) -> Result<HashSet<String>, Error> {
+        let query = r#"
+            SELECT logo_path FROM schools WHERE logo_path <> ''
+        "#;
+
+        let paths: Vec<String> = query_scalar(query).fetch_all(&self.database).await?;
+        Ok(paths.into_iter().collect())
+    }
+}
@@ -5,4 +5,13 @@ pub mod province_repository;
 pub mod city_repository;
 pub mod school_repository;
 pub mod user_repository;
-pub mod db_transaction_repository;
\ No newline at end of file
+pub mod db_transaction_repository;
+pub mod in_memory_repository;
+pub mod health_repository;
+pub mod job_repository;
+pub mod schedule_repository;
+pub mod maintenance_repository;
+pub mod outbox_repository;
+pub mod webhook_repository;
+pub mod audit_log_repository;
+pub mod idempotency_repository;
@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::{query_scalar, Error};
+use uuid::Uuid;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
+use crate::internal::entities::event::DomainEvent;
+
+#[async_trait]
+pub trait OutboxRepository: Send + Sync {
+    // Call on transaction-bound repositories, so the events exist only if
+    // the change they describe commits.
+    async fn append(&self, events: &[DomainEvent]) -> Result<(), Error>;
+    // Marks up to `limit` undispatched events dispatched and queues a job of
+    // `kind` for each of them and each subscriber, with a payload of
+    // `{"subscriber": .., "event": <PublishedEvent>}`, all in one statement.
+    // Returns how many events went out.
+    async fn relay(&self, limit: u32, kind: &str, subscribers: &[String], max_attempts: i32) -> Result<u64, Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxRepositoryImpl {
+    database: PgExecutor,
+}
+
+impl OutboxRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl OutboxRepository for OutboxRepositoryImpl {
+    #[instrument(name = "OutboxRepository::append", skip_all)]
+    async fn append(&self, events: &[DomainEvent]) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO outbox_events (id, event_type, payload, occurred_at) VALUES ($1, $2, $3, $4)
+        "#;
+
+        for event in events {
+            let payload = serde_json::to_value(event).map_err(|err| Error::Encode(Box::new(err)))?;
+            sqlx::query(query)
+                .bind(Uuid::new_v4())
+                .bind(event.event_type())
+                .bind(payload)
+                .bind(Utc::now())
+                .execute(&mut *conn)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "OutboxRepository::relay", skip_all)]
+    async fn relay(&self, limit: u32, kind: &str, subscribers: &[String], max_attempts: i32) -> Result<u64, Error> {
+        let mut conn = self.database.acquire().await?;
+        // The data-modifying CTEs run together, so an event is never marked
+        // dispatched without its jobs or the other way round.
+        let query = r#"
+            WITH claimed AS (
+                UPDATE outbox_events SET dispatched_at = NOW()
+                WHERE id IN (
+                    SELECT id FROM outbox_events WHERE dispatched_at IS NULL
+                    ORDER BY occurred_at ASC
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, payload, occurred_at
+            ), queued AS (
+                INSERT INTO jobs (id, kind, payload, max_attempts)
+                SELECT gen_random_uuid(), $2, jsonb_build_object(
+                    'subscriber', subscriber,
+                    'event', claimed.payload || jsonb_build_object('id', claimed.id, 'occurred_at', claimed.occurred_at)
+                ), $4
+                FROM claimed CROSS JOIN unnest($3::text[]) AS subscriber
+            )
+            SELECT COUNT(*) FROM claimed
+        "#;
+
+        let relayed: i64 = query_scalar(query)
+            .bind(limit as i64)
+            .bind(kind)
+            .bind(subscribers)
+            .bind(max_attempts)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(relayed as u64)
+    }
+}
@@ -1,11 +1,14 @@
+use async_trait::async_trait;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::province::{Province, ProvinceFromTable};
-use sqlx::{query_as, Error, PgPool};
-
-pub trait ProvinceRepository {
-    fn new(database: PgPool) -> Self;
+use sqlx::{query_as, Error};
 
+#[async_trait]
+pub trait ProvinceRepository: Send + Sync {
     async fn list(&self) -> Result<Vec<ProvinceFromTable>, Error>;
-    async fn get_by_id(&self, id: String) -> Result<Province, Error>;
+    async fn get_by_id(&self, id: String) -> Result<ProvinceFromTable, Error>;
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<ProvinceFromTable>, Error>;
     async fn create(&self, province: &Province) -> Result<(), Error>;
     // async fn update(&self, province: &Province) -> Result<(), Error>;
 
@@ -13,42 +16,66 @@ pub trait ProvinceRepository {
 
 #[derive(Debug, Clone)]
 pub struct ProvinceRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl ProvinceRepository for ProvinceRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl ProvinceRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
 
+#[async_trait]
+impl ProvinceRepository for ProvinceRepositoryImpl {
+    #[instrument(name = "ProvinceRepository::list", skip_all)]
     async fn list(&self) -> Result<Vec<ProvinceFromTable>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM provinces ORDER BY name
         "#;
 
 
         let rows = query_as(query)
-            .fetch_all(&self.database)
+            .fetch_all(&mut *conn)
             .await?;
 
 
         Ok(rows)
     }
 
-    async fn get_by_id(&self, id: String) -> Result<Province, Error> {
+    #[instrument(name = "ProvinceRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: String) -> Result<ProvinceFromTable, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
         SELECT * FROM provinces WHERE id = $1
     "#;
 
-        let province = query_as::<_, Province>(query)
+        let province = query_as::<_, ProvinceFromTable>(query)
             .bind(id)
-            .fetch_one(&self.database)
+            .fetch_one(&mut *conn)
             .await?;
 
         Ok(province)
     }
 
+    #[instrument(name = "ProvinceRepository::get_by_ids", skip_all)]
+    async fn get_by_ids(&self, ids: &[String]) -> Result<Vec<ProvinceFromTable>, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM provinces WHERE id = ANY($1)
+        "#;
+
+        let rows = query_as(query)
+            .bind(ids)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(rows)
+    }
+
+    #[instrument(name = "ProvinceRepository::create", skip_all)]
     async fn create(&self, province: &Province) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             INSERT INTO provinces (id, name, created_at, updated_at, deleted_at)
             VALUES ($1, $2, $3, $4, $5)
@@ -60,7 +87,7 @@ impl ProvinceRepository for ProvinceRepositoryImpl {
             .bind(province.created_at)
             .bind(province.updated_at)
             .bind(province.deleted_at)
-            .execute(&self.database)
+            .execute(&mut *conn)
             .await?;
 
         Ok(())
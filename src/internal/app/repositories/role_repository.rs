@@ -1,74 +1,117 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use sqlx::{query_as, Error, QueryBuilder};
 use uuid::Uuid;
+use tracing::instrument;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::role::Role;
 
-pub trait RoleRepository {
-        fn new(database: PgPool) -> Self;
-
-        async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Role>, i64), Error>;
+#[async_trait]
+pub trait RoleRepository: Send + Sync {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Role>, Error>;
     async fn get_by_id(&self, id: Uuid) -> Result<Role, Error>;
+    async fn get_trashed(&self, id: Uuid) -> Result<Role, Error>;
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Role>, Error>;
     async fn get_by_name(&self, name: String) -> Result<Role, Error>;
     async fn create(&self, role: &Role) -> Result<(), Error>;
-    async fn update(&self, role: &Role) -> Result<(), Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    // Both fail with RowNotFound unless the row is live and still at the
+    // given version, which they bump.
+    async fn update(&self, role: &Role) -> Result<Role, Error>;
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error>;
+    async fn restore(&self, id: Uuid) -> Result<Role, Error>;
+    // Permanently deletes a soft-deleted role no user references, failing
+    // with RowNotFound otherwise.
+    async fn purge(&self, id: Uuid) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
 pub struct RoleRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl RoleRepository for RoleRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl RoleRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
+
+#[async_trait]
+impl RoleRepository for RoleRepositoryImpl {
+    #[instrument(name = "RoleRepository::list", skip_all)]
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Role>, Error> {
+        let mut conn = self.database.acquire().await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM roles WHERE ");
+        query.push_conditions(&mut select);
+        query.push_page(&mut select, page);
+        let rows = select.build_query_as().fetch_all(&mut *conn).await?;
+
+        let total = if page.include_total {
+            let mut count = QueryBuilder::new("SELECT COUNT(*) AS total FROM roles WHERE ");
+            query.push_conditions(&mut count);
+            let total: (i64,) = count.build_query_as().fetch_one(&mut *conn).await?;
+            Some(total.0)
+        } else {
+            None
+        };
+
+        Ok(Page::new(rows, query, page, total))
+    }
 
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Role>, i64), Error> {
+    #[instrument(name = "RoleRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<Role, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM roles ORDER BY name ASC LIMIT $1 OFFSET $2
+            SELECT * FROM roles WHERE id = $1 AND deleted_at IS NULL
         "#;
 
-        let count_query = r#"
-            SELECT COUNT(*) AS total FROM roles
-        "#;
+        let role = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        let rows = query_as(query)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-            .fetch_all(&self.database)
-            .await?;
+        Ok(role)
+    }
 
-        let total: (i64,) = query_as(count_query)
-            .fetch_one(&self.database)
-            .await?;
+    #[instrument(name = "RoleRepository::get_trashed", skip_all)]
+    async fn get_trashed(&self, id: Uuid) -> Result<Role, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM roles WHERE id = $1 AND deleted_at IS NOT NULL
+        "#;
 
-        Ok((rows, total.0))
+        let role = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(role)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<Role, Error> {
+    #[instrument(name = "RoleRepository::get_by_ids", skip_all)]
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Role>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM roles WHERE id = $1 AND deleted_at IS NULL
+            SELECT * FROM roles WHERE id = ANY($1) AND deleted_at IS NULL
         "#;
 
-        let role = query_as(query).bind(id).fetch_one(&self.database).await?;
+        let roles = query_as(query).bind(ids).fetch_all(&mut *conn).await?;
 
-        Ok(role)
+        Ok(roles)
     }
 
+    #[instrument(name = "RoleRepository::get_by_name", skip_all)]
     async fn get_by_name(&self, name: String) -> Result<Role, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM roles WHERE name = $1 AND deleted_at IS NULL
         "#;
 
-        let role = query_as(query).bind(name).fetch_one(&self.database).await?;
+        let role = query_as(query).bind(name).fetch_one(&mut *conn).await?;
 
         Ok(role)
     }
 
+    #[instrument(name = "RoleRepository::create", skip_all)]
     async fn create(&self, role: &Role) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            INSERT INTO roles (id, name, created_at, updated_at, deleted_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO roles (id, name, created_at, updated_at, deleted_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6)
         "#;
 
         sqlx::query(query)
@@ -77,40 +120,84 @@ impl RoleRepository for RoleRepositoryImpl {
             .bind(role.created_at)
             .bind(role.updated_at)
             .bind(role.deleted_at)
-            .execute(&self.database)
+            .bind(role.version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(())
     }
 
-    async fn update(&self, role: &Role) -> Result<(), Error> {
+    #[instrument(name = "RoleRepository::update", skip_all)]
+    async fn update(&self, role: &Role) -> Result<Role, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-        UPDATE roles
-            SET name = $1, updated_at = $2
-            WHERE id = $3
+            UPDATE roles
+            SET name = $1, updated_at = $2, version = version + 1
+            WHERE id = $3 AND version = $4 AND deleted_at IS NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let updated = query_as(query)
             .bind(&role.name)
             .bind(role.updated_at)
             .bind(role.id)
-            .execute(&self.database)
+            .bind(role.version)
+            .fetch_one(&mut *conn)
             .await?;
 
-        Ok(())
+        Ok(updated)
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+    #[instrument(name = "RoleRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            DELETE FROM roles WHERE id = $1
+            UPDATE roles SET deleted_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $2 AND deleted_at IS NULL
         "#;
 
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.database)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "RoleRepository::restore", skip_all)]
+    async fn restore(&self, id: Uuid) -> Result<Role, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE roles SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *
+        "#;
+
+        let role = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(role)
+    }
+
+    #[instrument(name = "RoleRepository::purge", skip_all)]
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM roles WHERE id = $1 AND deleted_at IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM users WHERE users.role_id = roles.id)
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query_as, Connection, Error, PgConnection, PgPool};
+use uuid::Uuid;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
+use crate::internal::entities::schedule::ScheduleRun;
+
+// Advisory lock key held by whichever instance currently runs the scheduler.
+const SCHEDULER_LOCK_ID: i64 = 0x5c_4e_d0_1e;
+
+/// Proof that this instance is the scheduler leader. Leadership ends when it
+/// is released or when the session holding it is lost.
+#[async_trait]
+pub trait Leadership: Send {
+    async fn is_held(&mut self) -> bool;
+    async fn release(self: Box<Self>);
+}
+
+#[async_trait]
+pub trait ScheduleRepository: Send + Sync {
+    async fn list(&self) -> Result<Vec<ScheduleRun>, Error>;
+    async fn record(&self, name: &str, run_at: DateTime<Utc>, job_id: Uuid) -> Result<(), Error>;
+    // Returns `None` while another instance is the leader.
+    async fn try_lead(&self) -> Result<Option<Box<dyn Leadership>>, Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduleRepositoryImpl {
+    // For the leader lock, which holds a session of its own.
+    pool: PgPool,
+    database: PgExecutor,
+}
+
+impl ScheduleRepositoryImpl {
+    pub fn new(pool: PgPool, database: PgExecutor) -> Self {
+        Self { pool, database }
+    }
+}
+
+// The lock belongs to the session, so its connection is detached from the
+// pool: dropping it, or the process dying, ends the session and frees the lock
+// for another instance, instead of handing a locked session back to the pool.
+pub struct PgLeadership {
+    connection: PgConnection,
+}
+
+#[async_trait]
+impl Leadership for PgLeadership {
+    async fn is_held(&mut self) -> bool {
+        self.connection.ping().await.is_ok()
+    }
+
+    async fn release(mut self: Box<Self>) {
+        let _ = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(SCHEDULER_LOCK_ID)
+            .execute(&mut self.connection)
+            .await;
+        let _ = self.connection.close().await;
+    }
+}
+
+#[async_trait]
+impl ScheduleRepository for ScheduleRepositoryImpl {
+    #[instrument(name = "ScheduleRepository::list", skip_all)]
+    async fn list(&self) -> Result<Vec<ScheduleRun>, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM schedule_runs ORDER BY name ASC
+        "#;
+
+        query_as(query).fetch_all(&mut *conn).await
+    }
+
+    #[instrument(name = "ScheduleRepository::record", skip_all)]
+    async fn record(&self, name: &str, run_at: DateTime<Utc>, job_id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO schedule_runs (name, last_run_at, last_job_id, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (name) DO UPDATE
+            SET last_run_at = EXCLUDED.last_run_at, last_job_id = EXCLUDED.last_job_id, updated_at = NOW()
+        "#;
+
+        sqlx::query(query).bind(name).bind(run_at).bind(job_id).execute(&mut *conn).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "ScheduleRepository::try_lead", skip_all)]
+    async fn try_lead(&self) -> Result<Option<Box<dyn Leadership>>, Error> {
+        let mut connection = self.pool.acquire().await?.detach();
+        let (acquired,): (bool,) = query_as("SELECT pg_try_advisory_lock($1)")
+            .bind(SCHEDULER_LOCK_ID)
+            .fetch_one(&mut connection)
+            .await?;
+
+        if acquired {
+            Ok(Some(Box::new(PgLeadership { connection })))
+        } else {
+            let _ = connection.close().await;
+            Ok(None)
+        }
+    }
+}
@@ -1,75 +1,136 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{query_as, Error, QueryBuilder};
 use uuid::Uuid;
+use tracing::instrument;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::school::School;
 
-pub trait SchoolRepository {
-    fn new(database: PgPool) -> Self;
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<School>, i64), Error>;
+#[async_trait]
+pub trait SchoolRepository: Send + Sync {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<School>, Error>;
     async fn get_by_id(&self, id: Uuid) -> Result<School, Error>;
+    async fn get_trashed(&self, id: Uuid) -> Result<School, Error>;
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<School>, Error>;
     async fn get_by_subscription_id(&self, id: Uuid) -> Result<Vec<School>, Error>;
+    // Live schools still on a subscription that ended at or before `now`.
+    async fn get_expired_subscriptions(&self, now: DateTime<Utc>) -> Result<Vec<School>, Error>;
     async fn create(&self, subscription: &School) -> Result<School, Error>;
-    async fn update(&self, subscription: &School) -> Result<(), Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    // Both fail with RowNotFound unless the row is live and still at the
+    // given version, which they bump.
+    async fn update(&self, school: &School) -> Result<School, Error>;
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error>;
+    async fn restore(&self, id: Uuid) -> Result<School, Error>;
+    // Permanently deletes a soft-deleted school no user references, failing
+    // with RowNotFound otherwise.
+    async fn purge(&self, id: Uuid) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
 pub struct SchoolRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl SchoolRepository for SchoolRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl SchoolRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
+
+#[async_trait]
+impl SchoolRepository for SchoolRepositoryImpl {
+    #[instrument(name = "SchoolRepository::list", skip_all)]
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<School>, Error> {
+        let mut conn = self.database.acquire().await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM schools WHERE ");
+        query.push_conditions(&mut select);
+        query.push_page(&mut select, page);
+        let rows = select.build_query_as().fetch_all(&mut *conn).await?;
+
+        let total = if page.include_total {
+            let mut count = QueryBuilder::new("SELECT COUNT(*) AS total FROM schools WHERE ");
+            query.push_conditions(&mut count);
+            let total: (i64,) = count.build_query_as().fetch_one(&mut *conn).await?;
+            Some(total.0)
+        } else {
+            None
+        };
+
+        Ok(Page::new(rows, query, page, total))
+    }
 
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<School>, i64), Error> {
+    #[instrument(name = "SchoolRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<School, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM schools ORDER BY created_at ASC LIMIT $1 OFFSET $2
+            SELECT * FROM schools WHERE id = $1 AND deleted_at IS NULL
         "#;
 
-        let count_query = r#"
-            SELECT COUNT(*) AS total FROM schools
-        "#;
+        let subscription = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        let rows = query_as(query)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-            .fetch_all(&self.database)
-            .await?;
+        Ok(subscription)
+    }
 
-        let total: (i64,) = query_as(count_query)
-            .fetch_one(&self.database)
-            .await?;
+    #[instrument(name = "SchoolRepository::get_trashed", skip_all)]
+    async fn get_trashed(&self, id: Uuid) -> Result<School, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM schools WHERE id = $1 AND deleted_at IS NOT NULL
+        "#;
+
+        let school = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        Ok((rows, total.0))
+        Ok(school)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<School, Error> {
+    #[instrument(name = "SchoolRepository::get_by_ids", skip_all)]
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<School>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM schools WHERE id = $1 AND deleted_at IS NULL
+            SELECT * FROM schools WHERE id = ANY($1) AND deleted_at IS NULL
         "#;
 
-        let subscription = query_as(query).bind(id).fetch_one(&self.database).await?;
+        let schools = query_as(query).bind(ids).fetch_all(&mut *conn).await?;
 
-        Ok(subscription)
+        Ok(schools)
     }
 
+    #[instrument(name = "SchoolRepository::get_by_subscription_id", skip_all)]
     async fn get_by_subscription_id(&self, id: Uuid) -> Result<Vec<School>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM schools WHERE subscription_id = $1 AND deleted_at IS NULL
         "#;
 
-        let subscription = query_as(query).bind(id).fetch_all(&self.database).await?;
+        let subscription = query_as(query).bind(id).fetch_all(&mut *conn).await?;
 
         Ok(subscription)
     }
 
+    #[instrument(name = "SchoolRepository::get_expired_subscriptions", skip_all)]
+    async fn get_expired_subscriptions(&self, now: DateTime<Utc>) -> Result<Vec<School>, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM schools
+            WHERE subscription_id IS NOT NULL AND subscription_ends_at <= $1 AND deleted_at IS NULL
+            ORDER BY subscription_ends_at, id
+        "#;
 
+        let schools = query_as(query).bind(now).fetch_all(&mut *conn).await?;
+
+        Ok(schools)
+    }
+
+
+    #[instrument(name = "SchoolRepository::create", skip_all)]
     async fn create(&self, school: &School) -> Result<School, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            INSERT INTO schools (id, name, address, logo_path, subscription_id, province_id, city_id, created_at, updated_at, deleted_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            RETURNING id, name, address, logo_path, subscription_id, province_id, city_id, created_at, updated_at, deleted_at
+            INSERT INTO schools (id, name, address, logo_path, subscription_id, subscription_ends_at, province_id, city_id, created_at, updated_at, deleted_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            RETURNING *
         "#;
 
         let created_school = sqlx::query_as::<_, School>(query)
@@ -78,50 +139,97 @@ impl SchoolRepository for SchoolRepositoryImpl {
             .bind(&school.address)
             .bind(&school.logo_path)
             .bind(school.subscription_id)
+            .bind(school.subscription_ends_at)
             .bind(&school.province_id)
             .bind(&school.city_id)
             .bind(school.created_at)
             .bind(school.updated_at)
             .bind(school.deleted_at)
-            .fetch_one(&self.database)
+            .bind(school.version)
+            .fetch_one(&mut *conn)
             .await?;
 
         Ok(created_school)
     }
 
 
-    async fn update(&self, school: &School) -> Result<(), Error> {
+    #[instrument(name = "SchoolRepository::update", skip_all)]
+    async fn update(&self, school: &School) -> Result<School, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-        UPDATE schools
-            SET name = $1, address = $2, logo_path = $3, subscription_id = $4, province_id = $5, city_id = $6, updated_at = $7
-            WHERE id = $8
+            UPDATE schools
+            SET name = $1, address = $2, logo_path = $3, subscription_id = $4, subscription_ends_at = $5, province_id = $6, city_id = $7, updated_at = $8, version = version + 1
+            WHERE id = $9 AND version = $10 AND deleted_at IS NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let updated = query_as(query)
             .bind(&school.name)
             .bind(&school.address)
             .bind(&school.logo_path)
             .bind(school.subscription_id)
+            .bind(school.subscription_ends_at)
             .bind(&school.province_id)
             .bind(&school.city_id)
             .bind(school.updated_at)
             .bind(school.id)
-            .execute(&self.database)
+            .bind(school.version)
+            .fetch_one(&mut *conn)
             .await?;
 
+        Ok(updated)
+    }
+
+    #[instrument(name = "SchoolRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE schools SET deleted_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $2 AND deleted_at IS NULL
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .bind(version)
+            .execute(&mut *conn)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+    #[instrument(name = "SchoolRepository::restore", skip_all)]
+    async fn restore(&self, id: Uuid) -> Result<School, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            DELETE FROM schools WHERE id = $1
+            UPDATE schools SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let school = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(school)
+    }
+
+    #[instrument(name = "SchoolRepository::purge", skip_all)]
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM schools WHERE id = $1 AND deleted_at IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM users WHERE users.school_id = schools.id)
+        "#;
+
+        let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.database)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
-}
+}
\ No newline at end of file
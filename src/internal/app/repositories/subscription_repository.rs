@@ -1,74 +1,118 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use sqlx::{query_as, Error, QueryBuilder};
 use uuid::Uuid;
+use tracing::instrument;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::subscription::Subscription;
 
-pub trait SubscriptionRepository {
-    fn new(database: PgPool) -> Self;
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Subscription>, i64), Error>;
+#[async_trait]
+pub trait SubscriptionRepository: Send + Sync {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Subscription>, Error>;
     async fn get_by_id(&self, id: Uuid) -> Result<Subscription, Error>;
-    async fn get_by_subscription_type_id(&self, id: Uuid) -> Result<Vec<Subscription>, Error>;
+    async fn get_trashed(&self, id: Uuid) -> Result<Subscription, Error>;
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error>;
+    async fn get_by_subscription_type_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error>;
     async fn create(&self, subscription: &Subscription) -> Result<(), Error>;
-    async fn update(&self, subscription: &Subscription) -> Result<(), Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    // Both fail with RowNotFound unless the row is live and still at the
+    // given version, which they bump.
+    async fn update(&self, subscription: &Subscription) -> Result<Subscription, Error>;
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error>;
+    async fn restore(&self, id: Uuid) -> Result<Subscription, Error>;
+    // Permanently deletes a soft-deleted subscription no school references,
+    // failing with RowNotFound otherwise.
+    async fn purge(&self, id: Uuid) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
 pub struct SubscriptionRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl SubscriptionRepository for SubscriptionRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl SubscriptionRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
+
+#[async_trait]
+impl SubscriptionRepository for SubscriptionRepositoryImpl {
+    #[instrument(name = "SubscriptionRepository::list", skip_all)]
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<Subscription>, Error> {
+        let mut conn = self.database.acquire().await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM subscriptions WHERE ");
+        query.push_conditions(&mut select);
+        query.push_page(&mut select, page);
+        let rows = select.build_query_as().fetch_all(&mut *conn).await?;
+
+        let total = if page.include_total {
+            let mut count = QueryBuilder::new("SELECT COUNT(*) AS total FROM subscriptions WHERE ");
+            query.push_conditions(&mut count);
+            let total: (i64,) = count.build_query_as().fetch_one(&mut *conn).await?;
+            Some(total.0)
+        } else {
+            None
+        };
+
+        Ok(Page::new(rows, query, page, total))
+    }
 
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Subscription>, i64), Error> {
+    #[instrument(name = "SubscriptionRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<Subscription, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM subscriptions ORDER BY price ASC LIMIT $1 OFFSET $2
+            SELECT * FROM subscriptions WHERE id = $1 AND deleted_at IS NULL
         "#;
 
-        let count_query = r#"
-            SELECT COUNT(*) AS total FROM subscriptions
-        "#;
+        let subscription = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        let rows = query_as(query)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-            .fetch_all(&self.database)
-            .await?;
+        Ok(subscription)
+    }
 
-        let total: (i64,) = query_as(count_query)
-            .fetch_one(&self.database)
-            .await?;
+    #[instrument(name = "SubscriptionRepository::get_trashed", skip_all)]
+    async fn get_trashed(&self, id: Uuid) -> Result<Subscription, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM subscriptions WHERE id = $1 AND deleted_at IS NOT NULL
+        "#;
+
+        let subscription = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        Ok((rows, total.0))
+        Ok(subscription)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<Subscription, Error> {
+    #[instrument(name = "SubscriptionRepository::get_by_ids", skip_all)]
+    async fn get_by_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM subscriptions WHERE id = $1 AND deleted_at IS NULL
+            SELECT * FROM subscriptions WHERE id = ANY($1) AND deleted_at IS NULL
         "#;
 
-        let subscription = query_as(query).bind(id).fetch_one(&self.database).await?;
+        let subscriptions = query_as(query).bind(ids).fetch_all(&mut *conn).await?;
 
-        Ok(subscription)
+        Ok(subscriptions)
     }
 
-    async fn get_by_subscription_type_id(&self, id: Uuid) -> Result<Vec<Subscription>, Error> {
+    #[instrument(name = "SubscriptionRepository::get_by_subscription_type_ids", skip_all)]
+    async fn get_by_subscription_type_ids(&self, ids: &[Uuid]) -> Result<Vec<Subscription>, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM subscriptions WHERE subscription_type_id = $1 AND deleted_at IS NULL
+            SELECT * FROM subscriptions WHERE subscription_type_id = ANY($1) AND deleted_at IS NULL ORDER BY price, id
         "#;
 
-        let subscription = query_as(query).bind(id).fetch_all(&self.database).await?;
+        let subscriptions = query_as(query).bind(ids).fetch_all(&mut *conn).await?;
 
-        Ok(subscription)
+        Ok(subscriptions)
     }
 
 
+    #[instrument(name = "SubscriptionRepository::create", skip_all)]
     async fn create(&self, subscription: &Subscription) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            INSERT INTO subscriptions (id, name, price, subscription_type_id, created_at, updated_at, deleted_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            INSERT INTO subscriptions (id, name, price, subscription_type_id, created_at, updated_at, deleted_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         "#;
 
         sqlx::query(query)
@@ -79,40 +123,86 @@ impl SubscriptionRepository for SubscriptionRepositoryImpl {
             .bind(subscription.created_at)
             .bind(subscription.updated_at)
             .bind(subscription.deleted_at)
-            .execute(&self.database)
+            .bind(subscription.version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(())
     }
 
-    async fn update(&self, subscription: &Subscription) -> Result<(), Error> {
+    #[instrument(name = "SubscriptionRepository::update", skip_all)]
+    async fn update(&self, subscription: &Subscription) -> Result<Subscription, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-        UPDATE subscriptions
-            SET name = $1, price = $2, updated_at = $3
-            WHERE id = $4
+            UPDATE subscriptions
+            SET name = $1, price = $2, subscription_type_id = $3, updated_at = $4, version = version + 1
+            WHERE id = $5 AND version = $6 AND deleted_at IS NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let updated = query_as(query)
             .bind(&subscription.name)
             .bind(subscription.price)
+            .bind(subscription.subscription_type_id)
             .bind(subscription.updated_at)
             .bind(subscription.id)
-            .execute(&self.database)
+            .bind(subscription.version)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok(updated)
+    }
+
+    #[instrument(name = "SubscriptionRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE subscriptions SET deleted_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $2 AND deleted_at IS NULL
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+    #[instrument(name = "SubscriptionRepository::restore", skip_all)]
+    async fn restore(&self, id: Uuid) -> Result<Subscription, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            DELETE FROM subscriptions WHERE id = $1
+            UPDATE subscriptions SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let subscription = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(subscription)
+    }
+
+    #[instrument(name = "SubscriptionRepository::purge", skip_all)]
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM subscriptions WHERE id = $1 AND deleted_at IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM schools WHERE schools.subscription_id = subscriptions.id)
+        "#;
+
+        let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.database)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
-}
+}
\ No newline at end of file
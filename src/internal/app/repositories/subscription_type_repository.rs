@@ -1,62 +1,91 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use sqlx::{query_as, Error, QueryBuilder};
 use uuid::Uuid;
+use tracing::instrument;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::subscription_type::SubscriptionType;
 
-pub trait SubscriptionTypeRepository {
-    fn new(database: PgPool) -> Self;
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<SubscriptionType>, i64), Error>;
+#[async_trait]
+pub trait SubscriptionTypeRepository: Send + Sync {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<SubscriptionType>, Error>;
     async fn get_by_id(&self, id: Uuid) -> Result<SubscriptionType, Error>;
+    async fn get_trashed(&self, id: Uuid) -> Result<SubscriptionType, Error>;
     async fn create(&self, subscription_type: &SubscriptionType) -> Result<(), Error>;
-    async fn update(&self, subscription_type: &SubscriptionType) -> Result<(), Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    // Both fail with RowNotFound unless the row is live and still at the
+    // given version, which they bump.
+    async fn update(&self, subscription_type: &SubscriptionType) -> Result<SubscriptionType, Error>;
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error>;
+    async fn restore(&self, id: Uuid) -> Result<SubscriptionType, Error>;
+    // Permanently deletes a soft-deleted subscription type no subscription
+    // references, failing with RowNotFound otherwise.
+    async fn purge(&self, id: Uuid) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
 pub struct SubscriptionTypeRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl SubscriptionTypeRepository for SubscriptionTypeRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl SubscriptionTypeRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
 
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<SubscriptionType>, i64), Error> {
-        let query = r#"
-            SELECT * FROM subscription_types WHERE deleted_at IS NULL ORDER BY name ASC LIMIT $1 OFFSET $2
-        "#;
+#[async_trait]
+impl SubscriptionTypeRepository for SubscriptionTypeRepositoryImpl {
+    #[instrument(name = "SubscriptionTypeRepository::list", skip_all)]
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<SubscriptionType>, Error> {
+        let mut conn = self.database.acquire().await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM subscription_types WHERE ");
+        query.push_conditions(&mut select);
+        query.push_page(&mut select, page);
+        let rows = select.build_query_as().fetch_all(&mut *conn).await?;
+
+        let total = if page.include_total {
+            let mut count = QueryBuilder::new("SELECT COUNT(*) AS total FROM subscription_types WHERE ");
+            query.push_conditions(&mut count);
+            let total: (i64,) = count.build_query_as().fetch_one(&mut *conn).await?;
+            Some(total.0)
+        } else {
+            None
+        };
+
+        Ok(Page::new(rows, query, page, total))
+    }
 
-        let count_query = r#"
-            SELECT COUNT(*) AS total FROM subscription_types WHERE deleted_at IS NULL
+    #[instrument(name = "SubscriptionTypeRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM subscription_types WHERE id = $1 AND deleted_at IS NULL
         "#;
 
-        let rows = query_as(query)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-            .fetch_all(&self.database)
-            .await?;
-
-        let total: (i64,) = query_as(count_query)
-            .fetch_one(&self.database)
-            .await?;
+        let subscription_type = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        Ok((rows, total.0))
+        Ok(subscription_type)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+    #[instrument(name = "SubscriptionTypeRepository::get_trashed", skip_all)]
+    async fn get_trashed(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM subscription_types WHERE id = $1 AND deleted_at IS NULL
+            SELECT * FROM subscription_types WHERE id = $1 AND deleted_at IS NOT NULL
         "#;
 
-        let subscription_type = query_as(query).bind(id).fetch_one(&self.database).await?;
+        let subscription_type = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
         Ok(subscription_type)
     }
 
+    #[instrument(name = "SubscriptionTypeRepository::create", skip_all)]
     async fn create(&self, subscription_type: &SubscriptionType) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            INSERT INTO subscription_types (id, name, created_at, updated_at, deleted_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO subscription_types (id, name, created_at, updated_at, deleted_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6)
         "#;
 
         sqlx::query(query)
@@ -65,39 +94,84 @@ impl SubscriptionTypeRepository for SubscriptionTypeRepositoryImpl {
             .bind(subscription_type.created_at)
             .bind(subscription_type.updated_at)
             .bind(subscription_type.deleted_at)
-            .execute(&self.database)
+            .bind(subscription_type.version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(())
     }
 
-    async fn update(&self, subscription_type: &SubscriptionType) -> Result<(), Error> {
+    #[instrument(name = "SubscriptionTypeRepository::update", skip_all)]
+    async fn update(&self, subscription_type: &SubscriptionType) -> Result<SubscriptionType, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-        UPDATE subscription_types
-            SET name = $1, updated_at = $3
-            WHERE id = $4
+            UPDATE subscription_types
+            SET name = $1, updated_at = $2, version = version + 1
+            WHERE id = $3 AND version = $4 AND deleted_at IS NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let updated = query_as(query)
             .bind(&subscription_type.name)
             .bind(subscription_type.updated_at)
             .bind(subscription_type.id)
-            .execute(&self.database)
+            .bind(subscription_type.version)
+            .fetch_one(&mut *conn)
             .await?;
 
+        Ok(updated)
+    }
+
+    #[instrument(name = "SubscriptionTypeRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE subscription_types SET deleted_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $2 AND deleted_at IS NULL
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .bind(version)
+            .execute(&mut *conn)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+    #[instrument(name = "SubscriptionTypeRepository::restore", skip_all)]
+    async fn restore(&self, id: Uuid) -> Result<SubscriptionType, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            DELETE FROM subscription_types WHERE id = $1
+            UPDATE subscription_types SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *
         "#;
 
-        sqlx::query(query)
+        let subscription_type = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(subscription_type)
+    }
+
+    #[instrument(name = "SubscriptionTypeRepository::purge", skip_all)]
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM subscription_types WHERE id = $1 AND deleted_at IS NOT NULL
+            AND NOT EXISTS (SELECT 1 FROM subscriptions WHERE subscriptions.subscription_type_id = subscription_types.id)
+        "#;
+
+        let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.database)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 }
\ No newline at end of file
@@ -1,84 +1,117 @@
-use sqlx::{query_as, Error, PgPool};
+use async_trait::async_trait;
+use sqlx::{query_as, Error, QueryBuilder};
 use uuid::Uuid;
+use tracing::instrument;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
 use crate::internal::entities::user::User;
 
-pub trait UserRepository {
-    fn new(database: PgPool) -> Self;
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<User>, i64), Error>;
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<User>, Error>;
     async fn get_by_id(&self, id: Uuid) -> Result<User, Error>;
+    async fn get_trashed(&self, id: Uuid) -> Result<User, Error>;
     async fn get_by_email(&self, email: String) -> Result<User, Error>;
     async fn get_by_phone(&self, phone_number: String) -> Result<User, Error>;
     async fn create(&self, user: &User) -> Result<User, Error>;
+    // Both fail with RowNotFound unless the row is live and still at the
+    // given version, which they bump.
     async fn update(&self, user: &User) -> Result<User, Error>;
-    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error>;
+    async fn restore(&self, id: Uuid) -> Result<User, Error>;
+    // Permanently deletes a soft-deleted user, failing with RowNotFound
+    // otherwise.
+    async fn purge(&self, id: Uuid) -> Result<(), Error>;
 }
 
 #[derive(Debug, Clone)]
 pub struct UserRepositoryImpl {
-    database: PgPool,
+    database: PgExecutor,
 }
 
-impl UserRepository for UserRepositoryImpl {
-    fn new(database: PgPool) -> Self {
+impl UserRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
         Self { database }
     }
+}
+
+#[async_trait]
+impl UserRepository for UserRepositoryImpl {
+    #[instrument(name = "UserRepository::list", skip_all)]
+    async fn list(&self, query: &ListQuery, page: &PageRequest) -> Result<Page<User>, Error> {
+        let mut conn = self.database.acquire().await?;
+
+        let mut select = QueryBuilder::new("SELECT * FROM users WHERE ");
+        query.push_conditions(&mut select);
+        query.push_page(&mut select, page);
+        let rows = select.build_query_as().fetch_all(&mut *conn).await?;
+
+        let total = if page.include_total {
+            let mut count = QueryBuilder::new("SELECT COUNT(*) AS total FROM users WHERE ");
+            query.push_conditions(&mut count);
+            let total: (i64,) = count.build_query_as().fetch_one(&mut *conn).await?;
+            Some(total.0)
+        } else {
+            None
+        };
+
+        Ok(Page::new(rows, query, page, total))
+    }
 
-    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<User>, i64), Error> {
+    #[instrument(name = "UserRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM users WHERE deleted_at IS NULL ORDER BY created_at ASC LIMIT $1 OFFSET $2
+            SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL
         "#;
 
-        let count_query = r#"
-            SELECT COUNT(*) AS total FROM users WHERE deleted_at IS NULL
-        "#;
+        let user = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
-        let rows = query_as(query)
-            .bind(page_size as i64)
-            .bind(offset as i64)
-            .fetch_all(&self.database)
-            .await?;
-
-        let total: (i64,) = query_as(count_query)
-            .fetch_one(&self.database)
-            .await?;
-
-        Ok((rows, total.0))
+        Ok(user)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<User, Error> {
+    #[instrument(name = "UserRepository::get_trashed", skip_all)]
+    async fn get_trashed(&self, id: Uuid) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            SELECT * FROM users WHERE id = $1 AND deleted_at IS NULL
+            SELECT * FROM users WHERE id = $1 AND deleted_at IS NOT NULL
         "#;
 
-        let user = query_as(query).bind(id).fetch_one(&self.database).await?;
+        let user = query_as(query).bind(id).fetch_one(&mut *conn).await?;
 
         Ok(user)
     }
 
+    #[instrument(name = "UserRepository::get_by_email", skip_all)]
     async fn get_by_email(&self, email: String) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL
         "#;
 
-        let user = query_as(query).bind(email).fetch_one(&self.database).await?;
+        let user = query_as(query).bind(email).fetch_one(&mut *conn).await?;
 
         Ok(user)
     }
 
+    #[instrument(name = "UserRepository::get_by_phone", skip_all)]
     async fn get_by_phone(&self, phone_number: String) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             SELECT * FROM users WHERE phone_number = $1 AND deleted_at IS NULL
         "#;
 
-        let user = query_as(query).bind(phone_number).fetch_one(&self.database).await?;
+        let user = query_as(query).bind(phone_number).fetch_one(&mut *conn).await?;
 
         Ok(user)
     }
 
+    #[instrument(name = "UserRepository::create", skip_all)]
     async fn create(&self, user: &User) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            INSERT INTO users (id, name, email, phone_number, password, title, role_id, school_id, created_at, updated_at, deleted_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            INSERT INTO users (id, name, email, phone_number, password, title, status, role_id, school_id, created_at, updated_at, deleted_at, version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
             RETURNING *
         "#;
 
@@ -89,52 +122,97 @@ impl UserRepository for UserRepositoryImpl {
             .bind(&user.phone_number)
             .bind(&user.password)
             .bind(&user.title)
+            .bind(&user.status)
             .bind(user.role_id)
             .bind(user.school_id)
             .bind(user.created_at)
             .bind(user.updated_at)
             .bind(user.deleted_at)
-            .fetch_one(&self.database)
+            .bind(user.version)
+            .fetch_one(&mut *conn)
             .await?;
 
         Ok(created_user)
     }
 
+    #[instrument(name = "UserRepository::update", skip_all)]
     async fn update(&self, user: &User) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
             UPDATE users
-            SET name = $1, email = $2, phone_number = $3, password = $4, title = $5, role_id = $6, school_id = $7, updated_at = $8
-            WHERE id = $9 AND deleted_at IS NULL
-            RETURNING id, name, email, phone_number, password, title, role_id, school_id, created_at, updated_at, deleted_at
+            SET name = $1, email = $2, phone_number = $3, password = $4, title = $5, status = $6, role_id = $7, school_id = $8, updated_at = $9, version = version + 1
+            WHERE id = $10 AND version = $11 AND deleted_at IS NULL
+            RETURNING *
         "#;
 
-        let updated_user = sqlx::query_as::<_, User>(query)
+        let updated = query_as(query)
             .bind(&user.name)
             .bind(&user.email)
             .bind(&user.phone_number)
             .bind(&user.password)
             .bind(&user.title)
+            .bind(&user.status)
             .bind(user.role_id)
             .bind(user.school_id)
             .bind(user.updated_at)
             .bind(user.id)
-            .fetch_one(&self.database)
+            .bind(user.version)
+            .fetch_one(&mut *conn)
             .await?;
 
-        Ok(updated_user)
+        Ok(updated)
     }
 
 
-    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+    #[instrument(name = "UserRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid, version: i32) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
         let query = r#"
-            DELETE FROM users WHERE id = $1
+            UPDATE users SET deleted_at = NOW(), version = version + 1
+            WHERE id = $1 AND version = $2 AND deleted_at IS NULL
         "#;
 
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.database)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
-}
+
+    #[instrument(name = "UserRepository::restore", skip_all)]
+    async fn restore(&self, id: Uuid) -> Result<User, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE users SET deleted_at = NULL, updated_at = NOW(), version = version + 1
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            RETURNING *
+        "#;
+
+        let user = query_as(query).bind(id).fetch_one(&mut *conn).await?;
+
+        Ok(user)
+    }
+
+    #[instrument(name = "UserRepository::purge", skip_all)]
+    async fn purge(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM users WHERE id = $1 AND deleted_at IS NOT NULL
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,238 @@
+use async_trait::async_trait;
+use sqlx::{query_as, Error};
+use uuid::Uuid;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::PgExecutor;
+use crate::internal::entities::webhook::{DeliveryAttempt, Webhook, WebhookDelivery};
+
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Webhook>, i64), Error>;
+    async fn get_by_id(&self, id: Uuid) -> Result<Webhook, Error>;
+    async fn create(&self, webhook: &Webhook) -> Result<(), Error>;
+    async fn update(&self, webhook: &Webhook) -> Result<(), Error>;
+    // Deletes the webhook's deliveries with it; RowNotFound if there is none.
+    async fn delete(&self, id: Uuid) -> Result<(), Error>;
+    // The webhooks that want an event of `event_type`, about `school_id` if any.
+    async fn matching(&self, event_type: &str, school_id: Option<Uuid>) -> Result<Vec<Webhook>, Error>;
+    // Returns false, storing nothing, when the webhook already has a delivery
+    // of the same event.
+    async fn create_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, Error>;
+    async fn get_delivery(&self, id: Uuid) -> Result<WebhookDelivery, Error>;
+    // Newest first.
+    async fn list_deliveries(&self, webhook_id: Uuid, offset: u32, page_size: u32) -> Result<(Vec<WebhookDelivery>, i64), Error>;
+    // Stores the outcome of an attempt and counts it.
+    async fn record_attempt(&self, id: Uuid, attempt: &DeliveryAttempt) -> Result<WebhookDelivery, Error>;
+    // Puts a failed delivery back to pending with its attempts reset, keeping
+    // the outcome of its last one; any other delivery is left alone.
+    async fn reset_delivery(&self, id: Uuid) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookRepositoryImpl {
+    database: PgExecutor,
+}
+
+impl WebhookRepositoryImpl {
+    pub fn new(database: PgExecutor) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for WebhookRepositoryImpl {
+    #[instrument(name = "WebhookRepository::list", skip_all)]
+    async fn list(&self, offset: u32, page_size: u32) -> Result<(Vec<Webhook>, i64), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM webhooks ORDER BY created_at ASC LIMIT $1 OFFSET $2
+        "#;
+
+        let count_query = r#"
+            SELECT COUNT(*) AS total FROM webhooks
+        "#;
+
+        let rows = query_as(query)
+            .bind(page_size as i64)
+            .bind(offset as i64)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let total: (i64,) = query_as(count_query)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok((rows, total.0))
+    }
+
+    #[instrument(name = "WebhookRepository::get_by_id", skip_all)]
+    async fn get_by_id(&self, id: Uuid) -> Result<Webhook, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM webhooks WHERE id = $1
+        "#;
+
+        query_as(query).bind(id).fetch_one(&mut *conn).await
+    }
+
+    #[instrument(name = "WebhookRepository::create", skip_all)]
+    async fn create(&self, webhook: &Webhook) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO webhooks (id, school_id, url, secret, event_types, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#;
+
+        sqlx::query(query)
+            .bind(webhook.id)
+            .bind(webhook.school_id)
+            .bind(&webhook.url)
+            .bind(&webhook.secret)
+            .bind(&webhook.event_types)
+            .bind(webhook.created_at)
+            .bind(webhook.updated_at)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(name = "WebhookRepository::update", skip_all)]
+    async fn update(&self, webhook: &Webhook) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE webhooks SET url = $2, event_types = $3, updated_at = $4 WHERE id = $1
+        "#;
+
+        sqlx::query(query)
+            .bind(webhook.id)
+            .bind(&webhook.url)
+            .bind(&webhook.event_types)
+            .bind(webhook.updated_at)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    #[instrument(name = "WebhookRepository::delete", skip_all)]
+    async fn delete(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            DELETE FROM webhooks WHERE id = $1
+        "#;
+
+        let result = sqlx::query(query).bind(id).execute(&mut *conn).await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    #[instrument(name = "WebhookRepository::matching", skip_all)]
+    async fn matching(&self, event_type: &str, school_id: Option<Uuid>) -> Result<Vec<Webhook>, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM webhooks
+            WHERE (school_id IS NULL OR school_id = $2)
+              AND (event_types = '[]'::jsonb OR event_types ? $1)
+            ORDER BY created_at ASC
+        "#;
+
+        query_as(query).bind(event_type).bind(school_id).fetch_all(&mut *conn).await
+    }
+
+    #[instrument(name = "WebhookRepository::create_delivery", skip_all)]
+    async fn create_delivery(&self, delivery: &WebhookDelivery) -> Result<bool, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            INSERT INTO webhook_deliveries (id, webhook_id, event_id, event_type, payload, status, attempts, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (webhook_id, event_id) DO NOTHING
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(delivery.id)
+            .bind(delivery.webhook_id)
+            .bind(delivery.event_id)
+            .bind(&delivery.event_type)
+            .bind(&delivery.payload)
+            .bind(delivery.status)
+            .bind(delivery.attempts)
+            .bind(delivery.created_at)
+            .bind(delivery.updated_at)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    #[instrument(name = "WebhookRepository::get_delivery", skip_all)]
+    async fn get_delivery(&self, id: Uuid) -> Result<WebhookDelivery, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM webhook_deliveries WHERE id = $1
+        "#;
+
+        query_as(query).bind(id).fetch_one(&mut *conn).await
+    }
+
+    #[instrument(name = "WebhookRepository::list_deliveries", skip_all)]
+    async fn list_deliveries(&self, webhook_id: Uuid, offset: u32, page_size: u32) -> Result<(Vec<WebhookDelivery>, i64), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            SELECT * FROM webhook_deliveries WHERE webhook_id = $1
+            ORDER BY created_at DESC LIMIT $2 OFFSET $3
+        "#;
+
+        let count_query = r#"
+            SELECT COUNT(*) AS total FROM webhook_deliveries WHERE webhook_id = $1
+        "#;
+
+        let rows = query_as(query)
+            .bind(webhook_id)
+            .bind(page_size as i64)
+            .bind(offset as i64)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        let total: (i64,) = query_as(count_query)
+            .bind(webhook_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok((rows, total.0))
+    }
+
+    #[instrument(name = "WebhookRepository::record_attempt", skip_all)]
+    async fn record_attempt(&self, id: Uuid, attempt: &DeliveryAttempt) -> Result<WebhookDelivery, Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE webhook_deliveries
+            SET status = $2, attempts = attempts + 1, response_status = $3, response_body = $4, error = $5, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+        "#;
+
+        query_as(query)
+            .bind(id)
+            .bind(attempt.status)
+            .bind(attempt.response_status)
+            .bind(&attempt.response_body)
+            .bind(&attempt.error)
+            .fetch_one(&mut *conn)
+            .await
+    }
+
+    #[instrument(name = "WebhookRepository::reset_delivery", skip_all)]
+    async fn reset_delivery(&self, id: Uuid) -> Result<(), Error> {
+        let mut conn = self.database.acquire().await?;
+        let query = r#"
+            UPDATE webhook_deliveries SET status = 'pending', attempts = 0, updated_at = NOW()
+            WHERE id = $1 AND status = 'failed'
+        "#;
+
+        sqlx::query(query).bind(id).execute(&mut *conn).await?;
+        Ok(())
+    }
+}
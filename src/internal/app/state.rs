@@ -0,0 +1,203 @@
+use std::error::Error;
+use std::sync::Arc;
+use sqlx::PgPool;
+use crate::config::app_config::{AppConfig, SchedulerConfig};
+use crate::database::migrations::prepare;
+use crate::database::postgresql::get_pool;
+use crate::internal::app::events::relay::OutboxRelay;
+use crate::internal::app::events::webhooks::{DeliverWebhook, DeliverWebhookHandler, WebhookSender, WebhookSubscriber};
+use crate::internal::app::events::{DeliverEvent, DeliverEventHandler, EventBus};
+use crate::internal::app::jobs::check_subscription_expiry::{CheckSubscriptionExpiry, CheckSubscriptionExpiryHandler};
+use crate::internal::app::jobs::clean_uploads::{CleanUploads, CleanUploadsHandler};
+use crate::internal::app::jobs::purge_deleted::{PurgeDeleted, PurgeDeletedHandler};
+use crate::internal::app::jobs::scheduler::{ScheduledJob, Scheduler};
+use crate::internal::app::jobs::sync_cities::{SyncCities, SyncCitiesHandler};
+use crate::internal::app::jobs::sync_regions::{SyncRegions, SyncRegionsHandler};
+use crate::internal::app::jobs::worker::WorkerPool;
+use crate::internal::app::jobs::{JobQueue, JobRegistry};
+use crate::internal::app::repositories::audit_log_repository::{AuditLogRepository, AuditLogRepositoryImpl};
+use crate::internal::app::repositories::city_repository::{CityRepository, CityRepositoryImpl};
+use crate::internal::app::repositories::db_transaction_repository::{DbTransactionRepository, DbTransactionRepositoryImpl, PgExecutor};
+use crate::internal::app::repositories::health_repository::{HealthRepository, HealthRepositoryImpl};
+use crate::internal::app::repositories::idempotency_repository::{IdempotencyRepository, IdempotencyRepositoryImpl};
+use crate::internal::app::repositories::job_repository::{JobRepository, JobRepositoryImpl};
+use crate::internal::app::repositories::maintenance_repository::{MaintenanceRepository, MaintenanceRepositoryImpl};
+use crate::internal::app::repositories::outbox_repository::{OutboxRepository, OutboxRepositoryImpl};
+use crate::internal::app::repositories::province_repository::{ProvinceRepository, ProvinceRepositoryImpl};
+use crate::internal::app::repositories::role_repository::{RoleRepository, RoleRepositoryImpl};
+use crate::internal::app::repositories::schedule_repository::{ScheduleRepository, ScheduleRepositoryImpl};
+use crate::internal::app::repositories::school_repository::{SchoolRepository, SchoolRepositoryImpl};
+use crate::internal::app::repositories::subscription_repository::{SubscriptionRepository, SubscriptionRepositoryImpl};
+use crate::internal::app::repositories::subscription_type_repository::{SubscriptionTypeRepository, SubscriptionTypeRepositoryImpl};
+use crate::internal::app::repositories::user_repository::{UserRepository, UserRepositoryImpl};
+use crate::internal::app::repositories::webhook_repository::{WebhookRepository, WebhookRepositoryImpl};
+use crate::internal::app::usecases::audit_log_usecase::{AuditLogUseCase, AuditLogUseCaseImpl};
+use crate::internal::app::usecases::auth_usecase::{AuthUseCase, AuthUseCaseImpl};
+use crate::internal::app::usecases::city_usecase::{CityUseCase, CityUseCaseImpl};
+use crate::internal::app::usecases::health_usecase::{HealthUseCase, HealthUseCaseImpl};
+use crate::internal::app::usecases::job_usecase::{JobUseCase, JobUseCaseImpl};
+use crate::internal::app::usecases::province_usecase::{ProvinceUseCase, ProvinceUseCaseImpl};
+use crate::internal::app::usecases::role_usecase::{RoleUseCase, RoleUseCaseImpl};
+use crate::internal::app::usecases::schedule_usecase::{ScheduleUseCase, ScheduleUseCaseImpl};
+use crate::internal::app::usecases::school_usecase::{SchoolUseCase, SchoolUseCaseImpl};
+use crate::internal::app::usecases::subscription_type_usecase::{SubscriptionTypeUseCase, SubscriptionTypeUseCaseImpl};
+use crate::internal::app::usecases::subscription_usecase::{SubscriptionUseCase, SubscriptionUseCaseImpl};
+use crate::internal::app::usecases::user_usecase::{UserUseCase, UserUseCaseImpl};
+use crate::internal::app::usecases::webhook_usecase::{WebhookUseCase, WebhookUseCaseImpl};
+use crate::pkg::metrics::{InstrumentedStorage, Metrics};
+use crate::pkg::s3::{create_s3_client, FileStorage};
+use crate::pkg::supervisor::TaskSupervisor;
+
+#[derive(Clone)]
+pub struct Repositories {
+    pub subscription: Arc<dyn SubscriptionRepository>,
+    pub subscription_type: Arc<dyn SubscriptionTypeRepository>,
+    pub role: Arc<dyn RoleRepository>,
+    pub province: Arc<dyn ProvinceRepository>,
+    pub city: Arc<dyn CityRepository>,
+    pub school: Arc<dyn SchoolRepository>,
+    pub user: Arc<dyn UserRepository>,
+    pub db_transaction: Arc<dyn DbTransactionRepository>,
+    pub health: Arc<dyn HealthRepository>,
+    pub job: Arc<dyn JobRepository>,
+    pub schedule: Arc<dyn ScheduleRepository>,
+    pub maintenance: Arc<dyn MaintenanceRepository>,
+    pub outbox: Arc<dyn OutboxRepository>,
+    pub webhook: Arc<dyn WebhookRepository>,
+    pub audit_log: Arc<dyn AuditLogRepository>,
+    pub idempotency: Arc<dyn IdempotencyRepository>,
+}
+
+impl Repositories {
+    pub fn postgres(pool: PgPool) -> Self {
+        Self::bound(pool.clone(), PgExecutor::Pool(pool))
+    }
+
+    // Repositories whose queries run on `database`. Health checks, leader
+    // election and maintenance manage their own connections on `pool`.
+    pub fn bound(pool: PgPool, database: PgExecutor) -> Self {
+        Self {
+            subscription: Arc::new(SubscriptionRepositoryImpl::new(database.clone())),
+            subscription_type: Arc::new(SubscriptionTypeRepositoryImpl::new(database.clone())),
+            role: Arc::new(RoleRepositoryImpl::new(database.clone())),
+            province: Arc::new(ProvinceRepositoryImpl::new(database.clone())),
+            city: Arc::new(CityRepositoryImpl::new(database.clone())),
+            school: Arc::new(SchoolRepositoryImpl::new(database.clone())),
+            user: Arc::new(UserRepositoryImpl::new(database.clone())),
+            db_transaction: Arc::new(DbTransactionRepositoryImpl::new(pool.clone(), database.clone())),
+            health: Arc::new(HealthRepositoryImpl::new(pool.clone())),
+            job: Arc::new(JobRepositoryImpl::new(database.clone())),
+            schedule: Arc::new(ScheduleRepositoryImpl::new(pool.clone(), database.clone())),
+            idempotency: Arc::new(IdempotencyRepositoryImpl::new(pool.clone())),
+            maintenance: Arc::new(MaintenanceRepositoryImpl::new(pool)),
+            outbox: Arc::new(OutboxRepositoryImpl::new(database.clone())),
+            webhook: Arc::new(WebhookRepositoryImpl::new(database.clone())),
+            audit_log: Arc::new(AuditLogRepositoryImpl::new(database)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct UseCases {
+    pub subscription: Arc<dyn SubscriptionUseCase>,
+    pub subscription_type: Arc<dyn SubscriptionTypeUseCase>,
+    pub role: Arc<dyn RoleUseCase>,
+    pub province: Arc<dyn ProvinceUseCase>,
+    pub city: Arc<dyn CityUseCase>,
+    pub school: Arc<dyn SchoolUseCase>,
+    pub user: Arc<dyn UserUseCase>,
+    pub auth: Arc<dyn AuthUseCase>,
+    pub health: Arc<dyn HealthUseCase>,
+    pub job: Arc<dyn JobUseCase>,
+    pub schedule: Arc<dyn ScheduleUseCase>,
+    pub webhook: Arc<dyn WebhookUseCase>,
+    pub audit_log: Arc<dyn AuditLogUseCase>,
+}
+
+impl UseCases {
+    pub fn new(repositories: &Repositories, storage: Arc<dyn FileStorage>, metrics: Arc<Metrics>, scheduler: Scheduler, webhooks: WebhookSender) -> Self {
+        let r = repositories;
+        let jobs = JobQueue::new(r.job.clone());
+        Self {
+            subscription: Arc::new(SubscriptionUseCaseImpl::new(r.subscription.clone(), r.subscription_type.clone(), r.db_transaction.clone(), metrics.clone())),
+            subscription_type: Arc::new(SubscriptionTypeUseCaseImpl::new(r.subscription_type.clone(), r.subscription.clone(), r.db_transaction.clone())),
+            role: Arc::new(RoleUseCaseImpl::new(r.role.clone(), r.db_transaction.clone())),
+            province: Arc::new(ProvinceUseCaseImpl::new(r.province.clone())),
+            city: Arc::new(CityUseCaseImpl::new(r.city.clone(), r.province.clone(), jobs)),
+            school: Arc::new(SchoolUseCaseImpl::new(r.school.clone(), r.subscription.clone(), r.province.clone(), r.city.clone(), r.db_transaction.clone(), storage.clone(), metrics.clone())),
+            user: Arc::new(UserUseCaseImpl::new(r.user.clone(), r.role.clone(), r.school.clone(), r.db_transaction.clone())),
+            auth: Arc::new(AuthUseCaseImpl::new(r.user.clone(), r.role.clone(), r.db_transaction.clone(), metrics)),
+            health: Arc::new(HealthUseCaseImpl::new(r.health.clone(), storage)),
+            job: Arc::new(JobUseCaseImpl::new(r.job.clone(), r.db_transaction.clone())),
+            schedule: Arc::new(ScheduleUseCaseImpl::new(scheduler, r.schedule.clone(), r.job.clone())),
+            webhook: Arc::new(WebhookUseCaseImpl::new(r.webhook.clone(), r.school.clone(), webhooks)),
+            audit_log: Arc::new(AuditLogUseCaseImpl::new(r.audit_log.clone())),
+        }
+    }
+}
+
+/// The application's dependency graph, built once and shared by the HTTP
+/// server and any other entry point.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: AppConfig,
+    pub repositories: Repositories,
+    pub usecases: UseCases,
+    pub metrics: Arc<Metrics>,
+    pub supervisor: TaskSupervisor,
+    pub workers: WorkerPool,
+    pub scheduler: Scheduler,
+    pub relay: OutboxRelay,
+}
+
+impl AppState {
+    /// Connects to Postgres and S3 as configured, migrates and checks the
+    /// schema, and wires every module.
+    pub async fn build(config: AppConfig) -> Result<AppState, Box<dyn Error>> {
+        let pool = get_pool(&config.database).await?;
+        prepare(&pool, &config.database).await?;
+        let storage = create_s3_client(&config.s3).await?;
+        Ok(Self::from_repositories(config, Repositories::postgres(pool), Arc::new(storage)))
+    }
+
+    pub fn from_repositories(config: AppConfig, repositories: Repositories, storage: Arc<dyn FileStorage>) -> AppState {
+        let metrics = Arc::new(Metrics::new());
+        let storage = Arc::new(InstrumentedStorage::new(storage, metrics.clone()));
+        let supervisor = TaskSupervisor::new();
+        let scheduler = Scheduler::new(schedules(&config.scheduler), repositories.schedule.clone(), repositories.db_transaction.clone());
+        let webhooks = WebhookSender::new(repositories.webhook.clone());
+        let usecases = UseCases::new(&repositories, storage.clone(), metrics.clone(), scheduler.clone(), webhooks.clone());
+        let bus = EventBus::new()
+            .subscribe(WebhookSubscriber::new(repositories.webhook.clone(), repositories.db_transaction.clone()));
+        let relay = OutboxRelay::new(repositories.outbox.clone(), &bus, config.worker.poll_interval);
+        let registry = JobRegistry::new()
+            .register::<SyncCities>(SyncCitiesHandler::new(usecases.city.clone()))
+            .register::<SyncRegions>(SyncRegionsHandler::new(usecases.province.clone(), usecases.city.clone()))
+            .register::<PurgeDeleted>(PurgeDeletedHandler::new(repositories.maintenance.clone(), repositories.idempotency.clone()))
+            .register::<CleanUploads>(CleanUploadsHandler::new(repositories.maintenance.clone(), storage))
+            .register::<CheckSubscriptionExpiry>(CheckSubscriptionExpiryHandler::new(repositories.school.clone(), repositories.db_transaction.clone()))
+            .register::<DeliverEvent>(DeliverEventHandler::new(bus))
+            .register::<DeliverWebhook>(DeliverWebhookHandler::new(repositories.webhook.clone(), webhooks));
+        let workers = WorkerPool::new(repositories.job.clone(), registry, config.worker.clone());
+        AppState { config, repositories, usecases, metrics, supervisor, workers, scheduler, relay }
+    }
+}
+
+// The recurring jobs, minus any whose schedule is turned off.
+fn schedules(config: &SchedulerConfig) -> Vec<ScheduledJob> {
+    let retention = chrono::Duration::from_std(config.retention).unwrap_or_default();
+    let mut schedules = Vec::new();
+    if let Some(schedule) = config.sync_regions.clone() {
+        schedules.push(ScheduledJob::new(schedule, |_| SyncRegions {}));
+    }
+    if let Some(schedule) = config.purge_deleted.clone() {
+        schedules.push(ScheduledJob::new(schedule, move |now| PurgeDeleted { before: now - retention }));
+    }
+    if let Some(schedule) = config.clean_uploads.clone() {
+        schedules.push(ScheduledJob::new(schedule, |_| CleanUploads {}));
+    }
+    if let Some(schedule) = config.check_subscription_expiry.clone() {
+        schedules.push(ScheduledJob::new(schedule, |now| CheckSubscriptionExpiry { now }));
+    }
+    schedules
+}
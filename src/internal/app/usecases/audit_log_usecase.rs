@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use actix_web::http::StatusCode;
+use tracing::instrument;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::repositories::audit_log_repository::AuditLogRepository;
+use crate::internal::entities::audit_log::{AuditLog, AuditLogFilter};
+
+#[async_trait]
+pub trait AuditLogUseCase: Send + Sync {
+    async fn list(&self, filter: AuditLogFilter, page: u32, page_size: u32) -> Result<(Vec<AuditLog>, i64), ErrorResponse>;
+}
+
+#[derive(Clone)]
+pub struct AuditLogUseCaseImpl {
+    repository: Arc<dyn AuditLogRepository>,
+}
+
+impl AuditLogUseCaseImpl {
+    pub fn new(repository: Arc<dyn AuditLogRepository>) -> Self {
+        Self { repository }
+    }
+}
+
+#[async_trait]
+impl AuditLogUseCase for AuditLogUseCaseImpl {
+    #[instrument(name = "AuditLogUseCase::list", skip_all)]
+    async fn list(&self, filter: AuditLogFilter, page: u32, page_size: u32) -> Result<(Vec<AuditLog>, i64), ErrorResponse> {
+        if page == 0 || page_size == 0 {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from >= to {
+                return Err(ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    Some("Invalid date range".to_string()),
+                    Some("FAILED".to_string()),
+                ));
+            }
+        }
+
+        let offset = (page - 1) * page_size;
+        Ok(self.repository.list(&filter, offset, page_size).await?)
+    }
+}
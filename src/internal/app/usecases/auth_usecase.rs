@@ -1,48 +1,51 @@
+use std::sync::Arc;
+use async_trait::async_trait;
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use bcrypt::{hash, DEFAULT_COST};
 use chrono::Utc;
 use uuid::Uuid;
+use tracing::instrument;
 use crate::helpers::custom_error::ErrorResponse;
-use crate::internal::app::repositories::db_transaction_repository::{DbTransactionRepository, DbTransactionRepositoryImpl};
-use crate::internal::app::repositories::role_repository::{RoleRepository, RoleRepositoryImpl};
-use crate::internal::app::repositories::school_repository::{SchoolRepository, SchoolRepositoryImpl};
-use crate::internal::app::repositories::user_repository::{UserRepository, UserRepositoryImpl};
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::role_repository::RoleRepository;
+use crate::internal::app::repositories::user_repository::UserRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::event::DomainEvent;
 use crate::internal::entities::school::School;
 use crate::internal::entities::user::{User, UserStatus};
 use crate::pkg::dto::auth_dto::RegisterDto;
+use crate::pkg::metrics::Metrics;
 
-pub trait AuthUseCase {
-    fn new(
-        user_repository: UserRepositoryImpl,
-        role_repository: RoleRepositoryImpl,
-        school_repository: SchoolRepositoryImpl,
-        db_transaction_repository: DbTransactionRepositoryImpl,
-    ) -> Self;
-
+#[async_trait]
+pub trait AuthUseCase: Send + Sync {
     async fn register(&self, form: Json<RegisterDto>) -> Result<User, ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AuthUseCaseImpl {
-    user_repository: UserRepositoryImpl,
-    role_repository: RoleRepositoryImpl,
-    school_repository: SchoolRepositoryImpl,
-    db_transaction_repository: DbTransactionRepositoryImpl,
+    user_repository: Arc<dyn UserRepository>,
+    role_repository: Arc<dyn RoleRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+    metrics: Arc<Metrics>,
 }
 
-impl AuthUseCase for AuthUseCaseImpl {
-    fn new(user_repository: UserRepositoryImpl, role_repository: RoleRepositoryImpl, school_repository: SchoolRepositoryImpl,
-           db_transaction_repository: DbTransactionRepositoryImpl,
+impl AuthUseCaseImpl {
+    pub fn new(user_repository: Arc<dyn UserRepository>, role_repository: Arc<dyn RoleRepository>,
+           db_transaction_repository: Arc<dyn DbTransactionRepository>, metrics: Arc<Metrics>,
     ) -> Self {
         Self {
             user_repository,
             role_repository,
-            school_repository,
             db_transaction_repository,
+            metrics,
         }
     }
+}
 
+#[async_trait]
+impl AuthUseCase for AuthUseCaseImpl {
+    #[instrument(name = "AuthUseCase::register", skip_all)]
     async fn register(&self, form: Json<RegisterDto>) -> Result<User, ErrorResponse> {
         let RegisterDto {
             name,
@@ -76,25 +79,23 @@ impl AuthUseCase for AuthUseCaseImpl {
             ));
         }
 
-        let tx = match self.db_transaction_repository.begin_transaction().await {
-            Ok(transaction) => transaction,
+        let user_role = match self.role_repository.get_by_name("user".to_string()).await {
+            Ok(role) => role,
             Err(err) => {
                 return Err(ErrorResponse::new(
-                    StatusCode::INTERNAL_SERVER_ERROR,
+                    StatusCode::BAD_REQUEST,
                     Some(err.to_string()),
                     Some("FAILED".to_string()),
-                ))
+                ));
             }
         };
 
-
-        let user_role = match self.role_repository.get_by_name("user".to_string()).await {
-            Ok(role) => role,
-            Err(err) => {
-                tx.rollback().await.unwrap();
+        let hashed_password = match hash(password, DEFAULT_COST) {
+            Ok(h) => h,
+            Err(_) => {
                 return Err(ErrorResponse::new(
-                    StatusCode::BAD_REQUEST,
-                    Some(err.to_string()),
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Some("Failed to hash password".to_string()),
                     Some("FAILED".to_string()),
                 ));
             }
@@ -106,36 +107,13 @@ impl AuthUseCase for AuthUseCaseImpl {
             address: "".to_string(),
             logo_path: "".to_string(),
             subscription_id: None,
+            subscription_ends_at: None,
             province_id: None,
             city_id: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
-        };
-
-        let school = match self.school_repository.create(&school).await {
-            Ok((school)) => school,
-            Err(err) => {
-                tx.rollback().await.unwrap();
-                return Err(ErrorResponse::new(
-                    StatusCode::BAD_REQUEST,
-                    Some(err.to_string()),
-                    Some("FAILED".to_string()),
-                ));
-            }
-        };
-
-
-        let hashed_password = match hash(password, DEFAULT_COST) {
-            Ok(h) => h,
-            Err(_) => {
-                tx.rollback().await.unwrap();
-                return Err(ErrorResponse::new(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Some("Failed to hash password".to_string()),
-                    Some("FAILED".to_string()),
-                ));
-            }
+            version: 1,
         };
 
         // Create the user entity.
@@ -152,21 +130,122 @@ impl AuthUseCase for AuthUseCaseImpl {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.user_repository.create(&user).await {
-            Ok(user) => {
-                tx.commit().await.unwrap();
-                Ok(user)
-            },
-            Err(error) => {
-                tx.rollback().await.unwrap();
-                Err(ErrorResponse::new(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Some(error.to_string()),
-                    Some("FAILED".to_string()),
-                ))
-            }
-        }
+        let user = with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let school = tx.school.create(&school).await.map_err(|err| ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some(err.to_string()),
+                Some("FAILED".to_string()),
+            ))?;
+            let user = tx.user.create(&user).await?;
+            tx.audit_log.record(&AuditLog::created(&school)).await?;
+            tx.audit_log.record(&AuditLog::created(&user)).await?;
+
+            let events = [
+                DomainEvent::SchoolCreated { school_id: school.id, name: school.name.clone() },
+                DomainEvent::UserRegistered { user_id: user.id, school_id: school.id, email: user.email.clone() },
+            ];
+            tx.outbox.append(&events).await?;
+            Ok::<_, ErrorResponse>(user)
+        }).await?;
+
+        self.metrics.registrations.inc();
+        Ok(user)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::internal::entities::role::Role;
+
+    fn setup() -> (InMemoryDatabase, AuthUseCaseImpl) {
+        let database = InMemoryDatabase::new();
+        database.tables().roles.push(Role {
+            id: Uuid::new_v4(),
+            name: "user".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        });
+        let r = database.repositories();
+        let usecase = AuthUseCaseImpl::new(r.user, r.role, r.db_transaction, Arc::new(Metrics::new()));
+        (database, usecase)
+    }
+
+    fn register_dto(email: &str, phone_number: &str) -> Json<RegisterDto> {
+        Json(RegisterDto {
+            name: "Budi".to_string(),
+            email: email.to_string(),
+            phone_number: phone_number.to_string(),
+            password: "secret".to_string(),
+            school_name: "SMA 1".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn register_creates_school_and_pending_user() {
+        let (database, usecase) = setup();
+
+        let user = usecase.register(register_dto("budi@example.com", "0811")).await.unwrap();
+
+        let tables = database.tables();
+        assert_eq!(tables.users.len(), 1);
+        assert_eq!(tables.schools.len(), 1);
+        assert_eq!(tables.schools[0].name, "SMA 1");
+        assert_eq!(user.school_id, tables.schools[0].id);
+        assert_eq!(user.role_id, tables.roles[0].id);
+        assert_eq!(user.status, UserStatus::Pending);
+        assert!(bcrypt::verify("secret", &user.password).unwrap());
+        let events: Vec<&str> = tables.outbox_events.iter().map(|event| event.event_type.as_str()).collect();
+        assert_eq!(events, ["school.created", "user.registered"]);
+        assert_eq!(tables.outbox_events[1].payload["user_id"], user.id.to_string());
+    }
+
+    #[tokio::test]
+    async fn register_rejects_missing_fields() {
+        let (_, usecase) = setup();
+
+        let err = usecase.register(register_dto(" ", "0811")).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn register_rejects_used_email_and_phone() {
+        let (_, usecase) = setup();
+        usecase.register(register_dto("budi@example.com", "0811")).await.unwrap();
+
+        let err = usecase.register(register_dto("budi@example.com", "0812")).await.unwrap_err();
+        assert_eq!(err.message.as_deref(), Some("Email is already used"));
+
+        let err = usecase.register(register_dto("other@example.com", "0811")).await.unwrap_err();
+        assert_eq!(err.message.as_deref(), Some("Phone is already used"));
+    }
+
+    #[tokio::test]
+    async fn register_fails_without_user_role() {
+        let (database, usecase) = setup();
+        database.tables().roles.clear();
+
+        let err = usecase.register(register_dto("budi@example.com", "0811")).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        assert!(database.tables().schools.is_empty());
+    }
+
+    #[tokio::test]
+    async fn register_reuses_email_of_deleted_user() {
+        let (database, usecase) = setup();
+        usecase.register(register_dto("budi@example.com", "0811")).await.unwrap();
+        database.tables().users[0].deleted_at = Some(Utc::now());
+
+        usecase.register(register_dto("budi@example.com", "0811")).await.unwrap();
+
+        assert_eq!(database.tables().users.len(), 2);
+        assert_eq!(database.tables().schools.len(), 2);
+    }
+}
@@ -1,28 +1,42 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, instrument, warn};
 use crate::helpers::custom_error::ErrorResponse;
-use crate::internal::app::repositories::city_repository::{CityRepository, CityRepositoryImpl};
-use crate::internal::app::repositories::province_repository::{ProvinceRepository, ProvinceRepositoryImpl};
+use crate::internal::app::jobs::sync_cities::SyncCities;
+use crate::internal::app::jobs::JobQueue;
+use crate::internal::app::repositories::city_repository::CityRepository;
+use crate::internal::app::repositories::province_repository::ProvinceRepository;
 use crate::internal::entities::city::{City, CityDataResponse};
 use actix_web::http::StatusCode;
 use chrono::Utc;
-use std::fmt::Debug;
 
-pub trait CityUseCase {
-    fn new(repository: CityRepositoryImpl, province_repository: ProvinceRepositoryImpl) -> Self;
+#[async_trait]
+pub trait CityUseCase: Send + Sync {
     async fn list(&self) -> Result<Vec<City>, ErrorResponse>;
+    async fn get(&self, id: String) -> Result<City, ErrorResponse>;
+    // Queues a sync for a worker and returns straight away.
     async fn create(&self) -> Result<(), ErrorResponse>;
+    // Syncs in the foreground, stopping between provinces once `cancel` fires.
+    async fn sync(&self, cancel: CancellationToken) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct CityUseCaseImpl {
-    repository: CityRepositoryImpl,
-    province_repository: ProvinceRepositoryImpl,
+    repository: Arc<dyn CityRepository>,
+    province_repository: Arc<dyn ProvinceRepository>,
+    jobs: JobQueue,
 }
 
-impl CityUseCase for CityUseCaseImpl {
-    fn new(repository: CityRepositoryImpl, province_repository: ProvinceRepositoryImpl) -> Self {
-        Self { repository, province_repository }
+impl CityUseCaseImpl {
+    pub fn new(repository: Arc<dyn CityRepository>, province_repository: Arc<dyn ProvinceRepository>, jobs: JobQueue) -> Self {
+        Self { repository, province_repository, jobs }
     }
+}
 
+#[async_trait]
+impl CityUseCase for CityUseCaseImpl {
+    #[instrument(name = "CityUseCase::list", skip_all)]
     async fn list(&self) -> Result<Vec<City>, ErrorResponse> {
         match self.repository.list().await {
             Ok(cities) => Ok(cities),
@@ -34,7 +48,41 @@ impl CityUseCase for CityUseCaseImpl {
         }
     }
 
+
+    #[instrument(name = "CityUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<City, ErrorResponse> {
+        match self.repository.get_by_id(id).await {
+            Ok(city) => Ok(city),
+            Err(sqlx::Error::RowNotFound) => Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("City not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "CityUseCase::create", skip_all)]
     async fn create(&self) -> Result<(), ErrorResponse> {
+        match self.jobs.enqueue(&SyncCities {}).await {
+            Ok(job) => {
+                info!(job_id = %job.id, "City sync queued");
+                Ok(())
+            }
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "CityUseCase::sync", skip_all)]
+    async fn sync(&self, cancel: CancellationToken) -> Result<(), ErrorResponse> {
         let provinces = match self.province_repository.list().await {
             Ok(provinces) => provinces,
             Err(error) => {
@@ -46,7 +94,15 @@ impl CityUseCase for CityUseCaseImpl {
             }
         };
 
-        for province in provinces {
+        let total = provinces.len();
+        for (done, province) in provinces.into_iter().enumerate() {
+            // Each province is stored completely before the next starts, and
+            // existing cities are skipped, so a rerun picks up from here.
+            if cancel.is_cancelled() {
+                info!(done, total, "City sync stopped early; run it again to resume");
+                return Ok(());
+            }
+
             let url = format!("https://wilayah.id/api/regencies/{}.json", province.id);
 
             // Fetch cities data for the current province
@@ -59,7 +115,7 @@ impl CityUseCase for CityUseCaseImpl {
                     )
                 })?,
                 Err(err) => {
-                    eprintln!("Failed to fetch cities for province {}: {}", province.id, err);
+                    warn!(province_id = %province.id, error = %err, "Failed to fetch cities for province");
                     continue; // Skip this province and proceed to the next one
                 }
             };
@@ -79,20 +135,17 @@ impl CityUseCase for CityUseCaseImpl {
                 match self.repository.get_by_id(city_data.code.clone()).await {
                     Ok(_) => {
                         // City exists, skip insertion
-                        eprintln!("City with code {} already exists, skipping.", city_data.code.clone());
+                        debug!(code = %city_data.code, "City already exists, skipping");
                     }
                     Err(sqlx::Error::RowNotFound) => {
                         // City does not exist, insert it
                         if let Err(error) = self.repository.create(&city).await {
-                            eprintln!("Error inserting city {:?}: {}", city, error);
+                            error!(code = %city.code, error = %error, "Failed to insert city");
                         }
                     }
                     Err(error) => {
                         // Handle unexpected errors from the database
-                        eprintln!(
-                            "Error checking existence of city with code {}: {}",
-                            city.code, error
-                        );
+                        error!(code = %city.code, error = %error, "Failed to check whether city exists");
                     }
                 }
             }
@@ -0,0 +1,177 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde::Serialize;
+use actix_web::http::StatusCode;
+use tracing::instrument;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::repositories::health_repository::{HealthRepository, PoolStats};
+use crate::pkg::s3::FileStorage;
+
+// How long a single dependency may take before it counts as down.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub latency_ms: u128,
+}
+
+impl CheckResult {
+    pub fn is_up(&self) -> bool {
+        self.status == CheckStatus::Up
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub database: CheckResult,
+    pub storage: CheckResult,
+    pub migrations: CheckResult,
+}
+
+#[async_trait]
+pub trait HealthUseCase: Send + Sync {
+    async fn ready(&self) -> ReadinessReport;
+    async fn pool_stats(&self) -> Result<PoolStats, ErrorResponse>;
+}
+
+#[derive(Clone)]
+pub struct HealthUseCaseImpl {
+    repository: Arc<dyn HealthRepository>,
+    storage: Arc<dyn FileStorage>,
+}
+
+impl HealthUseCaseImpl {
+    pub fn new(repository: Arc<dyn HealthRepository>, storage: Arc<dyn FileStorage>) -> Self {
+        Self { repository, storage }
+    }
+}
+
+async fn check<F, E>(future: F) -> CheckResult
+where
+    F: Future<Output = Result<(), E>>,
+    E: ToString,
+{
+    let started = Instant::now();
+    let error = match tokio::time::timeout(CHECK_TIMEOUT, future).await {
+        Ok(Ok(())) => None,
+        Ok(Err(error)) => Some(error.to_string()),
+        Err(_) => Some(format!("timed out after {}ms", CHECK_TIMEOUT.as_millis())),
+    };
+
+    CheckResult {
+        status: if error.is_none() { CheckStatus::Up } else { CheckStatus::Down },
+        error,
+        latency_ms: started.elapsed().as_millis(),
+    }
+}
+
+#[async_trait]
+impl HealthUseCase for HealthUseCaseImpl {
+    #[instrument(name = "HealthUseCase::ready", skip_all)]
+    async fn ready(&self) -> ReadinessReport {
+        let migrations = async {
+            match self.repository.pending_migrations().await {
+                Ok(pending) if pending.is_empty() => Ok(()),
+                Ok(pending) => Err(format!("{} pending migration(s): {:?}", pending.len(), pending)),
+                Err(error) => Err(error.to_string()),
+            }
+        };
+
+        let (database, storage, migrations) = tokio::join!(
+            check(self.repository.ping()),
+            check(self.storage.check()),
+            check(migrations),
+        );
+
+        ReadinessReport {
+            ready: database.is_up() && storage.is_up() && migrations.is_up(),
+            database,
+            storage,
+            migrations,
+        }
+    }
+
+    #[instrument(name = "HealthUseCase::pool_stats", skip_all)]
+    async fn pool_stats(&self) -> Result<PoolStats, ErrorResponse> {
+        match tokio::time::timeout(CHECK_TIMEOUT, self.repository.pool_stats()).await {
+            Ok(Ok(stats)) => Ok(stats),
+            Ok(Err(error)) => Err(ErrorResponse::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(_) => Err(ErrorResponse::new(
+                StatusCode::SERVICE_UNAVAILABLE,
+                Some(format!("Timed out acquiring a database connection after {}ms", CHECK_TIMEOUT.as_millis())),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::pkg::s3::{InMemoryStorage, StorageError, StoredObject};
+    use actix_multipart::form::tempfile::TempFile;
+
+    struct SlowStorage;
+
+    #[async_trait]
+    impl FileStorage for SlowStorage {
+        async fn upload(&self, _file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError> {
+            Ok(file_path)
+        }
+
+        async fn check(&self) -> Result<(), StorageError> {
+            tokio::time::sleep(CHECK_TIMEOUT * 2).await;
+            Ok(())
+        }
+
+        async fn list(&self, _prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+            Ok(vec![])
+        }
+
+        async fn delete(&self, _file_path: &str) -> Result<(), StorageError> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn ready_when_every_check_passes() {
+        let r = InMemoryDatabase::new().repositories();
+        let usecase = HealthUseCaseImpl::new(r.health, Arc::new(InMemoryStorage::default()));
+
+        let report = usecase.ready().await;
+
+        assert!(report.ready);
+        assert!(report.database.is_up() && report.storage.is_up() && report.migrations.is_up());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_dependency_times_out() {
+        let r = InMemoryDatabase::new().repositories();
+        let usecase = HealthUseCaseImpl::new(r.health, Arc::new(SlowStorage));
+
+        let report = usecase.ready().await;
+
+        assert!(!report.ready);
+        assert_eq!(report.storage.status, CheckStatus::Down);
+        assert!(report.storage.error.as_deref().unwrap().starts_with("timed out"));
+        assert!(report.database.is_up());
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use actix_web::http::StatusCode;
+use tracing::instrument;
+use uuid::Uuid;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::events::webhooks::DeliverWebhook;
+use crate::internal::app::jobs::JobDefinition;
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::job_repository::JobRepository;
+use crate::internal::entities::job::{Job, JobStatus};
+
+#[async_trait]
+pub trait JobUseCase: Send + Sync {
+    async fn list(&self, status: Option<JobStatus>, page: u32, page_size: u32) -> Result<(Vec<Job>, i64), ErrorResponse>;
+    async fn get(&self, id: String) -> Result<Job, ErrorResponse>;
+    // Runs a dead or cancelled job again, with its attempts reset. A webhook
+    // delivery job puts its failed delivery back to pending too.
+    async fn retry(&self, id: String) -> Result<Job, ErrorResponse>;
+    // Stops a pending job from running.
+    async fn cancel(&self, id: String) -> Result<Job, ErrorResponse>;
+}
+
+#[derive(Clone)]
+pub struct JobUseCaseImpl {
+    repository: Arc<dyn JobRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+}
+
+impl JobUseCaseImpl {
+    pub fn new(repository: Arc<dyn JobRepository>, db_transaction_repository: Arc<dyn DbTransactionRepository>) -> Self {
+        Self { repository, db_transaction_repository }
+    }
+
+    fn parse_id(id: &str) -> Result<Uuid, ErrorResponse> {
+        id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid job id".to_string()),
+            Some("FAILED".to_string()),
+        ))
+    }
+
+    // The repository reports RowNotFound both for a missing job and for one in
+    // the wrong state; look it up again to tell the two apart.
+    async fn transition_error(&self, id: Uuid, error: sqlx::Error, action: &str) -> ErrorResponse {
+        match error {
+            sqlx::Error::RowNotFound => match self.repository.get_by_id(id).await {
+                Ok(job) => ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some(format!("Cannot {} a {} job", action, job.status.as_str())),
+                    Some("FAILED".to_string()),
+                ),
+                Err(_) => ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Job not found".to_string()),
+                    Some("FAILED".to_string()),
+                ),
+            },
+            error => ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl JobUseCase for JobUseCaseImpl {
+    #[instrument(name = "JobUseCase::list", skip_all)]
+    async fn list(&self, status: Option<JobStatus>, page: u32, page_size: u32) -> Result<(Vec<Job>, i64), ErrorResponse> {
+        if page == 0 || page_size == 0 {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let offset = (page - 1) * page_size;
+
+        match self.repository.list(status, offset, page_size).await {
+            Ok((jobs, total_data)) => Ok((jobs, total_data)),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "JobUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<Job, ErrorResponse> {
+        let id = Self::parse_id(&id)?;
+
+        match self.repository.get_by_id(id).await {
+            Ok(job) => Ok(job),
+            Err(sqlx::Error::RowNotFound) => Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("Job not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "JobUseCase::retry", skip_all)]
+    async fn retry(&self, id: String) -> Result<Job, ErrorResponse> {
+        let id = Self::parse_id(&id)?;
+
+        let result = with_transaction(&*self.db_transaction_repository, |tx| async move {
+            let job = tx.job.retry(id).await?;
+            // The delivery was marked failed along with the job's last attempt,
+            // and the job skips deliveries that aren't pending.
+            if job.kind == DeliverWebhook::KIND {
+                let payload: DeliverWebhook = serde_json::from_value(job.payload.clone())
+                    .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+                tx.webhook.reset_delivery(payload.delivery_id).await?;
+            }
+            Ok::<_, sqlx::Error>(job)
+        }).await;
+
+        match result {
+            Ok(job) => Ok(job),
+            Err(error) => Err(self.transition_error(id, error, "retry").await),
+        }
+    }
+
+    #[instrument(name = "JobUseCase::cancel", skip_all)]
+    async fn cancel(&self, id: String) -> Result<Job, ErrorResponse> {
+        let id = Self::parse_id(&id)?;
+
+        match self.repository.cancel(id).await {
+            Ok(job) => Ok(job),
+            Err(error) => Err(self.transition_error(id, error, "cancel").await),
+        }
+    }
+}
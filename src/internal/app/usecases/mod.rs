@@ -5,4 +5,9 @@ pub mod province_usecase;
 pub mod city_usecase;
 pub mod school_usecase;
 pub mod user_usecase;
-pub mod auth_usecase;
\ No newline at end of file
+pub mod auth_usecase;
+pub mod health_usecase;
+pub mod job_usecase;
+pub mod schedule_usecase;
+pub mod webhook_usecase;
+pub mod audit_log_usecase;
@@ -1,26 +1,33 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use tracing::{debug, error, instrument};
 use crate::helpers::custom_error::ErrorResponse;
-use crate::internal::app::repositories::province_repository::{ProvinceRepository, ProvinceRepositoryImpl};
+use crate::internal::app::repositories::province_repository::ProvinceRepository;
 use crate::internal::entities::province::{Province, ProvinceDataResponse, ProvinceFromTable};
 use actix_web::http::StatusCode;
 use chrono::Utc;
-use std::fmt::Debug;
 
-pub trait ProvinceUseCase {
-    fn new(repository: ProvinceRepositoryImpl) -> Self;
+#[async_trait]
+pub trait ProvinceUseCase: Send + Sync {
     async fn list(&self) -> Result<Vec<ProvinceFromTable>, ErrorResponse>;
+    async fn get(&self, id: String) -> Result<ProvinceFromTable, ErrorResponse>;
     async fn create(&self) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ProvinceUseCaseImpl {
-    repository: ProvinceRepositoryImpl,
+    repository: Arc<dyn ProvinceRepository>,
 }
 
-impl ProvinceUseCase for ProvinceUseCaseImpl {
-    fn new(repository: ProvinceRepositoryImpl) -> Self {
+impl ProvinceUseCaseImpl {
+    pub fn new(repository: Arc<dyn ProvinceRepository>) -> Self {
         Self { repository }
     }
+}
 
+#[async_trait]
+impl ProvinceUseCase for ProvinceUseCaseImpl {
+    #[instrument(name = "ProvinceUseCase::list", skip_all)]
     async fn list(&self) -> Result<Vec<ProvinceFromTable>, ErrorResponse> {
         match self.repository.list().await {
             Ok(provinces) => Ok(provinces),
@@ -32,6 +39,25 @@ impl ProvinceUseCase for ProvinceUseCaseImpl {
         }
     }
 
+
+    #[instrument(name = "ProvinceUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<ProvinceFromTable, ErrorResponse> {
+        match self.repository.get_by_id(id).await {
+            Ok(province) => Ok(province),
+            Err(sqlx::Error::RowNotFound) => Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("Province not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "ProvinceUseCase::create", skip_all)]
     async fn create(&self) -> Result<(), ErrorResponse> {
         let url = "https://wilayah.id/api/provinces.json";
 
@@ -68,20 +94,17 @@ impl ProvinceUseCase for ProvinceUseCaseImpl {
             match self.repository.get_by_id(province_data.code.clone()).await {
                 Ok(_) => {
                     // Province exists, skip insertion
-                    eprintln!("Province with code {} already exists, skipping.", province_data.code.clone());
+                    debug!(code = %province_data.code, "Province already exists, skipping");
                 }
                 Err(sqlx::Error::RowNotFound) => {
                     // Province does not exist, insert it
                     if let Err(error) = self.repository.create(&province).await {
-                        eprintln!("Error inserting province {:?}: {}", province, error);
+                        error!(code = %province.code, error = %error, "Failed to insert province");
                     }
                 }
                 Err(error) => {
                     // Handle unexpected errors from the database
-                    eprintln!(
-                        "Error checking existence of province with code {}: {}",
-                        province.code, error
-                    );
+                    error!(code = %province.code, error = %error, "Failed to check whether province exists");
                 }
             }
         }
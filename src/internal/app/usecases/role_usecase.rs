@@ -1,31 +1,85 @@
+use std::sync::Arc;
+use async_trait::async_trait;
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use chrono::Utc;
+use serde_json::Value;
+use tracing::instrument;
 use crate::helpers::custom_error::ErrorResponse;
-use crate::internal::app::repositories::role_repository::{RoleRepository, RoleRepositoryImpl};
+use crate::helpers::merge_patch;
+use crate::helpers::precondition::{precondition_failed, IfMatch};
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::role_repository::RoleRepository;
+use crate::internal::entities::audit_log::AuditLog;
 use crate::internal::entities::role::Role;
-use crate::pkg::dto::role_dto::{CreateRoleDto, UpdateRoleDto};
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::pkg::dto::role_dto::{CreateRoleDto, RolePatch, UpdateRoleDto};
 
-pub trait RoleUseCase {
-    fn new(repository: RoleRepositoryImpl) -> Self;
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Role>, i64), ErrorResponse>;
+#[async_trait]
+pub trait RoleUseCase: Send + Sync {
+    async fn list(&self, query: ListQuery, page: PageRequest) -> Result<Page<Role>, ErrorResponse>;
+    async fn get(&self, id: String) -> Result<Role, ErrorResponse>;
     async fn create(&self, form: Json<CreateRoleDto>) -> Result<(), ErrorResponse>;
-    async fn update(&self, id: String, form: Json<UpdateRoleDto>) -> Result<(), ErrorResponse>;
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateRoleDto>) -> Result<Role, ErrorResponse>;
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<Role, ErrorResponse>;
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse>;
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct RoleUseCaseImpl {
-    repository: RoleRepositoryImpl,
+    repository: Arc<dyn RoleRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
 }
 
-impl RoleUseCase for RoleUseCaseImpl {
-    fn new(repository: RoleRepositoryImpl) -> Self {
-        Self { repository }
+impl RoleUseCaseImpl {
+    pub fn new(repository: Arc<dyn RoleRepository>, db_transaction_repository: Arc<dyn DbTransactionRepository>) -> Self {
+        Self { repository, db_transaction_repository }
+    }
+
+    // Writes `changes` over `current` unless the role moved past the
+    // version the client matched.
+    async fn save(&self, current: Role, if_match: IfMatch, changes: RolePatch) -> Result<Role, ErrorResponse> {
+        if_match.check(current.version)?;
+
+        let name = changes.name.trim().to_string();
+        if name.is_empty() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid role name".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let role = Role {
+            name,
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let updated = match tx.role.update(&role).await {
+                Ok(updated) => updated,
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another role already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::updated(&current, &updated)).await?;
+            Ok(updated)
+        }).await
     }
+}
 
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Role>, i64), ErrorResponse> {
-        if page == 0 || page_size == 0 {
+#[async_trait]
+impl RoleUseCase for RoleUseCaseImpl {
+    #[instrument(name = "RoleUseCase::list", skip_all)]
+    async fn list(&self, query: ListQuery, page: PageRequest) -> Result<Page<Role>, ErrorResponse> {
+        if !page.is_valid() {
             return Err(ErrorResponse::new(
                 StatusCode::BAD_REQUEST,
                 Some("Invalid pagination parameters".to_string()),
@@ -33,10 +87,32 @@ impl RoleUseCase for RoleUseCaseImpl {
             ));
         }
 
-        let offset = (page - 1) * page_size;
+        match self.repository.list(&query, &page).await {
+            Ok(roles) => Ok(roles),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+
+    #[instrument(name = "RoleUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<Role, ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid role id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
 
-        match self.repository.list(offset, page_size).await {
-            Ok((roles, total_data)) => Ok((roles, total_data)),
+        match self.repository.get_by_id(id).await {
+            Ok(role) => Ok(role),
+            Err(sqlx::Error::RowNotFound) => Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("Role not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
             Err(error) => Err(ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Some(error.to_string()),
@@ -45,6 +121,7 @@ impl RoleUseCase for RoleUseCaseImpl {
         }
     }
 
+    #[instrument(name = "RoleUseCase::create", skip_all)]
     async fn create(&self, form: Json<CreateRoleDto>) -> Result<(), ErrorResponse> {
         let CreateRoleDto {
             name,
@@ -64,54 +141,126 @@ impl RoleUseCase for RoleUseCaseImpl {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.repository.create(&role).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            tx.role.create(&role).await?;
+            tx.audit_log.record(&AuditLog::created(&role)).await?;
+            Ok::<_, ErrorResponse>(())
+        }).await
     }
 
-    async fn update(&self, id: String, form: Json<UpdateRoleDto>) -> Result<(), ErrorResponse> {
-        let UpdateRoleDto {
-            name
-        } = form.into_inner();
+    #[instrument(name = "RoleUseCase::update", skip_all)]
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateRoleDto>) -> Result<Role, ErrorResponse> {
+        let UpdateRoleDto { name } = form.into_inner();
 
-        let mut role = match self.repository.get_by_id(id.parse().unwrap()).await {
-            Ok(role) => role,
-            Err(error) => return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
+        let role = self.get(id).await?;
+        let changes = RolePatch {
+            name: name.unwrap_or(role.name.clone()),
         };
 
-        role.name = name.unwrap_or(role.name).trim().to_string();
-        role.updated_at = Utc::now();
+        self.save(role, if_match, changes).await
+    }
 
+    #[instrument(name = "RoleUseCase::patch", skip_all)]
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<Role, ErrorResponse> {
+        let role = self.get(id).await?;
+        let changes = merge_patch::apply(&RolePatch::from(&role), &patch)?;
 
-        match self.repository.update(&role).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+        self.save(role, if_match, changes).await
     }
 
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
-        match self.repository.delete(id.parse().unwrap()).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+    #[instrument(name = "RoleUseCase::delete", skip_all)]
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid role id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let role = match tx.role.get_by_id(id).await {
+                Ok(role) => role,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Role not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            if_match.check(role.version)?;
+            match tx.role.delete(id, role.version).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::deleted(&role)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "RoleUseCase::restore", skip_all)]
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid role id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let role = match tx.role.get_trashed(id).await {
+                Ok(role) => role,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted role not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let restored = match tx.role.restore(id).await {
+                Ok(restored) => restored,
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another role already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::restored(&role, &restored)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "RoleUseCase::purge", skip_all)]
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid role id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let role = match tx.role.get_trashed(id).await {
+                Ok(role) => role,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted role not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            match tx.role.purge(id).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Role is still assigned to users".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::purged(&role)).await?;
+            Ok(())
+        }).await
     }
 }
\ No newline at end of file
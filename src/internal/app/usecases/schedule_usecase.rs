@@ -0,0 +1,64 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use actix_web::http::StatusCode;
+use tracing::instrument;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::jobs::scheduler::Scheduler;
+use crate::internal::app::repositories::job_repository::JobRepository;
+use crate::internal::app::repositories::schedule_repository::ScheduleRepository;
+use crate::internal::entities::schedule::ScheduleOverview;
+
+#[async_trait]
+pub trait ScheduleUseCase: Send + Sync {
+    async fn list(&self) -> Result<Vec<ScheduleOverview>, ErrorResponse>;
+}
+
+#[derive(Clone)]
+pub struct ScheduleUseCaseImpl {
+    scheduler: Scheduler,
+    repository: Arc<dyn ScheduleRepository>,
+    job_repository: Arc<dyn JobRepository>,
+}
+
+impl ScheduleUseCaseImpl {
+    pub fn new(scheduler: Scheduler, repository: Arc<dyn ScheduleRepository>, job_repository: Arc<dyn JobRepository>) -> Self {
+        Self { scheduler, repository, job_repository }
+    }
+}
+
+#[async_trait]
+impl ScheduleUseCase for ScheduleUseCaseImpl {
+    #[instrument(name = "ScheduleUseCase::list", skip_all)]
+    async fn list(&self) -> Result<Vec<ScheduleOverview>, ErrorResponse> {
+        let runs = self.repository.list().await.map_err(|error| ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Some(error.to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        let mut overviews = Vec::with_capacity(self.scheduler.schedules().len());
+        for schedule in self.scheduler.schedules() {
+            let run = runs.iter().find(|run| run.name == schedule.name);
+            let last_run_at = run.map(|run| run.last_run_at);
+            let last_job_id = run.and_then(|run| run.last_job_id);
+
+            // The job may have been cleaned up since; the outcome is then unknown.
+            let last_job = match last_job_id {
+                Some(id) => self.job_repository.get_by_id(id).await.ok(),
+                None => None,
+            };
+
+            overviews.push(ScheduleOverview {
+                name: schedule.name.to_string(),
+                cron: schedule.schedule.to_string(),
+                last_run_at,
+                last_job_id,
+                last_status: last_job.as_ref().map(|job| job.status),
+                last_error: last_job.and_then(|job| job.last_error),
+                next_run_at: self.scheduler.next_run(schedule, last_run_at),
+            });
+        }
+
+        Ok(overviews)
+    }
+}
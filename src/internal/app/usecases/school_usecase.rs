@@ -1,39 +1,124 @@
-use crate::internal::app::repositories::school_repository::{SchoolRepository, SchoolRepositoryImpl};
+use std::sync::Arc;
+use async_trait::async_trait;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::school_repository::SchoolRepository;
+use crate::internal::app::repositories::subscription_repository::SubscriptionRepository;
+use crate::internal::app::repositories::province_repository::ProvinceRepository;
+use crate::internal::app::repositories::city_repository::CityRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::event::DomainEvent;
 use crate::internal::entities::school::School;
-use crate::pkg::dto::school_dto::{CreateSchoolDto, UpdateSchoolDto};
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::pkg::dto::school_dto::{CreateSchoolDto, SchoolPatch, UpdateSchoolDto};
+use crate::pkg::dto::subscription_dto::SubscriptionResponse;
 use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::include::{belongs_to, keys, Included, Includes};
+use crate::helpers::merge_patch;
+use crate::helpers::precondition::{precondition_failed, IfMatch};
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use chrono::Utc;
-use std::fmt::Debug;
+use serde_json::Value;
 
-use aws_sdk_s3::Client;
 use actix_multipart::form::MultipartForm;
 use uuid::Uuid;
-use crate::pkg::s3::upload_file_to_s3;
+use crate::pkg::s3::FileStorage;
+use crate::pkg::metrics::Metrics;
 // Method to upload a logo to S3
 
-pub trait SchoolUseCase {
-    fn new(repository: SchoolRepositoryImpl, s3_client: Client) -> Self;
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<School>, i64), ErrorResponse>;
+#[async_trait]
+pub trait SchoolUseCase: Send + Sync {
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<School>>, ErrorResponse>;
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<School>, ErrorResponse>;
     async fn create(&self, form: MultipartForm<CreateSchoolDto>) -> Result<(), ErrorResponse>;
-    async fn update(&self, id: String, form: Json<UpdateSchoolDto>) -> Result<(), ErrorResponse>;
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSchoolDto>) -> Result<School, ErrorResponse>;
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<School, ErrorResponse>;
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse>;
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SchoolUseCaseImpl {
-    repository: SchoolRepositoryImpl,
-    s3_client: Client,
+    repository: Arc<dyn SchoolRepository>,
+    subscription_repository: Arc<dyn SubscriptionRepository>,
+    province_repository: Arc<dyn ProvinceRepository>,
+    city_repository: Arc<dyn CityRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+    storage: Arc<dyn FileStorage>,
+    metrics: Arc<Metrics>,
 }
 
-impl SchoolUseCase for SchoolUseCaseImpl {
-    fn new(repository: SchoolRepositoryImpl, s3_client: Client) -> Self {
-        Self { repository, s3_client }
+impl SchoolUseCaseImpl {
+    pub fn new(repository: Arc<dyn SchoolRepository>, subscription_repository: Arc<dyn SubscriptionRepository>,
+               province_repository: Arc<dyn ProvinceRepository>, city_repository: Arc<dyn CityRepository>,
+               db_transaction_repository: Arc<dyn DbTransactionRepository>, storage: Arc<dyn FileStorage>, metrics: Arc<Metrics>,
+    ) -> Self {
+        Self { repository, subscription_repository, province_repository, city_repository, db_transaction_repository, storage, metrics }
     }
 
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<School>, i64), ErrorResponse> {
-        if page == 0 || page_size == 0 {
+    // Nests the relations `includes` asks for under `schools`, with one query
+    // per relation however many schools there are.
+    async fn include(&self, schools: Vec<School>, includes: &Includes) -> Result<Vec<Included<School>>, ErrorResponse> {
+        let mut schools = Included::wrap(schools);
+        if includes.contains("subscription") {
+            let subscriptions = self.subscription_repository.get_by_ids(&keys(&schools, |school| school.subscription_id)).await?;
+            belongs_to(&mut schools, "subscription", subscriptions.into_iter().map(SubscriptionResponse::from).collect(), |subscription| subscription.id, |school| school.subscription_id);
+        }
+        if includes.contains("province") {
+            let provinces = self.province_repository.get_by_ids(&keys(&schools, |school| school.province_id.clone())).await?;
+            belongs_to(&mut schools, "province", provinces, |province| province.id.clone(), |school| school.province_id.clone());
+        }
+        if includes.contains("city") {
+            let cities = self.city_repository.get_by_ids(&keys(&schools, |school| school.city_id.clone())).await?;
+            belongs_to(&mut schools, "city", cities, |city| city.code.clone(), |school| school.city_id.clone());
+        }
+        Ok(schools)
+    }
+
+    // Writes `changes` over `current` unless the school moved past the
+    // version the client matched.
+    async fn save(&self, current: School, if_match: IfMatch, changes: SchoolPatch) -> Result<School, ErrorResponse> {
+        if_match.check(current.version)?;
+
+        if changes.name.trim().is_empty() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid school name".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let school = School {
+            name: changes.name,
+            address: changes.address,
+            logo_path: changes.logo_path,
+            subscription_id: changes.subscription_id,
+            subscription_ends_at: changes.subscription_ends_at,
+            province_id: changes.province_id,
+            city_id: changes.city_id,
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let updated = match tx.school.update(&school).await {
+                Ok(updated) => updated,
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::updated(&current, &updated)).await?;
+            Ok(updated)
+        }).await
+    }
+}
+
+#[async_trait]
+impl SchoolUseCase for SchoolUseCaseImpl {
+    #[instrument(name = "SchoolUseCase::list", skip_all)]
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<School>>, ErrorResponse> {
+        if !page.is_valid() {
             return Err(ErrorResponse::new(
                 StatusCode::BAD_REQUEST,
                 Some("Invalid pagination parameters".to_string()),
@@ -41,23 +126,53 @@ impl SchoolUseCase for SchoolUseCaseImpl {
             ));
         }
 
-        let offset = (page - 1) * page_size;
+        let mut schools = match self.repository.list(&query, &page).await {
+            Ok(schools) => schools,
+            Err(error) => return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        };
+
+        let rows = self.include(std::mem::take(&mut schools.rows), &includes).await?;
+        Ok(schools.with_rows(rows))
+    }
+
 
-        match self.repository.list(offset, page_size).await {
-            Ok((schools, total_data)) => Ok((schools, total_data)),
-            Err(error) => Err(ErrorResponse::new(
+    #[instrument(name = "SchoolUseCase::get", skip_all)]
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<School>, ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid school id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        let school = match self.repository.get_by_id(id).await {
+            Ok(school) => school,
+            Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("School not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => return Err(ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Some(error.to_string()),
                 Some("FAILED".to_string()),
             )),
-        }
+        };
+
+        let mut schools = self.include(vec![school], &includes).await?;
+        Ok(schools.remove(0))
     }
 
+    #[instrument(name = "SchoolUseCase::create", skip_all)]
     async fn create(&self, form: MultipartForm<CreateSchoolDto>) -> Result<(), ErrorResponse> {
         let CreateSchoolDto {
             name,
             address,
             subscription_id,
+            subscription_ends_at,
             province_id,
             city_id,
             logo,
@@ -74,15 +189,15 @@ impl SchoolUseCase for SchoolUseCaseImpl {
         let address_str = address.map_or_else(|| "".to_string(), |addr| addr.to_string());
         let province_id_option = province_id.map_or_else(|| None, |id| Some(id.to_string()));
         let city_id_option = city_id.map_or_else(|| None, |id| Some(id.to_string()));
-        let subscription_id_option = subscription_id.map_or(None, |id| Some(id.to_string().parse().unwrap_or_default()));
+        let subscription_id_option = subscription_id.map(|id| id.to_string().parse().unwrap_or_default());
 
 
         let mut file_path = String::from("");
 
         // Upload file to S3 and get the path
-       if !logo.is_none() {
+       if logo.is_some() {
            file_path = format!("school-logo/{}.{}", Uuid::new_v4(), "png");
-           match upload_file_to_s3(self.s3_client.clone(), logo, file_path.clone()).await {
+           match self.storage.upload(logo, file_path.clone()).await {
                Ok(path) => path,
                Err(e) => return Err(ErrorResponse::new(
                    StatusCode::INTERNAL_SERVER_ERROR,
@@ -99,73 +214,216 @@ impl SchoolUseCase for SchoolUseCaseImpl {
             address: address_str,
             logo_path: file_path.clone(),
             subscription_id: subscription_id_option,
+            subscription_ends_at: subscription_ends_at.map(|ends_at| ends_at.into_inner()),
             province_id: province_id_option,
             city_id: city_id_option,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.repository.create(&school).await {
-            Ok(_school) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let school = tx.school.create(&school).await?;
+            tx.audit_log.record(&AuditLog::created(&school)).await?;
+            tx.outbox.append(&[DomainEvent::SchoolCreated { school_id: school.id, name: school.name }]).await?;
+            Ok::<_, ErrorResponse>(())
+        }).await?;
+
+        self.metrics.schools_created.inc();
+        Ok(())
     }
 
-    async fn update(&self, id: String, form: Json<UpdateSchoolDto>) -> Result<(), ErrorResponse> {
+    #[instrument(name = "SchoolUseCase::update", skip_all)]
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSchoolDto>) -> Result<School, ErrorResponse> {
         let UpdateSchoolDto {
             name,
             address,
             logo_path,
             subscription_id,
+            subscription_ends_at,
             province_id,
             city_id,
         } = form.into_inner();
 
-        let school = match self.repository.get_by_id(id.parse().unwrap()).await {
-            Ok(school) => school,
-            Err(error) => return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
+        // Omitted fields keep their value; PATCH with null clears them.
+        let school = self.get(id, Includes::default()).await?.row;
+        let changes = SchoolPatch {
+            name: name.unwrap_or(school.name.clone()),
+            address: address.unwrap_or(school.address.clone()),
+            logo_path: logo_path.unwrap_or(school.logo_path.clone()),
+            subscription_id: subscription_id.or(school.subscription_id),
+            subscription_ends_at: subscription_ends_at.or(school.subscription_ends_at),
+            province_id: province_id.or(school.province_id.clone()),
+            city_id: city_id.or(school.city_id.clone()),
         };
 
-        let updated_school = School {
-            id: school.id,
-            name: name.unwrap_or(school.name),
-            address: address.unwrap_or(school.address),
-            logo_path: logo_path.unwrap_or(school.logo_path),
-            subscription_id,
-            province_id,
-            city_id,
-            created_at: school.created_at,
-            updated_at: Utc::now(),
-            deleted_at: None,
-        };
+        self.save(school, if_match, changes).await
+    }
 
-        match self.repository.update(&updated_school).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+    #[instrument(name = "SchoolUseCase::patch", skip_all)]
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<School, ErrorResponse> {
+        let school = self.get(id, Includes::default()).await?.row;
+        let changes = merge_patch::apply(&SchoolPatch::from(&school), &patch)?;
+
+        self.save(school, if_match, changes).await
     }
 
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
-        match self.repository.delete(id.parse().unwrap()).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+    #[instrument(name = "SchoolUseCase::delete", skip_all)]
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid school id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let school = match tx.school.get_by_id(id).await {
+                Ok(school) => school,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("School not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            if_match.check(school.version)?;
+            match tx.school.delete(id, school.version).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::deleted(&school)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "SchoolUseCase::restore", skip_all)]
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid school id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let school = match tx.school.get_trashed(id).await {
+                Ok(school) => school,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted school not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let restored = tx.school.restore(id).await?;
+            tx.audit_log.record(&AuditLog::restored(&school, &restored)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "SchoolUseCase::purge", skip_all)]
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid school id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let school = match tx.school.get_trashed(id).await {
+                Ok(school) => school,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted school not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            match tx.school.purge(id).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("School still has users".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::purged(&school)).await?;
+            Ok(())
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use actix_multipart::form::tempfile::TempFile;
+    use actix_multipart::form::text::Text;
+    use super::*;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::pkg::s3::InMemoryStorage;
+
+    fn setup() -> (InMemoryDatabase, Arc<InMemoryStorage>, SchoolUseCaseImpl) {
+        let database = InMemoryDatabase::new();
+        let storage = Arc::new(InMemoryStorage::default());
+        let r = database.repositories();
+        let usecase = SchoolUseCaseImpl::new(r.school, r.subscription, r.province, r.city, r.db_transaction, storage.clone(), Arc::new(Metrics::new()));
+        (database, storage, usecase)
+    }
+
+    fn create_dto(name: &str, logo: Option<TempFile>) -> MultipartForm<CreateSchoolDto> {
+        MultipartForm(CreateSchoolDto {
+            name: Text(name.to_string()),
+            address: Some(Text("Jl. Merdeka 1".to_string())),
+            subscription_id: None,
+            subscription_ends_at: None,
+            province_id: Some(Text("31".to_string())),
+            city_id: None,
+            logo,
+        })
+    }
+
+    fn logo(contents: &[u8]) -> TempFile {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(contents).unwrap();
+        TempFile { file, content_type: None, file_name: Some("logo.png".to_string()), size: contents.len() }
+    }
+
+    #[tokio::test]
+    async fn create_stores_school_without_logo() {
+        let (database, _, usecase) = setup();
+
+        usecase.create(create_dto("SMA 1", None)).await.unwrap();
+
+        let tables = database.tables();
+        assert_eq!(tables.schools.len(), 1);
+        assert_eq!(tables.schools[0].name, "SMA 1");
+        assert_eq!(tables.schools[0].address, "Jl. Merdeka 1");
+        assert_eq!(tables.schools[0].province_id.as_deref(), Some("31"));
+        assert_eq!(tables.schools[0].logo_path, "");
+        assert_eq!(tables.outbox_events.len(), 1);
+        assert_eq!(tables.outbox_events[0].event_type, "school.created");
+    }
+
+    #[tokio::test]
+    async fn create_uploads_logo() {
+        let (database, storage, usecase) = setup();
+
+        usecase.create(create_dto("SMA 1", Some(logo(b"png-bytes")))).await.unwrap();
+
+        let logo_path = database.tables().schools[0].logo_path.clone();
+        assert!(logo_path.starts_with("school-logo/"));
+        assert_eq!(storage.get(&logo_path).as_deref(), Some(&b"png-bytes"[..]));
+    }
+
+    #[tokio::test]
+    async fn create_rejects_blank_name() {
+        let (database, _, usecase) = setup();
+
+        let err = usecase.create(create_dto(" ", None)).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        assert!(database.tables().schools.is_empty());
     }
 }
@@ -1,44 +1,112 @@
-use crate::internal::app::repositories::subscription_type_repository::{SubscriptionTypeRepository, SubscriptionTypeRepositoryImpl};
-use crate::internal::entities::subscription_type::{SubscriptionType, SubscriptionTypeResponse};
-use crate::pkg::dto::subscription_type_dto::{CreateSubscriptionTypeDto, UpdateSubscriptionTypeDto};
+use std::sync::Arc;
+use async_trait::async_trait;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::subscription_type_repository::SubscriptionTypeRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::subscription_type::SubscriptionType;
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::pkg::dto::subscription_dto::SubscriptionResponse;
+use crate::pkg::dto::subscription_type_dto::{CreateSubscriptionTypeDto, SubscriptionTypePatch, UpdateSubscriptionTypeDto};
 use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::include::{has_many, keys, Included, Includes};
+use crate::helpers::merge_patch;
+use crate::helpers::precondition::{precondition_failed, IfMatch};
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use chrono::Utc;
-use std::fmt::Debug;
-use crate::internal::app::repositories::subscription_repository::{SubscriptionRepository, SubscriptionRepositoryImpl};
+use serde_json::Value;
+use crate::internal::app::repositories::subscription_repository::SubscriptionRepository;
 
-pub trait SubscriptionTypeUseCase {
-    fn new(repository: SubscriptionTypeRepositoryImpl, subscription_repository_impl: SubscriptionRepositoryImpl) -> Self;
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<SubscriptionTypeResponse>, i64), ErrorResponse>;
+#[async_trait]
+pub trait SubscriptionTypeUseCase: Send + Sync {
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<SubscriptionType>>, ErrorResponse>;
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<SubscriptionType>, ErrorResponse>;
     async fn create(&self, form: Json<CreateSubscriptionTypeDto>) -> Result<(), ErrorResponse>;
-    async fn update(&self, id: String, form: Json<UpdateSubscriptionTypeDto>) -> Result<(), ErrorResponse>;
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSubscriptionTypeDto>) -> Result<SubscriptionType, ErrorResponse>;
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<SubscriptionType, ErrorResponse>;
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse>;
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SubscriptionTypeUseCaseImpl {
-    repository: SubscriptionTypeRepositoryImpl,
-    subscription_repository: SubscriptionRepositoryImpl,
+    repository: Arc<dyn SubscriptionTypeRepository>,
+    subscription_repository: Arc<dyn SubscriptionRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
 }
 
-impl SubscriptionTypeUseCase for SubscriptionTypeUseCaseImpl {
-    fn new(repository: SubscriptionTypeRepositoryImpl, subscription_repository: SubscriptionRepositoryImpl) -> Self {
-        Self { repository, subscription_repository }
+impl SubscriptionTypeUseCaseImpl {
+    pub fn new(repository: Arc<dyn SubscriptionTypeRepository>, subscription_repository: Arc<dyn SubscriptionRepository>,
+               db_transaction_repository: Arc<dyn DbTransactionRepository>,
+    ) -> Self {
+        Self { repository, subscription_repository, db_transaction_repository }
     }
 
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<SubscriptionTypeResponse>, i64), ErrorResponse> {
-        if page == 0 || page_size == 0 {
+    // Nests the relations `includes` asks for under `subscription_types`, with
+    // one query per relation however many subscription types there are.
+    async fn include(&self, subscription_types: Vec<SubscriptionType>, includes: &Includes) -> Result<Vec<Included<SubscriptionType>>, ErrorResponse> {
+        let mut subscription_types = Included::wrap(subscription_types);
+        if includes.contains("subscriptions") {
+            let subscriptions = self.subscription_repository
+                .get_by_subscription_type_ids(&keys(&subscription_types, |subscription_type| Some(subscription_type.id)))
+                .await?;
+            has_many(&mut subscription_types, "subscriptions", subscriptions.into_iter().map(SubscriptionResponse::from).collect(), |subscription| subscription.subscription_type_id, |subscription_type| subscription_type.id);
+        }
+        Ok(subscription_types)
+    }
+
+    // Writes `changes` over `current` unless the subscription type moved past the
+    // version the client matched.
+    async fn save(&self, current: SubscriptionType, if_match: IfMatch, changes: SubscriptionTypePatch) -> Result<SubscriptionType, ErrorResponse> {
+        if_match.check(current.version)?;
+
+        let name = changes.name.trim().to_string();
+        if name.is_empty() {
             return Err(ErrorResponse::new(
                 StatusCode::BAD_REQUEST,
-                Some("Invalid pagination parameters".to_string()),
+                Some("Invalid subscription_type name".to_string()),
                 Some("FAILED".to_string()),
             ));
         }
 
-        let offset = (page - 1) * page_size;
+        let subscription_type = SubscriptionType {
+            name,
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let updated = match tx.subscription_type.update(&subscription_type).await {
+                Ok(updated) => updated,
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another subscription type already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::updated(&current, &updated)).await?;
+            Ok(updated)
+        }).await
+    }
+}
+
+#[async_trait]
+impl SubscriptionTypeUseCase for SubscriptionTypeUseCaseImpl {
+    #[instrument(name = "SubscriptionTypeUseCase::list", skip_all)]
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<SubscriptionType>>, ErrorResponse> {
+        if !page.is_valid() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
 
-        let (subscription_types, total_data) = self.repository.list(offset, page_size)
+        let mut subscription_types = self.repository.list(&query, &page)
             .await
             .map_err(|err| {
                 ErrorResponse::new(
@@ -48,26 +116,38 @@ impl SubscriptionTypeUseCase for SubscriptionTypeUseCaseImpl {
                 )
             })?;
 
-        let (response_data, ) = tokio::try_join!(
-            async {
-                let mut responses =  Vec::new();
-                for st in subscription_types {
-                    let subscriptions = self.subscription_repository.get_by_subscription_type_id(st.id).await.unwrap_or_else(|_| vec![]);
-                    responses.push(SubscriptionTypeResponse{
-                        id: st.id,
-                        name: st.name,
-                        created_at: st.created_at,
-                        updated_at: st.updated_at,
-                        deleted_at: st.deleted_at,
-                            subscriptions: subscriptions,
-                    });
-                }
-                Ok(responses)
-            }
-        )?;
-        Ok((response_data, total_data))
+        let rows = self.include(std::mem::take(&mut subscription_types.rows), &includes).await?;
+        Ok(subscription_types.with_rows(rows))
+    }
+
+
+    #[instrument(name = "SubscriptionTypeUseCase::get", skip_all)]
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<SubscriptionType>, ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription type id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        let subscription_type = match self.repository.get_by_id(id).await {
+            Ok(subscription_type) => subscription_type,
+            Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("Subscription type not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        };
+
+        let mut subscription_types = self.include(vec![subscription_type], &includes).await?;
+        Ok(subscription_types.remove(0))
     }
 
+    #[instrument(name = "SubscriptionTypeUseCase::create", skip_all)]
     async fn create(&self, form: Json<CreateSubscriptionTypeDto>) -> Result<(), ErrorResponse> {
         let CreateSubscriptionTypeDto { name } = form.into_inner();
         // Validate input
@@ -85,58 +165,126 @@ impl SubscriptionTypeUseCase for SubscriptionTypeUseCaseImpl {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.repository.create(&subscription_type).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            tx.subscription_type.create(&subscription_type).await?;
+            tx.audit_log.record(&AuditLog::created(&subscription_type)).await?;
+            Ok::<_, ErrorResponse>(())
+        }).await
     }
 
-    async fn update(&self, id: String, form: Json<UpdateSubscriptionTypeDto>) -> Result<(), ErrorResponse> {
+    #[instrument(name = "SubscriptionTypeUseCase::update", skip_all)]
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSubscriptionTypeDto>) -> Result<SubscriptionType, ErrorResponse> {
         let UpdateSubscriptionTypeDto { name } = form.into_inner();
 
-        let subscription_type = match self.repository.get_by_id(id.parse().unwrap()).await {
-            Ok(subscription_type) => subscription_type,
-            Err(error) => return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
+        let subscription_type = self.get(id, Includes::default()).await?.row;
+        let changes = SubscriptionTypePatch {
+            name: name.unwrap_or(subscription_type.name.clone()),
         };
 
-        let updated_name = name.unwrap_or(subscription_type.name);
+        self.save(subscription_type, if_match, changes).await
+    }
 
-        let updated_subscription_type = SubscriptionType {
-            id: subscription_type.id,
-            name: updated_name.trim().to_string(),
-            created_at: subscription_type.created_at,
-            updated_at: Utc::now(),
-            deleted_at: None,
-        };
+    #[instrument(name = "SubscriptionTypeUseCase::patch", skip_all)]
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<SubscriptionType, ErrorResponse> {
+        let subscription_type = self.get(id, Includes::default()).await?.row;
+        let changes = merge_patch::apply(&SubscriptionTypePatch::from(&subscription_type), &patch)?;
 
-        match self.repository.update(&updated_subscription_type).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+        self.save(subscription_type, if_match, changes).await
     }
 
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
-        match self.repository.delete(id.parse().unwrap()).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+    #[instrument(name = "SubscriptionTypeUseCase::delete", skip_all)]
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription type id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let subscription_type = match tx.subscription_type.get_by_id(id).await {
+                Ok(subscription_type) => subscription_type,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Subscription type not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            if_match.check(subscription_type.version)?;
+            match tx.subscription_type.delete(id, subscription_type.version).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::deleted(&subscription_type)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "SubscriptionTypeUseCase::restore", skip_all)]
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription type id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let subscription_type = match tx.subscription_type.get_trashed(id).await {
+                Ok(subscription_type) => subscription_type,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted subscription type not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let restored = match tx.subscription_type.restore(id).await {
+                Ok(restored) => restored,
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another subscription type already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::restored(&subscription_type, &restored)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "SubscriptionTypeUseCase::purge", skip_all)]
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription type id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let subscription_type = match tx.subscription_type.get_trashed(id).await {
+                Ok(subscription_type) => subscription_type,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted subscription type not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            match tx.subscription_type.purge(id).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Subscription type still has subscriptions".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            }
+            tx.audit_log.record(&AuditLog::purged(&subscription_type)).await?;
+            Ok(())
+        }).await
     }
 }
\ No newline at end of file
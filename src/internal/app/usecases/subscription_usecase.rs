@@ -1,45 +1,144 @@
-use crate::internal::app::repositories::subscription_repository::{SubscriptionRepository, SubscriptionRepositoryImpl};
+use std::sync::Arc;
+use async_trait::async_trait;
+use tracing::instrument;
+use std::future::Future;
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::subscription_repository::SubscriptionRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::event::DomainEvent;
 use crate::internal::entities::subscription::Subscription;
-use crate::pkg::dto::subscription_dto::{CreateSubscriptionDto, UpdateSubscriptionDto};
+use crate::database::list_query::{ListQuery, Page, PageRequest};
+use crate::pkg::dto::subscription_dto::{CreateSubscriptionDto, SubscriptionPatch, UpdateSubscriptionDto};
 use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::merge_patch;
+use crate::helpers::precondition::{precondition_failed, IfMatch};
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use chrono::Utc;
-use std::fmt::Debug;
-use crate::internal::app::repositories::subscription_type_repository::{SubscriptionTypeRepository, SubscriptionTypeRepositoryImpl};
+use serde_json::Value;
+use crate::internal::app::repositories::subscription_type_repository::SubscriptionTypeRepository;
+use crate::internal::app::state::Repositories;
+use crate::pkg::metrics::Metrics;
 
-pub trait SubscriptionUseCase {
-    fn new(repository: SubscriptionRepositoryImpl, subscription_type_repository: SubscriptionTypeRepositoryImpl) -> Self;
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Subscription>, i64), ErrorResponse>;
+#[async_trait]
+pub trait SubscriptionUseCase: Send + Sync {
+    async fn list(&self, query: ListQuery, page: PageRequest) -> Result<Page<Subscription>, ErrorResponse>;
+    async fn get(&self, id: String) -> Result<Subscription, ErrorResponse>;
     async fn create(&self, form: Json<CreateSubscriptionDto>) -> Result<(), ErrorResponse>;
-    async fn update(&self, id: String, form: Json<UpdateSubscriptionDto>) -> Result<(), ErrorResponse>;
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSubscriptionDto>) -> Result<Subscription, ErrorResponse>;
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<Subscription, ErrorResponse>;
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse>;
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct SubscriptionUseCaseImpl {
-    repository: SubscriptionRepositoryImpl,
-    subscription_type_repository: SubscriptionTypeRepositoryImpl,
+    repository: Arc<dyn SubscriptionRepository>,
+    subscription_type_repository: Arc<dyn SubscriptionTypeRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
+    metrics: Arc<Metrics>,
 }
 
-impl SubscriptionUseCase for SubscriptionUseCaseImpl {
-    fn new(repository: SubscriptionRepositoryImpl, subscription_type_repository: SubscriptionTypeRepositoryImpl) -> Self {
-        Self { repository, subscription_type_repository }
+impl SubscriptionUseCaseImpl {
+    pub fn new(repository: Arc<dyn SubscriptionRepository>, subscription_type_repository: Arc<dyn SubscriptionTypeRepository>,
+               db_transaction_repository: Arc<dyn DbTransactionRepository>, metrics: Arc<Metrics>,
+    ) -> Self {
+        Self { repository, subscription_type_repository, db_transaction_repository, metrics }
+    }
+
+    // Runs `change` on transaction-bound repositories and records the audit
+    // log entry it returns and a SubscriptionChanged event with it, then
+    // counts it under `action`.
+    async fn apply<T, F, Fut>(&self, id: uuid::Uuid, action: &str, change: F) -> Result<T, ErrorResponse>
+    where
+        T: Send,
+        F: FnOnce(Repositories) -> Fut + Send,
+        Fut: Future<Output = Result<(T, AuditLog), ErrorResponse>> + Send,
+    {
+        let event = DomainEvent::SubscriptionChanged { subscription_id: id, action: action.to_string() };
+        let output = with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let (output, audit) = change(tx.clone()).await?;
+            tx.audit_log.record(&audit).await?;
+            tx.outbox.append(&[event]).await?;
+            Ok::<_, ErrorResponse>(output)
+        }).await?;
+
+        self.metrics.subscriptions_changed.with_label_values(&[action]).inc();
+        Ok(output)
     }
 
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Subscription>, i64), ErrorResponse> {
-        if page == 0 || page_size == 0 {
+    // Writes `changes` over `current` unless the subscription moved past the
+    // version the client matched.
+    async fn save(&self, current: Subscription, if_match: IfMatch, changes: SubscriptionPatch) -> Result<Subscription, ErrorResponse> {
+        if_match.check(current.version)?;
+
+        let name = changes.name.trim().to_string();
+        if name.is_empty() {
             return Err(ErrorResponse::new(
                 StatusCode::BAD_REQUEST,
-                Some("Invalid pagination parameters".to_string()),
+                Some("Invalid subscription name".to_string()),
                 Some("FAILED".to_string()),
             ));
         }
+        if changes.price <= 0 {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid subscription price".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+        if changes.subscription_type_id != current.subscription_type_id {
+            match self.subscription_type_repository.get_by_id(changes.subscription_type_id).await {
+                Ok(_) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    Some("Subscription type ID not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            }
+        }
 
-        let offset = (page - 1) * page_size;
+        let subscription = Subscription {
+            name,
+            price: changes.price,
+            subscription_type_id: changes.subscription_type_id,
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        self.apply(subscription.id, "updated", move |tx| async move {
+            let updated = match tx.subscription.update(&subscription).await {
+                Ok(updated) => updated,
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another subscription already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let audit = AuditLog::updated(&current, &updated);
+            Ok((updated, audit))
+        }).await
+    }
+}
 
-        match self.repository.list(offset, page_size).await {
-            Ok((subscriptions, total_data)) => Ok((subscriptions, total_data)),
+#[async_trait]
+impl SubscriptionUseCase for SubscriptionUseCaseImpl {
+    #[instrument(name = "SubscriptionUseCase::list", skip_all)]
+    async fn list(&self, query: ListQuery, page: PageRequest) -> Result<Page<Subscription>, ErrorResponse> {
+        if !page.is_valid() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        match self.repository.list(&query, &page).await {
+            Ok(subscriptions) => Ok(subscriptions),
             Err(error) => Err(ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Some(error.to_string()),
@@ -48,6 +147,31 @@ impl SubscriptionUseCase for SubscriptionUseCaseImpl {
         }
     }
 
+
+    #[instrument(name = "SubscriptionUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<Subscription, ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        match self.repository.get_by_id(id).await {
+            Ok(subscription) => Ok(subscription),
+            Err(sqlx::Error::RowNotFound) => Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("Subscription not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        }
+    }
+
+    #[instrument(name = "SubscriptionUseCase::create", skip_all)]
     async fn create(&self, form: Json<CreateSubscriptionDto>) -> Result<(), ErrorResponse> {
         let CreateSubscriptionDto { name, price, subscription_type_id } = form.into_inner();
 
@@ -102,62 +226,194 @@ impl SubscriptionUseCase for SubscriptionUseCaseImpl {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.repository.create(&subscription).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+        self.apply(subscription.id, "created", move |tx| async move {
+            tx.subscription.create(&subscription).await?;
+            Ok(((), AuditLog::created(&subscription)))
+        }).await
     }
 
-    async fn update(&self, id: String, form: Json<UpdateSubscriptionDto>) -> Result<(), ErrorResponse> {
+    #[instrument(name = "SubscriptionUseCase::update", skip_all)]
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateSubscriptionDto>) -> Result<Subscription, ErrorResponse> {
         let UpdateSubscriptionDto { name, price , subscription_type_id} = form.into_inner();
 
-        let subscription = match self.repository.get_by_id(id.parse().unwrap()).await {
-            Ok(subscription) => subscription,
-            Err(error) => return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
+        let subscription = self.get(id).await?;
+        let changes = SubscriptionPatch {
+            name: name.unwrap_or(subscription.name.clone()),
+            price: price.unwrap_or(subscription.price),
+            subscription_type_id: subscription_type_id.unwrap_or(subscription.subscription_type_id),
         };
 
-        let updated_name = name.unwrap_or(subscription.name);
-        let updated_price = price.unwrap_or(subscription.price);
-        let updated_subscription_type_id = subscription_type_id.unwrap_or(subscription.subscription_type_id);
+        self.save(subscription, if_match, changes).await
+    }
+
+    #[instrument(name = "SubscriptionUseCase::patch", skip_all)]
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<Subscription, ErrorResponse> {
+        let subscription = self.get(id).await?;
+        let changes = merge_patch::apply(&SubscriptionPatch::from(&subscription), &patch)?;
+
+        self.save(subscription, if_match, changes).await
+    }
+
+    #[instrument(name = "SubscriptionUseCase::delete", skip_all)]
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse> {
+        let subscription = self.get(id).await?;
+        if_match.check(subscription.version)?;
+
+        self.apply(subscription.id, "deleted", move |tx| async move {
+            match tx.subscription.delete(subscription.id, subscription.version).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
+            }
+            Ok(((), AuditLog::deleted(&subscription)))
+        }).await
+    }
 
-        let updated_subscription = Subscription{
-            id: subscription.id,
-            name: updated_name.trim().to_string(),
-            price: updated_price,
-            subscription_type_id: updated_subscription_type_id,
-            created_at: subscription.created_at,
+    #[instrument(name = "SubscriptionUseCase::restore", skip_all)]
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        self.apply(id, "restored", move |tx| async move {
+            let subscription = match tx.subscription.get_trashed(id).await {
+                Ok(subscription) => subscription,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted subscription not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let restored = match tx.subscription.restore(id).await {
+                Ok(restored) => restored,
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another subscription already has this name".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            Ok(((), AuditLog::restored(&subscription, &restored)))
+        }).await
+    }
+
+    #[instrument(name = "SubscriptionUseCase::purge", skip_all)]
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid subscription id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        self.apply(id, "purged", move |tx| async move {
+            let subscription = match tx.subscription.get_trashed(id).await {
+                Ok(subscription) => subscription,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted subscription not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            match tx.subscription.purge(id).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Subscription is still used by schools".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            }
+            Ok(((), AuditLog::purged(&subscription)))
+        }).await
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::internal::entities::subscription_type::SubscriptionType;
+
+    fn setup() -> (InMemoryDatabase, SubscriptionUseCaseImpl, Uuid) {
+        let database = InMemoryDatabase::new();
+        let subscription_type_id = Uuid::new_v4();
+        database.tables().subscription_types.push(SubscriptionType {
+            id: subscription_type_id,
+            name: "Monthly".to_string(),
+            created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
-        };
+            version: 1,
+        });
+        let r = database.repositories();
+        let usecase = SubscriptionUseCaseImpl::new(r.subscription, r.subscription_type, r.db_transaction, Arc::new(Metrics::new()));
+        (database, usecase, subscription_type_id)
+    }
 
-        match self.repository.update(&updated_subscription).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
-        }
+    fn create_dto(name: &str, price: i32, subscription_type_id: Uuid) -> Json<CreateSubscriptionDto> {
+        Json(CreateSubscriptionDto { name: name.to_string(), price, subscription_type_id })
     }
 
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
-        match self.repository.delete(id.parse().unwrap()).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            ))
+    #[tokio::test]
+    async fn create_stores_valid_subscription() {
+        let (database, usecase, subscription_type_id) = setup();
+
+        usecase.create(create_dto("Basic", 100_000, subscription_type_id)).await.unwrap();
+
+        let tables = database.tables();
+        assert_eq!(tables.subscriptions.len(), 1);
+        assert_eq!(tables.subscriptions[0].name, "Basic");
+        assert_eq!(tables.subscriptions[0].subscription_type_id, subscription_type_id);
+        assert_eq!(tables.outbox_events.len(), 1);
+        assert_eq!(tables.outbox_events[0].event_type, "subscription.changed");
+    }
+
+    #[tokio::test]
+    async fn create_rejects_blank_name() {
+        let (_, usecase, subscription_type_id) = setup();
+
+        let err = usecase.create(create_dto("  ", 100_000, subscription_type_id)).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        assert_eq!(err.message.as_deref(), Some("Invalid subscription name"));
+    }
+
+    #[tokio::test]
+    async fn create_rejects_non_positive_price() {
+        let (_, usecase, subscription_type_id) = setup();
+
+        for price in [0, -1] {
+            let err = usecase.create(create_dto("Basic", price, subscription_type_id)).await.unwrap_err();
+            assert_eq!(err.message.as_deref(), Some("Invalid subscription price"));
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn create_rejects_unknown_subscription_type() {
+        let (database, usecase, _) = setup();
+
+        let err = usecase.create(create_dto("Basic", 100_000, Uuid::new_v4())).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        assert_eq!(err.message.as_deref(), Some("Subscription type ID not found"));
+        assert!(database.tables().subscriptions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_reports_duplicate_name() {
+        let (database, usecase, subscription_type_id) = setup();
+        usecase.create(create_dto("Basic", 100_000, subscription_type_id)).await.unwrap();
+
+        let err = usecase.create(create_dto("Basic", 200_000, subscription_type_id)).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(database.tables().outbox_events.len(), 1);
+    }
+}
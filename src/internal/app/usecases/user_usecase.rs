@@ -1,50 +1,160 @@
-use crate::internal::app::repositories::user_repository::{UserRepository, UserRepositoryImpl};
+use std::sync::Arc;
+use async_trait::async_trait;
+use tracing::instrument;
+use crate::internal::app::repositories::db_transaction_repository::{with_transaction, DbTransactionRepository};
+use crate::internal::app::repositories::user_repository::UserRepository;
+use crate::internal::entities::audit_log::AuditLog;
+use crate::internal::entities::event::DomainEvent;
 use crate::internal::entities::user::{User, UserStatus};
+use crate::database::list_query::{ListQuery, Page, PageRequest};
 use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::include::{belongs_to, keys, Included, Includes};
+use crate::helpers::merge_patch;
+use crate::helpers::precondition::{precondition_failed, IfMatch};
 use actix_web::http::StatusCode;
 use actix_web::web::Json;
 use bcrypt::{hash, DEFAULT_COST};
 use chrono::Utc;
+use serde_json::Value;
 use uuid::Uuid;
-use crate::internal::app::repositories::role_repository::{RoleRepository, RoleRepositoryImpl};
-use crate::internal::app::repositories::school_repository::{SchoolRepository, SchoolRepositoryImpl};
-use crate::pkg::dto::user_dto::{CreateUserDto, UpdateUserDto};
-
-pub trait UserUseCase {
-    fn new(
-        repository: UserRepositoryImpl,
-        role_repository: RoleRepositoryImpl,
-        school_repository: SchoolRepositoryImpl,
-    ) -> Self;
-
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<User>, i64), ErrorResponse>;
+use crate::internal::app::repositories::role_repository::RoleRepository;
+use crate::internal::app::repositories::school_repository::SchoolRepository;
+use crate::pkg::dto::user_dto::{CreateUserDto, UpdateUserDto, UserPatch};
+use crate::pkg::dto::role_dto::RoleResponse;
+use crate::pkg::dto::school_dto::SchoolResponse;
+
+#[async_trait]
+pub trait UserUseCase: Send + Sync {
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<User>>, ErrorResponse>;
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<User>, ErrorResponse>;
     async fn create(&self, form: Json<CreateUserDto>) -> Result<User, ErrorResponse>;
-    async fn update(&self, id: String, form: Json<UpdateUserDto>) -> Result<User, ErrorResponse>;
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateUserDto>) -> Result<User, ErrorResponse>;
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<User, ErrorResponse>;
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse>;
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse>;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct UserUseCaseImpl {
-    repository: UserRepositoryImpl,
-    role_repository: RoleRepositoryImpl,
-    school_repository: SchoolRepositoryImpl,
+    repository: Arc<dyn UserRepository>,
+    role_repository: Arc<dyn RoleRepository>,
+    school_repository: Arc<dyn SchoolRepository>,
+    db_transaction_repository: Arc<dyn DbTransactionRepository>,
 }
 
-impl UserUseCase for UserUseCaseImpl {
-    fn new(
-        repository: UserRepositoryImpl,
-        role_repository: RoleRepositoryImpl,
-        school_repository: SchoolRepositoryImpl,
+impl UserUseCaseImpl {
+    pub fn new(
+        repository: Arc<dyn UserRepository>,
+        role_repository: Arc<dyn RoleRepository>,
+        school_repository: Arc<dyn SchoolRepository>,
+        db_transaction_repository: Arc<dyn DbTransactionRepository>,
     ) -> Self {
         Self {
             repository,
             role_repository,
             school_repository,
+            db_transaction_repository,
         }
     }
 
-    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<User>, i64), ErrorResponse> {
-        if page == 0 || page_size == 0 {
+    // Nests the relations `includes` asks for under `users`, with one query
+    // per relation however many users there are.
+    async fn include(&self, users: Vec<User>, includes: &Includes) -> Result<Vec<Included<User>>, ErrorResponse> {
+        let mut users = Included::wrap(users);
+        if includes.contains("role") {
+            let roles = self.role_repository.get_by_ids(&keys(&users, |user| Some(user.role_id))).await?;
+            belongs_to(&mut users, "role", roles.into_iter().map(RoleResponse::from).collect(), |role| role.id, |user| Some(user.role_id));
+        }
+        if includes.contains("school") {
+            let schools = self.school_repository.get_by_ids(&keys(&users, |user| Some(user.school_id))).await?;
+            belongs_to(&mut users, "school", schools.into_iter().map(SchoolResponse::from).collect(), |school| school.id, |user| Some(user.school_id));
+        }
+        Ok(users)
+    }
+
+    // Writes `changes` over `current` unless the user moved past the version
+    // the client matched.
+    async fn save(&self, current: User, if_match: IfMatch, changes: UserPatch) -> Result<User, ErrorResponse> {
+        if_match.check(current.version)?;
+
+        if changes.name.trim().is_empty() || changes.email.trim().is_empty() || changes.phone_number.trim().is_empty() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Missing required fields".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        // Validate role existence.
+        if changes.role_id != current.role_id && self.role_repository.get_by_id(changes.role_id).await.is_err() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some(format!("Role with ID {} does not exist", changes.role_id)),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        // Validate school existence.
+        if changes.school_id != current.school_id && self.school_repository.get_by_id(changes.school_id).await.is_err() {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some(format!("School with ID {} does not exist", changes.school_id)),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let hashed_password = if let Some(pwd) = changes.password {
+            // Hash the new password if provided
+            match hash(pwd, DEFAULT_COST) {
+                Ok(h) => h,
+                Err(_) => {
+                    return Err(ErrorResponse::new(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Some("Failed to hash password".to_string()),
+                        Some("FAILED".to_string()),
+                    ));
+                }
+            }
+        } else {
+            current.password.clone() // Keep the old password if no new one is provided
+        };
+
+        let user = User {
+            name: changes.name,
+            email: changes.email,
+            phone_number: changes.phone_number,
+            password: hashed_password,
+            title: changes.title,
+            status: changes.status,
+            role_id: changes.role_id,
+            school_id: changes.school_id,
+            updated_at: Utc::now(),
+            ..current.clone()
+        };
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let updated = match tx.user.update(&user).await {
+                Ok(updated) => updated,
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another user already has this email or phone number".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::updated(&current, &updated)).await?;
+            Ok(updated)
+        }).await
+    }
+}
+
+#[async_trait]
+impl UserUseCase for UserUseCaseImpl {
+    #[instrument(name = "UserUseCase::list", skip_all)]
+    async fn list(&self, query: ListQuery, page: PageRequest, includes: Includes) -> Result<Page<Included<User>>, ErrorResponse> {
+        if !page.is_valid() {
             return Err(ErrorResponse::new(
                 StatusCode::BAD_REQUEST,
                 Some("Invalid pagination parameters".to_string()),
@@ -52,18 +162,47 @@ impl UserUseCase for UserUseCaseImpl {
             ));
         }
 
-        let offset = (page - 1) * page_size;
+        let mut users = match self.repository.list(&query, &page).await {
+            Ok(users) => users,
+            Err(error) => return Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Some(error.to_string()),
+                Some("FAILED".to_string()),
+            )),
+        };
+
+        let rows = self.include(std::mem::take(&mut users.rows), &includes).await?;
+        Ok(users.with_rows(rows))
+    }
+
+
+    #[instrument(name = "UserUseCase::get", skip_all)]
+    async fn get(&self, id: String, includes: Includes) -> Result<Included<User>, ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid user id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
 
-        match self.repository.list(offset, page_size).await {
-            Ok((users, total_data)) => Ok((users, total_data)),
-            Err(error) => Err(ErrorResponse::new(
+        let user = match self.repository.get_by_id(id).await {
+            Ok(user) => user,
+            Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                Some("User not found".to_string()),
+                Some("FAILED".to_string()),
+            )),
+            Err(error) => return Err(ErrorResponse::new(
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Some(error.to_string()),
                 Some("FAILED".to_string()),
             )),
-        }
+        };
+
+        let mut users = self.include(vec![user], &includes).await?;
+        Ok(users.remove(0))
     }
 
+    #[instrument(name = "UserUseCase::create", skip_all)]
     async fn create(&self, form: Json<CreateUserDto>) -> Result<User, ErrorResponse> {
         let CreateUserDto {
             name,
@@ -131,19 +270,18 @@ impl UserUseCase for UserUseCaseImpl {
             created_at: Utc::now(),
             updated_at: Utc::now(),
             deleted_at: None,
+            version: 1,
         };
 
-        match self.repository.create(&user).await {
-            Ok(user) => Ok(user),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let user = tx.user.create(&user).await?;
+            tx.audit_log.record(&AuditLog::created(&user)).await?;
+            Ok::<_, ErrorResponse>(user)
+        }).await
     }
 
-    async fn update(&self, id: String, form: Json<UpdateUserDto>) -> Result<User, ErrorResponse> {
+    #[instrument(name = "UserUseCase::update", skip_all)]
+    async fn update(&self, id: String, if_match: IfMatch, form: Json<UpdateUserDto>) -> Result<User, ErrorResponse> {
         let UpdateUserDto {
             name,
             email,
@@ -155,86 +293,359 @@ impl UserUseCase for UserUseCaseImpl {
             school_id,
         } = form.into_inner();
 
-        // Validate role existence.
-        if let Some(role_id) = role_id {
-            if self.role_repository.get_by_id(role_id).await.is_err() {
-                return Err(ErrorResponse::new(
-                    StatusCode::BAD_REQUEST,
-                    Some(format!("Role with ID {} does not exist", role_id)),
+        let user = self.get(id, Includes::default()).await?.row;
+        let changes = UserPatch {
+            name: name.unwrap_or(user.name.clone()),
+            email: email.unwrap_or(user.email.clone()),
+            phone_number: phone_number.unwrap_or(user.phone_number.clone()),
+            password,
+            title: title.unwrap_or(user.title.clone()),
+            status: status.unwrap_or(user.status.clone()),
+            role_id: role_id.unwrap_or(user.role_id),
+            school_id: school_id.unwrap_or(user.school_id),
+        };
+
+        self.save(user, if_match, changes).await
+    }
+
+    #[instrument(name = "UserUseCase::patch", skip_all)]
+    async fn patch(&self, id: String, if_match: IfMatch, patch: Value) -> Result<User, ErrorResponse> {
+        let user = self.get(id, Includes::default()).await?.row;
+        let changes = merge_patch::apply(&UserPatch::from(&user), &patch)?;
+
+        self.save(user, if_match, changes).await
+    }
+
+    #[instrument(name = "UserUseCase::delete", skip_all)]
+    async fn delete(&self, id: String, if_match: IfMatch) -> Result<(), ErrorResponse> {
+        let user_id: Uuid = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid user id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let user = match tx.user.get_by_id(user_id).await {
+                Ok(user) => user,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("User not found".to_string()),
                     Some("FAILED".to_string()),
-                ));
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            if_match.check(user.version)?;
+            match tx.user.delete(user_id, user.version).await {
+                Ok(()) => {}
+                Err(sqlx::Error::RowNotFound) => return Err(precondition_failed()),
+                Err(error) => return Err(error.into()),
             }
-        }
+            tx.audit_log.record(&AuditLog::deleted(&user)).await?;
+            tx.outbox.append(&[DomainEvent::UserDeleted { user_id }]).await?;
+            Ok(())
+        }).await
+    }
 
-        // Validate school existence.
-        if let Some(school_id) = school_id {
-            if self.school_repository.get_by_id(school_id).await.is_err() {
-                return Err(ErrorResponse::new(
-                    StatusCode::BAD_REQUEST,
-                    Some(format!("School with ID {} does not exist", school_id)),
+    #[instrument(name = "UserUseCase::restore", skip_all)]
+    async fn restore(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid user id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let user = match tx.user.get_trashed(id).await {
+                Ok(user) => user,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted user not found".to_string()),
                     Some("FAILED".to_string()),
-                ));
-            }
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            let restored = match tx.user.restore(id).await {
+                Ok(restored) => restored,
+                Err(sqlx::Error::Database(error)) if error.is_unique_violation() => return Err(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    Some("Another user already has this email or phone number".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.audit_log.record(&AuditLog::restored(&user, &restored)).await?;
+            Ok(())
+        }).await
+    }
+
+    #[instrument(name = "UserUseCase::purge", skip_all)]
+    async fn purge(&self, id: String) -> Result<(), ErrorResponse> {
+        let id = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid user id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        with_transaction(&*self.db_transaction_repository, move |tx| async move {
+            let user = match tx.user.get_trashed(id).await {
+                Ok(user) => user,
+                Err(sqlx::Error::RowNotFound) => return Err(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    Some("Deleted user not found".to_string()),
+                    Some("FAILED".to_string()),
+                )),
+                Err(error) => return Err(error.into()),
+            };
+            tx.user.purge(id).await?;
+            tx.audit_log.record(&AuditLog::purged(&user)).await?;
+            Ok(())
+        }).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use super::*;
+    use crate::database::list_query::decode_cursor;
+    use crate::internal::app::repositories::in_memory_repository::InMemoryDatabase;
+    use crate::internal::entities::trash::Trashed;
+    use crate::internal::entities::role::Role;
+    use crate::internal::entities::school::School;
+
+    struct Fixture {
+        database: InMemoryDatabase,
+        usecase: UserUseCaseImpl,
+        role_id: Uuid,
+        school_id: Uuid,
+    }
+
+    fn setup() -> Fixture {
+        let database = InMemoryDatabase::new();
+        let role_id = Uuid::new_v4();
+        let school_id = Uuid::new_v4();
+        {
+            let mut tables = database.tables();
+            tables.roles.push(Role {
+                id: role_id,
+                name: "admin".to_string(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            });
+            tables.schools.push(School {
+                id: school_id,
+                name: "SMA 1".to_string(),
+                address: "".to_string(),
+                logo_path: "".to_string(),
+                subscription_id: None,
+                subscription_ends_at: None,
+                province_id: None,
+                city_id: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+                deleted_at: None,
+                version: 1,
+            });
         }
+        let r = database.repositories();
+        let usecase = UserUseCaseImpl::new(r.user, r.role, r.school, r.db_transaction);
+        Fixture { database, usecase, role_id, school_id }
+    }
 
-        // Fetch the existing user and prepare updated user entity.
-        let user = self.repository.get_by_id(id.parse().unwrap()).await.map_err(|error| {
-            ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )
-        })?;
+    fn create_dto(role_id: Option<Uuid>, school_id: Option<Uuid>) -> Json<CreateUserDto> {
+        Json(CreateUserDto {
+            name: "Siti".to_string(),
+            email: "siti@example.com".to_string(),
+            phone_number: "0813".to_string(),
+            password: "secret".to_string(),
+            title: Some("Teacher".to_string()),
+            role_id,
+            school_id,
+        })
+    }
 
-        let hashed_password = if let Some(pwd) = password {
-            // Hash the new password if provided
-            match hash(pwd, DEFAULT_COST) {
-                Ok(h) => h,
-                Err(_) => {
-                    return Err(ErrorResponse::new(
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Some("Failed to hash password".to_string()),
-                        Some("FAILED".to_string()),
-                    ));
-                }
-            }
-        } else {
-            user.password // Keep the old password if no new one is provided
-        };
+    fn update_dto() -> UpdateUserDto {
+        UpdateUserDto {
+            name: None,
+            email: None,
+            phone_number: None,
+            password: None,
+            title: None,
+            status: None,
+            role_id: None,
+            school_id: None,
+        }
+    }
 
-        let updated_user = User {
-            id: user.id,
-            name: name.unwrap_or(user.name),
-            email: email.unwrap_or(user.email),
-            phone_number: phone_number.unwrap_or(user.phone_number),
-            password: hashed_password,
-            title: title.unwrap_or(user.title),
-            status: status.unwrap_or(user.status),
-            role_id: role_id.unwrap_or(user.role_id),
-            school_id: school_id.unwrap_or(user.school_id),
-            created_at: user.created_at,
-            updated_at: Utc::now(),
-            deleted_at: None,
+    #[tokio::test]
+    async fn create_hashes_password_and_links_role_and_school() {
+        let f = setup();
+
+        let user = f.usecase.create(create_dto(Some(f.role_id), Some(f.school_id))).await.unwrap();
+
+        assert_eq!(user.role_id, f.role_id);
+        assert_eq!(user.school_id, f.school_id);
+        assert_eq!(user.title, "Teacher");
+        assert_eq!(user.status, UserStatus::Pending);
+        assert!(bcrypt::verify("secret", &user.password).unwrap());
+        assert_eq!(f.database.tables().users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn create_rejects_unknown_role_or_school() {
+        let f = setup();
+
+        let err = f.usecase.create(create_dto(Some(Uuid::new_v4()), Some(f.school_id))).await.unwrap_err();
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+
+        let err = f.usecase.create(create_dto(Some(f.role_id), Some(Uuid::new_v4()))).await.unwrap_err();
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+
+        assert!(f.database.tables().users.is_empty());
+    }
+
+    #[tokio::test]
+    async fn create_rejects_missing_fields() {
+        let f = setup();
+        let mut dto = create_dto(None, None);
+        dto.password = "  ".to_string();
+
+        let err = f.usecase.create(dto).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_changes_given_fields_and_keeps_password() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(Some(f.role_id), Some(f.school_id))).await.unwrap();
+
+        let dto = UpdateUserDto {
+            name: Some("Siti Aminah".to_string()),
+            status: Some(UserStatus::Verified),
+            ..update_dto()
         };
+        let updated = f.usecase.update(user.id.to_string(), IfMatch::Any, Json(dto)).await.unwrap();
 
-        match self.repository.update(&updated_user).await {
-            Ok(user) => Ok(user),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
-        }
+        assert_eq!(updated.name, "Siti Aminah");
+        assert_eq!(updated.status, UserStatus::Verified);
+        assert_eq!(updated.email, user.email);
+        assert_eq!(updated.password, user.password);
+        assert_eq!(updated.created_at, user.created_at);
     }
 
-    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
-        match self.repository.delete(id.parse().unwrap()).await {
-            Ok(()) => Ok(()),
-            Err(error) => Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Some(error.to_string()),
-                Some("FAILED".to_string()),
-            )),
+    #[tokio::test]
+    async fn update_rehashes_new_password() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        let dto = UpdateUserDto { password: Some("changed".to_string()), ..update_dto() };
+        let updated = f.usecase.update(user.id.to_string(), IfMatch::Any, Json(dto)).await.unwrap();
+
+        assert!(bcrypt::verify("changed", &updated.password).unwrap());
+    }
+
+    #[tokio::test]
+    async fn update_rejects_unknown_school() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        let dto = UpdateUserDto { school_id: Some(Uuid::new_v4()), ..update_dto() };
+        let err = f.usecase.update(user.id.to_string(), IfMatch::Any, Json(dto)).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+        assert_eq!(f.database.tables().users[0].school_id, user.school_id);
+    }
+
+    #[tokio::test]
+    async fn update_rejects_stale_version() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        let dto = UpdateUserDto { name: Some("Siti Aminah".to_string()), ..update_dto() };
+        let updated = f.usecase.update(user.id.to_string(), IfMatch::Versions(vec![user.version]), Json(dto)).await.unwrap();
+        assert_eq!(updated.version, user.version + 1);
+
+        let dto = UpdateUserDto { name: Some("Budi".to_string()), ..update_dto() };
+        let err = f.usecase.update(user.id.to_string(), IfMatch::Versions(vec![user.version]), Json(dto)).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(f.database.tables().users[0].name, "Siti Aminah");
+    }
+
+    #[tokio::test]
+    async fn patch_merges_fields_and_rehashes_password() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        let patch = serde_json::json!({"title": "Principal", "password": "changed"});
+        let updated = f.usecase.patch(user.id.to_string(), IfMatch::Any, patch).await.unwrap();
+
+        assert_eq!(updated.title, "Principal");
+        assert_eq!(updated.name, user.name);
+        assert!(bcrypt::verify("changed", &updated.password).unwrap());
+    }
+
+    #[tokio::test]
+    async fn patch_rejects_unknown_fields() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        let err = f.usecase.patch(user.id.to_string(), IfMatch::Any, serde_json::json!({"version": 9})).await.unwrap_err();
+
+        assert_eq!(err.err_type, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn delete_records_user_deleted_event() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(None, None)).await.unwrap();
+
+        f.usecase.delete(user.id.to_string(), IfMatch::Any).await.unwrap();
+
+        let tables = f.database.tables();
+        assert!(tables.users[0].deleted_at.is_some());
+        assert_eq!(tables.outbox_events.len(), 1);
+        assert_eq!(tables.outbox_events[0].payload["user_id"], user.id.to_string());
+    }
+
+    #[tokio::test]
+    async fn list_pages_by_cursor() {
+        let f = setup();
+        for (name, email, phone_number) in [("Ani", "ani@example.com", "0811"), ("Budi", "budi@example.com", "0812"), ("Citra", "citra@example.com", "0813")] {
+            let dto = Json(CreateUserDto { name: name.to_string(), email: email.to_string(), phone_number: phone_number.to_string(), ..create_dto(None, None).into_inner() });
+            f.usecase.create(dto).await.unwrap();
         }
+        let mut params = HashMap::new();
+        params.insert("sort".to_string(), "-name".to_string());
+        let query = ListQuery::parse::<User>(Trashed::Without, &params).unwrap();
+
+        let first = f.usecase.list(query.clone(), PageRequest::first(2), Includes::default()).await.unwrap();
+        assert_eq!(first.rows.iter().map(|user| user.row.name.as_str()).collect::<Vec<_>>(), vec!["Citra", "Budi"]);
+
+        let position = decode_cursor(&query, first.next_cursor.as_ref().unwrap()).unwrap();
+        let second = f.usecase.list(query.clone(), PageRequest { position, ..PageRequest::first(2) }, Includes::default()).await.unwrap();
+        assert_eq!(second.rows.iter().map(|user| user.row.name.as_str()).collect::<Vec<_>>(), vec!["Ani"]);
+        assert!(second.next_cursor.is_none());
+
+        let position = decode_cursor(&query, second.prev_cursor.as_ref().unwrap()).unwrap();
+        let back = f.usecase.list(query, PageRequest { position, ..PageRequest::first(2) }, Includes::default()).await.unwrap();
+        assert_eq!(back.rows.iter().map(|user| user.row.name.as_str()).collect::<Vec<_>>(), vec!["Citra", "Budi"]);
+        assert!(back.prev_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn get_includes_role_and_school() {
+        let f = setup();
+        let user = f.usecase.create(create_dto(Some(f.role_id), Some(f.school_id))).await.unwrap();
+
+        let includes = Includes::parse::<User>(Some("role,school")).unwrap();
+        let user = f.usecase.get(user.id.to_string(), includes).await.unwrap();
+
+        let user = serde_json::to_value(&user).unwrap();
+        assert_eq!(user["role"]["name"], "admin");
+        assert_eq!(user["school"]["name"], "SMA 1");
     }
 }
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use async_trait::async_trait;
+use actix_web::http::StatusCode;
+use actix_web::web::Json;
+use chrono::Utc;
+use serde_json::json;
+use sqlx::types::Json as JsonColumn;
+use tracing::instrument;
+use uuid::Uuid;
+use crate::helpers::custom_error::ErrorResponse;
+use crate::internal::app::events::webhooks::WebhookSender;
+use crate::internal::app::repositories::school_repository::SchoolRepository;
+use crate::internal::app::repositories::webhook_repository::WebhookRepository;
+use crate::internal::entities::event::DomainEvent;
+use crate::internal::entities::webhook::{Webhook, WebhookDelivery};
+use crate::pkg::dto::webhook_dto::{CreateWebhookDto, UpdateWebhookDto};
+
+// The type of the events sent by `test`.
+const TEST_EVENT_TYPE: &str = "webhook.test";
+
+#[async_trait]
+pub trait WebhookUseCase: Send + Sync {
+    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Webhook>, i64), ErrorResponse>;
+    async fn get(&self, id: String) -> Result<Webhook, ErrorResponse>;
+    async fn create(&self, form: Json<CreateWebhookDto>) -> Result<Webhook, ErrorResponse>;
+    async fn update(&self, id: String, form: Json<UpdateWebhookDto>) -> Result<Webhook, ErrorResponse>;
+    async fn delete(&self, id: String) -> Result<(), ErrorResponse>;
+    async fn deliveries(&self, id: String, page: u32, page_size: u32) -> Result<(Vec<WebhookDelivery>, i64), ErrorResponse>;
+    // Sends a `webhook.test` event straight away, outside the job queue, and
+    // returns the logged delivery.
+    async fn test(&self, id: String) -> Result<WebhookDelivery, ErrorResponse>;
+}
+
+#[derive(Clone)]
+pub struct WebhookUseCaseImpl {
+    repository: Arc<dyn WebhookRepository>,
+    school_repository: Arc<dyn SchoolRepository>,
+    sender: WebhookSender,
+}
+
+impl WebhookUseCaseImpl {
+    pub fn new(repository: Arc<dyn WebhookRepository>, school_repository: Arc<dyn SchoolRepository>, sender: WebhookSender) -> Self {
+        Self { repository, school_repository, sender }
+    }
+
+    async fn find(&self, id: &str) -> Result<Webhook, ErrorResponse> {
+        let id: Uuid = id.parse().map_err(|_| ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid webhook id".to_string()),
+            Some("FAILED".to_string()),
+        ))?;
+
+        match self.repository.get_by_id(id).await {
+            Ok(webhook) => Ok(webhook),
+            Err(sqlx::Error::RowNotFound) => Err(not_found()),
+            Err(error) => Err(error.into()),
+        }
+    }
+}
+
+fn not_found() -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::NOT_FOUND,
+        Some("Webhook not found".to_string()),
+        Some("FAILED".to_string()),
+    )
+}
+
+fn validate_url(url: &str) -> Result<(), ErrorResponse> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some("Invalid webhook url".to_string()),
+            Some("FAILED".to_string()),
+        )),
+    }
+}
+
+fn validate_event_types(event_types: &[String]) -> Result<(), ErrorResponse> {
+    match event_types.iter().find(|event_type| !DomainEvent::TYPES.contains(&event_type.as_str())) {
+        Some(unknown) => Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            Some(format!("Unknown event type {}", unknown)),
+            Some("FAILED".to_string()),
+        )),
+        None => Ok(()),
+    }
+}
+
+#[async_trait]
+impl WebhookUseCase for WebhookUseCaseImpl {
+    #[instrument(name = "WebhookUseCase::list", skip_all)]
+    async fn list(&self, page: u32, page_size: u32) -> Result<(Vec<Webhook>, i64), ErrorResponse> {
+        if page == 0 || page_size == 0 {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let offset = (page - 1) * page_size;
+        Ok(self.repository.list(offset, page_size).await?)
+    }
+
+    #[instrument(name = "WebhookUseCase::get", skip_all)]
+    async fn get(&self, id: String) -> Result<Webhook, ErrorResponse> {
+        self.find(&id).await
+    }
+
+    #[instrument(name = "WebhookUseCase::create", skip_all)]
+    async fn create(&self, form: Json<CreateWebhookDto>) -> Result<Webhook, ErrorResponse> {
+        let CreateWebhookDto { url, school_id, event_types, secret } = form.into_inner();
+        let url = url.trim().to_string();
+        let event_types = event_types.unwrap_or_default();
+        validate_url(&url)?;
+        validate_event_types(&event_types)?;
+
+        if let Some(school_id) = school_id {
+            if self.school_repository.get_by_id(school_id).await.is_err() {
+                return Err(ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    Some("School ID not found".to_string()),
+                    Some("FAILED".to_string()),
+                ));
+            }
+        }
+
+        let secret = match secret {
+            Some(secret) if secret.trim().is_empty() => {
+                return Err(ErrorResponse::new(
+                    StatusCode::BAD_REQUEST,
+                    Some("Invalid webhook secret".to_string()),
+                    Some("FAILED".to_string()),
+                ));
+            }
+            Some(secret) => secret,
+            // 244 random bits.
+            None => format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        };
+
+        let webhook = Webhook {
+            id: Uuid::new_v4(),
+            school_id,
+            url,
+            secret,
+            event_types: JsonColumn(event_types),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        self.repository.create(&webhook).await?;
+        Ok(webhook)
+    }
+
+    #[instrument(name = "WebhookUseCase::update", skip_all)]
+    async fn update(&self, id: String, form: Json<UpdateWebhookDto>) -> Result<Webhook, ErrorResponse> {
+        let UpdateWebhookDto { url, event_types } = form.into_inner();
+        let mut webhook = self.find(&id).await?;
+
+        if let Some(url) = url {
+            let url = url.trim().to_string();
+            validate_url(&url)?;
+            webhook.url = url;
+        }
+        if let Some(event_types) = event_types {
+            validate_event_types(&event_types)?;
+            webhook.event_types = JsonColumn(event_types);
+        }
+        webhook.updated_at = Utc::now();
+
+        self.repository.update(&webhook).await?;
+        Ok(webhook)
+    }
+
+    #[instrument(name = "WebhookUseCase::delete", skip_all)]
+    async fn delete(&self, id: String) -> Result<(), ErrorResponse> {
+        let webhook = self.find(&id).await?;
+
+        match self.repository.delete(webhook.id).await {
+            Ok(()) => Ok(()),
+            Err(sqlx::Error::RowNotFound) => Err(not_found()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    #[instrument(name = "WebhookUseCase::deliveries", skip_all)]
+    async fn deliveries(&self, id: String, page: u32, page_size: u32) -> Result<(Vec<WebhookDelivery>, i64), ErrorResponse> {
+        if page == 0 || page_size == 0 {
+            return Err(ErrorResponse::new(
+                StatusCode::BAD_REQUEST,
+                Some("Invalid pagination parameters".to_string()),
+                Some("FAILED".to_string()),
+            ));
+        }
+
+        let webhook = self.find(&id).await?;
+        let offset = (page - 1) * page_size;
+        Ok(self.repository.list_deliveries(webhook.id, offset, page_size).await?)
+    }
+
+    #[instrument(name = "WebhookUseCase::test", skip_all)]
+    async fn test(&self, id: String) -> Result<WebhookDelivery, ErrorResponse> {
+        let webhook = self.find(&id).await?;
+        let event_id = Uuid::new_v4();
+        let payload = json!({
+            "type": TEST_EVENT_TYPE,
+            "id": event_id,
+            "occurred_at": Utc::now(),
+            "webhook_id": webhook.id,
+        });
+
+        let delivery = WebhookDelivery::new(webhook.id, event_id, TEST_EVENT_TYPE, payload);
+        self.repository.create_delivery(&delivery).await?;
+        Ok(self.sender.send(&webhook, &delivery, true).await?)
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::helpers::custom_response::current_request_id;
+use crate::helpers::request_context::current_request_context;
+
+// Fields whose values never reach the audit log; a change to one shows up
+// without either value.
+const REDACTED_FIELDS: &[&str] = &["password"];
+const REDACTED: &str = "[redacted]";
+
+/// A record whose changes are audited, under its table's name.
+pub trait Audited: TableSchema + Serialize {
+    fn entity_id(&self) -> Uuid;
+
+    // The school the record belongs to, if any.
+    fn school_id(&self) -> Option<Uuid> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "audit_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Purged,
+}
+
+/// One change to one record: who made it, in which request, and the record
+/// before and after.
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub entity_type: String,
+    pub entity_id: Uuid,
+    pub action: AuditAction,
+    pub actor_id: Option<String>,
+    pub actor: Option<String>,
+    pub school_id: Option<Uuid>,
+    pub ip: Option<String>,
+    pub request_id: Option<String>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    // `{field: {"before": .., "after": ..}}` for each field that differs.
+    pub changes: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl AuditLog {
+    pub fn created<T: Audited>(record: &T) -> Self {
+        Self::new(AuditAction::Created, record, None, Some(record))
+    }
+
+    pub fn updated<T: Audited>(before: &T, after: &T) -> Self {
+        Self::new(AuditAction::Updated, after, Some(before), Some(after))
+    }
+
+    pub fn deleted<T: Audited>(record: &T) -> Self {
+        Self::new(AuditAction::Deleted, record, Some(record), None)
+    }
+
+    pub fn restored<T: Audited>(before: &T, after: &T) -> Self {
+        Self::new(AuditAction::Restored, after, Some(before), Some(after))
+    }
+
+    pub fn purged<T: Audited>(record: &T) -> Self {
+        Self::new(AuditAction::Purged, record, Some(record), None)
+    }
+
+    // Attributed to the request being served, if any.
+    fn new<T: Audited>(action: AuditAction, record: &T, before: Option<&T>, after: Option<&T>) -> Self {
+        let before = before.map(snapshot);
+        let after = after.map(snapshot);
+        let changes = diff(before.as_ref(), after.as_ref());
+        let context = current_request_context();
+
+        AuditLog {
+            id: Uuid::new_v4(),
+            entity_type: T::TABLE.to_string(),
+            entity_id: record.entity_id(),
+            action,
+            actor_id: context.actor_id,
+            actor: context.actor,
+            school_id: record.school_id(),
+            ip: context.ip,
+            request_id: current_request_id(),
+            before: before.map(redact),
+            after: after.map(redact),
+            changes: redact(changes),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+fn snapshot<T: Serialize>(record: &T) -> Value {
+    serde_json::to_value(record).unwrap_or(Value::Null)
+}
+
+fn diff(before: Option<&Value>, after: Option<&Value>) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let changes: Map<String, Value> = fields.into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| (field.clone(), json!({
+            "before": before.get(field).cloned().unwrap_or(Value::Null),
+            "after": after.get(field).cloned().unwrap_or(Value::Null),
+        })))
+        .collect();
+    Value::Object(changes)
+}
+
+fn redact(mut value: Value) -> Value {
+    if let Some(fields) = value.as_object_mut() {
+        for field in REDACTED_FIELDS {
+            match fields.get_mut(*field) {
+                Some(Value::Object(change)) => change.values_mut().for_each(|value| *value = json!(REDACTED)),
+                Some(value) => *value = json!(REDACTED),
+                None => {}
+            }
+        }
+    }
+    value
+}
+
+/// Narrows a listing of audit logs; unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    // Matches either the actor's id or their email or username.
+    pub actor: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl TableSchema for AuditLog {
+    const TABLE: &'static str = "audit_logs";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("entity_type", ColumnType::Text),
+        column("entity_id", ColumnType::Uuid),
+        column("action", ColumnType::Enum("audit_action")),
+        nullable("actor_id", ColumnType::Text),
+        nullable("actor", ColumnType::Text),
+        nullable("school_id", ColumnType::Uuid),
+        nullable("ip", ColumnType::Text),
+        nullable("request_id", ColumnType::Text),
+        nullable("before", ColumnType::Jsonb),
+        nullable("after", ColumnType::Jsonb),
+        column("changes", ColumnType::Jsonb),
+        column("created_at", ColumnType::Timestamptz),
+    ];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::entities::user::{User, UserStatus};
+
+    fn user() -> User {
+        User {
+            id: Uuid::new_v4(),
+            name: "Budi".to_string(),
+            email: "budi@example.com".to_string(),
+            password: "hash".to_string(),
+            phone_number: "0811".to_string(),
+            title: String::new(),
+            status: UserStatus::Pending,
+            role_id: Uuid::new_v4(),
+            school_id: Uuid::new_v4(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            deleted_at: None,
+            version: 1,
+        }
+    }
+
+    #[test]
+    fn updates_record_only_changed_fields() {
+        let before = user();
+        let after = User { name: "Budi S".to_string(), ..before.clone() };
+
+        let log = AuditLog::updated(&before, &after);
+
+        assert_eq!(log.entity_type, "users");
+        assert_eq!(log.entity_id, before.id);
+        assert_eq!(log.school_id, Some(before.school_id));
+        assert_eq!(log.changes, json!({"name": {"before": "Budi", "after": "Budi S"}}));
+    }
+
+    #[test]
+    fn passwords_are_redacted() {
+        let before = user();
+        let after = User { password: "new hash".to_string(), ..before.clone() };
+
+        let log = AuditLog::updated(&before, &after);
+
+        assert_eq!(log.changes, json!({"password": {"before": REDACTED, "after": REDACTED}}));
+        assert_eq!(log.before.unwrap()["password"], REDACTED);
+        assert_eq!(log.after.unwrap()["password"], REDACTED);
+    }
+
+    #[test]
+    fn creations_have_no_before() {
+        let log = AuditLog::created(&user());
+
+        assert_eq!(log.action, AuditAction::Created);
+        assert!(log.before.is_none());
+        assert_eq!(log.changes["email"], json!({"before": null, "after": "budi@example.com"}));
+        assert!(log.actor.is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
 
 #[derive(Debug, Deserialize)]
 pub struct CityDataResponse {
@@ -13,12 +14,25 @@ pub struct CityResponse {
     pub name: String,
 }
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct City {
+    #[sqlx(rename = "id")]
     pub code: String,
     pub name: String,
     pub province_id: String,
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for last updated date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
-}
\ No newline at end of file
+}
+
+impl TableSchema for City {
+    const TABLE: &'static str = "cities";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Text),
+        column("name", ColumnType::Text),
+        column("province_id", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+    ];
+}
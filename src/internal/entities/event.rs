@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+
+/// Something that happened in the domain, for other features to react to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum DomainEvent {
+    #[serde(rename = "user.registered")]
+    UserRegistered { user_id: Uuid, school_id: Uuid, email: String },
+    #[serde(rename = "school.created")]
+    SchoolCreated { school_id: Uuid, name: String },
+    // `action` is created, updated, deleted, restored or purged.
+    #[serde(rename = "subscription.changed")]
+    SubscriptionChanged { subscription_id: Uuid, action: String },
+    #[serde(rename = "user.deleted")]
+    UserDeleted { user_id: Uuid },
+    // The school's subscription ended, and the school no longer has one.
+    #[serde(rename = "school.subscription_expired")]
+    SchoolSubscriptionExpired { school_id: Uuid, subscription_id: Uuid },
+}
+
+impl DomainEvent {
+    pub const TYPES: &'static [&'static str] = &["user.registered", "school.created", "subscription.changed", "user.deleted", "school.subscription_expired"];
+
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            DomainEvent::UserRegistered { .. } => "user.registered",
+            DomainEvent::SchoolCreated { .. } => "school.created",
+            DomainEvent::SubscriptionChanged { .. } => "subscription.changed",
+            DomainEvent::UserDeleted { .. } => "user.deleted",
+            DomainEvent::SchoolSubscriptionExpired { .. } => "school.subscription_expired",
+        }
+    }
+
+    // The school the event concerns, for events that concern one.
+    pub fn school_id(&self) -> Option<Uuid> {
+        match self {
+            DomainEvent::UserRegistered { school_id, .. }
+            | DomainEvent::SchoolCreated { school_id, .. }
+            | DomainEvent::SchoolSubscriptionExpired { school_id, .. } => Some(*school_id),
+            DomainEvent::SubscriptionChanged { .. } | DomainEvent::UserDeleted { .. } => None,
+        }
+    }
+}
+
+/// An event as subscribers receive it: the event's fields alongside `type`,
+/// `id` and `occurred_at`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedEvent {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+}
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct OutboxEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub occurred_at: DateTime<Utc>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+}
+
+impl TableSchema for OutboxEvent {
+    const TABLE: &'static str = "outbox_events";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("event_type", ColumnType::Text),
+        column("payload", ColumnType::Jsonb),
+        column("occurred_at", ColumnType::Timestamptz),
+        nullable("dispatched_at", ColumnType::Timestamptz),
+    ];
+}
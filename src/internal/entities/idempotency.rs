@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+
+/// A request made with an `Idempotency-Key`, and once it's done the response
+/// that retries of it get until `expires_at`.
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyKey {
+    pub key: String,
+    // Method and path, e.g. `POST /api/v1/schools`.
+    pub endpoint: String,
+    // Hex SHA-256 of the request body.
+    pub fingerprint: String,
+    // None while the first request is still being handled.
+    pub status_code: Option<i32>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    // Until when the request that claimed the key is assumed to be running;
+    // once it passes, an unfinished key can be claimed again.
+    pub locked_until: DateTime<Utc>,
+}
+
+impl IdempotencyKey {
+    pub fn is_completed(&self) -> bool {
+        self.status_code.is_some()
+    }
+}
+
+impl TableSchema for IdempotencyKey {
+    const TABLE: &'static str = "idempotency_keys";
+    const COLUMNS: &'static [Column] = &[
+        column("key", ColumnType::Text),
+        column("endpoint", ColumnType::Text),
+        column("fingerprint", ColumnType::Text),
+        nullable("status_code", ColumnType::Int4),
+        nullable("content_type", ColumnType::Text),
+        nullable("response_body", ColumnType::Bytea),
+        column("created_at", ColumnType::Timestamptz),
+        column("expires_at", ColumnType::Timestamptz),
+        column("locked_until", ColumnType::Timestamptz),
+    ];
+}
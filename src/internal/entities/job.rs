@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Succeeded,
+    // Failed on every attempt; only an admin retry runs it again.
+    Dead,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Dead => "dead",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Value,
+    pub status: JobStatus,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub locked_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableSchema for Job {
+    const TABLE: &'static str = "jobs";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("kind", ColumnType::Text),
+        column("payload", ColumnType::Jsonb),
+        column("status", ColumnType::Enum("job_status")),
+        column("attempts", ColumnType::Int4),
+        column("max_attempts", ColumnType::Int4),
+        column("run_at", ColumnType::Timestamptz),
+        nullable("locked_at", ColumnType::Timestamptz),
+        nullable("last_error", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+    ];
+}
@@ -5,4 +5,11 @@ pub mod province;
 pub mod city;
 pub mod school;
 pub mod user;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod job;
+pub mod schedule;
+pub mod event;
+pub mod webhook;
+pub mod audit_log;
+pub mod trash;
+pub mod idempotency;
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
 
 #[derive(Debug, Deserialize)]
 pub struct ProvinceDataResponse {
@@ -13,7 +14,7 @@ pub struct ProvinceResponse {
     pub name: String,
 }
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct Province {
     pub code: String,
     pub name: String,
@@ -22,11 +23,22 @@ pub struct Province {
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
 }
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct ProvinceFromTable {
     pub id: String,
     pub name: String,
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for last updated date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
-}
\ No newline at end of file
+}
+
+impl TableSchema for ProvinceFromTable {
+    const TABLE: &'static str = "provinces";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Text),
+        column("name", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+    ];
+}
@@ -2,12 +2,41 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use crate::database::list_query::{Listable, Sort};
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::audit_log::Audited;
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct Role {
     pub id: Uuid,
     pub name: String,
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for last updated date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
-}
\ No newline at end of file
+    pub version: i32,  // Bumped by every write, and sent as the ETag
+}
+
+impl TableSchema for Role {
+    const TABLE: &'static str = "roles";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+        column("version", ColumnType::Int4),
+    ];
+}
+
+impl Listable for Role {
+    const FILTERABLE: &'static [&'static str] = &[];
+    const SORTABLE: &'static [&'static str] = &["name", "created_at"];
+    const SEARCHABLE: &'static [&'static str] = &[];
+    const DEFAULT_SORT: &'static [Sort] = &[Sort::asc("name", ColumnType::Text)];
+}
+
+impl Audited for Role {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
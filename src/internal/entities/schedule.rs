@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::job::JobStatus;
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct ScheduleRun {
+    pub name: String,
+    pub last_run_at: DateTime<Utc>,
+    pub last_job_id: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TableSchema for ScheduleRun {
+    const TABLE: &'static str = "schedule_runs";
+    const COLUMNS: &'static [Column] = &[
+        column("name", ColumnType::Text),
+        column("last_run_at", ColumnType::Timestamptz),
+        nullable("last_job_id", ColumnType::Uuid),
+        column("updated_at", ColumnType::Timestamptz),
+    ];
+}
+
+/// A schedule as shown to admins: when it last fired, how the job it queued
+/// went, and when it fires next.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleOverview {
+    pub name: String,
+    pub cron: String,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_job_id: Option<Uuid>,
+    pub last_status: Option<JobStatus>,
+    pub last_error: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+}
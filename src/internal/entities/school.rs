@@ -2,17 +2,62 @@ use serde::{Deserialize, Serialize};
 use sqlx::{FromRow};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::database::list_query::{Listable, Sort};
+use crate::helpers::include::Includable;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::audit_log::Audited;
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct School {
     pub id: Uuid,               // UUID type for unique subscription identifier
     pub name: String,           // Subscription name, not null, unique
     pub address: String,
     pub logo_path: String,
     pub subscription_id: Option<Uuid>,               // UUID type for unique subscription identifier
+    pub subscription_ends_at: Option<DateTime<Utc>>,  // When the subscription runs out, if it does
     pub province_id: Option<String>,           // Subscription name, not null, unique
     pub city_id: Option<String>,           // Subscription name, not null, unique
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for last updated date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
-}
\ No newline at end of file
+    pub version: i32,  // Bumped by every write, and sent as the ETag
+}
+
+impl TableSchema for School {
+    const TABLE: &'static str = "schools";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        column("address", ColumnType::Text),
+        column("logo_path", ColumnType::Text),
+        nullable("subscription_id", ColumnType::Uuid),
+        nullable("subscription_ends_at", ColumnType::Timestamptz),
+        nullable("province_id", ColumnType::Text),
+        nullable("city_id", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+        column("version", ColumnType::Int4),
+    ];
+}
+
+impl Listable for School {
+    const FILTERABLE: &'static [&'static str] = &["subscription_id", "province_id", "city_id"];
+    const SORTABLE: &'static [&'static str] = &["name", "created_at", "updated_at"];
+    const SEARCHABLE: &'static [&'static str] = &["name"];
+    const DEFAULT_SORT: &'static [Sort] = &[Sort::asc("created_at", ColumnType::Timestamptz)];
+}
+
+impl Includable for School {
+    const INCLUDABLE: &'static [&'static str] = &["subscription", "province", "city"];
+}
+
+impl Audited for School {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn school_id(&self) -> Option<Uuid> {
+        Some(self.id)
+    }
+}
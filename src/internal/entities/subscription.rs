@@ -2,8 +2,11 @@ use serde::{Deserialize, Serialize};
 use sqlx::{FromRow};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::database::list_query::{Listable, Sort};
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::audit_log::Audited;
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct Subscription {
     pub id: Uuid,               // UUID type for unique subscription identifier
     pub name: String,           // Subscription name, not null, unique
@@ -12,4 +15,32 @@ pub struct Subscription {
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for last updated date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deleted date
-}
\ No newline at end of file
+    pub version: i32,  // Bumped by every write, and sent as the ETag
+}
+
+impl TableSchema for Subscription {
+    const TABLE: &'static str = "subscriptions";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        column("price", ColumnType::Int4),
+        column("subscription_type_id", ColumnType::Uuid),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+        column("version", ColumnType::Int4),
+    ];
+}
+
+impl Listable for Subscription {
+    const FILTERABLE: &'static [&'static str] = &["subscription_type_id"];
+    const SORTABLE: &'static [&'static str] = &["name", "price", "created_at"];
+    const SEARCHABLE: &'static [&'static str] = &[];
+    const DEFAULT_SORT: &'static [Sort] = &[Sort::asc("price", ColumnType::Int4)];
+}
+
+impl Audited for Subscription {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
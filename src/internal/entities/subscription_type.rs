@@ -2,23 +2,46 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
-use crate::internal::entities::subscription::Subscription;
+use crate::database::list_query::{Listable, Sort};
+use crate::helpers::include::Includable;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::audit_log::Audited;
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct SubscriptionType {
     pub id: Uuid,               // UUID for unique SubscriptionType identifier
     pub name: String,           // Subscription type name
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deletion date
+    pub version: i32,  // Bumped by every write, and sent as the ETag
 }
 
-#[derive(Debug, FromRow, Deserialize, Serialize)]
-pub struct SubscriptionTypeResponse {
-    pub id: Uuid,               // UUID for unique SubscriptionType identifier
-    pub name: String,           // Subscription type name
-    pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
-    pub updated_at: DateTime<Utc>,  // Timestamp with time zone for creation date
-    pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deletion date
-    pub subscriptions: Vec<Subscription>,
-}
\ No newline at end of file
+impl TableSchema for SubscriptionType {
+    const TABLE: &'static str = "subscription_types";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+        column("version", ColumnType::Int4),
+    ];
+}
+
+impl Listable for SubscriptionType {
+    const FILTERABLE: &'static [&'static str] = &[];
+    const SORTABLE: &'static [&'static str] = &["name", "created_at"];
+    const SEARCHABLE: &'static [&'static str] = &[];
+    const DEFAULT_SORT: &'static [Sort] = &[Sort::asc("name", ColumnType::Text)];
+}
+
+impl Includable for SubscriptionType {
+    const INCLUDABLE: &'static [&'static str] = &["subscriptions"];
+}
+
+impl Audited for SubscriptionType {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Which rows a listing returns, going by their `deleted_at`: the live ones
+/// (the default), the soft-deleted ones, or both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trashed {
+    #[default]
+    Without,
+    With,
+    Only,
+}
+
+impl Trashed {
+    pub fn includes_live(self) -> bool {
+        self != Trashed::Only
+    }
+
+    pub fn includes_trashed(self) -> bool {
+        self != Trashed::Without
+    }
+
+    pub fn matches(self, deleted_at: Option<DateTime<Utc>>) -> bool {
+        match deleted_at {
+            Some(_) => self.includes_trashed(),
+            None => self.includes_live(),
+        }
+    }
+}
@@ -2,21 +2,25 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use crate::database::list_query::{Listable, Sort};
+use crate::helpers::include::Includable;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+use crate::internal::entities::audit_log::Audited;
 
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "user_status")] // Must match the name of the SQL ENUM
 #[sqlx(rename_all = "lowercase")] // Match the case of ENUM values in the database
 pub enum UserStatus {
     Verified,
     Pending,
 }
-#[derive(Debug, FromRow, Deserialize, Serialize)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
 pub struct User {
     pub id: Uuid,               // UUID for unique SubscriptionType identifier
     pub name: String,           // Subscription type name
     pub email: String,           // Subscription type name
-    pub password: String,           // Subscription type name
+    pub password: String,           // Bcrypt hash; kept out of responses by UserResponse
     pub phone_number: String,           // Subscription type name
     pub title: String,           // Subscription type name
     pub status: UserStatus,     // Subscription type name
@@ -25,4 +29,45 @@ pub struct User {
     pub created_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub updated_at: DateTime<Utc>,  // Timestamp with time zone for creation date
     pub deleted_at: Option<DateTime<Utc>>,  // Nullable timestamp for deletion date
-}
\ No newline at end of file
+    pub version: i32,  // Bumped by every write, and sent as the ETag
+}
+
+impl TableSchema for User {
+    const TABLE: &'static str = "users";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("name", ColumnType::Text),
+        column("email", ColumnType::Text),
+        column("password", ColumnType::Text),
+        column("phone_number", ColumnType::Text),
+        column("title", ColumnType::Text),
+        column("status", ColumnType::Enum("user_status")),
+        column("role_id", ColumnType::Uuid),
+        column("school_id", ColumnType::Uuid),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+        nullable("deleted_at", ColumnType::Timestamptz),
+        column("version", ColumnType::Int4),
+    ];
+}
+
+impl Listable for User {
+    const FILTERABLE: &'static [&'static str] = &["status", "role_id", "school_id"];
+    const SORTABLE: &'static [&'static str] = &["name", "email", "created_at", "updated_at"];
+    const SEARCHABLE: &'static [&'static str] = &["name", "email"];
+    const DEFAULT_SORT: &'static [Sort] = &[Sort::asc("created_at", ColumnType::Timestamptz)];
+}
+
+impl Includable for User {
+    const INCLUDABLE: &'static [&'static str] = &["role", "school"];
+}
+
+impl Audited for User {
+    fn entity_id(&self) -> Uuid {
+        self.id
+    }
+
+    fn school_id(&self) -> Option<Uuid> {
+        Some(self.school_id)
+    }
+}
@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+use sqlx::FromRow;
+use uuid::Uuid;
+use crate::database::schema::{column, nullable, Column, ColumnType, TableSchema};
+
+/// An endpoint that domain events are posted to, signed with `secret`.
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct Webhook {
+    pub id: Uuid,
+    // None for a platform-wide endpoint, which gets every school's events.
+    pub school_id: Option<Uuid>,
+    pub url: String,
+    // Only shown when the webhook is created; see `WebhookResponse`.
+    pub secret: String,
+    // Empty for all event types.
+    pub event_types: Json<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// Whether an event of `event_type`, about `school_id` if any, goes here.
+    pub fn wants(&self, event_type: &str, school_id: Option<Uuid>) -> bool {
+        let school_matches = self.school_id.is_none() || self.school_id == school_id;
+        let type_matches = self.event_types.is_empty() || self.event_types.iter().any(|wanted| wanted == event_type);
+        school_matches && type_matches
+    }
+}
+
+impl TableSchema for Webhook {
+    const TABLE: &'static str = "webhooks";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        nullable("school_id", ColumnType::Uuid),
+        column("url", ColumnType::Text),
+        column("secret", ColumnType::Text),
+        column("event_types", ColumnType::Jsonb),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookDeliveryStatus {
+    // Waiting for its first attempt or a retry.
+    Pending,
+    Succeeded,
+    // Failed on every attempt.
+    Failed,
+}
+
+/// One event sent, or to be sent, to one webhook, with the outcome of the
+/// latest attempt.
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook_id: Uuid, event_id: Uuid, event_type: &str, payload: Value) -> Self {
+        WebhookDelivery {
+            id: Uuid::new_v4(),
+            webhook_id,
+            event_id,
+            event_type: event_type.to_string(),
+            payload,
+            status: WebhookDeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            response_body: None,
+            error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl TableSchema for WebhookDelivery {
+    const TABLE: &'static str = "webhook_deliveries";
+    const COLUMNS: &'static [Column] = &[
+        column("id", ColumnType::Uuid),
+        column("webhook_id", ColumnType::Uuid),
+        column("event_id", ColumnType::Uuid),
+        column("event_type", ColumnType::Text),
+        column("payload", ColumnType::Jsonb),
+        column("status", ColumnType::Enum("webhook_delivery_status")),
+        column("attempts", ColumnType::Int4),
+        nullable("response_status", ColumnType::Int4),
+        nullable("response_body", ColumnType::Text),
+        nullable("error", ColumnType::Text),
+        column("created_at", ColumnType::Timestamptz),
+        column("updated_at", ColumnType::Timestamptz),
+    ];
+}
+
+/// What an attempt to deliver came to.
+#[derive(Debug, Clone)]
+pub struct DeliveryAttempt {
+    pub status: WebhookDeliveryStatus,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub error: Option<String>,
+}
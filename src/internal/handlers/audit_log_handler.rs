@@ -0,0 +1,31 @@
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+use actix_web::web::Query;
+use crate::helpers::custom_response::{ApiResponse, PaginationMeta};
+use crate::internal::app::usecases::audit_log_usecase::AuditLogUseCase;
+use crate::pkg::dto::audit_log_dto::ListAuditLogsQuery;
+
+#[derive(Clone)]
+pub struct AuditLogHandlerImpl {
+    service: Arc<dyn AuditLogUseCase>,
+}
+
+impl AuditLogHandlerImpl {
+    pub fn new(service: Arc<dyn AuditLogUseCase>) -> Self {
+        Self { service }
+    }
+}
+
+pub async fn audit_log_handler_list(req: HttpRequest, handler: web::Data<AuditLogHandlerImpl>, params: Query<ListAuditLogsQuery>) -> HttpResponse {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+
+    match handler.service.list(params.filter(), page, page_size).await {
+        Ok((logs, total_data)) => ApiResponse::new(logs)
+            .pagination(PaginationMeta::new(page, page_size, total_data))
+            .message("Successfully fetched audit logs")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
@@ -1,35 +1,30 @@
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::Response;
-use actix_web::{web, HttpResponse, Responder};
-use serde_json::json;
-use crate::internal::app::usecases::auth_usecase::{AuthUseCase, AuthUseCaseImpl};
+use std::sync::Arc;
+use crate::helpers::custom_response::ApiResponse;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+use crate::internal::app::usecases::auth_usecase::AuthUseCase;
 use crate::pkg::dto::auth_dto::RegisterDto;
+use crate::pkg::dto::user_dto::UserResponse;
 
 #[derive(Clone)]
 pub struct AuthHandlerImpl {
-    service: AuthUseCaseImpl,
+    service: Arc<dyn AuthUseCase>,
 }
 
 impl AuthHandlerImpl {
-    pub fn new(service: AuthUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn AuthUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn register(handler: web::Data<AuthHandlerImpl>,
+pub async fn register(req: HttpRequest,
+                      handler: web::Data<AuthHandlerImpl>,
                       input: web::Json<RegisterDto>,
-) -> impl Responder {
-    match handler.service.register(input).await {
-        Ok(user) => {
-            let response = Response {
-                data: user,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully created user",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+) -> HttpResponse {
+    match handler.service.register(input).await.map(UserResponse::from) {
+        Ok(user) => ApiResponse::new(user)
+            .message("Successfully created user")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
@@ -1,44 +1,50 @@
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::Response;
-use crate::internal::app::usecases::city_usecase::{CityUseCase, CityUseCaseImpl};
-use actix_web::{web, HttpResponse, Responder};
-use serde_json::json;
+use std::sync::Arc;
+use crate::helpers::custom_response::ApiResponse;
+use crate::internal::app::usecases::city_usecase::CityUseCase;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 
 #[derive(Clone)]
 pub struct CityHandlerImpl {
-    service: CityUseCaseImpl,
+    service: Arc<dyn CityUseCase>,
 }
 
 impl CityHandlerImpl {
-    pub fn new(service: CityUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn CityUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn city_handler_list(handler: web::Data<CityHandlerImpl>) -> impl Responder {
+pub async fn city_handler_list(req: HttpRequest, handler: web::Data<CityHandlerImpl>) -> HttpResponse {
     match handler.service.list().await {
-        Ok(cities) => {
-            let response = Response {
-                data: cities,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully fetched cities",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+        Ok(cities) => ApiResponse::new(cities)
+            .message("Successfully fetched cities")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn city_handler_get(
+    req: HttpRequest,
+    handler: web::Data<CityHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match handler.service.get(path.into_inner()).await {
+        Ok(city) => ApiResponse::new(city)
+            .message("Successfully fetched city")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn city_handler_create(
+    req: HttpRequest,
     handler: web::Data<CityHandlerImpl>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create().await {
-        Ok(_) => HttpResponse::Created().json(json!({
-            "message": "City sync successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response()
+        Ok(_) => ApiResponse::empty()
+            .message("City sync started")
+            .respond(&req, StatusCode::ACCEPTED),
+        Err(err) => err.respond(&req)
     }
-}
\ No newline at end of file
+}
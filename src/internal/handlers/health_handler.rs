@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use crate::helpers::build_info::BuildInfo;
+use crate::helpers::custom_response::ApiResponse;
+use crate::internal::app::usecases::health_usecase::HealthUseCase;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+
+#[derive(Clone)]
+pub struct HealthHandlerImpl {
+    service: Arc<dyn HealthUseCase>,
+}
+
+impl HealthHandlerImpl {
+    pub fn new(service: Arc<dyn HealthUseCase>) -> Self {
+        Self { service }
+    }
+}
+
+// The process is up and serving requests; dependencies aren't consulted.
+pub async fn health_handler_live(req: HttpRequest) -> HttpResponse {
+    ApiResponse::empty()
+        .message("Service is alive")
+        .respond(&req, StatusCode::OK)
+}
+
+pub async fn health_handler_ready(req: HttpRequest, handler: web::Data<HealthHandlerImpl>) -> HttpResponse {
+    let report = handler.service.ready().await;
+    if report.ready {
+        ApiResponse::new(report)
+            .message("Service is ready")
+            .respond(&req, StatusCode::OK)
+    } else {
+        ApiResponse::new(report)
+            .message("Service is not ready")
+            .respond(&req, StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+pub async fn health_handler_version(req: HttpRequest) -> HttpResponse {
+    ApiResponse::new(BuildInfo::current())
+        .message("Successfully fetched version")
+        .respond(&req, StatusCode::OK)
+}
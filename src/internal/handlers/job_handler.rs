@@ -0,0 +1,73 @@
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+use actix_web::web::Query;
+use crate::helpers::custom_response::{ApiResponse, PaginationMeta};
+use crate::internal::app::usecases::job_usecase::JobUseCase;
+use crate::pkg::dto::job_dto::ListJobsQuery;
+
+#[derive(Clone)]
+pub struct JobHandlerImpl {
+    service: Arc<dyn JobUseCase>,
+}
+
+impl JobHandlerImpl {
+    pub fn new(service: Arc<dyn JobUseCase>) -> Self {
+        Self { service }
+    }
+}
+
+pub async fn job_handler_list(req: HttpRequest, handler: web::Data<JobHandlerImpl>, params: Query<ListJobsQuery>) -> HttpResponse {
+    let page = params.page.unwrap_or(1);
+    let page_size = params.page_size.unwrap_or(10);
+
+    match handler.service.list(params.status, page, page_size).await {
+        Ok((jobs, total_data)) => ApiResponse::new(jobs)
+            .pagination(PaginationMeta::new(page, page_size, total_data))
+            .message("Successfully fetched jobs")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn job_handler_get(
+    req: HttpRequest,
+    handler: web::Data<JobHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.get(path_id).await {
+        Ok(job) => ApiResponse::new(job)
+            .message("Successfully fetched job")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn job_handler_retry(
+    req: HttpRequest,
+    handler: web::Data<JobHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.retry(path_id).await {
+        Ok(job) => ApiResponse::new(job)
+            .message("Job queued for retry")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn job_handler_cancel(
+    req: HttpRequest,
+    handler: web::Data<JobHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.cancel(path_id).await {
+        Ok(job) => ApiResponse::new(job)
+            .message("Job cancelled")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+use crate::internal::app::usecases::health_usecase::HealthUseCase;
+use crate::pkg::metrics::Metrics;
+use actix_web::{web, HttpResponse};
+
+#[derive(Clone)]
+pub struct MetricsHandlerImpl {
+    service: Arc<dyn HealthUseCase>,
+    metrics: Arc<Metrics>,
+}
+
+impl MetricsHandlerImpl {
+    pub fn new(service: Arc<dyn HealthUseCase>, metrics: Arc<Metrics>) -> Self {
+        Self { service, metrics }
+    }
+}
+
+// Prometheus text format rather than the JSON envelope. Pool gauges are
+// sampled here; when the pool can't be reached they keep their last value.
+pub async fn metrics_handler_export(handler: web::Data<MetricsHandlerImpl>) -> HttpResponse {
+    if let Ok(stats) = handler.service.pool_stats().await {
+        handler.metrics.db_pool_size.set(stats.size as i64);
+        handler.metrics.db_pool_idle.set(stats.idle as i64);
+        handler.metrics.db_pool_acquire_wait.set(stats.acquire_wait.as_secs_f64());
+    }
+
+    HttpResponse::Ok()
+        .content_type(prometheus::TEXT_FORMAT)
+        .body(handler.metrics.render())
+}
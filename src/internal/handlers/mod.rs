@@ -5,4 +5,10 @@ pub mod province_handler;
 pub mod city_handler;
 pub mod school_handler;
 pub mod user_handler;
-pub mod auth_handler;
\ No newline at end of file
+pub mod auth_handler;
+pub mod health_handler;
+pub mod metrics_handler;
+pub mod job_handler;
+pub mod schedule_handler;
+pub mod webhook_handler;
+pub mod audit_log_handler;
@@ -1,44 +1,50 @@
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::Response;
-use crate::internal::app::usecases::province_usecase::{ProvinceUseCase, ProvinceUseCaseImpl};
-use actix_web::{web, HttpResponse, Responder};
-use serde_json::json;
+use std::sync::Arc;
+use crate::helpers::custom_response::ApiResponse;
+use crate::internal::app::usecases::province_usecase::ProvinceUseCase;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 
 #[derive(Clone)]
 pub struct ProvinceHandlerImpl {
-    service: ProvinceUseCaseImpl,
+    service: Arc<dyn ProvinceUseCase>,
 }
 
 impl ProvinceHandlerImpl {
-    pub fn new(service: ProvinceUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn ProvinceUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn province_handler_list(handler: web::Data<ProvinceHandlerImpl>) -> impl Responder {
+pub async fn province_handler_list(req: HttpRequest, handler: web::Data<ProvinceHandlerImpl>) -> HttpResponse {
     match handler.service.list().await {
-        Ok(provinces) => {
-            let response = Response {
-                data: provinces,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully fetched provinces",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+        Ok(provinces) => ApiResponse::new(provinces)
+            .message("Successfully fetched provinces")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn province_handler_get(
+    req: HttpRequest,
+    handler: web::Data<ProvinceHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match handler.service.get(path.into_inner()).await {
+        Ok(province) => ApiResponse::new(province)
+            .message("Successfully fetched province")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn province_handler_create(
+    req: HttpRequest,
     handler: web::Data<ProvinceHandlerImpl>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create().await {
-        Ok(_) => HttpResponse::Created().json(json!({
-            "message": "Province sync successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response()
+        Ok(_) => ApiResponse::empty()
+            .message("Province sync successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req)
     }
-}
\ No newline at end of file
+}
@@ -1,84 +1,163 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde_json::json;
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::{PaginatedResponse, PaginationParams};
-use crate::internal::app::usecases::role_usecase::{RoleUseCase, RoleUseCaseImpl};
-use crate::pkg::dto::role_dto::{CreateRoleDto, UpdateRoleDto};
+use serde_json::Value;
+use crate::internal::entities::role::Role;
+use crate::helpers::custom_response::{ApiResponse, FieldsParams, ListParams, PaginationMeta, PaginationParams, TrashedParams};
+use crate::helpers::precondition::IfMatch;
+use crate::internal::app::usecases::role_usecase::RoleUseCase;
+use crate::pkg::dto::role_dto::{CreateRoleDto, UpdateRoleDto, RoleResponse};
 
 #[derive(Clone)]
 pub struct RoleHandlerImpl {
-    service: RoleUseCaseImpl,
+    service: Arc<dyn RoleUseCase>,
 }
 
 impl RoleHandlerImpl {
-    pub fn new(service: RoleUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn RoleUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn role_handler_list(handler: web::Data<RoleHandlerImpl>, params: Query<PaginationParams>) -> impl Responder {
-    let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
+pub async fn role_handler_list(req: HttpRequest, handler: web::Data<RoleHandlerImpl>, params: Query<PaginationParams>, trashed: Query<TrashedParams>, list: Query<ListParams>, fields: Query<FieldsParams>) -> HttpResponse {
+    let query = match list.query::<Role>(trashed.trashed()) {
+        Ok(query) => query,
+        Err(err) => return err.respond(&req),
+    };
+    let page = match params.request(&query) {
+        Ok(page) => page,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<RoleResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.list(page, page_size).await {
-        Ok((roles, total_data)) => {
-            let total_pages = (total_data as f32 / page_size as f32).ceil() as u32;
-            let response = PaginatedResponse {
-                data: roles,
-                page_size,
-                page,
-                total_pages,
-                total_data: total_data as u32,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully fetched roles",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+    match handler.service.list(query, page.clone()).await.map(|roles| roles.map(RoleResponse::from)) {
+        Ok(roles) => ApiResponse::new(fields.select(&roles.rows))
+            .pagination(PaginationMeta::of(&page, &roles))
+            .message("Successfully fetched roles")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn role_handler_get(
+    req: HttpRequest,
+    handler: web::Data<RoleHandlerImpl>,
+    path: web::Path<String>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let fields = match fields.fields::<RoleResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.get(path_id).await.map(RoleResponse::from) {
+        Ok(role) => ApiResponse::new(fields.select(&role))
+            .etag(role.version)
+            .message("Successfully fetched role")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn role_handler_create(
+    req: HttpRequest,
     handler: web::Data<RoleHandlerImpl>,
     input: web::Json<CreateRoleDto>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create(input).await {
-        Ok(_) => HttpResponse::Created().json(json!({
-            "message": "Role created successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response()
+        Ok(_) => ApiResponse::empty()
+            .message("Role created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req)
     }
 }
 
 pub async fn role_handler_update(
+    req: HttpRequest,
     handler: web::Data<RoleHandlerImpl>,
     path: web::Path<String>,
     input: web::Json<UpdateRoleDto>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.update(path_id, input).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Role updated successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.update(path_id, if_match, input).await.map(RoleResponse::from) {
+        Ok(role) => ApiResponse::new(&role)
+            .etag(role.version)
+            .message("Role updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn role_handler_patch(
+    req: HttpRequest,
+    handler: web::Data<RoleHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<Value>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.patch(path_id, if_match, input.into_inner()).await.map(RoleResponse::from) {
+        Ok(role) => ApiResponse::new(&role)
+            .etag(role.version)
+            .message("Role updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn role_handler_delete(
+    req: HttpRequest,
     handler: web::Data<RoleHandlerImpl>,
     path: web::Path<String>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.delete(path_id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Role deleted successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.delete(path_id, if_match).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Role deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
     }
-}
\ No newline at end of file
+}
+
+pub async fn role_handler_restore(
+    req: HttpRequest,
+    handler: web::Data<RoleHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.restore(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Role restored successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
+
+pub async fn role_handler_purge(
+    req: HttpRequest,
+    handler: web::Data<RoleHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.purge(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Role purged successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
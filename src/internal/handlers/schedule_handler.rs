@@ -0,0 +1,25 @@
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+use crate::helpers::custom_response::ApiResponse;
+use crate::internal::app::usecases::schedule_usecase::ScheduleUseCase;
+
+#[derive(Clone)]
+pub struct ScheduleHandlerImpl {
+    service: Arc<dyn ScheduleUseCase>,
+}
+
+impl ScheduleHandlerImpl {
+    pub fn new(service: Arc<dyn ScheduleUseCase>) -> Self {
+        Self { service }
+    }
+}
+
+pub async fn schedule_handler_list(req: HttpRequest, handler: web::Data<ScheduleHandlerImpl>) -> HttpResponse {
+    match handler.service.list().await {
+        Ok(schedules) => ApiResponse::new(schedules)
+            .message("Successfully fetched schedules")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
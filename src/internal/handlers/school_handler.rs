@@ -1,96 +1,191 @@
+use std::sync::Arc;
 use actix_multipart::form::MultipartForm;
-use crate::internal::app::usecases::school_usecase::{SchoolUseCase, SchoolUseCaseImpl};
-use crate::pkg::dto::school_dto::{CreateSchoolDto, UpdateSchoolDto};
-use actix_web::{web, HttpResponse, Responder};
+use crate::internal::app::usecases::school_usecase::SchoolUseCase;
+use crate::pkg::dto::school_dto::{CreateSchoolDto, UpdateSchoolDto, SchoolResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde_json::json;
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::{PaginatedResponse, PaginationParams};
+use serde_json::Value;
+use crate::internal::entities::school::School;
+use crate::helpers::custom_response::{ApiResponse, FieldsParams, IncludeParams, ListParams, PaginationMeta, PaginationParams, TrashedParams};
+use crate::helpers::precondition::IfMatch;
 
 #[derive(Clone)]
 pub struct SchoolHandlerImpl {
-    service: SchoolUseCaseImpl,
+    service: Arc<dyn SchoolUseCase>,
 }
 
 impl SchoolHandlerImpl {
-    pub fn new(service: SchoolUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn SchoolUseCase>) -> Self {
         Self { service }
     }
 }
 
 // Handler for listing schools
 pub async fn school_handler_list(
+    req: HttpRequest,
     handler: web::Data<SchoolHandlerImpl>,
     params: Query<PaginationParams>,
-) -> impl Responder {
-    let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
+    trashed: Query<TrashedParams>,
+    list: Query<ListParams>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let query = match list.query::<School>(trashed.trashed()) {
+        Ok(query) => query,
+        Err(err) => return err.respond(&req),
+    };
+    let page = match params.request(&query) {
+        Ok(page) => page,
+        Err(err) => return err.respond(&req),
+    };
+    let includes = match include.includes::<School>() {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<SchoolResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.list(page, page_size).await {
-        Ok((schools, total_data)) => {
-            let response = PaginatedResponse {
-                data: schools,
-                page_size,
-                page,
-                total_pages: (total_data as f32 / page_size as f32).ceil() as u32,
-                total_data: total_data as u32,
-            };
+    match handler.service.list(query, page.clone(), includes).await.map(|schools| schools.map(|school| school.map(SchoolResponse::from))) {
+        Ok(schools) => ApiResponse::new(fields.select(&schools.rows))
+            .pagination(PaginationMeta::of(&page, &schools))
+            .message("Successfully fetched schools")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for fetching a single school
+pub async fn school_handler_get(
+    req: HttpRequest,
+    handler: web::Data<SchoolHandlerImpl>,
+    path: web::Path<String>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let school_id = path.into_inner();
+    let includes = match include.includes::<School>() {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<SchoolResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-            HttpResponse::Ok().json(json!({
-                "data": response,
-                "message": "Successfully fetched schools",
-                "code": 200
-            }))
-        }
-        Err(err) => err.error_response(),
+    match handler.service.get(school_id, includes).await.map(|school| school.map(SchoolResponse::from)) {
+        Ok(school) => ApiResponse::new(fields.select(&school))
+            .etag(school.row.version)
+            .message("Successfully fetched school")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for creating a school
 pub async fn school_handler_create(
+    req: HttpRequest,
     handler: web::Data<SchoolHandlerImpl>,
     input: MultipartForm<CreateSchoolDto>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create(input).await {
-        Ok(school) => HttpResponse::Created().json(json!({
-            "data": school,
-            "message": "School created successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response(),
+        Ok(school) => ApiResponse::new(school)
+            .message("School created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for updating a school
 pub async fn school_handler_update(
+    req: HttpRequest,
     handler: web::Data<SchoolHandlerImpl>,
     path: web::Path<String>,
     input: web::Json<UpdateSchoolDto>,
-) -> impl Responder {
+) -> HttpResponse {
     let school_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.update(school_id, input).await {
-        Ok(school) => HttpResponse::Ok().json(json!({
-            "data": school,
-            "message": "School updated successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response(),
+    match handler.service.update(school_id, if_match, input).await.map(SchoolResponse::from) {
+        Ok(school) => ApiResponse::new(&school)
+            .etag(school.version)
+            .message("School updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for partially updating a school with a JSON Merge Patch
+pub async fn school_handler_patch(
+    req: HttpRequest,
+    handler: web::Data<SchoolHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<Value>,
+) -> HttpResponse {
+    let school_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+
+    match handler.service.patch(school_id, if_match, input.into_inner()).await.map(SchoolResponse::from) {
+        Ok(school) => ApiResponse::new(&school)
+            .etag(school.version)
+            .message("School updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for deleting a school
 pub async fn school_handler_delete(
+    req: HttpRequest,
     handler: web::Data<SchoolHandlerImpl>,
     path: web::Path<String>,
-) -> impl Responder {
+) -> HttpResponse {
     let school_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+
+    match handler.service.delete(school_id, if_match).await {
+        Ok(_) => ApiResponse::empty()
+            .message("School deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
 
-    match handler.service.delete(school_id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "School deleted successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response(),
+pub async fn school_handler_restore(
+    req: HttpRequest,
+    handler: web::Data<SchoolHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.restore(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("School restored successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
+
+pub async fn school_handler_purge(
+    req: HttpRequest,
+    handler: web::Data<SchoolHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.purge(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("School purged successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
     }
 }
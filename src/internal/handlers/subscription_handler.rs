@@ -1,84 +1,163 @@
-use crate::internal::app::usecases::subscription_usecase::{SubscriptionUseCase, SubscriptionUseCaseImpl};
-use crate::pkg::dto::subscription_dto::{CreateSubscriptionDto, UpdateSubscriptionDto};
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde_json::json;
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::{PaginatedResponse, PaginationParams};
+use serde_json::Value;
+use crate::internal::entities::subscription::Subscription;
+use crate::helpers::custom_response::{ApiResponse, FieldsParams, ListParams, PaginationMeta, PaginationParams, TrashedParams};
+use crate::helpers::precondition::IfMatch;
+use crate::internal::app::usecases::subscription_usecase::SubscriptionUseCase;
+use crate::pkg::dto::subscription_dto::{CreateSubscriptionDto, UpdateSubscriptionDto, SubscriptionResponse};
 
 #[derive(Clone)]
 pub struct SubscriptionHandlerImpl {
-    service: SubscriptionUseCaseImpl,
+    service: Arc<dyn SubscriptionUseCase>,
 }
 
 impl SubscriptionHandlerImpl {
-    pub fn new(service: SubscriptionUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn SubscriptionUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn subscription_handler_list(handler: web::Data<SubscriptionHandlerImpl>, params: Query<PaginationParams>) -> impl Responder {
-    let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
+pub async fn subscription_handler_list(req: HttpRequest, handler: web::Data<SubscriptionHandlerImpl>, params: Query<PaginationParams>, trashed: Query<TrashedParams>, list: Query<ListParams>, fields: Query<FieldsParams>) -> HttpResponse {
+    let query = match list.query::<Subscription>(trashed.trashed()) {
+        Ok(query) => query,
+        Err(err) => return err.respond(&req),
+    };
+    let page = match params.request(&query) {
+        Ok(page) => page,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<SubscriptionResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.list(page, page_size).await {
-        Ok((subscriptions, total_data)) => {
-            let total_pages = (total_data as f32 / page_size as f32).ceil() as u32;
-            let response = PaginatedResponse {
-                data: subscriptions,
-                page_size,
-                page,
-                total_pages,
-                total_data: total_data as u32,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully fetched subscriptions",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+    match handler.service.list(query, page.clone()).await.map(|subscriptions| subscriptions.map(SubscriptionResponse::from)) {
+        Ok(subscriptions) => ApiResponse::new(fields.select(&subscriptions.rows))
+            .pagination(PaginationMeta::of(&page, &subscriptions))
+            .message("Successfully fetched subscriptions")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn subscription_handler_get(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionHandlerImpl>,
+    path: web::Path<String>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let fields = match fields.fields::<SubscriptionResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.get(path_id).await.map(SubscriptionResponse::from) {
+        Ok(subscription) => ApiResponse::new(fields.select(&subscription))
+            .etag(subscription.version)
+            .message("Successfully fetched subscription")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn subscription_handler_create(
+    req: HttpRequest,
     handler: web::Data<SubscriptionHandlerImpl>,
     input: web::Json<CreateSubscriptionDto>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create(input).await {
-        Ok(_) => HttpResponse::Created().json(json!({
-            "message": "Subscription created successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response()
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req)
     }
 }
 
 pub async fn subscription_handler_update(
+    req: HttpRequest,
     handler: web::Data<SubscriptionHandlerImpl>,
     path: web::Path<String>,
     input: web::Json<UpdateSubscriptionDto>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.update(path_id, input).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Subscription updated successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.update(path_id, if_match, input).await.map(SubscriptionResponse::from) {
+        Ok(subscription) => ApiResponse::new(&subscription)
+            .etag(subscription.version)
+            .message("Subscription updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn subscription_handler_patch(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<Value>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.patch(path_id, if_match, input.into_inner()).await.map(SubscriptionResponse::from) {
+        Ok(subscription) => ApiResponse::new(&subscription)
+            .etag(subscription.version)
+            .message("Subscription updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn subscription_handler_delete(
+    req: HttpRequest,
     handler: web::Data<SubscriptionHandlerImpl>,
     path: web::Path<String>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.delete(path_id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Subscription deleted successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.delete(path_id, if_match).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
     }
-}
\ No newline at end of file
+}
+
+pub async fn subscription_handler_restore(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.restore(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription restored successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
+
+pub async fn subscription_handler_purge(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.purge(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription purged successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
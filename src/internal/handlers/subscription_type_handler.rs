@@ -1,84 +1,206 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde_json::json;
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::{PaginatedResponse, PaginationParams};
-use crate::internal::app::usecases::subscription_type_usecase::{SubscriptionTypeUseCase, SubscriptionTypeUseCaseImpl};
-use crate::pkg::dto::subscription_type_dto::{CreateSubscriptionTypeDto, UpdateSubscriptionTypeDto};
+use serde_json::Value;
+use crate::internal::entities::subscription_type::SubscriptionType;
+use crate::helpers::custom_response::{ApiResponse, FieldsParams, IncludeParams, ListParams, PaginationMeta, PaginationParams, TrashedParams};
+use crate::helpers::custom_error::ErrorResponse;
+use crate::helpers::include::Includes;
+use crate::helpers::precondition::IfMatch;
+use crate::internal::app::usecases::subscription_type_usecase::SubscriptionTypeUseCase;
+use crate::pkg::dto::subscription_type_dto::{CreateSubscriptionTypeDto, UpdateSubscriptionTypeDto, SubscriptionTypeResponse};
 
 #[derive(Clone)]
 pub struct SubscriptionTypeHandlerImpl {
-    service: SubscriptionTypeUseCaseImpl,
+    service: Arc<dyn SubscriptionTypeUseCase>,
 }
 
 impl SubscriptionTypeHandlerImpl {
-    pub fn new(service: SubscriptionTypeUseCaseImpl) -> SubscriptionTypeHandlerImpl {
+    pub fn new(service: Arc<dyn SubscriptionTypeUseCase>) -> Self {
         Self { service }
     }
 }
 
-pub async fn subscription_type_handler_list(handler: web::Data<SubscriptionTypeHandlerImpl>, params: Query<PaginationParams>) -> impl Responder {
-    let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
-
-    match handler.service.list(page, page_size).await {
-        Ok((subscriptions, total_data)) => {
-            let total_pages = (total_data as f32 / page_size as f32).ceil() as u32;
-            let response = PaginatedResponse {
-                data: subscriptions,
-                page_size,
-                page,
-                total_pages,
-                total_data: total_data as u32,
-            };
-            HttpResponse::Ok().json(json!({
-            "data": response,
-            "message": "Successfully fetched subscriptions",
-            "code": 200
-        }))
-        }
-        Err(err) => err.error_response(),
+// v1 has always nested each type's subscriptions, so it keeps doing so unless
+// `include` says otherwise; from v2 they are only sent when asked for.
+const V1_DEFAULT_INCLUDE: &str = "subscriptions";
+
+pub async fn subscription_type_handler_list(req: HttpRequest, handler: web::Data<SubscriptionTypeHandlerImpl>, params: Query<PaginationParams>, trashed: Query<TrashedParams>, list: Query<ListParams>, include: Query<IncludeParams>, fields: Query<FieldsParams>) -> HttpResponse {
+    list_subscription_types(req, handler, params, trashed, list, include.includes_or::<SubscriptionType>(V1_DEFAULT_INCLUDE), fields).await
+}
+
+pub async fn subscription_type_handler_list_v2(req: HttpRequest, handler: web::Data<SubscriptionTypeHandlerImpl>, params: Query<PaginationParams>, trashed: Query<TrashedParams>, list: Query<ListParams>, include: Query<IncludeParams>, fields: Query<FieldsParams>) -> HttpResponse {
+    list_subscription_types(req, handler, params, trashed, list, include.includes::<SubscriptionType>(), fields).await
+}
+
+async fn list_subscription_types(req: HttpRequest, handler: web::Data<SubscriptionTypeHandlerImpl>, params: Query<PaginationParams>, trashed: Query<TrashedParams>, list: Query<ListParams>, includes: Result<Includes, ErrorResponse>, fields: Query<FieldsParams>) -> HttpResponse {
+    let query = match list.query::<SubscriptionType>(trashed.trashed()) {
+        Ok(query) => query,
+        Err(err) => return err.respond(&req),
+    };
+    let page = match params.request(&query) {
+        Ok(page) => page,
+        Err(err) => return err.respond(&req),
+    };
+    let includes = match includes {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<SubscriptionTypeResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
+
+    match handler.service.list(query, page.clone(), includes).await.map(|subscription_types| subscription_types.map(|subscription_type| subscription_type.map(SubscriptionTypeResponse::from))) {
+        Ok(subscription_types) => ApiResponse::new(fields.select(&subscription_types.rows))
+            .pagination(PaginationMeta::of(&page, &subscription_types))
+            .message("Successfully fetched subscription types")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn subscription_type_handler_get(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    get_subscription_type(req, handler, path, include.includes_or::<SubscriptionType>(V1_DEFAULT_INCLUDE), fields).await
+}
+
+pub async fn subscription_type_handler_get_v2(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    get_subscription_type(req, handler, path, include.includes::<SubscriptionType>(), fields).await
+}
+
+async fn get_subscription_type(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+    includes: Result<Includes, ErrorResponse>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let includes = match includes {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<SubscriptionTypeResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.get(path_id, includes).await.map(|subscription_type| subscription_type.map(SubscriptionTypeResponse::from)) {
+        Ok(subscription_type) => ApiResponse::new(fields.select(&subscription_type))
+            .etag(subscription_type.row.version)
+            .message("Successfully fetched subscription type")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn subscription_type_handler_create(
+    req: HttpRequest,
     handler: web::Data<SubscriptionTypeHandlerImpl>,
     input: web::Json<CreateSubscriptionTypeDto>,
-) -> impl Responder {
+) -> HttpResponse {
     match handler.service.create(input).await {
-        Ok(_) => HttpResponse::Created().json(json!({
-            "message": "Subscription created successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response()
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription type created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req)
     }
 }
 
 pub async fn subscription_type_handler_update(
+    req: HttpRequest,
     handler: web::Data<SubscriptionTypeHandlerImpl>,
     path: web::Path<String>,
     input: web::Json<UpdateSubscriptionTypeDto>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.update(path_id, input).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Subscription updated successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.update(path_id, if_match, input).await.map(SubscriptionTypeResponse::from) {
+        Ok(subscription_type) => ApiResponse::new(&subscription_type)
+            .etag(subscription_type.version)
+            .message("Subscription type updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+pub async fn subscription_type_handler_patch(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<Value>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.patch(path_id, if_match, input.into_inner()).await.map(SubscriptionTypeResponse::from) {
+        Ok(subscription_type) => ApiResponse::new(&subscription_type)
+            .etag(subscription_type.version)
+            .message("Subscription type updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 pub async fn subscription_type_handler_delete(
+    req: HttpRequest,
     handler: web::Data<SubscriptionTypeHandlerImpl>,
     path: web::Path<String>,
-) -> impl Responder {
+) -> HttpResponse {
     let path_id = path.into_inner();
-    match handler.service.delete(path_id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "Subscription deleted successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response()
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+    match handler.service.delete(path_id, if_match).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription type deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
     }
-}
\ No newline at end of file
+}
+
+pub async fn subscription_type_handler_restore(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.restore(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription type restored successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
+
+pub async fn subscription_type_handler_purge(
+    req: HttpRequest,
+    handler: web::Data<SubscriptionTypeHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.purge(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Subscription type purged successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
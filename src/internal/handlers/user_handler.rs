@@ -1,95 +1,190 @@
-use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde_json::json;
-use crate::helpers::custom_error::ResponseError;
-use crate::helpers::custom_response::{PaginatedResponse, PaginationParams};
-use crate::internal::app::usecases::user_usecase::{UserUseCase, UserUseCaseImpl};
-use crate::pkg::dto::user_dto::{CreateUserDto, UpdateUserDto};
+use serde_json::Value;
+use crate::internal::entities::user::User;
+use crate::helpers::custom_response::{ApiResponse, FieldsParams, IncludeParams, ListParams, PaginationMeta, PaginationParams, TrashedParams};
+use crate::helpers::precondition::IfMatch;
+use crate::internal::app::usecases::user_usecase::UserUseCase;
+use crate::pkg::dto::user_dto::{CreateUserDto, UpdateUserDto, UserResponse};
 
 #[derive(Clone)]
 pub struct UserHandlerImpl {
-    service: UserUseCaseImpl,
+    service: Arc<dyn UserUseCase>,
 }
 
 impl UserHandlerImpl {
-    pub fn new(service: UserUseCaseImpl) -> Self {
+    pub fn new(service: Arc<dyn UserUseCase>) -> Self {
         Self { service }
     }
 }
 
 // Handler for listing users
 pub async fn user_handler_list(
+    req: HttpRequest,
     handler: web::Data<UserHandlerImpl>,
     params: Query<PaginationParams>,
-) -> impl Responder {
-    let page = params.page.unwrap_or(1);
-    let page_size = params.page_size.unwrap_or(10);
+    trashed: Query<TrashedParams>,
+    list: Query<ListParams>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let query = match list.query::<User>(trashed.trashed()) {
+        Ok(query) => query,
+        Err(err) => return err.respond(&req),
+    };
+    let page = match params.request(&query) {
+        Ok(page) => page,
+        Err(err) => return err.respond(&req),
+    };
+    let includes = match include.includes::<User>() {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<UserResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.list(page, page_size).await {
-        Ok((users, total_data)) => {
-            let response = PaginatedResponse {
-                data: users,
-                page_size,
-                page,
-                total_pages: (total_data as f32 / page_size as f32).ceil() as u32,
-                total_data: total_data as u32,
-            };
+    match handler.service.list(query, page.clone(), includes).await.map(|users| users.map(|user| user.map(UserResponse::from))) {
+        Ok(users) => ApiResponse::new(fields.select(&users.rows))
+            .pagination(PaginationMeta::of(&page, &users))
+            .message("Successfully fetched users")
+            .respond_negotiated(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for fetching a single user
+pub async fn user_handler_get(
+    req: HttpRequest,
+    handler: web::Data<UserHandlerImpl>,
+    path: web::Path<String>,
+    include: Query<IncludeParams>,
+    fields: Query<FieldsParams>,
+) -> HttpResponse {
+    let user_id = path.into_inner();
+    let includes = match include.includes::<User>() {
+        Ok(includes) => includes,
+        Err(err) => return err.respond(&req),
+    };
+    let fields = match fields.fields::<UserResponse>() {
+        Ok(fields) => fields,
+        Err(err) => return err.respond(&req),
+    };
 
-            HttpResponse::Ok().json(json!({
-                "data": response,
-                "message": "Successfully fetched users",
-                "code": 200
-            }))
-        }
-        Err(err) => err.error_response(),
+    match handler.service.get(user_id, includes).await.map(|user| user.map(UserResponse::from)) {
+        Ok(user) => ApiResponse::new(fields.select(&user))
+            .etag(user.row.version)
+            .message("Successfully fetched user")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for creating a user
 pub async fn user_handler_create(
+    req: HttpRequest,
     handler: web::Data<UserHandlerImpl>,
     input: web::Json<CreateUserDto>,
-) -> impl Responder {
-    match handler.service.create(input).await {
-        Ok(user) => HttpResponse::Created().json(json!({
-            "data": user,
-            "message": "User created successfully",
-            "code": 201
-        })),
-        Err(err) => err.error_response(),
+) -> HttpResponse {
+    match handler.service.create(input).await.map(UserResponse::from) {
+        Ok(user) => ApiResponse::new(user)
+            .message("User created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for updating a user
 pub async fn user_handler_update(
+    req: HttpRequest,
     handler: web::Data<UserHandlerImpl>,
     path: web::Path<String>,
     input: web::Json<UpdateUserDto>,
-) -> impl Responder {
+) -> HttpResponse {
     let user_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
 
-    match handler.service.update(user_id, input).await {
-        Ok(user) => HttpResponse::Ok().json(json!({
-            "data": user,
-            "message": "User updated successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response(),
+    match handler.service.update(user_id, if_match, input).await.map(UserResponse::from) {
+        Ok(user) => ApiResponse::new(&user)
+            .etag(user.version)
+            .message("User updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for partially updating a user with a JSON Merge Patch
+pub async fn user_handler_patch(
+    req: HttpRequest,
+    handler: web::Data<UserHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<Value>,
+) -> HttpResponse {
+    let user_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+
+    match handler.service.patch(user_id, if_match, input.into_inner()).await.map(UserResponse::from) {
+        Ok(user) => ApiResponse::new(&user)
+            .etag(user.version)
+            .message("User updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
     }
 }
 
 // Handler for deleting a user
 pub async fn user_handler_delete(
+    req: HttpRequest,
     handler: web::Data<UserHandlerImpl>,
     path: web::Path<String>,
-) -> impl Responder {
+) -> HttpResponse {
     let user_id = path.into_inner();
+    let if_match = match IfMatch::from_request(&req) {
+        Ok(if_match) => if_match,
+        Err(err) => return err.respond(&req),
+    };
+
+    match handler.service.delete(user_id, if_match).await {
+        Ok(_) => ApiResponse::empty()
+            .message("User deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
 
-    match handler.service.delete(user_id).await {
-        Ok(_) => HttpResponse::Ok().json(json!({
-            "message": "User deleted successfully",
-            "code": 200
-        })),
-        Err(err) => err.error_response(),
+pub async fn user_handler_restore(
+    req: HttpRequest,
+    handler: web::Data<UserHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.restore(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("User restored successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
+    }
+}
+
+pub async fn user_handler_purge(
+    req: HttpRequest,
+    handler: web::Data<UserHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    let path_id = path.into_inner();
+    match handler.service.purge(path_id).await {
+        Ok(_) => ApiResponse::empty()
+            .message("User purged successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req)
     }
 }
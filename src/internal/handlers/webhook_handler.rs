@@ -0,0 +1,127 @@
+use std::sync::Arc;
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::StatusCode;
+use actix_web::web::Query;
+use crate::helpers::custom_response::{ApiResponse, PaginationMeta, PaginationParams};
+use crate::internal::app::usecases::webhook_usecase::WebhookUseCase;
+use crate::pkg::dto::webhook_dto::{CreateWebhookDto, CreatedWebhookResponse, UpdateWebhookDto, WebhookResponse};
+
+#[derive(Clone)]
+pub struct WebhookHandlerImpl {
+    service: Arc<dyn WebhookUseCase>,
+}
+
+impl WebhookHandlerImpl {
+    pub fn new(service: Arc<dyn WebhookUseCase>) -> Self {
+        Self { service }
+    }
+}
+
+// Handler for listing webhooks
+pub async fn webhook_handler_list(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    params: Query<PaginationParams>,
+) -> HttpResponse {
+    let page = params.page();
+    let page_size = params.page_size();
+
+    match handler.service.list(page, page_size).await {
+        Ok((webhooks, total_data)) => ApiResponse::new(webhooks.into_iter().map(WebhookResponse::from).collect::<Vec<_>>())
+            .pagination(PaginationMeta::new(page, page_size, total_data))
+            .message("Successfully fetched webhooks")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for fetching a single webhook
+pub async fn webhook_handler_get(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match handler.service.get(path.into_inner()).await.map(WebhookResponse::from) {
+        Ok(webhook) => ApiResponse::new(webhook)
+            .message("Successfully fetched webhook")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for creating a webhook; the only response that includes its secret
+pub async fn webhook_handler_create(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    input: web::Json<CreateWebhookDto>,
+) -> HttpResponse {
+    match handler.service.create(input).await.map(CreatedWebhookResponse::from) {
+        Ok(webhook) => ApiResponse::new(webhook)
+            .message("Webhook created successfully")
+            .respond(&req, StatusCode::CREATED),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for updating a webhook
+pub async fn webhook_handler_update(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    path: web::Path<String>,
+    input: web::Json<UpdateWebhookDto>,
+) -> HttpResponse {
+    match handler.service.update(path.into_inner(), input).await.map(WebhookResponse::from) {
+        Ok(webhook) => ApiResponse::new(webhook)
+            .message("Webhook updated successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for deleting a webhook
+pub async fn webhook_handler_delete(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match handler.service.delete(path.into_inner()).await {
+        Ok(_) => ApiResponse::empty()
+            .message("Webhook deleted successfully")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for listing a webhook's deliveries, newest first
+pub async fn webhook_handler_deliveries(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    path: web::Path<String>,
+    params: Query<PaginationParams>,
+) -> HttpResponse {
+    let page = params.page();
+    let page_size = params.page_size();
+
+    match handler.service.deliveries(path.into_inner(), page, page_size).await {
+        Ok((deliveries, total_data)) => ApiResponse::new(deliveries)
+            .pagination(PaginationMeta::new(page, page_size, total_data))
+            .message("Successfully fetched webhook deliveries")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
+
+// Handler for sending a test event; succeeds whatever the endpoint answers,
+// with the outcome in the returned delivery
+pub async fn webhook_handler_test(
+    req: HttpRequest,
+    handler: web::Data<WebhookHandlerImpl>,
+    path: web::Path<String>,
+) -> HttpResponse {
+    match handler.service.test(path.into_inner()).await {
+        Ok(delivery) => ApiResponse::new(delivery)
+            .message("Test event sent")
+            .respond(&req, StatusCode::OK),
+        Err(err) => err.respond(&req),
+    }
+}
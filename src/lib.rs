@@ -0,0 +1,6 @@
+pub mod config;
+pub mod database;
+pub mod internal;
+pub mod cmd;
+pub mod pkg;
+pub mod helpers;
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::internal::entities::audit_log::AuditLogFilter;
+
+#[derive(Debug, Deserialize)]
+pub struct ListAuditLogsQuery {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    // A table name, such as `schools`.
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub actor: Option<String>,
+    // RFC 3339; `from` is inclusive and `to` exclusive.
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl ListAuditLogsQuery {
+    pub fn filter(&self) -> AuditLogFilter {
+        AuditLogFilter {
+            entity_type: self.entity_type.clone(),
+            entity_id: self.entity_id,
+            actor: self.actor.clone(),
+            from: self.from,
+            to: self.to,
+        }
+    }
+}
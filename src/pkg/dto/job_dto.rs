@@ -0,0 +1,9 @@
+use serde::Deserialize;
+use crate::internal::entities::job::JobStatus;
+
+#[derive(Debug, Deserialize)]
+pub struct ListJobsQuery {
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub status: Option<JobStatus>,
+}
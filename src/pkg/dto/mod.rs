@@ -3,4 +3,6 @@ pub mod subscription_type_dto;
 pub mod role_dto;
 pub mod school_dto;
 pub mod user_dto;
-pub mod auth_dto;
\ No newline at end of file
+pub mod auth_dto;pub mod job_dto;
+pub mod webhook_dto;
+pub mod audit_log_dto;
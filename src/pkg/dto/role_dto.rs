@@ -1,4 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use crate::internal::entities::role::Role;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::helpers::fields::View;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateRoleDto {
@@ -9,3 +13,44 @@ pub struct CreateRoleDto {
 pub struct UpdateRoleDto {
     pub name: Option<String>,
 }
+
+// The editable fields of a role, which PATCH merges its body over.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RolePatch {
+    pub name: String,
+}
+
+impl From<&Role> for RolePatch {
+    fn from(role: &Role) -> Self {
+        Self { name: role.name.clone() }
+    }
+}
+
+// What clients see of a role.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl View for RoleResponse {
+    const FIELDS: &'static [&'static str] = &["id", "name", "created_at", "updated_at", "deleted_at", "version"];
+}
+
+impl From<Role> for RoleResponse {
+    fn from(role: Role) -> Self {
+        Self {
+            id: role.id,
+            name: role.name,
+            created_at: role.created_at,
+            updated_at: role.updated_at,
+            deleted_at: role.deleted_at,
+            version: role.version,
+        }
+    }
+}
@@ -3,6 +3,9 @@ use actix_multipart::form::tempfile::TempFile;
 use actix_multipart::form::text::Text;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::internal::entities::school::School;
+use chrono::{DateTime, Utc};
+use crate::helpers::fields::View;
 
 #[derive(Debug, MultipartForm)]
 pub struct CreateSchoolDto {
@@ -10,6 +13,7 @@ pub struct CreateSchoolDto {
     pub address: Option<Text<String>>,             // Address of the school
     // pub logo_path: Option<String>,   // Optional logo path for the school
     pub subscription_id: Option<Text<Uuid>>,       // Associated subscription ID
+    pub subscription_ends_at: Option<Text<DateTime<Utc>>>, // When the subscription runs out
     pub province_id: Option<Text<String>>,         // Province ID
     pub city_id: Option<Text<String>>,             // City ID
     pub logo: Option<TempFile>
@@ -21,6 +25,74 @@ pub struct UpdateSchoolDto {
     pub address: Option<String>,      // Optional updated address
     pub logo_path: Option<String>,    // Optional updated logo path
     pub subscription_id: Option<Uuid>, // Optional updated subscription ID
+    pub subscription_ends_at: Option<DateTime<Utc>>, // Optional updated subscription end
     pub province_id: Option<String>,  // Optional updated province ID
     pub city_id: Option<String>,      // Optional updated city ID
 }
+
+// The editable fields of a school, which PATCH merges its body over.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SchoolPatch {
+    pub name: String,
+    pub address: String,
+    pub logo_path: String,
+    pub subscription_id: Option<Uuid>,
+    pub subscription_ends_at: Option<DateTime<Utc>>,
+    pub province_id: Option<String>,
+    pub city_id: Option<String>,
+}
+
+impl From<&School> for SchoolPatch {
+    fn from(school: &School) -> Self {
+        Self {
+            name: school.name.clone(),
+            address: school.address.clone(),
+            logo_path: school.logo_path.clone(),
+            subscription_id: school.subscription_id,
+            subscription_ends_at: school.subscription_ends_at,
+            province_id: school.province_id.clone(),
+            city_id: school.city_id.clone(),
+        }
+    }
+}
+
+// What clients see of a school.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchoolResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub address: String,
+    pub logo_path: String,
+    pub subscription_id: Option<Uuid>,
+    pub subscription_ends_at: Option<DateTime<Utc>>,
+    pub province_id: Option<String>,
+    pub city_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl View for SchoolResponse {
+    const FIELDS: &'static [&'static str] = &["id", "name", "address", "logo_path", "subscription_id", "subscription_ends_at", "province_id", "city_id", "created_at", "updated_at", "deleted_at", "version"];
+}
+
+impl From<School> for SchoolResponse {
+    fn from(school: School) -> Self {
+        Self {
+            id: school.id,
+            name: school.name,
+            address: school.address,
+            logo_path: school.logo_path,
+            subscription_id: school.subscription_id,
+            subscription_ends_at: school.subscription_ends_at,
+            province_id: school.province_id,
+            city_id: school.city_id,
+            created_at: school.created_at,
+            updated_at: school.updated_at,
+            deleted_at: school.deleted_at,
+            version: school.version,
+        }
+    }
+}
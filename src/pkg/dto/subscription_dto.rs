@@ -1,5 +1,8 @@
-use serde::{Deserialize};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::internal::entities::subscription::Subscription;
+use chrono::{DateTime, Utc};
+use crate::helpers::fields::View;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSubscriptionDto {
@@ -15,3 +18,53 @@ pub struct UpdateSubscriptionDto {
     pub subscription_type_id: Option<Uuid>
 }
 
+// The editable fields of a subscription, which PATCH merges its body over.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionPatch {
+    pub name: String,
+    pub price: i32,
+    pub subscription_type_id: Uuid,
+}
+
+impl From<&Subscription> for SubscriptionPatch {
+    fn from(subscription: &Subscription) -> Self {
+        Self {
+            name: subscription.name.clone(),
+            price: subscription.price,
+            subscription_type_id: subscription.subscription_type_id,
+        }
+    }
+}
+
+// What clients see of a subscription.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub price: i32,
+    pub subscription_type_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl View for SubscriptionResponse {
+    const FIELDS: &'static [&'static str] = &["id", "name", "price", "subscription_type_id", "created_at", "updated_at", "deleted_at", "version"];
+}
+
+impl From<Subscription> for SubscriptionResponse {
+    fn from(subscription: Subscription) -> Self {
+        Self {
+            id: subscription.id,
+            name: subscription.name,
+            price: subscription.price,
+            subscription_type_id: subscription.subscription_type_id,
+            created_at: subscription.created_at,
+            updated_at: subscription.updated_at,
+            deleted_at: subscription.deleted_at,
+            version: subscription.version,
+        }
+    }
+}
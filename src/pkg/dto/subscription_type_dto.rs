@@ -1,4 +1,8 @@
-use serde::{Deserialize};
+use serde::{Deserialize, Serialize};
+use crate::internal::entities::subscription_type::SubscriptionType;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use crate::helpers::fields::View;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSubscriptionTypeDto {
@@ -10,3 +14,43 @@ pub struct UpdateSubscriptionTypeDto {
     pub name: Option<String>,
 }
 
+// The editable fields of a subscription type, which PATCH merges its body over.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SubscriptionTypePatch {
+    pub name: String,
+}
+
+impl From<&SubscriptionType> for SubscriptionTypePatch {
+    fn from(subscription_type: &SubscriptionType) -> Self {
+        Self { name: subscription_type.name.clone() }
+    }
+}
+
+// What clients see of a subscription type.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionTypeResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl View for SubscriptionTypeResponse {
+    const FIELDS: &'static [&'static str] = &["id", "name", "created_at", "updated_at", "deleted_at", "version"];
+}
+
+impl From<SubscriptionType> for SubscriptionTypeResponse {
+    fn from(subscription_type: SubscriptionType) -> Self {
+        Self {
+            id: subscription_type.id,
+            name: subscription_type.name,
+            created_at: subscription_type.created_at,
+            updated_at: subscription_type.updated_at,
+            deleted_at: subscription_type.deleted_at,
+            version: subscription_type.version,
+        }
+    }
+}
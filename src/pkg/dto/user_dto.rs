@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use crate::internal::entities::user::UserStatus;
+use crate::internal::entities::user::{User, UserStatus};
+use chrono::{DateTime, Utc};
+use crate::helpers::fields::View;
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CreateUserDto {
@@ -24,3 +26,74 @@ pub struct UpdateUserDto {
     pub role_id: Option<Uuid>,              // Optional updated role ID
     pub school_id: Option<Uuid>,            // Optional updated school ID
 }
+
+// The editable fields of a user, which PATCH merges its body over. The
+// password is write-only, so it is only present when being changed.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserPatch {
+    pub name: String,
+    pub email: String,
+    pub phone_number: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub title: String,
+    pub status: UserStatus,
+    pub role_id: Uuid,
+    pub school_id: Uuid,
+}
+
+impl From<&User> for UserPatch {
+    fn from(user: &User) -> Self {
+        Self {
+            name: user.name.clone(),
+            email: user.email.clone(),
+            phone_number: user.phone_number.clone(),
+            password: None,
+            title: user.title.clone(),
+            status: user.status.clone(),
+            role_id: user.role_id,
+            school_id: user.school_id,
+        }
+    }
+}
+
+// What clients see of a user; the password hash is never sent back.
+#[derive(Debug, Clone, Serialize)]
+pub struct UserResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub phone_number: String,
+    pub title: String,
+    pub status: UserStatus,
+    pub role_id: Uuid,
+    pub school_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+    pub version: i32,
+}
+
+impl View for UserResponse {
+    const FIELDS: &'static [&'static str] = &["id", "name", "email", "phone_number", "title", "status", "role_id", "school_id", "created_at", "updated_at", "deleted_at", "version"];
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            phone_number: user.phone_number,
+            title: user.title,
+            status: user.status,
+            role_id: user.role_id,
+            school_id: user.school_id,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            deleted_at: user.deleted_at,
+            version: user.version,
+        }
+    }
+}
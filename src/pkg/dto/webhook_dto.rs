@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::internal::entities::webhook::Webhook;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookDto {
+    pub url: String,
+    // Leave out for a platform-wide webhook.
+    pub school_id: Option<Uuid>,
+    // Leave out or empty for every event type.
+    pub event_types: Option<Vec<String>>,
+    // Generated when left out.
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookDto {
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+}
+
+// What clients see of a webhook; the signing secret is left out.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookResponse {
+    pub id: Uuid,
+    pub school_id: Option<Uuid>,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            id: webhook.id,
+            school_id: webhook.school_id,
+            url: webhook.url,
+            event_types: webhook.event_types.0,
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+// A newly created webhook, the only view with the secret its deliveries are
+// signed with.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookResponse,
+    pub secret: String,
+}
+
+impl From<Webhook> for CreatedWebhookResponse {
+    fn from(webhook: Webhook) -> Self {
+        let secret = webhook.secret.clone();
+        Self { webhook: webhook.into(), secret }
+    }
+}
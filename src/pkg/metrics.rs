@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::time::Instant;
+use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
+use prometheus::{Encoder, Gauge, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder};
+use crate::pkg::s3::{FileStorage, StorageError, StoredObject};
+
+// Every collector the service exports. Each `AppState` owns its own registry,
+// so parallel test apps don't see each other's numbers.
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub db_pool_size: IntGauge,
+    pub db_pool_idle: IntGauge,
+    pub db_pool_acquire_wait: Gauge,
+    pub s3_uploads: IntCounterVec,
+    pub s3_upload_duration: HistogramVec,
+    pub registrations: IntCounter,
+    pub schools_created: IntCounter,
+    pub subscriptions_changed: IntCounterVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new();
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests served"),
+            &["method", "route", "status"],
+        ).unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Time taken to serve HTTP requests"),
+            &["method", "route", "status"],
+        ).unwrap();
+        let db_pool_size = IntGauge::new("db_pool_connections", "Connections currently open in the Postgres pool").unwrap();
+        let db_pool_idle = IntGauge::new("db_pool_idle_connections", "Open Postgres connections not in use").unwrap();
+        let db_pool_acquire_wait = Gauge::new(
+            "db_pool_acquire_wait_seconds",
+            "Time taken to acquire a Postgres connection, sampled on each scrape",
+        ).unwrap();
+        let s3_uploads = IntCounterVec::new(
+            Opts::new("s3_uploads_total", "Files uploaded to object storage"),
+            &["outcome"],
+        ).unwrap();
+        let s3_upload_duration = HistogramVec::new(
+            HistogramOpts::new("s3_upload_duration_seconds", "Time taken to upload a file to object storage"),
+            &["outcome"],
+        ).unwrap();
+        let registrations = IntCounter::new("sekula_registrations_total", "Successful registrations").unwrap();
+        let schools_created = IntCounter::new("sekula_schools_created_total", "Schools created through the API").unwrap();
+        let subscriptions_changed = IntCounterVec::new(
+            Opts::new("sekula_subscriptions_changed_total", "Subscriptions created, updated, deleted, restored or purged"),
+            &["action"],
+        ).unwrap();
+
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry.register(Box::new(http_request_duration.clone())).unwrap();
+        registry.register(Box::new(db_pool_size.clone())).unwrap();
+        registry.register(Box::new(db_pool_idle.clone())).unwrap();
+        registry.register(Box::new(db_pool_acquire_wait.clone())).unwrap();
+        registry.register(Box::new(s3_uploads.clone())).unwrap();
+        registry.register(Box::new(s3_upload_duration.clone())).unwrap();
+        registry.register(Box::new(registrations.clone())).unwrap();
+        registry.register(Box::new(schools_created.clone())).unwrap();
+        registry.register(Box::new(subscriptions_changed.clone())).unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_size,
+            db_pool_idle,
+            db_pool_acquire_wait,
+            s3_uploads,
+            s3_upload_duration,
+            registrations,
+            schools_created,
+            subscriptions_changed,
+        }
+    }
+
+    /// Every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Wraps any storage to count and time its uploads.
+pub struct InstrumentedStorage {
+    inner: Arc<dyn FileStorage>,
+    metrics: Arc<Metrics>,
+}
+
+impl InstrumentedStorage {
+    pub fn new(inner: Arc<dyn FileStorage>, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait]
+impl FileStorage for InstrumentedStorage {
+    async fn upload(&self, file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError> {
+        let started = Instant::now();
+        let result = self.inner.upload(file_param, file_path).await;
+
+        let outcome = if result.is_ok() { "success" } else { "failure" };
+        self.metrics.s3_uploads.with_label_values(&[outcome]).inc();
+        self.metrics.s3_upload_duration.with_label_values(&[outcome]).observe(started.elapsed().as_secs_f64());
+        result
+    }
+
+    async fn check(&self) -> Result<(), StorageError> {
+        self.inner.check().await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+        self.inner.list(prefix).await
+    }
+
+    async fn delete(&self, file_path: &str) -> Result<(), StorageError> {
+        self.inner.delete(file_path).await
+    }
+}
@@ -1,2 +1,5 @@
 pub mod dto;
-pub mod s3;
\ No newline at end of file
+pub mod s3;
+pub mod metrics;
+pub mod telemetry;
+pub mod supervisor;
@@ -1,39 +1,72 @@
+use std::collections::HashMap;
 use std::error::Error;
+use std::sync::Mutex;
 use actix_multipart::form::tempfile::TempFile;
+use async_trait::async_trait;
 use aws_config::BehaviorVersion;
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_s3::Client;
-use aws_sdk_s3::config::Credentials;
+use aws_sdk_s3::config::{Credentials, Region};
 use aws_sdk_s3::primitives::ByteStream;
+use chrono::{DateTime, Utc};
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
+use crate::config::app_config::S3Config;
 
-pub async fn create_s3_client() -> Result<Client, Box<dyn Error>> {
+pub type StorageError = Box<dyn Error + Send + Sync>;
+
+// Where uploaded files end up; returns the public URL of the stored object.
+#[async_trait]
+pub trait FileStorage: Send + Sync {
+    async fn upload(&self, file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError>;
+    // Fails when the storage can't currently accept uploads.
+    async fn check(&self) -> Result<(), StorageError>;
+    // Every stored object whose path starts with `prefix`.
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError>;
+    async fn delete(&self, file_path: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredObject {
+    pub path: String,
+    pub last_modified: DateTime<Utc>,
+}
+
+// S3 client bound to the configured bucket.
+#[derive(Debug, Clone)]
+pub struct S3Storage {
+    client: Client,
+    bucket: String,
+    public_url: String,
+}
+
+pub async fn create_s3_client(config: &S3Config) -> Result<S3Storage, Box<dyn Error>> {
     // Custom region for your S3-compatible storage
-    let region_provider = RegionProviderChain::default_provider().or_else("custom-region");
+    let region_provider = RegionProviderChain::default_provider().or_else(Region::new(config.region.clone()));
     // Provide the credentials manually
     let credentials_provider = Credentials::new(
-        std::env::var("AWS_S3_ACCESS_KEY_ID").unwrap_or(String::from("")),    // Access key
-        std::env::var("AWS_S3_SECRET_ACCESS_KEY").unwrap_or(String::from("")),    // Access key
+        config.access_key_id.clone(),
+        config.secret_access_key.clone(),
         None, None, "Static",
     );
     // Load the custom S3 configuration with provided credentials
     let shared_config = aws_config::defaults(BehaviorVersion::latest())
         .region(region_provider)
-        .endpoint_url("https://is3.cloudhost.id") // Custom S3-compatible endpoint
+        .endpoint_url(&config.endpoint) // Custom S3-compatible endpoint
         .credentials_provider(
             credentials_provider
         )
         .load()
         .await;
     // Create and return the S3 client
-    Ok(Client::new(&shared_config))
+    Ok(S3Storage {
+        client: Client::new(&shared_config),
+        bucket: config.bucket.clone(),
+        public_url: config.public_url.trim_end_matches('/').to_string(),
+    })
 }
 
-pub async fn upload_file_to_s3(s3: Client, file_param: Option<TempFile>, file_path: String) -> Result<String, Box<dyn Error>> {
-    // Initialize S3 client
-    let client = s3;
-
+pub async fn upload_file_to_s3(s3: &S3Storage, file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError> {
     // Ensure the logo is present
     let temp_file = file_param.ok_or_else(|| {
         Box::new(std::io::Error::new(
@@ -52,9 +85,9 @@ pub async fn upload_file_to_s3(s3: Client, file_param: Option<TempFile>, file_pa
     let byte_stream = ByteStream::from(buffer);
 
     // Upload file to S3
-    client
+    s3.client
         .put_object()
-        .bucket("sekula-storage")
+        .bucket(&s3.bucket)
         .key(&file_path)
         .body(byte_stream)
         .send()
@@ -62,6 +95,103 @@ pub async fn upload_file_to_s3(s3: Client, file_param: Option<TempFile>, file_pa
         .map_err(|e| format!("Failed to upload file: {:?}", e))?;
 
     // Return the S3 URL
-    let s3_url = format!("https://{}/{}", "is3.cloudhost.id/sekula-storage", file_path);
+    let s3_url = format!("{}/{}", s3.public_url, file_path);
     Ok(s3_url)
 }
+
+#[async_trait]
+impl FileStorage for S3Storage {
+    async fn upload(&self, file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError> {
+        upload_file_to_s3(self, file_param, file_path).await
+    }
+
+    async fn check(&self) -> Result<(), StorageError> {
+        self.client.head_bucket().bucket(&self.bucket).send().await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+        let mut objects = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let page = self.client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+
+            for object in page.contents() {
+                let (Some(path), Some(modified)) = (object.key(), object.last_modified()) else {
+                    continue;
+                };
+                objects.push(StoredObject {
+                    path: path.to_string(),
+                    last_modified: DateTime::from_timestamp(modified.secs(), modified.subsec_nanos()).unwrap_or_default(),
+                });
+            }
+
+            continuation_token = page.next_continuation_token().map(str::to_string);
+            if continuation_token.is_none() {
+                return Ok(objects);
+            }
+        }
+    }
+
+    async fn delete(&self, file_path: &str) -> Result<(), StorageError> {
+        self.client.delete_object().bucket(&self.bucket).key(file_path).send().await?;
+        Ok(())
+    }
+}
+
+// Keeps uploads in memory, for tests and local runs without a bucket.
+#[derive(Debug, Default)]
+pub struct InMemoryStorage {
+    objects: Mutex<HashMap<String, MemoryObject>>,
+}
+
+#[derive(Debug)]
+struct MemoryObject {
+    contents: Vec<u8>,
+    last_modified: DateTime<Utc>,
+}
+
+impl InMemoryStorage {
+    pub fn get(&self, file_path: &str) -> Option<Vec<u8>> {
+        self.objects.lock().unwrap().get(file_path).map(|object| object.contents.clone())
+    }
+
+    // Stores an object directly, as if it had been uploaded at `last_modified`.
+    pub fn put(&self, file_path: &str, contents: &[u8], last_modified: DateTime<Utc>) {
+        self.objects.lock().unwrap().insert(file_path.to_string(), MemoryObject { contents: contents.to_vec(), last_modified });
+    }
+}
+
+#[async_trait]
+impl FileStorage for InMemoryStorage {
+    async fn upload(&self, file_param: Option<TempFile>, file_path: String) -> Result<String, StorageError> {
+        let temp_file = file_param.ok_or("No file provided")?;
+        let buffer = tokio::fs::read(temp_file.file.path()).await?;
+        self.objects.lock().unwrap().insert(file_path.clone(), MemoryObject { contents: buffer, last_modified: Utc::now() });
+        Ok(format!("memory://{}", file_path))
+    }
+
+    async fn check(&self) -> Result<(), StorageError> {
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<StoredObject>, StorageError> {
+        let mut objects: Vec<StoredObject> = self.objects.lock().unwrap().iter()
+            .filter(|(path, _)| path.starts_with(prefix))
+            .map(|(path, object)| StoredObject { path: path.clone(), last_modified: object.last_modified })
+            .collect();
+        objects.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(objects)
+    }
+
+    async fn delete(&self, file_path: &str) -> Result<(), StorageError> {
+        self.objects.lock().unwrap().remove(file_path);
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{info, info_span, warn, Instrument};
+
+/// Owns the background work started outside a request, so shutdown can ask it
+/// to stop and wait for it instead of dropping it mid-write.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSupervisor {
+    tracker: TaskTracker,
+    cancel: CancellationToken,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `task` in the background. The task is handed a token that is
+    /// cancelled on shutdown; it should check it at points where stopping
+    /// leaves consistent state behind. Returns false, without running the
+    /// task, once shutdown has begun.
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F) -> bool
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        if self.cancel.is_cancelled() {
+            warn!(task = name, "Not starting task, shutting down");
+            return false;
+        }
+
+        let future = task(self.cancel.child_token());
+        self.tracker.spawn(
+            async move {
+                info!("Task started");
+                future.await;
+                info!("Task finished");
+            }
+            .instrument(info_span!("task", name)),
+        );
+        true
+    }
+
+    /// A token cancelled when shutdown begins, for work run in the foreground.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.child_token()
+    }
+
+    pub fn running(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Tells every task to stop and refuses new ones, without waiting.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+        self.tracker.close();
+    }
+
+    /// Cancels every task and waits up to `timeout` for them to finish.
+    /// Returns false if some were still running when the time ran out.
+    pub async fn shutdown(&self, timeout: Duration) -> bool {
+        self.cancel();
+
+        if self.tracker.is_empty() {
+            return true;
+        }
+        info!(running = self.tracker.len(), "Waiting for background tasks to stop");
+        match tokio::time::timeout(timeout, self.tracker.wait()).await {
+            Ok(()) => true,
+            Err(_) => {
+                warn!(running = self.tracker.len(), timeout_secs = timeout.as_secs(), "Background tasks did not stop in time");
+                false
+            }
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM where there is one.
+pub async fn shutdown_signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.ok();
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn shutdown_waits_for_tasks_to_checkpoint() {
+        let supervisor = TaskSupervisor::new();
+        let checkpointed = Arc::new(AtomicBool::new(false));
+        let flag = checkpointed.clone();
+
+        supervisor.spawn("test", move |cancel| async move {
+            cancel.cancelled().await;
+            flag.store(true, Ordering::SeqCst);
+        });
+
+        assert!(supervisor.shutdown(Duration::from_secs(1)).await);
+        assert!(checkpointed.load(Ordering::SeqCst));
+        assert_eq!(supervisor.running(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_gives_up_after_timeout() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("stubborn", |_cancel| tokio::time::sleep(Duration::from_secs(60)));
+
+        assert!(!supervisor.shutdown(Duration::from_secs(1)).await);
+        assert_eq!(supervisor.running(), 1);
+    }
+
+    #[tokio::test]
+    async fn no_tasks_start_after_shutdown() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.shutdown(Duration::from_secs(1)).await;
+
+        assert!(!supervisor.spawn("late", |_cancel| async {}));
+    }
+}
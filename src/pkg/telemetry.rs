@@ -0,0 +1,69 @@
+use std::error::Error;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+use crate::config::app_config::{LogConfig, LogFormat};
+
+/// Keeps the span exporter alive; call `shutdown` before exiting so buffered
+/// spans are flushed.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(err) = provider.shutdown() {
+                eprintln!("Failed to flush spans: {}", err);
+            }
+        }
+    }
+}
+
+/// Installs the global subscriber: logs to stderr in the configured format,
+/// plus OTLP span export when an endpoint is set. Events from the `log` crate
+/// (actix, sqlx) are routed through it too.
+pub fn init(config: &LogConfig) -> Result<Telemetry, Box<dyn Error>> {
+    let filter = EnvFilter::try_new(&config.level)?;
+
+    // JSON lines carry every enclosing span, so the request id from the
+    // `http_request` span is on each line logged while serving a request.
+    let json = (config.format == LogFormat::Json).then(|| {
+        fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(std::io::stderr)
+    });
+    let pretty = (config.format == LogFormat::Pretty).then(|| fmt::layer().with_writer(std::io::stderr));
+
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+                .build()?;
+            Some(SdkTracerProvider::builder()
+                .with_batch_exporter(exporter)
+                .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build())
+                .build())
+        }
+        None => None,
+    };
+    let otel = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(config.service_name.clone()))
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(json)
+        .with(pretty)
+        .with(otel)
+        .try_init()?;
+
+    Ok(Telemetry { provider })
+}
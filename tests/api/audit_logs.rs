@@ -0,0 +1,115 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use crate::helpers::{TestApp, TRUSTED_PROXY};
+use crate::spawn_app;
+
+async fn audit_logs(app: &TestApp, query: &str) -> Vec<Value> {
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/audit-logs?{}", query)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    res.data().as_array().unwrap().clone()
+}
+
+#[actix_web::test]
+async fn role_changes_are_audited_with_their_actor() {
+    let app = spawn_app!();
+    let actor = app.bearer_auth("admin");
+    let request = |req: TestRequest| req
+        .insert_header(actor.clone())
+        .insert_header(("X-Request-Id", "audit-test"))
+        .peer_addr(format!("{}:4000", TRUSTED_PROXY).parse().unwrap())
+        .insert_header(("X-Forwarded-For", "203.0.113.7"));
+
+    app.call(request(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"})))).await;
+    let id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].as_str().unwrap().to_string();
+    let res = app.call(request(TestRequest::put().uri(&format!("/api/v1/roles/{}", id)).insert_header((header::IF_MATCH, "*")).set_json(json!({"name": "headmaster"})))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/roles/{}", id)).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let logs = audit_logs(&app, &format!("entity_type=roles&entity_id={}", id)).await;
+    let actions: Vec<&str> = logs.iter().map(|log| log["action"].as_str().unwrap()).collect();
+    assert_eq!(actions, ["deleted", "updated", "created"]);
+
+    let updated = &logs[1];
+    assert_eq!(updated["actor"], "test@example.com");
+    assert!(updated["actor_id"].is_string());
+    assert_eq!(updated["ip"], "203.0.113.7");
+    assert_eq!(updated["request_id"], "audit-test");
+    assert_eq!(updated["changes"]["name"], json!({"before": "teacher", "after": "headmaster"}));
+    assert_eq!(updated["before"]["name"], "teacher");
+    assert_eq!(updated["after"]["name"], "headmaster");
+
+    let deleted = &logs[0];
+    assert!(deleted["actor"].is_null());
+    assert!(deleted["after"].is_null());
+    assert_eq!(deleted["before"]["name"], "headmaster");
+}
+
+#[actix_web::test]
+async fn user_audit_logs_name_the_school_and_hide_passwords() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let role_id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].clone();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, address, logo_path) VALUES ('SMA 1', '', '') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/users").insert_header(app.super_admin_auth()).set_json(json!({
+        "name": "Siti",
+        "email": "siti@example.com",
+        "phone_number": "0813",
+        "password": "secret",
+        "role_id": role_id,
+        "school_id": school_id,
+    }))).await;
+    let id = res.data()["id"].as_str().unwrap().to_string();
+    app.call(TestRequest::put().uri(&format!("/api/v1/users/{}", id)).insert_header((header::IF_MATCH, "*")).insert_header(app.super_admin_auth())
+        .set_json(json!({"password": "new secret"}))).await;
+
+    let logs = audit_logs(&app, "entity_type=users&actor=admin").await;
+    assert_eq!(logs.len(), 2);
+    assert_eq!(logs[0]["school_id"], school_id.to_string());
+    assert_eq!(logs[0]["changes"]["password"], json!({"before": "[redacted]", "after": "[redacted]"}));
+    assert_eq!(logs[1]["after"]["password"], "[redacted]");
+    assert!(!logs.iter().any(|log| log.to_string().contains("$2")), "a bcrypt hash leaked");
+}
+
+#[actix_web::test]
+async fn audit_logs_filter_by_date_range() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+
+    assert_eq!(audit_logs(&app, "from=2000-01-01T00:00:00Z").await.len(), 1);
+    assert!(audit_logs(&app, "from=2100-01-01T00:00:00Z").await.is_empty());
+    assert!(audit_logs(&app, "to=2000-01-01T00:00:00Z").await.is_empty());
+
+    let res = app.call(TestRequest::get().uri("/api/v1/audit-logs?from=2001-01-01T00:00:00Z&to=2000-01-01T00:00:00Z")
+        .insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn forwarded_addresses_are_ignored_from_untrusted_peers() {
+    let app = spawn_app!();
+    let res = app.call(TestRequest::post().uri("/api/v1/roles")
+        .peer_addr("198.51.100.4:4000".parse().unwrap())
+        .insert_header(("X-Forwarded-For", "203.0.113.7"))
+        .set_json(json!({"name": "teacher"}))).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+
+    let logs = audit_logs(&app, "entity_type=roles").await;
+    assert_eq!(logs[0]["ip"], "198.51.100.4");
+}
+
+#[actix_web::test]
+async fn audit_logs_require_super_admin() {
+    let app = spawn_app!();
+    let res = app.call(TestRequest::get().uri("/api/v1/audit-logs")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::get().uri("/api/v1/audit-logs").insert_header(("Authorization", "Basic d3Jvbmc6d3Jvbmc="))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,51 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::spawn_app;
+
+fn register_body(email: &str, phone_number: &str) -> serde_json::Value {
+    json!({
+        "name": "Budi",
+        "email": email,
+        "phone_number": phone_number,
+        "password": "secret",
+        "school_name": "SMA 1",
+    })
+}
+
+#[actix_web::test]
+async fn register_creates_user_and_school() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(register_body("budi@example.com", "0811"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["email"], "budi@example.com");
+    assert_eq!(res.data()["status"], "Pending");
+    assert!(res.data().get("password").is_none());
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools")).await;
+    assert_eq!(res.data()[0]["name"], "SMA 1");
+}
+
+#[actix_web::test]
+async fn register_rejects_used_email() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(register_body("budi@example.com", "0811"))).await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(register_body("budi@example.com", "0812"))).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["errors"][0]["message"], "Email is already used");
+}
+
+#[actix_web::test]
+async fn register_requires_user_role() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(register_body("budi@example.com", "0811"))).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,70 @@
+use sekula_be::cmd::cli::CreateAdminArgs;
+use sekula_be::cmd::commands::admin::create_admin;
+use sekula_be::cmd::commands::migrate::revert;
+use sekula_be::cmd::commands::seed::seed;
+use sekula_be::database::migrations::{status, MIGRATOR};
+use sekula_be::internal::entities::user::UserStatus;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn seed_is_idempotent() {
+    let app = spawn_app!();
+
+    let created = seed(&app.state).await.unwrap();
+    assert_eq!(created, vec!["role admin", "role user", "subscription type Monthly", "subscription type Yearly"]);
+
+    assert!(seed(&app.state).await.unwrap().is_empty());
+    assert!(app.state.repositories.role.get_by_name("user".to_string()).await.is_ok());
+}
+
+#[actix_web::test]
+async fn create_admin_creates_verified_admin() {
+    let app = spawn_app!();
+    seed(&app.state).await.unwrap();
+
+    let admin = create_admin(&app.state, CreateAdminArgs {
+        name: "Admin".to_string(),
+        email: "admin@sekula.id".to_string(),
+        phone_number: "0800".to_string(),
+        password: "secret".to_string(),
+        school_id: None,
+        school_name: "Sekula".to_string(),
+    }).await.unwrap();
+
+    let role = app.state.repositories.role.get_by_name("admin".to_string()).await.unwrap();
+    assert_eq!(admin.role_id, role.id);
+    assert_eq!(admin.status, UserStatus::Verified);
+    assert_eq!(app.state.repositories.school.get_by_id(admin.school_id).await.unwrap().name, "Sekula");
+}
+
+#[actix_web::test]
+async fn create_admin_requires_seeded_role() {
+    let app = spawn_app!();
+
+    let err = create_admin(&app.state, CreateAdminArgs {
+        name: "Admin".to_string(),
+        email: "admin@sekula.id".to_string(),
+        phone_number: "0800".to_string(),
+        password: "secret".to_string(),
+        school_id: None,
+        school_name: "Sekula".to_string(),
+    }).await.unwrap_err();
+
+    assert!(err.to_string().contains("run `seed` first"));
+}
+
+#[actix_web::test]
+async fn migrate_down_and_up() {
+    let app = spawn_app!();
+    assert!(status(app.pool()).await.unwrap().iter().all(|migration| migration.applied));
+
+    let reverted = revert(app.pool(), 1).await.unwrap();
+
+    let migrations = status(app.pool()).await.unwrap();
+    let last = migrations.last().unwrap();
+    assert_eq!(reverted, vec![last.version]);
+    assert!(!last.applied);
+
+    MIGRATOR.run(app.pool()).await.unwrap();
+    assert!(status(app.pool()).await.unwrap().iter().all(|migration| migration.applied));
+}
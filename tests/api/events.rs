@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use async_trait::async_trait;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use sekula_be::internal::app::events::relay::OutboxRelay;
+use sekula_be::internal::app::events::{DeliverEvent, DeliverEventHandler, EventBus, EventSubscriber};
+use sekula_be::internal::app::jobs::worker::WorkerPool;
+use sekula_be::internal::app::jobs::JobRegistry;
+use sekula_be::internal::entities::event::{DomainEvent, PublishedEvent};
+use crate::spawn_app;
+
+#[derive(Clone, Default)]
+struct Recorder {
+    events: Arc<Mutex<Vec<PublishedEvent>>>,
+}
+
+#[async_trait]
+impl EventSubscriber for Recorder {
+    fn name(&self) -> &'static str {
+        "recorder"
+    }
+
+    async fn handle(&self, event: &PublishedEvent) -> Result<(), String> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+async fn register(app: &crate::helpers::TestApp, email: &str) -> StatusCode {
+    let body = json!({
+        "name": "Budi",
+        "email": email,
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    });
+    app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(body)).await.status
+}
+
+#[actix_web::test]
+async fn registration_events_reach_subscribers() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    assert_eq!(register(&app, "budi@example.com").await, StatusCode::OK);
+
+    let types: Vec<String> = sqlx::query_scalar("SELECT event_type FROM outbox_events ORDER BY event_type")
+        .fetch_all(app.pool()).await.unwrap();
+    assert_eq!(types, ["school.created", "user.registered"]);
+
+    let recorder = Recorder::default();
+    let bus = EventBus::new().subscribe(recorder.clone());
+    let relay = OutboxRelay::new(app.state.repositories.outbox.clone(), &bus, app.state.config.worker.poll_interval);
+    let registry = JobRegistry::new().register::<DeliverEvent>(DeliverEventHandler::new(bus));
+    let workers = WorkerPool::new(app.state.repositories.job.clone(), registry, app.state.config.worker.clone());
+
+    assert_eq!(relay.run_once().await.unwrap(), 2);
+    assert_eq!(relay.run_once().await.unwrap(), 0);
+    let cancel = CancellationToken::new();
+    while workers.run_once(&cancel).await.unwrap() > 0 {}
+
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 2);
+    let registered = events.iter()
+        .find_map(|event| match &event.event {
+            DomainEvent::UserRegistered { email, .. } => Some(email.clone()),
+            _ => None,
+        });
+    assert_eq!(registered.as_deref(), Some("budi@example.com"));
+}
+
+#[actix_web::test]
+async fn failed_registration_records_no_events() {
+    let app = spawn_app!();
+
+    // Registration fails without the `user` role.
+    assert_eq!(register(&app, "budi@example.com").await, StatusCode::BAD_REQUEST);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox_events").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(count, 0);
+}
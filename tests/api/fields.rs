@@ -0,0 +1,82 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use uuid::Uuid;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+async fn create_user(app: &TestApp) -> serde_json::Value {
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let role_id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].clone();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, address, logo_path) VALUES ('SMA 1', '', '') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    let res = app.call(TestRequest::post().uri("/api/v1/users").insert_header(app.super_admin_auth()).set_json(json!({
+        "name": "Siti",
+        "email": "siti@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "role_id": role_id,
+        "school_id": school_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    res.data().clone()
+}
+
+#[actix_web::test]
+async fn users_never_expose_their_password() {
+    let app = spawn_app!();
+    let user = create_user(&app).await;
+    assert!(user.get("password").is_none());
+    let id = user["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/users/{}", id)).insert_header(app.super_admin_auth())).await;
+    assert!(res.data().get("password").is_none());
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users").insert_header(app.super_admin_auth())).await;
+    assert!(res.data()[0].get("password").is_none());
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/users/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*")).set_json(json!({"password": "changed"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.data().get("password").is_none());
+
+    let res = app.call(TestRequest::patch().uri(&format!("/api/v1/users/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*")).set_json(json!({"title": "Principal"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.data().get("password").is_none());
+}
+
+#[actix_web::test]
+async fn fields_select_a_sparse_fieldset() {
+    let app = spawn_app!();
+    let user = create_user(&app).await;
+    let id = user["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?fields=id,name,email").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()[0], json!({"id": id, "name": "Siti", "email": "siti@example.com"}));
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+
+    // Included relations are kept whole.
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/users/{}?fields=name&include=role", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "Siti");
+    assert_eq!(res.data()["role"]["name"], "teacher");
+    assert!(res.data().get("email").is_none());
+    assert!(res.headers.contains_key(header::ETAG));
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles?fields=name")).await;
+    assert_eq!(res.data()[0], json!({"name": "teacher"}));
+}
+
+#[actix_web::test]
+async fn unknown_fields_are_rejected() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?fields=id,password").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools/{}?fields=secret", Uuid::new_v4()))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,51 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use sekula_be::cmd::commands::migrate::revert;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn live_answers_without_auth() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/health/live")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn ready_reports_each_dependency() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/health/ready")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["ready"], true);
+    for check in ["database", "storage", "migrations"] {
+        assert_eq!(res.data()[check]["status"], "up", "{} should be up", check);
+    }
+}
+
+#[actix_web::test]
+async fn ready_fails_with_pending_migrations() {
+    let app = spawn_app!();
+    revert(app.pool(), 1).await.unwrap();
+
+    let res = app.call(TestRequest::get().uri("/health/ready")).await;
+
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(res.data()["ready"], false);
+    assert_eq!(res.data()["database"]["status"], "up");
+    assert_eq!(res.data()["migrations"]["status"], "down");
+}
+
+#[actix_web::test]
+async fn version_reports_build_info() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/version")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["version"], env!("CARGO_PKG_VERSION"));
+    assert!(res.data()["git_sha"].as_str().is_some_and(|sha| !sha.is_empty()));
+    assert!(res.data()["build_time"].is_string());
+}
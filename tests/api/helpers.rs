@@ -0,0 +1,250 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::Arc;
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::http::StatusCode;
+use actix_web::middleware::from_fn;
+use actix_web::{test, App};
+use base64::engine::general_purpose;
+use base64::Engine;
+use chrono::Utc;
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde_json::{json, Value};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool};
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+use sekula_be::cmd::app::configure_app;
+use sekula_be::cmd::middlewares::metrics::metrics_middleware;
+use sekula_be::cmd::middlewares::request_context::request_context_middleware;
+use sekula_be::cmd::middlewares::request_id::request_id_middleware;
+use sekula_be::config::app_config::AppConfig;
+use sekula_be::database::migrations::MIGRATOR;
+use sekula_be::internal::app::state::{AppState, Repositories};
+use sekula_be::internal::entities::auth::Claims;
+use sekula_be::pkg::s3::InMemoryStorage;
+
+// Points at a Postgres server the tests may create and drop databases on,
+// e.g. `postgres://postgres@localhost/postgres`. Tests fail without it.
+const DATABASE_URL_ENV: &str = "TEST_DATABASE_URL";
+
+pub const BASIC_AUTH_SECRET: &str = "admin:secret";
+const JWT_SECRET: &str = "test-jwt-secret";
+// The one peer whose forwarding headers the app believes.
+pub const TRUSTED_PROXY: &str = "10.0.0.1";
+
+static TEMPLATE: OnceCell<String> = OnceCell::const_new();
+
+// Serialises template creation across test binaries running in parallel.
+const TEMPLATE_LOCK_ID: i64 = 0x5e_c0_1a_7e;
+
+/// A throwaway database cloned from the migrated template, dropped again when
+/// the test finishes.
+pub struct TestDatabase {
+    admin: PgConnectOptions,
+    pub name: String,
+    pub pool: PgPool,
+}
+
+impl TestDatabase {
+    async fn create(admin: PgConnectOptions) -> TestDatabase {
+        let template = TEMPLATE.get_or_init(|| create_template(admin.clone())).await;
+        let name = format!("sekula_test_{}", Uuid::new_v4().simple());
+
+        let mut conn = PgConnection::connect_with(&admin).await.expect("connect to test server");
+        sqlx::query(&format!(r#"CREATE DATABASE "{}" TEMPLATE "{}""#, name, template))
+            .execute(&mut conn)
+            .await
+            .expect("create test database");
+        conn.close().await.ok();
+
+        let pool = PgPoolOptions::new()
+            .max_connections(5)
+            .connect_with(admin.clone().database(&name))
+            .await
+            .expect("connect to test database");
+
+        TestDatabase { admin, name, pool }
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        let admin = self.admin.clone();
+        let name = self.name.clone();
+        // The test's runtime is shutting down, so drop the database from a
+        // runtime of our own.
+        std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+            runtime.block_on(async {
+                if let Ok(mut conn) = PgConnection::connect_with(&admin).await {
+                    let _ = sqlx::query(&format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, name))
+                        .execute(&mut conn)
+                        .await;
+                }
+            });
+        })
+        .join()
+        .ok();
+    }
+}
+
+// The template is named after the migration set, so changing a migration
+// builds a fresh one instead of reusing a stale schema.
+async fn create_template(admin: PgConnectOptions) -> String {
+    let mut hasher = DefaultHasher::new();
+    for migration in MIGRATOR.iter() {
+        migration.version.hash(&mut hasher);
+        migration.checksum.hash(&mut hasher);
+    }
+    let name = format!("sekula_test_template_{:016x}", hasher.finish());
+
+    let mut conn = PgConnection::connect_with(&admin).await.expect("connect to test server");
+    sqlx::query("SELECT pg_advisory_lock($1)").bind(TEMPLATE_LOCK_ID).execute(&mut conn).await.unwrap();
+
+    let ready: Option<bool> = sqlx::query_scalar("SELECT datistemplate FROM pg_database WHERE datname = $1")
+        .bind(&name)
+        .fetch_optional(&mut conn)
+        .await
+        .unwrap();
+
+    // A database that exists but was never marked as a template is left over
+    // from a run that failed half-way through migrating.
+    if ready != Some(true) {
+        sqlx::query(&format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, name)).execute(&mut conn).await.unwrap();
+        sqlx::query(&format!(r#"CREATE DATABASE "{}""#, name)).execute(&mut conn).await.unwrap();
+
+        let mut template = PgConnection::connect_with(&admin.clone().database(&name)).await.unwrap();
+        MIGRATOR.run(&mut template).await.expect("run migrations on template");
+        template.close().await.ok();
+
+        sqlx::query(&format!(r#"ALTER DATABASE "{}" IS_TEMPLATE true"#, name)).execute(&mut conn).await.unwrap();
+    }
+
+    sqlx::query("SELECT pg_advisory_unlock($1)").bind(TEMPLATE_LOCK_ID).execute(&mut conn).await.unwrap();
+    conn.close().await.ok();
+    name
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Value,
+}
+
+impl TestResponse {
+    pub fn data(&self) -> &Value {
+        &self.body["data"]
+    }
+}
+
+/// The real application wired to a fresh database, with in-memory file storage.
+pub struct TestApp {
+    pub state: AppState,
+    pub storage: Arc<InMemoryStorage>,
+    pub database: TestDatabase,
+}
+
+impl TestApp {
+    /// Panics when no test database is configured, so a missing database
+    /// can't pass for a green run.
+    pub async fn spawn() -> TestApp {
+        let url = std::env::var(DATABASE_URL_ENV).unwrap_or_else(|_| {
+            panic!("{} is not set; point it at a Postgres server the tests may create databases on", DATABASE_URL_ENV)
+        });
+        let admin = PgConnectOptions::from_str(&url).expect("valid TEST_DATABASE_URL");
+        let database = TestDatabase::create(admin).await;
+
+        let env: HashMap<String, String> = [
+            ("DATABASE_URL", url.as_str()),
+            ("JWT_SECRET", JWT_SECRET),
+            ("BASIC_AUTH_SECRET", BASIC_AUTH_SECRET),
+            ("SERVER_TRUSTED_PROXIES", TRUSTED_PROXY),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+        let config = AppConfig::from_sources(None, &env).expect("test config");
+
+        let storage = Arc::new(InMemoryStorage::default());
+        let state = AppState::from_repositories(config, Repositories::postgres(database.pool.clone()), storage.clone());
+
+        TestApp { state, storage, database }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.database.pool
+    }
+
+    /// Sends the request through a freshly initialised `App`, built exactly
+    /// as `main` builds it.
+    pub async fn call(&self, req: test::TestRequest) -> TestResponse {
+        let app = test::init_service(
+            App::new()
+                .configure(|cfg| configure_app(cfg, &self.state))
+                .wrap(from_fn(request_context_middleware))
+                .wrap(from_fn(metrics_middleware))
+                .wrap(from_fn(request_id_middleware)),
+        )
+        .await;
+        // Middleware rejections surface as errors; render them as the server would.
+        let res = match test::try_call_service(&app, req.to_request()).await {
+            Ok(res) => res.map_into_boxed_body().into_parts().1,
+            Err(err) => err.error_response(),
+        };
+        let status = res.status();
+        let headers = res.headers().clone();
+        let bytes = actix_web::body::to_bytes(res.into_body()).await.unwrap_or_default();
+        let body = serde_json::from_slice(&bytes).unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+        TestResponse { status, headers, body }
+    }
+
+    /// `Authorization` header accepted by the super admin middleware.
+    pub fn super_admin_auth(&self) -> (header::HeaderName, String) {
+        let token = general_purpose::STANDARD.encode(BASIC_AUTH_SECRET);
+        (header::AUTHORIZATION, format!("Basic {}", token))
+    }
+
+    /// `Authorization` header carrying a JWT for a user with the given role.
+    #[allow(dead_code)]
+    pub fn bearer_auth(&self, role: &str) -> (header::HeaderName, String) {
+        let claims = Claims {
+            sub: Uuid::new_v4().to_string(),
+            email: "test@example.com".to_string(),
+            role: role.to_string(),
+            exp: (Utc::now().timestamp() + 3600) as usize,
+        };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_SECRET.as_ref())).unwrap();
+        (header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    /// Creates a subscription type through the API and returns its id.
+    pub async fn create_subscription_type(&self, name: &str) -> String {
+        let res = self.call(test::TestRequest::post().uri("/api/v1/subscription_types").insert_header(self.super_admin_auth()).set_json(json!({"name": name}))).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+        let res = self.call(test::TestRequest::get().uri("/api/v1/subscription_types?sort=-created_at&page_size=1")).await;
+        res.data()[0]["id"].as_str().unwrap().to_string()
+    }
+
+    /// Creates a subscription through the API and returns its id.
+    pub async fn create_subscription(&self, subscription_type_id: &str, name: &str, price: i32) -> String {
+        let res = self.call(test::TestRequest::post().uri("/api/v1/subscriptions").insert_header(self.super_admin_auth()).set_json(json!({
+            "name": name,
+            "price": price,
+            "subscription_type_id": subscription_type_id,
+        }))).await;
+        assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+        let res = self.call(test::TestRequest::get().uri("/api/v1/subscriptions?sort=-created_at&page_size=1")).await;
+        res.data()[0]["id"].as_str().unwrap().to_string()
+    }
+}
+
+/// Binds a `TestApp`, panicking when there's no database to use.
+#[macro_export]
+macro_rules! spawn_app {
+    () => {
+        $crate::helpers::TestApp::spawn().await
+    };
+}
@@ -0,0 +1,142 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+async fn count_subscriptions(app: &TestApp) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM subscriptions").fetch_one(app.pool()).await.unwrap()
+}
+
+#[actix_web::test]
+async fn retries_replay_the_first_response() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+    let body = json!({"name": "Basic", "price": 100000, "subscription_type_id": subscription_type_id});
+
+    let first = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "retry-1")).set_json(&body)).await;
+    assert_eq!(first.status, StatusCode::CREATED);
+    assert!(first.headers.get("idempotency-replayed").is_none());
+
+    let retry = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "retry-1")).set_json(&body)).await;
+    assert_eq!(retry.status, StatusCode::CREATED);
+    assert_eq!(retry.headers.get("idempotency-replayed").unwrap(), "true");
+    assert_eq!(retry.body, first.body);
+    assert_eq!(count_subscriptions(&app).await, 1);
+
+    // Keys only count for the endpoint they were first sent to.
+    let res = app.call(TestRequest::post().uri("/api/v1/subscription_types").insert_header(("Idempotency-Key", "retry-1")).set_json(json!({"name": "Yearly"}))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+}
+
+#[actix_web::test]
+async fn reusing_a_key_with_another_body_is_rejected() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "retry-2")).set_json(json!({
+        "name": "Basic",
+        "price": 100000,
+        "subscription_type_id": subscription_type_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "retry-2")).set_json(json!({
+        "name": "Premium",
+        "price": 200000,
+        "subscription_type_id": subscription_type_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(count_subscriptions(&app).await, 1);
+}
+
+#[actix_web::test]
+async fn registration_retries_create_one_user() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    let body = json!({
+        "name": "Budi",
+        "email": "budi@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    });
+
+    let first = app.call(TestRequest::post().uri("/api/v1/auth/register").insert_header(("Idempotency-Key", "register-1")).set_json(&body)).await;
+    let retry = app.call(TestRequest::post().uri("/api/v1/auth/register").insert_header(("Idempotency-Key", "register-1")).set_json(&body)).await;
+    assert_eq!(retry.status, first.status);
+    assert_eq!(retry.body, first.body);
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(users, 1);
+}
+
+#[actix_web::test]
+async fn abandoned_keys_are_taken_over_once_their_lock_runs_out() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+    let body = json!({"name": "Basic", "price": 100000, "subscription_type_id": subscription_type_id});
+    // Left behind by a request whose worker died before finishing.
+    let fingerprint = hex::encode(Sha256::digest(serde_json::to_vec(&body).unwrap()));
+    sqlx::query(
+        "INSERT INTO idempotency_keys (key, endpoint, fingerprint, expires_at, locked_until)
+         VALUES ('abandoned', 'POST /api/v1/subscriptions', $1, NOW() + INTERVAL '1 day', NOW() + INTERVAL '1 minute')",
+    )
+    .bind(fingerprint)
+    .execute(app.pool())
+    .await
+    .unwrap();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "abandoned")).set_json(&body)).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    sqlx::query("UPDATE idempotency_keys SET locked_until = NOW() - INTERVAL '1 second'").execute(app.pool()).await.unwrap();
+    let first = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "abandoned")).set_json(&body)).await;
+    assert_eq!(first.status, StatusCode::CREATED);
+
+    // Once finished, the response is replayed however old the lock is.
+    sqlx::query("UPDATE idempotency_keys SET locked_until = NOW() - INTERVAL '1 second'").execute(app.pool()).await.unwrap();
+    let retry = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "abandoned")).set_json(&body)).await;
+    assert_eq!(retry.headers.get("idempotency-replayed").unwrap(), "true");
+    assert_eq!(count_subscriptions(&app).await, 1);
+}
+
+#[actix_web::test]
+async fn oversized_bodies_are_refused_unread() {
+    let app = spawn_app!();
+    let body = json!({
+        "name": "x".repeat(3 * 1024 * 1024),
+        "email": "budi@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    });
+
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").insert_header(("Idempotency-Key", "register-2")).set_json(&body)).await;
+    assert_eq!(res.status, StatusCode::PAYLOAD_TOO_LARGE);
+    let keys: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM idempotency_keys").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(keys, 0);
+}
+
+#[actix_web::test]
+async fn requests_without_a_key_are_not_replayed() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+
+    for name in ["Basic", "Premium"] {
+        let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").set_json(json!({
+            "name": name,
+            "price": 100000,
+            "subscription_type_id": subscription_type_id,
+        }))).await;
+        assert_eq!(res.status, StatusCode::CREATED);
+        assert!(res.headers.get("idempotency-replayed").is_none());
+    }
+    assert_eq!(count_subscriptions(&app).await, 2);
+
+    let body = json!({"name": "Gold", "price": 100000, "subscription_type_id": subscription_type_id});
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").insert_header(("Idempotency-Key", "")).set_json(&body)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,113 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use uuid::Uuid;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn subscription_types_include_subscriptions() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+    app.create_subscription(&subscription_type_id, "Basic", 100000).await;
+    app.create_subscription(&subscription_type_id, "Premium", 250000).await;
+    app.call(TestRequest::post().uri("/api/v1/subscription_types").set_json(json!({"name": "Yearly"}))).await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/subscription_types?include=subscriptions")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let subscription_types = res.data().as_array().unwrap().clone();
+    assert_eq!(subscription_types[0]["name"], "Monthly");
+    let names: Vec<_> = subscription_types[0]["subscriptions"].as_array().unwrap().iter().map(|row| row["name"].clone()).collect();
+    assert_eq!(names, vec!["Basic", "Premium"]);
+    assert_eq!(subscription_types[1]["subscriptions"], json!([]));
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscription_types/{}?include=subscriptions", subscription_type_id))).await;
+    assert_eq!(res.data()["subscriptions"].as_array().unwrap().len(), 2);
+
+    // v1 and the legacy paths nest subscriptions unless told otherwise.
+    for uri in ["/api/v1/subscription_types", "/subscription_types"] {
+        let res = app.call(TestRequest::get().uri(uri)).await;
+        assert_eq!(res.data()[0]["subscriptions"].as_array().unwrap().len(), 2);
+    }
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscription_types/{}", subscription_type_id))).await;
+    assert_eq!(res.data()["subscriptions"].as_array().unwrap().len(), 2);
+    let res = app.call(TestRequest::get().uri("/api/v1/subscription_types?include=")).await;
+    assert!(res.data()[0].get("subscriptions").is_none());
+
+    // From v2 they are only loaded when asked for.
+    let res = app.call(TestRequest::get().uri("/api/v2/subscription_types")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.data()[0].get("subscriptions").is_none());
+    let res = app.call(TestRequest::get().uri(&format!("/api/v2/subscription_types/{}", subscription_type_id))).await;
+    assert!(res.data().get("subscriptions").is_none());
+    let res = app.call(TestRequest::get().uri("/api/v2/subscription_types?include=subscriptions")).await;
+    assert_eq!(res.data()[0]["subscriptions"].as_array().unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn schools_include_subscription_province_and_city() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+    let subscription_id = app.create_subscription(&subscription_type_id, "Basic", 100000).await;
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA')").execute(app.pool()).await.unwrap();
+    sqlx::query("INSERT INTO cities (id, name, province_id) VALUES ('31.71', 'KOTA JAKARTA PUSAT', '31')").execute(app.pool()).await.unwrap();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, address, logo_path, subscription_id, province_id, city_id) VALUES ('SMA 1', '', '', $1, '31', '31.71') RETURNING id")
+        .bind(Uuid::parse_str(&subscription_id).unwrap())
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO schools (name, address, logo_path) VALUES ('SMA 2', '', '')").execute(app.pool()).await.unwrap();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?include=subscription,province,city&sort=name")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let schools = res.data().as_array().unwrap().clone();
+    assert_eq!(schools[0]["subscription"]["name"], "Basic");
+    assert_eq!(schools[0]["province"]["name"], "DKI JAKARTA");
+    assert_eq!(schools[0]["city"]["name"], "KOTA JAKARTA PUSAT");
+    assert_eq!(schools[1]["subscription"], json!(null));
+    assert_eq!(schools[1]["city"], json!(null));
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools/{}?include=province", school_id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["province"]["id"], "31");
+    assert!(res.data().get("city").is_none());
+}
+
+#[actix_web::test]
+async fn users_include_role_and_school() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let role_id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].clone();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, address, logo_path) VALUES ('SMA 1', '', '') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    app.call(TestRequest::post().uri("/api/v1/users").insert_header(app.super_admin_auth()).set_json(json!({
+        "name": "Siti",
+        "email": "siti@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "role_id": role_id,
+        "school_id": school_id,
+    }))).await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?include=role,school").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()[0]["role"]["name"], "teacher");
+    assert_eq!(res.data()[0]["school"]["name"], "SMA 1");
+
+    let user_id = res.data()[0]["id"].as_str().unwrap().to_string();
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/users/{}?include=school", user_id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.data()["school"]["id"], school_id.to_string());
+    assert!(res.data().get("role").is_none());
+}
+
+#[actix_web::test]
+async fn unknown_relations_are_rejected() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?include=users")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/users/{}?include=password", Uuid::new_v4())).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,181 @@
+use std::time::Duration;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use sekula_be::internal::app::jobs::worker::WorkerPool;
+use sekula_be::internal::app::jobs::{JobDefinition, JobHandler, JobQueue, JobRegistry};
+use sekula_be::internal::entities::job::Job;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+#[derive(Serialize, Deserialize)]
+struct Flaky {
+    fail: bool,
+}
+
+impl JobDefinition for Flaky {
+    const KIND: &'static str = "flaky";
+    const MAX_ATTEMPTS: i32 = 2;
+}
+
+struct FlakyHandler;
+
+#[async_trait]
+impl JobHandler<Flaky> for FlakyHandler {
+    async fn handle(&self, job: Flaky, _cancel: CancellationToken) -> Result<(), String> {
+        if job.fail { Err("boom".to_string()) } else { Ok(()) }
+    }
+}
+
+fn workers(app: &TestApp) -> WorkerPool {
+    let registry = JobRegistry::new().register::<Flaky>(FlakyHandler);
+    WorkerPool::new(app.state.repositories.job.clone(), registry, app.state.config.worker.clone())
+}
+
+async fn enqueue(app: &TestApp, job: Flaky) -> Job {
+    JobQueue::new(app.state.repositories.job.clone()).enqueue(&job).await.unwrap()
+}
+
+// Makes a job that is waiting out its backoff due straight away.
+async fn make_due(app: &TestApp, job: &Job) {
+    sqlx::query("UPDATE jobs SET run_at = NOW() WHERE id = $1").bind(job.id).execute(app.pool()).await.unwrap();
+}
+
+#[actix_web::test]
+async fn failed_jobs_back_off_then_die() {
+    let app = spawn_app!();
+    let workers = workers(&app);
+    let cancel = CancellationToken::new();
+    let job = enqueue(&app, Flaky { fail: true }).await;
+
+    assert_eq!(workers.run_once(&cancel).await.unwrap(), 1);
+    let stored = app.state.repositories.job.get_by_id(job.id).await.unwrap();
+    assert_eq!(stored.status.as_str(), "pending");
+    assert_eq!(stored.attempts, 1);
+    assert_eq!(stored.last_error.as_deref(), Some("boom"));
+    assert!(stored.run_at > stored.updated_at + chrono::Duration::from_std(Duration::from_secs(5)).unwrap());
+
+    // Not due yet, so there is nothing to claim.
+    assert_eq!(workers.run_once(&cancel).await.unwrap(), 0);
+
+    make_due(&app, &job).await;
+    assert_eq!(workers.run_once(&cancel).await.unwrap(), 1);
+    let stored = app.state.repositories.job.get_by_id(job.id).await.unwrap();
+    assert_eq!(stored.status.as_str(), "dead");
+    assert_eq!(stored.attempts, 2);
+}
+
+#[actix_web::test]
+async fn stale_workers_cannot_record_an_outcome() {
+    let app = spawn_app!();
+    let jobs = &app.state.repositories.job;
+    let job = enqueue(&app, Flaky { fail: false }).await;
+
+    let first = jobs.claim(1, Duration::from_secs(600)).await.unwrap().remove(0);
+    // The first worker looks dead, so a second one takes the job over.
+    sqlx::query("UPDATE jobs SET locked_at = NOW() - INTERVAL '1 hour' WHERE id = $1").bind(job.id).execute(app.pool()).await.unwrap();
+    let second = jobs.claim(1, Duration::from_secs(600)).await.unwrap().remove(0);
+    assert_eq!(second.attempts, first.attempts + 1);
+
+    assert!(matches!(jobs.heartbeat(job.id, first.attempts).await, Err(sqlx::Error::RowNotFound)));
+    jobs.heartbeat(job.id, second.attempts).await.unwrap();
+
+    // The first worker finishing late leaves the second attempt running.
+    jobs.fail(job.id, first.attempts, "late", None).await.unwrap();
+    let stored = jobs.get_by_id(job.id).await.unwrap();
+    assert_eq!(stored.status.as_str(), "running");
+    assert!(stored.locked_at.unwrap() > Utc::now() - chrono::Duration::minutes(1));
+
+    jobs.succeed(job.id, second.attempts).await.unwrap();
+    assert_eq!(jobs.get_by_id(job.id).await.unwrap().status.as_str(), "succeeded");
+}
+
+#[actix_web::test]
+async fn jobs_without_a_handler_fail() {
+    let app = spawn_app!();
+    let job = enqueue(&app, Flaky { fail: false }).await;
+
+    // The application's own workers don't know the `flaky` kind.
+    assert_eq!(app.state.workers.run_once(&CancellationToken::new()).await.unwrap(), 1);
+    let stored = app.state.repositories.job.get_by_id(job.id).await.unwrap();
+    assert_eq!(stored.status.as_str(), "pending");
+    assert!(stored.last_error.unwrap().contains("No handler"));
+}
+
+#[actix_web::test]
+async fn admin_can_cancel_and_retry_jobs() {
+    let app = spawn_app!();
+    let auth = app.super_admin_auth();
+    let job = enqueue(&app, Flaky { fail: false }).await;
+    let uri = format!("/api/v1/admin/jobs/{}", job.id);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs?status=pending").insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data().as_array().unwrap().len(), 1);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs?status=dead").insert_header(auth.clone())).await;
+    assert_eq!(res.data().as_array().unwrap().len(), 0);
+
+    let res = app.call(TestRequest::post().uri(&format!("{}/retry", uri)).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let res = app.call(TestRequest::post().uri(&format!("{}/cancel", uri)).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["status"], "cancelled");
+
+    // Cancelled jobs are never claimed.
+    assert_eq!(workers(&app).run_once(&CancellationToken::new()).await.unwrap(), 0);
+
+    let res = app.call(TestRequest::post().uri(&format!("{}/cancel", uri)).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let res = app.call(TestRequest::post().uri(&format!("{}/retry", uri)).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["status"], "pending");
+
+    assert_eq!(workers(&app).run_once(&CancellationToken::new()).await.unwrap(), 1);
+    let res = app.call(TestRequest::get().uri(&uri).insert_header(auth)).await;
+    assert_eq!(res.data()["status"], "succeeded");
+}
+
+#[actix_web::test]
+async fn unknown_jobs_are_not_found() {
+    let app = spawn_app!();
+    let auth = app.super_admin_auth();
+    let uri = format!("/api/v1/admin/jobs/{}", uuid::Uuid::new_v4());
+
+    let res = app.call(TestRequest::get().uri(&uri).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app.call(TestRequest::post().uri(&format!("{}/cancel", uri)).insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs/not-a-uuid").insert_header(auth)).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn job_endpoints_reject_wrong_credentials() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs").insert_header(("Authorization", "Basic d3Jvbmc6d3Jvbmc="))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn job_endpoints_reject_missing_credentials() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs").insert_header(("Authorization", "Bearer token"))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    // Credentials that don't match aren't repeated back.
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs").insert_header(("Authorization", "Basic d3Jvbmc6d3Jvbmc="))).await;
+    assert!(!res.body.to_string().contains("wrong"));
+}
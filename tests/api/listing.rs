@@ -0,0 +1,129 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use uuid::Uuid;
+use crate::helpers::{TestApp, TestResponse};
+use crate::spawn_app;
+
+async fn insert_school(app: &TestApp, name: &str, province_id: &str) -> Uuid {
+    sqlx::query_scalar("INSERT INTO schools (name, address, logo_path, province_id) VALUES ($1, '', '', $2) RETURNING id")
+        .bind(name)
+        .bind(province_id)
+        .fetch_one(app.pool())
+        .await
+        .unwrap()
+}
+
+fn names(res: &TestResponse) -> Vec<String> {
+    res.data().as_array().unwrap().iter().map(|row| row["name"].as_str().unwrap().to_string()).collect()
+}
+
+#[actix_web::test]
+async fn schools_filter_sort_and_search() {
+    let app = spawn_app!();
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA'), ('32', 'JAWA BARAT')").execute(app.pool()).await.unwrap();
+    insert_school(&app, "SMA Negeri 1 Jakarta", "31").await;
+    insert_school(&app, "SMA Negeri 8 Jakarta", "31").await;
+    insert_school(&app, "SMK Negeri 2 Bandung", "32").await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?filter%5Bprovince_id%5D=31&sort=-name")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(names(&res), vec!["SMA Negeri 8 Jakarta", "SMA Negeri 1 Jakarta"]);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 2);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?q=negeri%202&page_size=1")).await;
+    assert_eq!(names(&res), vec!["SMK Negeri 2 Bandung"]);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+
+    // Wildcards in the term are matched literally.
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?q=%25")).await;
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 0);
+}
+
+#[actix_web::test]
+async fn users_search_names_and_emails() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let role_id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].clone();
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA')").execute(app.pool()).await.unwrap();
+    let school_id = insert_school(&app, "SMA 1", "31").await;
+    for (name, email, phone_number) in [("Siti", "siti@example.com", "0811"), ("Budi", "budi@sekolah.id", "0812")] {
+        app.call(TestRequest::post().uri("/api/v1/users").insert_header(app.super_admin_auth()).set_json(json!({
+            "name": name,
+            "email": email,
+            "phone_number": phone_number,
+            "password": "secret",
+            "role_id": role_id,
+            "school_id": school_id,
+        }))).await;
+    }
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?q=SEKOLAH").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(names(&res), vec!["Budi"]);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?sort=name&filter%5Bstatus%5D=pending").insert_header(app.super_admin_auth())).await;
+    assert_eq!(names(&res), vec!["Budi", "Siti"]);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users?filter%5Bstatus%5D=verified").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 0);
+}
+
+#[actix_web::test]
+async fn listings_reject_unknown_fields() {
+    let app = spawn_app!();
+
+    for uri in [
+        "/api/v1/schools?sort=logo_path",
+        "/api/v1/schools?filter%5Bsubscription_id%5D=not-a-uuid",
+        "/api/v1/subscriptions?q=basic",
+        "/api/v1/roles?filter%5Bname%5D=teacher",
+    ] {
+        let res = app.call(TestRequest::get().uri(uri)).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn cursors_walk_a_listing_without_gaps_or_repeats() {
+    let app = spawn_app!();
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA')").execute(app.pool()).await.unwrap();
+    for name in ["SMA 2", "SMA 4", "SMA 6", "SMA 8", "SMA 9"] {
+        insert_school(&app, name, "31").await;
+    }
+
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?sort=name&page_size=2&include_total=false")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(names(&res), vec!["SMA 2", "SMA 4"]);
+    assert!(res.body["meta"]["pagination"]["total_data"].is_null());
+    assert!(res.body["meta"]["pagination"]["prev_cursor"].is_null());
+    let next = res.body["meta"]["pagination"]["next_cursor"].as_str().unwrap().to_string();
+
+    // Rows added before the cursor don't shift the pages after it.
+    insert_school(&app, "SMA 1", "31").await;
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools?sort=name&page_size=2&cursor={}", next))).await;
+    assert_eq!(names(&res), vec!["SMA 6", "SMA 8"]);
+    assert!(res.body["meta"]["pagination"]["page"].is_null());
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 6);
+    let prev = res.body["meta"]["pagination"]["prev_cursor"].as_str().unwrap().to_string();
+    let next = res.body["meta"]["pagination"]["next_cursor"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools?sort=name&page_size=2&cursor={}", next))).await;
+    assert_eq!(names(&res), vec!["SMA 9"]);
+    assert!(res.body["meta"]["pagination"]["next_cursor"].is_null());
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools?sort=name&page_size=2&cursor={}", prev))).await;
+    assert_eq!(names(&res), vec!["SMA 2", "SMA 4"]);
+    let prev = res.body["meta"]["pagination"]["prev_cursor"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools?sort=name&page_size=2&cursor={}", prev))).await;
+    assert_eq!(names(&res), vec!["SMA 1"]);
+    assert!(res.body["meta"]["pagination"]["prev_cursor"].is_null());
+
+    // A cursor only fits the sort it was made for.
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools?sort=-name&cursor={}", next))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    let res = app.call(TestRequest::get().uri("/api/v1/schools?cursor=garbage")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,26 @@
+mod helpers;
+mod audit_logs;
+mod auth;
+mod commands;
+mod events;
+mod fields;
+mod health;
+mod idempotency;
+mod includes;
+mod jobs;
+mod listing;
+mod metrics;
+mod preconditions;
+mod regions;
+mod request_ids;
+mod roles;
+mod schedules;
+mod schema;
+mod schools;
+mod subscription_types;
+mod subscriptions;
+mod transactions;
+mod trash;
+mod users;
+mod versioning;
+mod webhooks;
@@ -0,0 +1,48 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn metrics_label_requests_by_route_pattern() {
+    let app = spawn_app!();
+    app.call(TestRequest::get().uri("/api/v1/roles/00000000-0000-0000-0000-000000000001")).await;
+    app.call(TestRequest::get().uri("/api/v1/roles/00000000-0000-0000-0000-000000000002")).await;
+
+    let res = app.call(TestRequest::get().uri("/metrics")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    let body = res.body.as_str().unwrap();
+    assert!(body.contains(r#"http_requests_total{method="GET",route="/api/v1/roles/{id}",status="404"} 2"#), "{}", body);
+    assert!(body.contains("http_request_duration_seconds_bucket"));
+    assert!(body.contains("db_pool_connections"));
+    assert!(body.contains("db_pool_acquire_wait_seconds"));
+}
+
+#[actix_web::test]
+async fn metrics_count_business_events() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(json!({
+        "name": "Budi",
+        "email": "budi@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    }))).await;
+
+    let res = app.call(TestRequest::get().uri("/metrics")).await;
+
+    let body = res.body.as_str().unwrap();
+    assert!(body.contains("sekula_registrations_total 1"), "{}", body);
+}
+
+#[actix_web::test]
+async fn metrics_need_no_auth() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/metrics")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.headers.get("content-type").unwrap().to_str().unwrap().starts_with("text/plain"));
+}
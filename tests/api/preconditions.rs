@@ -0,0 +1,112 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::test::TestRequest;
+use chrono::Utc;
+use serde_json::json;
+use uuid::Uuid;
+use sekula_be::internal::entities::school::School;
+use crate::helpers::{TestApp, TestResponse};
+use crate::spawn_app;
+
+async fn create_role(app: &TestApp) -> String {
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let res = app.call(TestRequest::get().uri("/api/v1/roles")).await;
+    res.data()[0]["id"].as_str().unwrap().to_string()
+}
+
+fn etag(res: &TestResponse) -> String {
+    res.headers.get(header::ETAG).unwrap().to_str().unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn writes_require_if_match() {
+    let app = spawn_app!();
+    let id = create_role(&app).await;
+    let uri = format!("/api/v1/roles/{}", id);
+
+    let res = app.call(TestRequest::put().uri(&uri).set_json(json!({"name": "headmaster"}))).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.call(TestRequest::patch().uri(&uri).set_json(json!({"name": "headmaster"}))).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.call(TestRequest::delete().uri(&uri)).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_REQUIRED);
+
+    let res = app.call(TestRequest::get().uri(&uri)).await;
+    assert_eq!(res.data()["name"], "teacher");
+}
+
+#[actix_web::test]
+async fn stale_etags_are_rejected() {
+    let app = spawn_app!();
+    let id = create_role(&app).await;
+    let uri = format!("/api/v1/roles/{}", id);
+
+    let res = app.call(TestRequest::get().uri(&uri)).await;
+    let first = etag(&res);
+    assert_eq!(first, "\"1\"");
+
+    let res = app.call(TestRequest::put().uri(&uri).insert_header((header::IF_MATCH, first.as_str())).set_json(json!({"name": "headmaster"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let second = etag(&res);
+    assert_eq!(second, "\"2\"");
+    assert_eq!(res.data()["version"], 2);
+
+    // A second admin still holding the first version loses.
+    let res = app.call(TestRequest::put().uri(&uri).insert_header((header::IF_MATCH, first.as_str())).set_json(json!({"name": "principal"}))).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+
+    let res = app.call(TestRequest::delete().uri(&uri).insert_header((header::IF_MATCH, first.as_str()))).await;
+    assert_eq!(res.status, StatusCode::PRECONDITION_FAILED);
+
+    let res = app.call(TestRequest::get().uri(&uri)).await;
+    assert_eq!(res.data()["name"], "headmaster");
+
+    let res = app.call(TestRequest::delete().uri(&uri).insert_header((header::IF_MATCH, second.as_str()))).await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn school_updates_keep_omitted_fields_and_patch_clears_nulls() {
+    let app = spawn_app!();
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA')").execute(app.pool()).await.unwrap();
+    sqlx::query("INSERT INTO cities (id, name, province_id) VALUES ('31.71', 'KOTA JAKARTA PUSAT', '31')").execute(app.pool()).await.unwrap();
+    let school = app.state.repositories.school.create(&School {
+        id: Uuid::new_v4(),
+        name: "SMA 1".to_string(),
+        address: "Jl. Merdeka 1".to_string(),
+        logo_path: "".to_string(),
+        subscription_id: None,
+        subscription_ends_at: None,
+        province_id: Some("31".to_string()),
+        city_id: Some("31.71".to_string()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        deleted_at: None,
+        version: 1,
+    }).await.unwrap();
+    let uri = format!("/api/v1/schools/{}", school.id);
+
+    let res = app.call(TestRequest::put().uri(&uri).insert_header((header::IF_MATCH, "\"1\"")).set_json(json!({"name": "SMA 2"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "SMA 2");
+    assert_eq!(res.data()["province_id"], "31");
+    assert_eq!(res.data()["city_id"], "31.71");
+
+    let res = app.call(TestRequest::patch().uri(&uri).insert_header((header::IF_MATCH, "\"2\"")).set_json(json!({
+        "address": "Jl. Sudirman 2",
+        "city_id": null,
+    }))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(etag(&res), "\"3\"");
+    assert_eq!(res.data()["name"], "SMA 2");
+    assert_eq!(res.data()["address"], "Jl. Sudirman 2");
+    assert_eq!(res.data()["province_id"], "31");
+    assert_eq!(res.data()["city_id"], serde_json::Value::Null);
+
+    let res = app.call(TestRequest::patch().uri(&uri).insert_header((header::IF_MATCH, "*")).set_json(json!({"version": 9}))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = app.call(TestRequest::patch().uri(&uri).insert_header((header::IF_MATCH, "*")).set_json(json!({"name": null}))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,81 @@
+use tokio_util::sync::CancellationToken;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+// Provinces and cities are normally synced from wilayah.id; seed them directly.
+async fn seed_regions(app: &TestApp) {
+    sqlx::query("INSERT INTO provinces (id, name) VALUES ('31', 'DKI JAKARTA'), ('32', 'JAWA BARAT')")
+        .execute(app.pool())
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO cities (id, name, province_id) VALUES ('31.71', 'KOTA JAKARTA PUSAT', '31')")
+        .execute(app.pool())
+        .await
+        .unwrap();
+}
+
+#[actix_web::test]
+async fn province_list_and_get() {
+    let app = spawn_app!();
+    seed_regions(&app).await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/provinces")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data().as_array().unwrap().len(), 2);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/provinces/31")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "DKI JAKARTA");
+
+    let res = app.call(TestRequest::get().uri("/api/v1/provinces/99")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn city_list_and_get() {
+    let app = spawn_app!();
+    seed_regions(&app).await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/cities")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data().as_array().unwrap().len(), 1);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/cities/31.71")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["province_id"], "31");
+
+    let res = app.call(TestRequest::get().uri("/api/v1/cities/99.99")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn city_sync_runs_as_a_job() {
+    let app = spawn_app!();
+    let auth = app.super_admin_auth();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/cities")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::post().uri("/api/v1/provinces")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs").insert_header(auth.clone())).await;
+    assert!(res.data().as_array().unwrap().is_empty());
+
+    let res = app.call(TestRequest::post().uri("/api/v1/cities").insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::ACCEPTED);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/jobs").insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let job = &res.data()[0];
+    assert_eq!(job["kind"], "sync_cities");
+    assert_eq!(job["status"], "pending");
+    let uri = format!("/api/v1/admin/jobs/{}", job["id"].as_str().unwrap());
+
+    // No provinces are stored, so the sync has nothing to fetch and succeeds.
+    assert_eq!(app.state.workers.run_once(&CancellationToken::new()).await.unwrap(), 1);
+
+    let res = app.call(TestRequest::get().uri(&uri).insert_header(auth)).await;
+    assert_eq!(res.data()["status"], "succeeded");
+    assert_eq!(res.data()["attempts"], 1);
+}
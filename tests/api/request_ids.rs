@@ -0,0 +1,51 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn request_id_is_generated_when_missing() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles")).await;
+
+    let header = res.headers.get("x-request-id").unwrap().to_str().unwrap();
+    assert!(!header.is_empty());
+    assert_eq!(res.body["request_id"], header);
+}
+
+#[actix_web::test]
+async fn request_id_is_propagated_to_response_header() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles").insert_header(("X-Request-Id", "req-42"))).await;
+
+    assert_eq!(res.headers.get("x-request-id").unwrap(), "req-42");
+}
+
+#[actix_web::test]
+async fn unusable_request_id_is_replaced() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles").insert_header(("X-Request-Id", "a".repeat(500)))).await;
+
+    let header = res.headers.get("x-request-id").unwrap().to_str().unwrap();
+    assert_ne!(header, "a".repeat(500));
+    assert_eq!(res.body["request_id"], header);
+}
+
+#[actix_web::test]
+async fn request_id_is_in_middleware_errors() {
+    let app = spawn_app!();
+
+    // "admin:wrong"
+    let res = app.call(
+        TestRequest::get()
+            .uri("/api/v1/users")
+            .insert_header(("Authorization", "Basic YWRtaW46d3Jvbmc="))
+            .insert_header(("X-Request-Id", "req-43")),
+    ).await;
+
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.body["request_id"], "req-43");
+    assert_eq!(res.headers.get("x-request-id").unwrap(), "req-43");
+}
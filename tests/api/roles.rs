@@ -0,0 +1,41 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn role_crud() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+    let id = res.data()[0]["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/roles/{}", id)).insert_header((header::IF_MATCH, "*")).set_json(json!({"name": "headmaster"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/roles/{}", id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "headmaster");
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/roles/{}", id)).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/roles/{}", id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn role_get_rejects_malformed_id() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles/not-a-uuid")).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["meta"]["code"], 400);
+}
@@ -0,0 +1,148 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use chrono::{Duration, Utc};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn schedules_show_last_and_next_run() {
+    let app = spawn_app!();
+    let auth = app.super_admin_auth();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/schedules").insert_header(auth.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let names: Vec<&str> = res.data().as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["sync_regions", "purge_deleted", "clean_uploads", "check_subscription_expiry"]);
+    assert_eq!(res.data()[0]["cron"], "0 0 2 * * *");
+    assert!(res.data()[0]["last_run_at"].is_null());
+    assert!(res.data()[0]["next_run_at"].is_string());
+
+    // Two days on, every daily schedule is due.
+    let queued = app.state.scheduler.run_due(Utc::now() + Duration::days(2)).await.unwrap();
+    assert_eq!(queued.len(), 4);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/schedules").insert_header(auth)).await;
+    let purge = &res.data()[1];
+    assert_eq!(purge["last_status"], "pending");
+    assert_eq!(purge["last_job_id"], queued[1].id.to_string());
+    assert!(purge["next_run_at"].as_str().unwrap() > purge["last_run_at"].as_str().unwrap());
+}
+
+#[actix_web::test]
+async fn schedules_require_super_admin() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/schedules")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/schedules").insert_header(("Authorization", "Basic YWRtaW46d3Jvbmc="))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn only_one_instance_leads() {
+    let app = spawn_app!();
+    let schedules = &app.state.repositories.schedule;
+
+    let mut leader = schedules.try_lead().await.unwrap().expect("first instance leads");
+    assert!(leader.is_held().await);
+    assert!(schedules.try_lead().await.unwrap().is_none());
+
+    leader.release().await;
+    assert!(schedules.try_lead().await.unwrap().is_some());
+}
+
+#[actix_web::test]
+async fn purge_keeps_recent_and_referenced_rows() {
+    let app = spawn_app!();
+    let role_id: Uuid = sqlx::query_scalar("INSERT INTO roles (name, deleted_at) VALUES ('teacher', NOW() - INTERVAL '90 days') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, deleted_at) VALUES ('SMA 1', NOW() - INTERVAL '90 days') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    for (email, deleted_days) in [("old@example.com", 90), ("recent@example.com", 1)] {
+        sqlx::query(
+            "INSERT INTO users (name, email, phone_number, password, title, role_id, school_id, deleted_at)
+             VALUES ('u', $1, $1, '', '', $2, $3, NOW() - make_interval(days => $4))",
+        )
+        .bind(email)
+        .bind(role_id)
+        .bind(school_id)
+        .bind(deleted_days)
+        .execute(app.pool())
+        .await
+        .unwrap();
+    }
+
+    app.state.scheduler.run_due(Utc::now() + Duration::days(2)).await.unwrap();
+    // Only run the purge; the region sync needs the network.
+    sqlx::query("UPDATE jobs SET status = 'cancelled' WHERE kind <> 'purge_deleted'").execute(app.pool()).await.unwrap();
+    assert_eq!(app.state.workers.run_once(&CancellationToken::new()).await.unwrap(), 1);
+
+    let users: Vec<String> = sqlx::query_scalar("SELECT email FROM users").fetch_all(app.pool()).await.unwrap();
+    assert_eq!(users, ["recent@example.com"]);
+    // Still referenced by the recently deleted user.
+    let schools: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM schools").fetch_one(app.pool()).await.unwrap();
+    let roles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM roles").fetch_one(app.pool()).await.unwrap();
+    assert_eq!((schools, roles), (1, 1));
+}
+
+#[actix_web::test]
+async fn clean_uploads_removes_old_orphans() {
+    let app = spawn_app!();
+    let old = Utc::now() - Duration::days(2);
+    app.storage.put("school-logo/used.png", b"used", old);
+    app.storage.put("school-logo/orphan.png", b"orphan", old);
+    app.storage.put("school-logo/fresh.png", b"fresh", Utc::now());
+    sqlx::query("INSERT INTO schools (name, logo_path) VALUES ('SMA 1', 'school-logo/used.png')")
+        .execute(app.pool())
+        .await
+        .unwrap();
+
+    app.state.scheduler.run_due(Utc::now() + Duration::days(2)).await.unwrap();
+    sqlx::query("UPDATE jobs SET status = 'cancelled' WHERE kind <> 'clean_uploads'").execute(app.pool()).await.unwrap();
+    assert_eq!(app.state.workers.run_once(&CancellationToken::new()).await.unwrap(), 1);
+
+    assert!(app.storage.get("school-logo/used.png").is_some());
+    assert!(app.storage.get("school-logo/orphan.png").is_none());
+    assert!(app.storage.get("school-logo/fresh.png").is_some());
+}
+
+#[actix_web::test]
+async fn expired_subscriptions_are_cleared() {
+    let app = spawn_app!();
+    let subscription_type_id: Uuid = sqlx::query_scalar("INSERT INTO subscription_types (name) VALUES ('Monthly') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    let subscription_id: Uuid = sqlx::query_scalar("INSERT INTO subscriptions (name, price, subscription_type_id) VALUES ('Basic', 100000, $1) RETURNING id")
+        .bind(subscription_type_id)
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    for (name, ends_in_days) in [("SMA 1", Some(1)), ("SMA 2", Some(5)), ("SMA 3", None)] {
+        sqlx::query("INSERT INTO schools (name, subscription_id, subscription_ends_at) VALUES ($1, $2, $3)")
+            .bind(name)
+            .bind(subscription_id)
+            .bind(ends_in_days.map(|days| Utc::now() + Duration::days(days)))
+            .execute(app.pool())
+            .await
+            .unwrap();
+    }
+
+    app.state.scheduler.run_due(Utc::now() + Duration::days(2)).await.unwrap();
+    sqlx::query("UPDATE jobs SET status = 'cancelled' WHERE kind <> 'check_subscription_expiry'").execute(app.pool()).await.unwrap();
+    assert_eq!(app.state.workers.run_once(&CancellationToken::new()).await.unwrap(), 1);
+
+    let schools: Vec<(String, Option<Uuid>)> = sqlx::query_as("SELECT name, subscription_id FROM schools ORDER BY name")
+        .fetch_all(app.pool())
+        .await
+        .unwrap();
+    assert_eq!(schools, [("SMA 1".to_string(), None), ("SMA 2".to_string(), Some(subscription_id)), ("SMA 3".to_string(), Some(subscription_id))]);
+
+    let events: Vec<String> = sqlx::query_scalar("SELECT event_type FROM outbox_events").fetch_all(app.pool()).await.unwrap();
+    assert_eq!(events, ["school.subscription_expired"]);
+}
@@ -0,0 +1,19 @@
+use sekula_be::database::schema::check_schema;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn migrated_schema_matches_entities() {
+    let app = spawn_app!();
+
+    assert!(check_schema(app.pool()).await.is_ok());
+}
+
+#[actix_web::test]
+async fn drift_is_reported() {
+    let app = spawn_app!();
+    sqlx::query("ALTER TABLE users DROP COLUMN title").execute(app.pool()).await.unwrap();
+
+    let err = check_schema(app.pool()).await.unwrap_err();
+
+    assert!(err.to_string().contains("column users.title is missing"));
+}
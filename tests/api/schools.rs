@@ -0,0 +1,85 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+const BOUNDARY: &str = "sekula-test-boundary";
+
+fn multipart(fields: &[(&str, &str)], logo: Option<&[u8]>) -> TestRequest {
+    let mut body = Vec::new();
+    for (name, value) in fields {
+        body.extend_from_slice(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            BOUNDARY, name, value
+        ).as_bytes());
+    }
+    if let Some(bytes) = logo {
+        body.extend_from_slice(format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"logo\"; filename=\"logo.png\"\r\nContent-Type: image/png\r\n\r\n",
+            BOUNDARY
+        ).as_bytes());
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+
+    TestRequest::post()
+        .uri("/api/v1/schools")
+        .insert_header((header::CONTENT_TYPE, format!("multipart/form-data; boundary={}", BOUNDARY)))
+        .set_payload(body)
+}
+
+async fn only_school(app: &TestApp) -> serde_json::Value {
+    let res = app.call(TestRequest::get().uri("/api/v1/schools")).await;
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+    res.data()[0].clone()
+}
+
+#[actix_web::test]
+async fn school_crud() {
+    let app = spawn_app!();
+
+    let res = app.call(multipart(&[("name", "SMA 1"), ("address", "Jl. Merdeka 1")], None)).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let school = only_school(&app).await;
+    let id = school["id"].as_str().unwrap().to_string();
+    assert_eq!(school["address"], "Jl. Merdeka 1");
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/schools/{}", id)).insert_header((header::IF_MATCH, "*")).set_json(json!({"name": "SMA 2"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools/{}", id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "SMA 2");
+    assert_eq!(res.data()["address"], "Jl. Merdeka 1");
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/schools/{}", id)).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/schools/{}", id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn school_create_uploads_logo() {
+    let app = spawn_app!();
+
+    let res = app.call(multipart(&[("name", "SMA 1")], Some(b"png-bytes"))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let school = only_school(&app).await;
+    let logo_path = school["logo_path"].as_str().unwrap();
+    assert_eq!(app.storage.get(logo_path).as_deref(), Some(&b"png-bytes"[..]));
+}
+
+#[actix_web::test]
+async fn school_create_requires_name() {
+    let app = spawn_app!();
+
+    let res = app.call(multipart(&[("name", " ")], None)).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,42 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn subscription_type_crud() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscription_types").set_json(json!({"name": "Monthly"}))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/subscription_types")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+    let id = res.data()[0]["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/subscription_types/{}", id)).insert_header((header::IF_MATCH, "*")).set_json(json!({"name": "Yearly"}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscription_types/{}", id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "Yearly");
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/subscription_types/{}", id)).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscription_types/{}", id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn subscription_type_rejects_duplicate_name() {
+    let app = spawn_app!();
+
+    let body = json!({"name": "Monthly"});
+    app.call(TestRequest::post().uri("/api/v1/subscription_types").set_json(&body)).await;
+    let res = app.call(TestRequest::post().uri("/api/v1/subscription_types").set_json(&body)).await;
+
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+}
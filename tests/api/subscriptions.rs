@@ -0,0 +1,71 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn subscription_crud() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").set_json(json!({
+        "name": "Basic",
+        "price": 100000,
+        "subscription_type_id": subscription_type_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = app.call(TestRequest::get().uri("/api/v1/subscriptions")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let id = res.data()[0]["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/subscriptions/{}", id)).insert_header((header::IF_MATCH, "*")).set_json(json!({"price": 150000}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscriptions/{}", id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["name"], "Basic");
+    assert_eq!(res.data()["price"], 150000);
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/subscriptions/{}", id)).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/subscriptions/{}", id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn subscription_create_validates_input() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").set_json(json!({
+        "name": "Basic",
+        "price": 0,
+        "subscription_type_id": subscription_type_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscriptions").set_json(json!({
+        "name": "Basic",
+        "price": 100000,
+        "subscription_type_id": uuid::Uuid::new_v4(),
+    }))).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["errors"][0]["message"], "Subscription type ID not found");
+}
+
+#[actix_web::test]
+async fn subscription_list_as_csv() {
+    let app = spawn_app!();
+    let subscription_type_id = app.create_subscription_type("Monthly").await;
+    app.create_subscription(&subscription_type_id, "Basic", 100000).await;
+
+    let res = app.call(TestRequest::get().uri("/api/v1/subscriptions").insert_header(("Accept", "text/csv"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    let csv = res.body.as_str().unwrap();
+    assert!(csv.starts_with("id,name,price,subscription_type_id"));
+    assert!(csv.contains("Basic,100000"));
+}
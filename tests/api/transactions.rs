@@ -0,0 +1,108 @@
+use std::panic::AssertUnwindSafe;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use chrono::Utc;
+use futures_util::FutureExt;
+use serde_json::json;
+use sqlx::Error;
+use uuid::Uuid;
+use sekula_be::internal::app::repositories::db_transaction_repository::with_transaction;
+use sekula_be::internal::entities::role::Role;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+fn role(name: &str) -> Role {
+    Role { id: Uuid::new_v4(), name: name.to_string(), created_at: Utc::now(), updated_at: Utc::now(), deleted_at: None, version: 1 }
+}
+
+async fn role_names(app: &TestApp) -> Vec<String> {
+    sqlx::query_scalar("SELECT name FROM roles ORDER BY name").fetch_all(app.pool()).await.unwrap()
+}
+
+async fn count(app: &TestApp, table: &str) -> i64 {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table)).fetch_one(app.pool()).await.unwrap()
+}
+
+#[actix_web::test]
+async fn writes_are_invisible_until_commit_and_undone_by_errors() {
+    let app = spawn_app!();
+    let r = &app.state.repositories;
+
+    with_transaction(&*r.db_transaction, |tx| async move {
+        tx.role.create(&role("admin")).await?;
+        // Reads in the transaction see the write; the pool doesn't yet.
+        assert_eq!(tx.role.get_by_name("admin".to_string()).await?.name, "admin");
+        assert!(r.role.get_by_name("admin".to_string()).await.is_err());
+        Ok::<(), Error>(())
+    }).await.unwrap();
+
+    let result: Result<(), Error> = with_transaction(&*r.db_transaction, |tx| async move {
+        tx.role.create(&role("user")).await?;
+        tx.role.create(&role("user")).await
+    }).await;
+
+    assert!(result.is_err());
+    assert_eq!(role_names(&app).await, ["admin"]);
+}
+
+#[actix_web::test]
+async fn panics_roll_back() {
+    let app = spawn_app!();
+    let r = &app.state.repositories;
+
+    let result = AssertUnwindSafe(with_transaction(&*r.db_transaction, |tx| async move {
+        tx.role.create(&role("admin")).await?;
+        panic!("boom");
+        #[allow(unreachable_code)]
+        Ok::<(), Error>(())
+    })).catch_unwind().await;
+
+    assert!(result.is_err());
+    assert!(role_names(&app).await.is_empty());
+}
+
+#[actix_web::test]
+async fn nested_transactions_use_savepoints() {
+    let app = spawn_app!();
+    let r = &app.state.repositories;
+
+    with_transaction(&*r.db_transaction, |tx| async move {
+        tx.role.create(&role("admin")).await?;
+        let failed: Result<(), Error> = with_transaction(&*tx.db_transaction, |tx| async move {
+            tx.role.create(&role("user")).await?;
+            Err(Error::RowNotFound)
+        }).await;
+        assert!(failed.is_err());
+        with_transaction(&*tx.db_transaction, |tx| async move { tx.role.create(&role("teacher")).await }).await?;
+        Ok::<(), Error>(())
+    }).await.unwrap();
+
+    assert_eq!(role_names(&app).await, ["admin", "teacher"]);
+}
+
+#[actix_web::test]
+async fn failed_registration_leaves_no_school_behind() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    // Fails the user insert, which comes after the school insert.
+    sqlx::query("CREATE FUNCTION reject_users() RETURNS trigger AS $$ BEGIN RAISE EXCEPTION 'rejected'; END $$ LANGUAGE plpgsql")
+        .execute(app.pool())
+        .await
+        .unwrap();
+    sqlx::query("CREATE TRIGGER reject_users BEFORE INSERT ON users FOR EACH ROW EXECUTE FUNCTION reject_users()")
+        .execute(app.pool())
+        .await
+        .unwrap();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(json!({
+        "name": "Budi",
+        "email": "budi@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(count(&app, "schools").await, 0);
+    assert_eq!(count(&app, "outbox_events").await, 0);
+}
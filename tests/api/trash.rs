@@ -0,0 +1,114 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::test::TestRequest;
+use serde_json::json;
+use crate::helpers::{TestApp, TestResponse};
+use crate::spawn_app;
+
+async fn create_role(app: &TestApp, name: &str) -> String {
+    app.call(TestRequest::post().uri("/api/v1/roles").insert_header(app.super_admin_auth()).set_json(json!({"name": name}))).await;
+    let res = app.call(TestRequest::get().uri("/api/v1/roles")).await;
+    let role = res.data().as_array().unwrap().iter().find(|role| role["name"] == name).unwrap();
+    role["id"].as_str().unwrap().to_string()
+}
+
+fn names(res: &TestResponse) -> Vec<String> {
+    res.data().as_array().unwrap().iter().map(|row| row["name"].as_str().unwrap().to_string()).collect()
+}
+
+#[actix_web::test]
+async fn deleted_rows_are_listed_in_the_trash_until_restored() {
+    let app = spawn_app!();
+    create_role(&app, "admin").await;
+    let id = create_role(&app, "teacher").await;
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/roles/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let deleted_at: Option<String> = sqlx::query_scalar("SELECT deleted_at::text FROM roles WHERE name = 'teacher'")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+    assert!(deleted_at.is_some());
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles")).await;
+    assert_eq!(names(&res), ["admin"]);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+    let res = app.call(TestRequest::get().uri("/api/v1/roles?trashed=only")).await;
+    assert_eq!(names(&res), ["teacher"]);
+    let res = app.call(TestRequest::get().uri("/api/v1/roles?trashed=with")).await;
+    assert_eq!(names(&res), ["admin", "teacher"]);
+
+    let res = app.call(TestRequest::post().uri(&format!("/api/v1/roles/{}/restore", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/roles/{}", id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let res = app.call(TestRequest::get().uri("/api/v1/roles?trashed=only")).await;
+    assert!(names(&res).is_empty());
+
+    // Only what is in the trash can be restored.
+    let res = app.call(TestRequest::post().uri(&format!("/api/v1/roles/{}/restore", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn deleted_names_can_be_reused_but_then_block_the_restore() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/subscription_types").insert_header(app.super_admin_auth()).set_json(json!({"name": "Monthly"}))).await;
+    let res = app.call(TestRequest::get().uri("/api/v1/subscription_types")).await;
+    let id = res.data()[0]["id"].as_str().unwrap().to_string();
+    app.call(TestRequest::delete().uri(&format!("/api/v1/subscription_types/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*"))).await;
+
+    let res = app.call(TestRequest::post().uri("/api/v1/subscription_types").insert_header(app.super_admin_auth()).set_json(json!({"name": "Monthly"}))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = app.call(TestRequest::post().uri(&format!("/api/v1/subscription_types/{}/restore", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn purge_is_for_super_admins_and_spares_referenced_rows() {
+    let app = spawn_app!();
+    let id = create_role(&app, "teacher").await;
+    let purge = || TestRequest::delete().uri(&format!("/api/v1/roles/{}/purge", id));
+
+    // Live rows have to go to the trash first.
+    let res = app.call(purge().insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    app.call(TestRequest::delete().uri(&format!("/api/v1/roles/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*"))).await;
+    let res = app.call(purge()).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(purge().insert_header((header::AUTHORIZATION, "Basic bm9ib2R5Om5vcGU="))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = app.call(purge().insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let roles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM roles").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(roles, 0);
+    let actions: Vec<String> = sqlx::query_scalar("SELECT action::text FROM audit_logs ORDER BY created_at")
+        .fetch_all(app.pool())
+        .await
+        .unwrap();
+    assert_eq!(actions, ["created", "deleted", "purged"]);
+
+    // A subscription type that subscriptions still point at stays put.
+    let type_id = app.create_subscription_type("Monthly").await;
+    app.create_subscription(&type_id, "Basic", 100000).await;
+    app.call(TestRequest::delete().uri(&format!("/api/v1/subscription_types/{}", type_id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*"))).await;
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/subscription_types/{}/purge", type_id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+}
+
+#[actix_web::test]
+async fn every_purge_refuses_requests_without_credentials() {
+    let app = spawn_app!();
+    let id = create_role(&app, "teacher").await;
+    app.call(TestRequest::delete().uri(&format!("/api/v1/roles/{}", id)).insert_header(app.super_admin_auth()).insert_header((header::IF_MATCH, "*"))).await;
+
+    for resource in ["roles", "schools", "users", "subscription_types", "subscriptions"] {
+        let res = app.call(TestRequest::delete().uri(&format!("/api/v1/{}/{}/purge", resource, id))).await;
+        assert_eq!(res.status, StatusCode::UNAUTHORIZED, "{}", resource);
+    }
+    let roles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM roles").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(roles, 1);
+}
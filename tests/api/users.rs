@@ -0,0 +1,66 @@
+use actix_web::http::header;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+use uuid::Uuid;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn users_reject_wrong_basic_credentials() {
+    let app = spawn_app!();
+
+    // "admin:wrong"
+    let res = app.call(TestRequest::get().uri("/api/v1/users").insert_header(("Authorization", "Basic YWRtaW46d3Jvbmc="))).await;
+
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn users_accept_super_admin() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users/not-a-uuid").insert_header(app.super_admin_auth())).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.body["errors"][0]["message"], "Invalid user id");
+}
+
+#[actix_web::test]
+async fn user_crud() {
+    let app = spawn_app!();
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "teacher"}))).await;
+    let role_id = app.call(TestRequest::get().uri("/api/v1/roles")).await.data()[0]["id"].clone();
+    let school_id: Uuid = sqlx::query_scalar("INSERT INTO schools (name, address, logo_path) VALUES ('SMA 1', '', '') RETURNING id")
+        .fetch_one(app.pool())
+        .await
+        .unwrap();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/users").insert_header(app.super_admin_auth()).set_json(json!({
+        "name": "Siti",
+        "email": "siti@example.com",
+        "phone_number": "0813",
+        "password": "secret",
+        "role_id": role_id,
+        "school_id": school_id,
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let id = res.data()["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::put().uri(&format!("/api/v1/users/{}", id)).insert_header((header::IF_MATCH, "*")).insert_header(app.super_admin_auth()).set_json(json!({
+        "title": "Teacher",
+        "status": "Verified",
+    }))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["title"], "Teacher");
+    assert_eq!(res.data()["status"], "Verified");
+
+    let res = app.call(TestRequest::get().uri("/api/v1/users").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["meta"]["pagination"]["total_data"], 1);
+
+    let res = app.call(TestRequest::delete().uri(&format!("/api/v1/users/{}", id)).insert_header((header::IF_MATCH, "*")).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/users/{}", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
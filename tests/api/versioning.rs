@@ -0,0 +1,23 @@
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use crate::spawn_app;
+
+#[actix_web::test]
+async fn legacy_paths_are_deprecated() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/roles")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("Deprecation").unwrap(), "true");
+    assert_eq!(res.headers.get("Link").unwrap(), r#"</api/v1/roles>; rel="successor-version""#);
+}
+
+#[actix_web::test]
+async fn request_id_is_echoed() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::get().uri("/api/v1/roles").insert_header(("X-Request-Id", "req-42"))).await;
+
+    assert_eq!(res.body["request_id"], "req-42");
+}
@@ -0,0 +1,216 @@
+use std::sync::{Arc, Mutex};
+use actix_web::http::header::HeaderMap;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use actix_web::web::Bytes;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+use sekula_be::internal::app::events::webhooks::sign;
+use crate::helpers::TestApp;
+use crate::spawn_app;
+
+type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+// An endpoint on a local port that answers every request with `status` and
+// `reply`, and records what it was sent.
+fn receiver(status: StatusCode, reply: &'static str) -> (String, Received) {
+    let received: Received = Arc::default();
+    let recorded = received.clone();
+    let server = HttpServer::new(move || {
+        let recorded = recorded.clone();
+        App::new().default_service(web::to(move |req: HttpRequest, body: Bytes| {
+            let recorded = recorded.clone();
+            async move {
+                recorded.lock().unwrap().push((req.headers().clone(), body));
+                HttpResponse::build(status).body(reply)
+            }
+        }))
+    })
+        .workers(1)
+        .disable_signals()
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let url = format!("http://{}/hooks", server.addrs()[0]);
+    actix_web::rt::spawn(server.run());
+    (url, received)
+}
+
+async fn create_webhook(app: &TestApp, body: Value) -> Value {
+    let res = app.call(TestRequest::post().uri("/api/v1/admin/webhooks").insert_header(app.super_admin_auth()).set_json(body)).await;
+    assert_eq!(res.status, StatusCode::CREATED, "{}", res.body);
+    res.data().clone()
+}
+
+async fn deliveries(app: &TestApp, webhook_id: &str) -> Vec<Value> {
+    let uri = format!("/api/v1/admin/webhooks/{}/deliveries", webhook_id);
+    let res = app.call(TestRequest::get().uri(&uri).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    res.data().as_array().unwrap().clone()
+}
+
+async fn register(app: &TestApp) {
+    app.call(TestRequest::post().uri("/api/v1/roles").set_json(json!({"name": "user"}))).await;
+    let body = json!({
+        "name": "Budi",
+        "email": "budi@example.com",
+        "phone_number": "0811",
+        "password": "secret",
+        "school_name": "SMA 1",
+    });
+    let res = app.call(TestRequest::post().uri("/api/v1/auth/register").set_json(body)).await;
+    assert_eq!(res.status, StatusCode::OK);
+}
+
+// Relays the outbox and works the queue until nothing is due.
+async fn drain(app: &TestApp) {
+    app.state.relay.run_once().await.unwrap();
+    let cancel = CancellationToken::new();
+    while app.state.workers.run_once(&cancel).await.unwrap() > 0 {}
+}
+
+#[actix_web::test]
+async fn test_events_are_signed_and_logged() {
+    let app = spawn_app!();
+    let (url, received) = receiver(StatusCode::OK, "thanks");
+
+    let webhook = create_webhook(&app, json!({"url": url, "secret": "shh"})).await;
+    assert_eq!(webhook["secret"], "shh");
+    let id = webhook["id"].as_str().unwrap().to_string();
+
+    let res = app.call(TestRequest::get().uri(&format!("/api/v1/admin/webhooks/{}", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.data().get("secret").is_none());
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/webhooks").insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.data()[0]["id"], id.as_str());
+    assert!(res.data()[0].get("secret").is_none());
+
+    let res = app.call(TestRequest::post().uri(&format!("/api/v1/admin/webhooks/{}/test", id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.data()["status"], "succeeded");
+    assert_eq!(res.data()["response_status"], 200);
+    assert_eq!(res.data()["response_body"], "thanks");
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    let (headers, body) = &received[0];
+    let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+    assert_eq!(header("x-sekula-event"), "webhook.test");
+    assert_eq!(header("x-sekula-delivery"), res.data()["id"].as_str().unwrap());
+    let timestamp: i64 = header("x-sekula-timestamp").parse().unwrap();
+    assert_eq!(header("x-sekula-signature"), sign("shh", timestamp, body));
+    let payload: Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(payload["type"], "webhook.test");
+
+    let logged = deliveries(&app, &id).await;
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0]["attempts"], 1);
+}
+
+#[actix_web::test]
+async fn events_reach_the_webhooks_that_want_them() {
+    let app = spawn_app!();
+    let (url, received) = receiver(StatusCode::NO_CONTENT, "thanks");
+
+    let registered = create_webhook(&app, json!({"url": url, "event_types": ["user.registered"]})).await;
+    let subscriptions = create_webhook(&app, json!({"url": url, "event_types": ["subscription.changed"]})).await;
+    register(&app).await;
+    drain(&app).await;
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0.get("x-sekula-event").unwrap(), "user.registered");
+
+    let logged = deliveries(&app, registered["id"].as_str().unwrap()).await;
+    assert_eq!(logged.len(), 1);
+    assert_eq!(logged[0]["status"], "succeeded");
+    assert_eq!(logged[0]["response_status"], 204);
+    assert!(deliveries(&app, subscriptions["id"].as_str().unwrap()).await.is_empty());
+}
+
+#[actix_web::test]
+async fn failed_deliveries_stay_pending_for_a_retry() {
+    let app = spawn_app!();
+    let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR, "thanks");
+
+    let webhook = create_webhook(&app, json!({"url": url, "event_types": ["user.registered"]})).await;
+    register(&app).await;
+    drain(&app).await;
+
+    assert_eq!(received.lock().unwrap().len(), 1);
+    let logged = deliveries(&app, webhook["id"].as_str().unwrap()).await;
+    assert_eq!(logged[0]["status"], "pending");
+    assert_eq!(logged[0]["attempts"], 1);
+    assert_eq!(logged[0]["response_status"], 500);
+    assert_eq!(logged[0]["error"], "Endpoint responded with 500 Internal Server Error");
+
+    let due: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs WHERE kind = 'deliver_webhook' AND status = 'pending'")
+        .fetch_one(app.pool()).await.unwrap();
+    assert_eq!(due, 1);
+}
+
+#[actix_web::test]
+async fn retrying_a_dead_delivery_job_sends_it_again() {
+    let app = spawn_app!();
+    let (url, received) = receiver(StatusCode::INTERNAL_SERVER_ERROR, "nope");
+
+    let webhook = create_webhook(&app, json!({"url": url, "event_types": ["user.registered"]})).await;
+    register(&app).await;
+    drain(&app).await;
+    // As left by a delivery that failed its last attempt.
+    sqlx::query("UPDATE webhook_deliveries SET status = 'failed', attempts = 8").execute(app.pool()).await.unwrap();
+    let job_id: String = sqlx::query_scalar("UPDATE jobs SET status = 'dead', attempts = 8 WHERE kind = 'deliver_webhook' RETURNING id::text")
+        .fetch_one(app.pool()).await.unwrap();
+
+    let res = app.call(TestRequest::post().uri(&format!("/api/v1/admin/jobs/{}/retry", job_id)).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.status, StatusCode::OK, "{}", res.body);
+    let logged = deliveries(&app, webhook["id"].as_str().unwrap()).await;
+    assert_eq!(logged[0]["status"], "pending");
+    assert_eq!(logged[0]["attempts"], 0);
+
+    drain(&app).await;
+    assert_eq!(received.lock().unwrap().len(), 2);
+    let logged = deliveries(&app, webhook["id"].as_str().unwrap()).await;
+    assert_eq!(logged[0]["status"], "pending");
+    assert_eq!(logged[0]["attempts"], 1);
+}
+
+#[actix_web::test]
+async fn long_responses_are_cut_short() {
+    let app = spawn_app!();
+    let reply: &'static str = "x".repeat(1024 * 1024).leak();
+    let (url, _) = receiver(StatusCode::OK, reply);
+
+    let webhook = create_webhook(&app, json!({"url": url})).await;
+    let uri = format!("/api/v1/admin/webhooks/{}/test", webhook["id"].as_str().unwrap());
+    let res = app.call(TestRequest::post().uri(&uri).insert_header(app.super_admin_auth())).await;
+    assert_eq!(res.data()["status"], "succeeded");
+    assert_eq!(res.data()["response_body"].as_str().unwrap().len(), 1024);
+}
+
+#[actix_web::test]
+async fn invalid_webhooks_are_rejected() {
+    let app = spawn_app!();
+
+    for body in [
+        json!({"url": "ftp://example.com"}),
+        json!({"url": "https://example.com", "event_types": ["user.exploded"]}),
+        json!({"url": "https://example.com", "school_id": "6f1c1f1e-8f55-4d7e-9a53-0d3c1e0c4f11"}),
+    ] {
+        let res = app.call(TestRequest::post().uri("/api/v1/admin/webhooks").insert_header(app.super_admin_auth()).set_json(body)).await;
+        assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[actix_web::test]
+async fn webhooks_require_super_admin() {
+    let app = spawn_app!();
+
+    let res = app.call(TestRequest::post().uri("/api/v1/admin/webhooks").set_json(json!({"url": "https://example.com"}))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    let res = app.call(TestRequest::get().uri("/api/v1/admin/webhooks")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let webhooks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhooks").fetch_one(app.pool()).await.unwrap();
+    assert_eq!(webhooks, 0);
+}